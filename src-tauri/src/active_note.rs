@@ -0,0 +1,58 @@
+use crate::error::AppResult;
+use crate::{db, read_only, settings};
+use rusqlite::params;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+// 当前打开的笔记，保存在设置中，重启后仍可回到上次的位置
+#[derive(Default)]
+pub struct ActiveNoteState(Mutex<Option<i64>>);
+
+// 启动或切换配置后读取保存的笔记 id
+pub fn init(app: &tauri::AppHandle) {
+    *app.state::<ActiveNoteState>().0.lock().unwrap() =
+        settings::get::<i64>(app, settings::LAST_ACTIVE_NOTE);
+}
+
+// 前端切换或关闭笔记时调用；只读模式下只记录在内存中
+#[tauri::command]
+pub fn set_active_note(app: tauri::AppHandle, note_id: Option<i64>) -> AppResult<()> {
+    let state = app.state::<ActiveNoteState>();
+    let mut current = state.0.lock().unwrap();
+    if *current == note_id {
+        return Ok(());
+    }
+    *current = note_id;
+    if !read_only::is_enabled(&app) {
+        settings::set(&app, settings::LAST_ACTIVE_NOTE, note_id)?;
+    }
+    Ok(())
+}
+
+// 启动时前端读取上次打开的笔记
+#[tauri::command]
+pub fn get_last_active_note(state: tauri::State<'_, ActiveNoteState>) -> Option<i64> {
+    *state.0.lock().unwrap()
+}
+
+// 窗口从托盘或最小化恢复时发送 restore-last-note，界面回到隐藏前打开的笔记；
+// 没有打开的笔记或该笔记已被删除时不发送
+pub fn restore(app: &tauri::AppHandle) {
+    let Some(note_id) = *app.state::<ActiveNoteState>().0.lock().unwrap() else {
+        return;
+    };
+    let exists = db::with_read_conn(app, |conn| {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM notes WHERE id = ?1)",
+            params![note_id],
+            |row| row.get::<_, bool>(0),
+        )
+    });
+    match exists {
+        Ok(true) => {
+            let _ = app.emit("restore-last-note", note_id);
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("{}", e),
+    }
+}
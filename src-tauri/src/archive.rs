@@ -0,0 +1,302 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+// 归档库与当前数据库放在同一目录：notes.db 对应 archive.db，其他数据库对应 <名称>.archive.db
+const ARCHIVE_FILE: &str = "archive.db";
+const PAGE_SIZE: usize = 50;
+
+// 标签以名称的 JSON 数组保存，恢复时按名称重新关联
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS archive.notes (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        content TEXT NOT NULL DEFAULT '',
+        editor_type TEXT NOT NULL DEFAULT 'tiptap',
+        created_at DATETIME,
+        updated_at DATETIME,
+        category_id INTEGER,
+        is_pinned BOOLEAN DEFAULT FALSE,
+        is_favorited BOOLEAN DEFAULT FALSE,
+        tags TEXT NOT NULL DEFAULT '[]',
+        archived_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS archive.idx_notes_archived_at ON notes (archived_at);";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchivedNote {
+    pub id: i64,
+    pub title: String,
+    pub updated_at: Option<String>,
+    pub archived_at: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchivePage {
+    pub notes: Vec<ArchivedNote>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+// 归档库与数据库位于同一目录（archive.db 或 <数据库名>.archive.db），列出或创建数据库时需要排除
+pub fn is_archive_stem(stem: &str) -> bool {
+    let archive_stem = ARCHIVE_FILE.trim_end_matches(".db");
+    stem == archive_stem || stem.ends_with(&format!(".{}", archive_stem))
+}
+
+pub fn archive_path(db_path: &Path) -> PathBuf {
+    let stem = db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if stem == db::DEFAULT_DATABASE {
+        db_path.with_file_name(ARCHIVE_FILE)
+    } else {
+        db_path.with_file_name(format!("{}.{}", stem, ARCHIVE_FILE))
+    }
+}
+
+// 在写连接上附加归档库后执行 f，无论成功与否都会分离，避免连接池中的连接带着附加的库
+fn with_archive<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> AppResult<T> {
+    let path = archive_path(&db::db_path(app)?);
+    db::with_write_conn(app, |conn| {
+        conn.execute("ATTACH DATABASE ?1 AS archive", [path.to_string_lossy()])?;
+        let result = conn.execute_batch(SCHEMA).and_then(|_| f(conn));
+        conn.execute_batch("DETACH DATABASE archive")?;
+        result
+    })
+}
+
+// 只读打开归档库，尚未归档过笔记时返回 None
+fn open_archive(app: &tauri::AppHandle) -> AppResult<Option<Connection>> {
+    let path = archive_path(&db::db_path(app)?);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let conn = Connection::open_with_flags(
+        &path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(wrap("db.open_failed"))?;
+    conn.busy_timeout(db::BUSY_TIMEOUT)
+        .map_err(wrap("db.open_failed"))?;
+    Ok(Some(conn))
+}
+
+fn archived_note(row: &rusqlite::Row) -> rusqlite::Result<ArchivedNote> {
+    let tags: String = row.get(4)?;
+    Ok(ArchivedNote {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        updated_at: row.get(2)?,
+        archived_at: row.get(3)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+    })
+}
+
+// 读取全部归档笔记，结构与 db::load_notes 一致
+pub fn load_notes(app: &tauri::AppHandle) -> AppResult<Vec<Value>> {
+    let Some(conn) = open_archive(app)? else {
+        return Ok(Vec::new());
+    };
+    let notes = conn
+        .prepare(
+            "SELECT id, title, content, editor_type, created_at, updated_at,
+                    category_id, is_pinned, is_favorited, tags
+               FROM notes ORDER BY id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                let tags: String = row.get(9)?;
+                Ok(json!({
+                    "id": row.get::<_, i64>(0)?,
+                    "title": row.get::<_, String>(1)?,
+                    "content": row.get::<_, String>(2)?,
+                    "editor_type": row.get::<_, String>(3)?,
+                    "created_at": row.get::<_, Option<String>>(4)?,
+                    "updated_at": row.get::<_, Option<String>>(5)?,
+                    "category_id": row.get::<_, Option<i64>>(6)?,
+                    "is_pinned": row.get::<_, Option<bool>>(7)?.unwrap_or(false),
+                    "is_favorited": row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+                    "tags": serde_json::from_str::<Vec<String>>(&tags).unwrap_or_default(),
+                    "archived": true,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(wrap("db.query_failed"))?;
+    Ok(notes)
+}
+
+// 把指定的笔记或最后修改时间早于 older_than（YYYY-MM-DD）的笔记移入归档库，返回移动的笔记数。
+// 笔记保留原 id，附件目录以笔记 id 命名，因此附件无需移动
+#[tauri::command]
+pub async fn archive_notes(
+    app: tauri::AppHandle,
+    note_ids: Option<Vec<i64>>,
+    older_than: Option<String>,
+) -> AppResult<usize> {
+    if let Some(date) = &older_than {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| AppError::new("archive.invalid_date").with("date", date))?;
+    }
+    if note_ids.is_none() && older_than.is_none() {
+        return Ok(0);
+    }
+    let ids = serde_json::to_string(&note_ids.unwrap_or_default()).unwrap_or_default();
+
+    let count = with_archive(&app, |conn| {
+        let tx = conn.transaction()?;
+        let selected: Vec<i64> = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM main.notes
+                  WHERE id IN (SELECT value FROM json_each(?1))
+                     OR (?2 IS NOT NULL AND updated_at < ?2)",
+            )?;
+            let rows = stmt
+                .query_map(params![ids, older_than], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            rows
+        };
+        let selected = serde_json::to_string(&selected).unwrap_or_default();
+
+        let count = tx.execute(
+            "INSERT OR REPLACE INTO archive.notes
+                    (id, title, content, editor_type, created_at, updated_at,
+                     category_id, is_pinned, is_favorited, tags)
+             SELECT n.id, n.title, n.content, n.editor_type, n.created_at, n.updated_at,
+                    n.category_id, n.is_pinned, n.is_favorited,
+                    (SELECT json_group_array(t.name)
+                       FROM main.note_tags nt JOIN main.tags t ON t.id = nt.tag_id
+                      WHERE nt.note_id = n.id)
+               FROM main.notes n
+              WHERE n.id IN (SELECT value FROM json_each(?1))",
+            [&selected],
+        )?;
+        tx.execute(
+            "DELETE FROM main.notes WHERE id IN (SELECT value FROM json_each(?1))",
+            [&selected],
+        )?;
+        tx.commit()?;
+        Ok(count)
+    })?;
+
+    log::info!("已归档 {} 条笔记", count);
+    Ok(count)
+}
+
+// 把归档的笔记移回当前数据库；原分类已删除时不再归入分类，标签按名称重新关联
+#[tauri::command]
+pub async fn unarchive_note(app: tauri::AppHandle, note_id: i64) -> AppResult<()> {
+    let restored = with_archive(&app, |conn| {
+        let tx = conn.transaction()?;
+        let tags: Option<String> = tx
+            .query_row(
+                "SELECT tags FROM archive.notes WHERE id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(tags) = tags else {
+            return Ok(false);
+        };
+
+        tx.execute(
+            "INSERT INTO main.notes
+                    (id, title, content, editor_type, created_at, updated_at,
+                     category_id, is_pinned, is_favorited)
+             SELECT a.id, a.title, a.content, a.editor_type, a.created_at, a.updated_at,
+                    (SELECT c.id FROM main.categories c WHERE c.id = a.category_id),
+                    a.is_pinned, a.is_favorited
+               FROM archive.notes a
+              WHERE a.id = ?1",
+            [note_id],
+        )?;
+        for tag in serde_json::from_str::<Vec<String>>(&tags).unwrap_or_default() {
+            tx.execute("INSERT OR IGNORE INTO main.tags (name) VALUES (?1)", [&tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO main.note_tags (note_id, tag_id)
+                 SELECT ?1, id FROM main.tags WHERE name = ?2",
+                params![note_id, tag],
+            )?;
+        }
+        tx.execute("DELETE FROM archive.notes WHERE id = ?1", [note_id])?;
+        tx.commit()?;
+        Ok(true)
+    })?;
+
+    if !restored {
+        return Err(AppError::new("archive.not_found").with("id", note_id));
+    }
+    log::info!("已从归档中恢复笔记 {}", note_id);
+    Ok(())
+}
+
+// 按归档时间从新到旧分页列出，page 从 0 开始
+#[tauri::command]
+pub async fn list_archived_notes(app: tauri::AppHandle, page: usize) -> AppResult<ArchivePage> {
+    let mut result = ArchivePage {
+        notes: Vec::new(),
+        total: 0,
+        page,
+        page_size: PAGE_SIZE,
+    };
+    let Some(conn) = open_archive(&app)? else {
+        return Ok(result);
+    };
+
+    result.total = conn
+        .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+        .map_err(wrap("db.query_failed"))?;
+    result.notes = conn
+        .prepare(
+            "SELECT id, title, updated_at, archived_at, tags FROM notes
+              ORDER BY archived_at DESC, id DESC LIMIT ?1 OFFSET ?2",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![PAGE_SIZE, page * PAGE_SIZE], archived_note)?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(wrap("db.query_failed"))?;
+    Ok(result)
+}
+
+// 在归档笔记的标题和内容中搜索（不区分大小写）
+#[tauri::command]
+pub async fn search_archive(app: tauri::AppHandle, query: String) -> AppResult<Vec<ArchivedNote>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(conn) = open_archive(&app)? else {
+        return Ok(Vec::new());
+    };
+
+    let pattern = format!(
+        "%{}%",
+        query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let notes = conn
+        .prepare(
+            "SELECT id, title, updated_at, archived_at, tags FROM notes
+              WHERE title LIKE ?1 ESCAPE '\\' OR content LIKE ?1 ESCAPE '\\'
+              ORDER BY archived_at DESC, id DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([pattern], archived_note)?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(wrap("db.query_failed"))?;
+    Ok(notes)
+}
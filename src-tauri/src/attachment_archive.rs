@@ -0,0 +1,223 @@
+use crate::attachments;
+use crate::error::{wrap, AppError, AppResult};
+use crate::screenshot::ATTACHMENTS_DIR;
+use crate::{db, portable, read_only};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+pub const ARCHIVE_FILE: &str = "attachments_archive.zip";
+// 归档后原文件替换为同名加该后缀的指针文件，内容为压缩包中的条目名
+pub const POINTER_SUFFIX: &str = ".archived";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchivedAttachment {
+    // attachments/<笔记 id>/<文件名>
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AttachmentArchiveReport {
+    pub dry_run: bool,
+    pub attachments: Vec<ArchivedAttachment>,
+    pub total_size: u64,
+    // 归档后压缩包的大小，预览时为 None
+    pub archive_size: Option<u64>,
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc())
+        })
+        .ok()
+}
+
+// 文件修改时间和所属笔记的修改时间都早于 cutoff 的附件；笔记已删除的附件同样计入
+fn candidates(
+    attachments_dir: &Path,
+    updated: &HashMap<i64, Option<DateTime<Utc>>>,
+    cutoff: DateTime<Utc>,
+) -> Vec<(PathBuf, ArchivedAttachment)> {
+    let mut found = Vec::new();
+    let Ok(note_dirs) = fs::read_dir(attachments_dir) else {
+        return found;
+    };
+    for note_dir in note_dirs.flatten() {
+        let id = note_dir.file_name().to_string_lossy().into_owned();
+        let Ok(note_id) = id.parse::<i64>() else {
+            continue;
+        };
+        let note_recent = updated
+            .get(&note_id)
+            .is_some_and(|time| time.is_none_or(|time| time >= cutoff));
+        if note_recent {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(note_dir.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || name.ends_with(POINTER_SUFFIX) {
+                continue;
+            }
+            let old = metadata
+                .modified()
+                .is_ok_and(|time| DateTime::<Utc>::from(time) < cutoff);
+            if old {
+                found.push((
+                    entry.path(),
+                    ArchivedAttachment {
+                        path: format!("{}/{}/{}", ATTACHMENTS_DIR, id, name),
+                        size: metadata.len(),
+                    },
+                ));
+            }
+        }
+    }
+    found.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+    found
+}
+
+// 重新生成压缩包：保留原有条目（去掉 remove 中的），再加入 add 中的文件；先写入临时文件再替换，中途失败不影响原压缩包
+fn rewrite_archive(
+    archive_path: &Path,
+    remove: &HashSet<&str>,
+    add: &[(PathBuf, String)],
+) -> AppResult<()> {
+    let temp_path = archive_path.with_extension("zip.tmp");
+    let file = File::create(&temp_path).map_err(wrap("attachment.archive_failed"))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let replaced: HashSet<&str> = add.iter().map(|(_, name)| name.as_str()).collect();
+
+    if archive_path.exists() {
+        let file = File::open(archive_path).map_err(wrap("attachment.archive_failed"))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(wrap("attachment.archive_failed"))?;
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index_raw(i)
+                .map_err(wrap("attachment.archive_failed"))?;
+            let name = entry.name().to_string();
+            if remove.contains(name.as_str()) || replaced.contains(name.as_str()) {
+                continue;
+            }
+            writer
+                .raw_copy_file(entry)
+                .map_err(wrap("attachment.archive_failed"))?;
+        }
+    }
+
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (path, name) in add {
+        let data = fs::read(path).map_err(wrap("attachment.archive_failed"))?;
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(wrap("attachment.archive_failed"))?;
+        writer
+            .write_all(&data)
+            .map_err(wrap("attachment.archive_failed"))?;
+    }
+    writer.finish().map_err(wrap("attachment.archive_failed"))?;
+    fs::rename(&temp_path, archive_path).map_err(wrap("attachment.archive_failed"))
+}
+
+// 把长期未修改的附件压缩进数据目录中的 attachments_archive.zip，原文件替换为指针文件以节省空间；
+// 应用没有记录笔记的打开时间，以笔记的修改时间判断是否近期使用。dry_run 时只返回将被归档的文件
+#[tauri::command]
+pub async fn archive_old_attachments(
+    app: tauri::AppHandle,
+    older_than_days: u32,
+    dry_run: Option<bool>,
+) -> AppResult<AttachmentArchiveReport> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        read_only::ensure_writable(&app)?;
+    }
+    let cutoff = Utc::now() - Duration::days(older_than_days as i64);
+    let updated: HashMap<i64, Option<DateTime<Utc>>> = db::with_read_conn(&app, |conn| {
+        let mut stmt = conn.prepare("SELECT id, updated_at FROM notes")?;
+        let rows = stmt
+            .query_map([], |row| {
+                let updated_at: Option<String> = row.get(1)?;
+                Ok((row.get(0)?, updated_at.as_deref().and_then(parse_time)))
+            })?
+            .collect::<rusqlite::Result<_>>();
+        rows
+    })?;
+
+    let data_dir = portable::resolve_data_dir(&app)?;
+    let found = candidates(&attachments::attachments_dir(&app)?, &updated, cutoff);
+    let total_size = found.iter().map(|(_, attachment)| attachment.size).sum();
+    let archive_path = data_dir.join(ARCHIVE_FILE);
+
+    if !dry_run && !found.is_empty() {
+        let add: Vec<(PathBuf, String)> = found
+            .iter()
+            .map(|(path, attachment)| (path.clone(), attachment.path.clone()))
+            .collect();
+        rewrite_archive(&archive_path, &HashSet::new(), &add)?;
+        // 压缩包写入成功后才替换原文件
+        for (path, name) in &add {
+            let pointer = PathBuf::from(format!("{}{}", path.to_string_lossy(), POINTER_SUFFIX));
+            fs::write(&pointer, name).map_err(wrap("attachment.archive_failed"))?;
+            fs::remove_file(path).map_err(wrap("attachment.archive_failed"))?;
+        }
+        log::info!("已归档 {} 个附件", add.len());
+    }
+
+    Ok(AttachmentArchiveReport {
+        dry_run,
+        attachments: found
+            .into_iter()
+            .map(|(_, attachment)| attachment)
+            .collect(),
+        total_size,
+        archive_size: (!dry_run)
+            .then(|| fs::metadata(&archive_path).ok().map(|meta| meta.len()))
+            .flatten(),
+    })
+}
+
+// 从压缩包中取回一个附件（attachments/<笔记 id>/<文件名>），恢复原文件并删除指针和压缩包中的条目
+#[tauri::command]
+pub async fn restore_archived_attachment(app: tauri::AppHandle, name: String) -> AppResult<()> {
+    read_only::ensure_writable(&app)?;
+    let data_dir = portable::resolve_data_dir(&app)?;
+    let path = attachments::resolve(&data_dir, &name)?;
+    let archive_path = data_dir.join(ARCHIVE_FILE);
+
+    let data = File::open(&archive_path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(file).ok())
+        .and_then(|mut archive| {
+            let mut entry = archive.by_name(&name).ok()?;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).ok()?;
+            Some(data)
+        })
+        .ok_or_else(|| AppError::new("attachment.not_archived").with("path", &name))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(wrap("attachment.restore_failed"))?;
+    }
+    fs::write(&path, data).map_err(wrap("attachment.restore_failed"))?;
+    let pointer = PathBuf::from(format!("{}{}", path.to_string_lossy(), POINTER_SUFFIX));
+    if let Err(e) = fs::remove_file(&pointer) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            return Err(AppError::new("attachment.restore_failed").with("error", e));
+        }
+    }
+    rewrite_archive(&archive_path, &HashSet::from([name.as_str()]), &[])?;
+    log::info!("已从归档恢复附件 {}", name);
+    Ok(())
+}
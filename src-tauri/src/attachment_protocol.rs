@@ -0,0 +1,179 @@
+use crate::attachments;
+use crate::markdown_import::percent_decode;
+use crate::portable;
+use crate::screenshot::ATTACHMENTS_DIR;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::http::{header, Request, Response, StatusCode};
+
+// 前端通过 attachment://localhost/<笔记 id>/<文件名> 访问附件（Windows 上为 http://attachment.localhost/...），
+// 不需要开放 asset 协议的文件系统范围；加上 ?thumb=<尺寸> 返回 PNG 缩略图
+pub const SCHEME: &str = "attachment";
+
+// 未指定结束位置的范围请求每次最多返回的字节数，视频等大文件按需分段读取；
+// 没有 Range 头时超过该大小的文件同样只返回第一段（206），不把整个文件读进内存
+const MAX_RANGE: u64 = 4 * 1024 * 1024;
+// 生成缩略图时源图片的文件大小、像素尺寸和解码内存上限
+const THUMB_MAX_FILE: u64 = 64 * 1024 * 1024;
+const THUMB_MAX_DIMENSION: u32 = 16384;
+const THUMB_MAX_ALLOC: u64 = 256 * 1024 * 1024;
+const THUMB_MIN: u32 = 16;
+const THUMB_MAX: u32 = 1024;
+
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(code)
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+// 只支持单个范围：bytes=start-end、bytes=start-、bytes=-suffix
+fn parse_range(value: &str, len: u64) -> Option<(u64, u64)> {
+    let last = len.checked_sub(1)?;
+    let (start, end) = value.strip_prefix("bytes=")?.trim().split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), last)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, start.saturating_add(MAX_RANGE - 1).min(last))
+        }
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(last)),
+    };
+    // 起点超出文件长度时返回 None，由调用方响应 416
+    (start < len && start <= end && end < len).then_some((start, end))
+}
+
+fn thumbnail(path: &Path, size: u32) -> Option<Vec<u8>> {
+    let size = size.clamp(THUMB_MIN, THUMB_MAX);
+    if std::fs::metadata(path).ok()?.len() > THUMB_MAX_FILE {
+        return None;
+    }
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(THUMB_MAX_DIMENSION);
+    limits.max_image_height = Some(THUMB_MAX_DIMENSION);
+    limits.max_alloc = Some(THUMB_MAX_ALLOC);
+    let mut reader = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?;
+    reader.limits(limits);
+    let image = reader.decode().ok()?.thumbnail(size, size);
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
+
+fn serve_file(path: &Path, range: Option<&str>) -> std::io::Result<Response<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache");
+
+    let range = match range {
+        Some(range) => range,
+        None if len <= MAX_RANGE => {
+            let mut body = Vec::with_capacity(len as usize);
+            file.read_to_end(&mut body)?;
+            return Ok(builder.body(body).unwrap_or_default());
+        }
+        None => "bytes=0-",
+    };
+    let Some((start, end)) = parse_range(range, len) else {
+        return Ok(Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new())
+            .unwrap_or_default());
+    };
+
+    let mut body = vec![0; (end - start + 1) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut body)?;
+    Ok(builder
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        )
+        .body(body)
+        .unwrap_or_default())
+}
+
+pub fn handle(app: &tauri::AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let uri = request.uri();
+    let Some((id, name)) = uri.path().trim_start_matches('/').split_once('/') else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let Ok(data_dir) = portable::resolve_data_dir(app) else {
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    // 与笔记中的相对路径使用同样的校验，不允许跳出附件目录
+    let relative = format!("{}/{}/{}", ATTACHMENTS_DIR, id, percent_decode(name));
+    let Ok(path) = attachments::resolve(&data_dir, &relative) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    if !path.is_file() {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    let thumb = uri.query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("thumb="))
+            .and_then(|size| size.parse::<u32>().ok())
+    });
+    if let Some(size) = thumb {
+        return match thumbnail(&path, size) {
+            Some(png) => Response::builder()
+                .header(header::CONTENT_TYPE, "image/png")
+                .body(png)
+                .unwrap_or_default(),
+            None => status(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        };
+    }
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    serve_file(&path, range).unwrap_or_else(|e| {
+        log::warn!("读取附件失败 {}: {}", relative, e);
+        status(StatusCode::INTERNAL_SERVER_ERROR)
+    })
+}
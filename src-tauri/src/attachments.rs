@@ -0,0 +1,282 @@
+use crate::error::{AppError, AppResult};
+use crate::export::parse_notes;
+use crate::markdown_import::percent_decode;
+use crate::screenshot::ATTACHMENTS_DIR;
+use crate::{attachment_archive, db, portable, read_only, versions};
+use regex::Regex;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+// 笔记中引用附件的相对路径，与 capture_screenshot、导入和剪藏生成的链接一致
+static ATTACHMENT_REF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"attachments/(\d+)/([^\s"'()<>\[\]?#]+)"#).unwrap());
+// 链接目标：HTML 的 src / href 属性值和 Markdown 链接、图片的地址
+static LINK_TARGET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?:\b(?:src|href)\s*=\s*["']?|\]\(\s*<?)([^"'()<>\s]+)"#).unwrap()
+});
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BrokenReference {
+    pub note_id: i64,
+    pub title: String,
+    // 笔记中的原始路径
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AttachmentAudit {
+    // 引用的文件不存在
+    pub broken: Vec<BrokenReference>,
+    // 没有任何笔记引用的文件，相对于应用数据目录
+    pub orphans: Vec<String>,
+}
+
+pub fn attachments_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(portable::resolve_data_dir(app)?.join(ATTACHMENTS_DIR))
+}
+
+// 统一为解码后的 attachments/<id>/<name>
+fn normalize(id: &str, name: &str) -> String {
+    format!("{}/{}/{}", ATTACHMENTS_DIR, id, percent_decode(name))
+}
+
+// attachments/<id>/<name> 形式的相对路径，不允许跳出附件目录
+pub fn resolve(data_dir: &Path, relative: &str) -> AppResult<PathBuf> {
+    let invalid = || AppError::new("attachment.invalid_path").with("path", relative);
+    let parts: Vec<&str> = relative.split('/').collect();
+    let [dir, id, name] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    let valid = *dir == ATTACHMENTS_DIR
+        && id.parse::<i64>().is_ok()
+        && !name.is_empty()
+        && *name != "."
+        && *name != ".."
+        && !name.contains('\\');
+    // Windows 上 C:foo 或绝对路径拼接后会替换掉附件目录
+    let single = matches!(
+        Path::new(name).components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(_)]
+    );
+    if !valid || !single {
+        return Err(invalid());
+    }
+    let base = data_dir.join(ATTACHMENTS_DIR);
+    let path = base.join(id).join(name);
+    if !contained(&base, &path) {
+        return Err(invalid());
+    }
+    Ok(path)
+}
+
+// 解析符号链接后仍须位于附件目录中；文件尚不存在（如恢复归档附件）时检查最近的已存在的上级目录
+fn contained(base: &Path, path: &Path) -> bool {
+    if !path.starts_with(base) {
+        return false;
+    }
+    let Ok(canonical_base) = base.canonicalize() else {
+        // 附件目录还不存在，其中也不会有符号链接
+        return true;
+    };
+    path.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(base))
+        .find(|ancestor| ancestor.exists())
+        .and_then(|ancestor| ancestor.canonicalize().ok())
+        .is_some_and(|canonical| canonical.starts_with(&canonical_base))
+}
+
+// 附件目录中的全部文件
+fn list_files(dir: &Path) -> BTreeSet<String> {
+    let mut files = BTreeSet::new();
+    let Ok(note_dirs) = fs::read_dir(dir) else {
+        return files;
+    };
+    for note_dir in note_dirs.flatten() {
+        let id = note_dir.file_name().to_string_lossy().into_owned();
+        let Ok(entries) = fs::read_dir(note_dir.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().is_file() {
+                // 已归档的附件按原文件名计入，引用它的笔记不算损坏
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let name = name
+                    .strip_suffix(attachment_archive::POINTER_SUFFIX)
+                    .unwrap_or(&name);
+                files.insert(format!("{}/{}/{}", ATTACHMENTS_DIR, id, name));
+            }
+        }
+    }
+    files
+}
+
+// 双向检查附件：笔记引用了不存在的文件，以及没有被任何笔记引用的文件
+#[tauri::command]
+pub async fn audit_attachments(
+    app: tauri::AppHandle,
+    notes_json: String,
+) -> AppResult<AttachmentAudit> {
+    let notes = parse_notes(&notes_json)?;
+    let files = list_files(&attachments_dir(&app)?);
+
+    let mut broken = Vec::new();
+    let mut referenced = HashSet::new();
+    for note in &notes {
+        let content = note["content"].as_str().unwrap_or("");
+        let mut seen = HashSet::new();
+        for caps in ATTACHMENT_REF.captures_iter(content) {
+            let path = normalize(&caps[1], &caps[2]);
+            if !files.contains(&path) && seen.insert(caps[0].to_string()) {
+                broken.push(BrokenReference {
+                    note_id: note["id"].as_i64().unwrap_or_default(),
+                    title: note["title"].as_str().unwrap_or("").to_string(),
+                    path: caps[0].to_string(),
+                });
+            }
+            referenced.insert(path);
+        }
+    }
+    let orphans = files
+        .into_iter()
+        .filter(|path| !referenced.contains(path))
+        .collect();
+
+    Ok(AttachmentAudit { broken, orphans })
+}
+
+// 删除 audit_attachments 报告的孤立文件，返回删除的文件数；附件目录删空后一并删除
+#[tauri::command]
+pub async fn delete_orphan_attachments(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> AppResult<usize> {
+    read_only::ensure_writable(&app)?;
+    let data_dir = portable::resolve_data_dir(&app)?;
+    let files: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| resolve(&data_dir, path))
+        .collect::<AppResult<_>>()?;
+
+    let mut deleted = 0;
+    for file in &files {
+        match fs::remove_file(file) {
+            Ok(()) => deleted += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::new("attachment.delete_failed").with("error", e)),
+        }
+        if let Some(dir) = file.parent() {
+            // 目录非空时删除失败，忽略即可
+            let _ = fs::remove_dir(dir);
+        }
+    }
+    log::info!("已删除 {} 个孤立附件", deleted);
+    Ok(deleted)
+}
+
+// 只替换链接目标中与 from 完全相同的附件引用，正文中的同名文字不变
+fn relink(content: &str, from: &str, to: &str) -> (String, usize) {
+    let mut count = 0;
+    let relinked = LINK_TARGET.replace_all(content, |caps: &regex::Captures| {
+        let whole = caps.get(0).unwrap();
+        let target = caps.get(1).unwrap();
+        let prefix = &content[whole.start()..target.start()];
+        let target = ATTACHMENT_REF.replace_all(target.as_str(), |reference: &regex::Captures| {
+            if &reference[0] == from {
+                count += 1;
+                to.to_string()
+            } else {
+                reference[0].to_string()
+            }
+        });
+        format!("{}{}", prefix, target)
+    });
+    (relinked.into_owned(), count)
+}
+
+// 把笔记中指向缺失文件的引用改为另一个附件（如 audit_attachments 报告的孤立文件），返回替换的次数。
+// 修改前保存历史版本
+#[tauri::command]
+pub async fn relink_attachment(
+    app: tauri::AppHandle,
+    note_id: i64,
+    from: String,
+    to: String,
+) -> AppResult<usize> {
+    let data_dir = portable::resolve_data_dir(&app)?;
+    if !resolve(&data_dir, &to)?.is_file() {
+        return Err(AppError::new("attachment.not_found").with("path", &to));
+    }
+
+    // from 须是完整的附件引用（如 audit_attachments 报告的路径）
+    if ATTACHMENT_REF
+        .find(&from)
+        .is_none_or(|m| m.as_str() != from)
+    {
+        return Err(AppError::new("attachment.invalid_path").with("path", &from));
+    }
+
+    let keep_versions = versions::enabled(&app);
+    let replaced = db::with_write_conn(&app, |conn| {
+        let tx = conn.transaction()?;
+        let content: Option<String> = tx
+            .query_row(
+                "SELECT content FROM notes WHERE id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(content) = content else {
+            return Ok(None);
+        };
+        let (relinked, count) = relink(&content, &from, &to);
+        if count == 0 {
+            return Ok(Some(0));
+        }
+        if keep_versions {
+            versions::snapshot(&tx, note_id)?;
+        }
+        tx.execute(
+            "UPDATE notes SET content = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![relinked, note_id],
+        )?;
+        tx.commit()?;
+        Ok(Some(count))
+    })?;
+
+    replaced.ok_or_else(|| AppError::new("attachment.note_not_found").with("id", note_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_rejects_malformed_paths() {
+        let data_dir = Path::new("/nonexistent-data-dir");
+        for relative in [
+            "attachments",
+            "attachments/1",
+            "attachments/1/",
+            "attachments/1/..",
+            "attachments/../1/a.png",
+            "attachments/1/../a.png",
+            "attachments/x/a.png",
+            "other/1/a.png",
+        ] {
+            assert!(resolve(data_dir, relative).is_err(), "{}", relative);
+        }
+    }
+
+    #[test]
+    fn resolve_accepts_attachment_paths() {
+        let data_dir = Path::new("/nonexistent-data-dir");
+        assert_eq!(
+            resolve(data_dir, "attachments/12/a.png").unwrap(),
+            data_dir.join(ATTACHMENTS_DIR).join("12").join("a.png")
+        );
+    }
+}
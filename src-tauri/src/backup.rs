@@ -0,0 +1,656 @@
+use crate::archive;
+use crate::databases::{self, BACKUP_PREFIX};
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use crate::{read_only, settings};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use tauri::{Emitter, Manager};
+
+// WAL 模式下先把日志合并回主文件，否则复制出的备份会缺少最近的修改
+pub fn checkpoint(app: &tauri::AppHandle) -> AppResult<()> {
+    db::with_write_conn(app, |conn| {
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+    })
+}
+
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupRecord {
+    path: String,
+    time: DateTime<Utc>,
+}
+
+// 同一时间只允许一个备份（以及恢复）操作，避免两个操作同时复制数据库文件得到不完整的备份
+#[derive(Default)]
+pub struct BackupState(Mutex<()>);
+
+impl BackupState {
+    // wait 为 false 时若已有备份在进行则返回 backup.in_progress
+    pub fn acquire(&self, wait: bool) -> AppResult<MutexGuard<'_, ()>> {
+        if wait {
+            return Ok(self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+        match self.0.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(AppError::new("backup.in_progress")),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.0.try_lock(), Err(TryLockError::WouldBlock))
+    }
+}
+
+#[tauri::command]
+pub fn is_backup_running(app: tauri::AppHandle) -> bool {
+    app.state::<BackupState>().is_running()
+}
+
+// 在应用数据目录中保存一份当前数据库的快照，有备份正在进行时等待其完成
+pub fn snapshot_current(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    let state = app.state::<BackupState>();
+    let _guard = state.acquire(true)?;
+    snapshot(app)
+}
+
+fn snapshot(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    let db_path = db::db_path(app)?;
+    if !db_path.exists() {
+        return Err(AppError::new("db.not_found"));
+    }
+
+    checkpoint(app)?;
+    let backup_path = db_path.with_file_name(format!(
+        "{}{}.db",
+        BACKUP_PREFIX,
+        Utc::now().format(SNAPSHOT_TIME_FORMAT)
+    ));
+    fs::copy(&db_path, &backup_path).map_err(wrap("backup.snapshot_failed"))?;
+
+    Ok(backup_path)
+}
+
+// full 为 true 时同时备份归档库，保存为备份文件旁的同名 .archive.db；
+// 已有备份在进行时，wait 为 true 则排队等待，否则返回 backup.in_progress
+#[tauri::command]
+pub async fn backup_database(
+    app: tauri::AppHandle,
+    file_path: String,
+    full: Option<bool>,
+    wait: Option<bool>,
+) -> AppResult<()> {
+    let state = app.state::<BackupState>();
+    let _guard = state.acquire(wait.unwrap_or(false))?;
+
+    // 获取应用数据目录中的数据库文件路径
+    let db_path = db::db_path(&app)?;
+
+    if !db_path.exists() {
+        return Err(AppError::new("db.not_found"));
+    }
+
+    // 只读模式下无法合并 WAL，改用 VACUUM INTO 从只读连接导出完整的数据库
+    if read_only::is_enabled(&app) {
+        db::with_read_conn(&app, |conn| {
+            conn.execute("VACUUM INTO ?1", [&file_path]).map(|_| ())
+        })
+        .map_err(|e| {
+            log::error!("备份数据库失败: {}", e);
+            AppError::new("backup.failed").with("error", e)
+        })?;
+    } else {
+        checkpoint(&app)?;
+
+        fs::copy(&db_path, &file_path).map_err(|e| {
+            log::error!("备份数据库失败: {}", e);
+            AppError::new("backup.failed").with("error", e)
+        })?;
+    }
+
+    let archive_path = archive::archive_path(&db_path);
+    if full.unwrap_or(false) && archive_path.exists() {
+        fs::copy(&archive_path, archive::archive_path(Path::new(&file_path))).map_err(|e| {
+            log::error!("备份归档库失败: {}", e);
+            AppError::new("backup.failed").with("error", e)
+        })?;
+    }
+    log::info!("数据库已备份");
+
+    // 用户选择的备份位置不固定，记录最近一次以便 get_last_backup_info 查询；只读模式下不保存
+    if !read_only::is_enabled(&app) {
+        let record = BackupRecord {
+            path: file_path,
+            time: Utc::now(),
+        };
+        if let Err(e) = settings::set(&app, settings::LAST_BACKUP, record) {
+            log::warn!("{}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LastBackupInfo {
+    // 还没有任何备份时为 false，其余字段均为 None
+    pub has_backup: bool,
+    pub time: Option<DateTime<Utc>>,
+    pub path: Option<String>,
+    pub size: Option<u64>,
+    pub days_elapsed: Option<i64>,
+    // manual：backup_database 保存的备份；snapshot：恢复数据库前自动保存的快照
+    pub kind: Option<&'static str>,
+}
+
+// 数据目录中的快照，时间取自文件名，无法解析时使用文件修改时间
+fn latest_snapshot(dir: &Path) -> Option<(DateTime<Utc>, PathBuf)> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".db")?;
+            let time = NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT)
+                .map(|time| time.and_utc())
+                .ok()
+                .or_else(|| {
+                    entry
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .map(DateTime::<Utc>::from)
+                })?;
+            Some((time, entry.path()))
+        })
+        .max_by_key(|(time, _)| *time)
+}
+
+// 最近一次备份：backup_database 记录的备份（文件仍存在时）和数据目录中的快照取较新的一个，
+// 界面可据此提示用户备份；没有备份时 has_backup 为 false 而不是返回错误
+#[tauri::command]
+pub fn get_last_backup_info(app: tauri::AppHandle) -> AppResult<LastBackupInfo> {
+    let manual = settings::get::<BackupRecord>(&app, settings::LAST_BACKUP)
+        .filter(|record| Path::new(&record.path).is_file())
+        .map(|record| (record.time, PathBuf::from(record.path), "manual"));
+    let snapshot = db::db_path(&app)?
+        .parent()
+        .and_then(latest_snapshot)
+        .map(|(time, path)| (time, path, "snapshot"));
+    let latest = [manual, snapshot]
+        .into_iter()
+        .flatten()
+        .max_by_key(|(time, _, _)| *time);
+
+    Ok(match latest {
+        Some((time, path, kind)) => LastBackupInfo {
+            has_backup: true,
+            time: Some(time),
+            size: fs::metadata(&path).ok().map(|m| m.len()),
+            path: Some(path.to_string_lossy().into_owned()),
+            days_elapsed: Some((Utc::now() - time).num_days()),
+            kind: Some(kind),
+        },
+        None => LastBackupInfo {
+            has_backup: false,
+            time: None,
+            path: None,
+            size: None,
+            days_elapsed: None,
+            kind: None,
+        },
+    })
+}
+
+#[tauri::command]
+pub async fn restore_database(app: tauri::AppHandle, file_path: String) -> AppResult<()> {
+    read_only::ensure_writable(&app)?;
+    if !std::path::Path::new(&file_path).exists() {
+        return Err(AppError::new("backup.file_not_found"));
+    }
+
+    // 获取应用数据目录中的数据库文件路径
+    let db_path = db::db_path(&app)?;
+
+    // 等待进行中的备份完成，恢复期间也不允许开始新的备份
+    let state = app.state::<BackupState>();
+    let _guard = state.acquire(true)?;
+
+    // 备份当前数据库（如果存在）
+    if db_path.exists() {
+        snapshot(&app)?;
+    }
+
+    // 先复制到临时文件，复制失败时当前数据库保持不变
+    let archive_backup = archive::archive_path(Path::new(&file_path));
+    let archive_path = archive::archive_path(&db_path);
+    let staged = stage(Path::new(&file_path), &db_path)?;
+    let staged_archive = if archive_backup.is_file() {
+        Some(stage(&archive_backup, &archive_path)?)
+    } else {
+        None
+    };
+
+    // 前端在调用前已关闭自己的连接；关闭连接池时合并 WAL，之后 WAL 仍有内容说明还有其他连接在使用
+    app.state::<db::DbPool>().close();
+    if wal_in_use(&db_path) {
+        let _ = fs::remove_file(&staged);
+        if let Some(staged_archive) = &staged_archive {
+            let _ = fs::remove_file(staged_archive);
+        }
+        return Err(AppError::new("backup.database_in_use"));
+    }
+
+    // 完整备份旁有归档库时一并恢复
+    replace_with(&staged, &db_path)?;
+    if let Some(staged_archive) = staged_archive {
+        replace_with(&staged_archive, &archive_path)?;
+    }
+    log::info!("数据库已从备份恢复");
+
+    // 通知前端重新打开连接
+    let _ = app.emit(
+        "database-switched",
+        databases::get_active_database(app.clone())?,
+    );
+
+    Ok(())
+}
+
+// 复制到目标旁的临时文件
+fn stage(source: &Path, target: &Path) -> AppResult<PathBuf> {
+    let mut staged = target.as_os_str().to_owned();
+    staged.push(".restoring");
+    let staged = PathBuf::from(staged);
+    fs::copy(source, &staged).map_err(|e| {
+        log::error!("复制备份文件失败: {}", e);
+        AppError::new("backup.restore_failed").with("error", e)
+    })?;
+    Ok(staged)
+}
+
+fn replace_with(staged: &Path, target: &Path) -> AppResult<()> {
+    fs::rename(staged, target).map_err(|e| {
+        log::error!("替换数据库文件失败: {}", e);
+        let _ = fs::remove_file(staged);
+        AppError::new("backup.restore_failed").with("error", e)
+    })
+}
+
+// 连接全部关闭并合并后 WAL 文件为空或已被 SQLite 删除；-wal / -shm 可能仍被其他连接持有，不删除
+fn wal_in_use(db_path: &Path) -> bool {
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    fs::metadata(PathBuf::from(wal)).is_ok_and(|meta| meta.len() > 0)
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RestoreTestReport {
+    pub ok: bool,
+    // 复制、打开、升级表结构、完整性检查或计数查询中发现的问题
+    pub problems: Vec<String>,
+    pub notes: Option<i64>,
+    pub categories: Option<i64>,
+    pub tags: Option<i64>,
+    // 旁边有归档库时一并检查
+    pub archive_checked: bool,
+    pub tested_at: DateTime<Utc>,
+}
+
+// integrity_check 的问题，每个库最多返回 100 条
+fn integrity_problems(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check(100)")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter(|row| !matches!(row.as_deref(), Ok("ok")))
+        .collect();
+    rows
+}
+
+fn count(conn: &Connection, table: &str) -> rusqlite::Result<i64> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+        row.get(0)
+    })
+}
+
+// 与恢复相同：打开副本并升级表结构（应用启动时也会执行），再检查完整性并统计各表行数
+fn check_restored(temp_path: &Path, archive: Option<&Path>, report: &mut RestoreTestReport) {
+    let conn = match db::open(temp_path) {
+        Ok(conn) => conn,
+        Err(e) => {
+            report.problems.push(e.to_string());
+            return;
+        }
+    };
+    if let Err(e) = db::migrate(&conn) {
+        report.problems.push(e.to_string());
+    }
+    match integrity_problems(&conn) {
+        Ok(problems) => report.problems.extend(problems),
+        Err(e) => report.problems.push(e.to_string()),
+    }
+    for (table, field) in [
+        ("notes", &mut report.notes),
+        ("categories", &mut report.categories),
+        ("tags", &mut report.tags),
+    ] {
+        match count(&conn, table) {
+            Ok(value) => *field = Some(value),
+            Err(e) => report.problems.push(e.to_string()),
+        }
+    }
+    if let Some(archive) = archive {
+        let result = db::open(archive).and_then(|conn| integrity_problems(&conn));
+        match result {
+            Ok(problems) => report.problems.extend(problems),
+            Err(e) => report.problems.push(e.to_string()),
+        }
+        report.archive_checked = true;
+    }
+}
+
+// 演练恢复：把备份复制到缓存目录中的临时数据库上检查，不影响当前数据，检查完删除临时文件
+#[tauri::command]
+pub async fn test_restore(
+    app: tauri::AppHandle,
+    file_path: String,
+) -> AppResult<RestoreTestReport> {
+    let source = Path::new(&file_path);
+    if !source.is_file() {
+        return Err(AppError::new("backup.file_not_found"));
+    }
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(wrap("app.cache_dir_unavailable"))?;
+    fs::create_dir_all(&dir).map_err(wrap("backup.restore_failed"))?;
+    let temp_path = dir.join(format!("restore_test_{}.db", uuid::Uuid::new_v4()));
+
+    let mut report = RestoreTestReport {
+        ok: false,
+        problems: Vec::new(),
+        notes: None,
+        categories: None,
+        tags: None,
+        archive_checked: false,
+        tested_at: Utc::now(),
+    };
+    let archive_backup = archive::archive_path(source);
+    let temp_archive = archive::archive_path(&temp_path);
+    let archive = archive_backup
+        .is_file()
+        .then(|| fs::copy(&archive_backup, &temp_archive).map(|_| temp_archive.as_path()));
+    match (fs::copy(source, &temp_path), archive.transpose()) {
+        (Ok(_), Ok(archive)) => check_restored(&temp_path, archive, &mut report),
+        (Err(e), _) | (_, Err(e)) => report.problems.push(e.to_string()),
+    }
+
+    for path in [&temp_path, &temp_archive] {
+        if let Err(e) = db::remove_wal_files(path) {
+            log::warn!("{}", e);
+        }
+        if let Err(e) = fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("删除临时数据库失败: {}", e);
+            }
+        }
+    }
+
+    report.ok = report.problems.is_empty();
+    if report.ok {
+        log::info!("备份恢复演练通过: {}", file_path);
+    } else {
+        log::warn!("备份恢复演练失败: {} {:?}", file_path, report.problems);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn delete_database(app: tauri::AppHandle) -> AppResult<()> {
+    read_only::ensure_writable(&app)?;
+    let db_path = db::db_path(&app)?;
+
+    // 前端在调用前已关闭自己的连接
+    app.state::<db::DbPool>().close();
+    if wal_in_use(&db_path) {
+        return Err(AppError::new("backup.database_in_use"));
+    }
+
+    if db_path.exists() {
+        fs::remove_file(&db_path).map_err(wrap("db.remove_failed"))?;
+    }
+
+    log::warn!("数据库已删除，应用即将重启");
+
+    // 重启应用以重新生成数据库
+    app.restart();
+}
+
+struct BackupNote {
+    title: String,
+    content: String,
+    updated_at: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BackupDiff {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+}
+
+// 以 immutable URI 只读打开备份，SQLite 不会在旁边创建 -wal / -shm 文件
+fn open_backup(path: &str) -> AppResult<Connection> {
+    let path = Path::new(path);
+    if !path.is_file() {
+        return Err(AppError::new("backup.file_not_found"));
+    }
+    let path = path
+        .canonicalize()
+        .map_err(wrap("backup.open_failed"))?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let path = path.trim_start_matches("//?/");
+    let mut uri = String::from("file:");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            '%' => uri.push_str("%25"),
+            '?' => uri.push_str("%3F"),
+            '#' => uri.push_str("%23"),
+            c => uri.push(c),
+        }
+    }
+    uri.push_str("?mode=ro&immutable=1");
+
+    let conn = Connection::open_with_flags(
+        uri,
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(wrap("backup.open_failed"))?;
+    conn.busy_timeout(db::BUSY_TIMEOUT)
+        .and_then(|_| conn.execute_batch("PRAGMA query_only = ON;"))
+        .map_err(wrap("backup.open_failed"))?;
+    Ok(conn)
+}
+
+fn load_backup_notes(conn: &Connection) -> AppResult<BTreeMap<i64, BackupNote>> {
+    let mut stmt = conn
+        .prepare("SELECT id, title, content, COALESCE(updated_at, '') FROM notes")
+        .map_err(wrap("backup.open_failed"))?;
+    let notes = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                BackupNote {
+                    title: row.get(1)?,
+                    content: row.get(2)?,
+                    updated_at: row.get(3)?,
+                },
+            ))
+        })
+        .and_then(|rows| rows.collect::<Result<_, _>>())
+        .map_err(wrap("backup.open_failed"))?;
+    Ok(notes)
+}
+
+// 按行的多重集合比较，得到新增和删除的行数（不考虑行的顺序）
+fn line_changes(old: &str, new: &str) -> (usize, usize) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    counts.values().fold((0, 0), |(added, removed), &n| {
+        if n > 0 {
+            (added + n as usize, removed)
+        } else {
+            (added, removed + n.unsigned_abs())
+        }
+    })
+}
+
+fn title_or_untitled(title: &str, untitled: &str) -> String {
+    let title = title.trim().replace('\n', " ");
+    if title.is_empty() {
+        untitled.to_string()
+    } else {
+        title
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// 比较两个备份中的笔记，写出 Markdown 格式的变更记录；只读打开备份，不访问当前数据库
+#[tauri::command]
+pub async fn diff_backups(
+    old_path: String,
+    new_path: String,
+    file_path: String,
+) -> AppResult<BackupDiff> {
+    let old = load_backup_notes(&open_backup(&old_path)?)?;
+    let new = load_backup_notes(&open_backup(&new_path)?)?;
+
+    let added: Vec<(&i64, &BackupNote)> =
+        new.iter().filter(|(id, _)| !old.contains_key(id)).collect();
+    let removed: Vec<(&i64, &BackupNote)> =
+        old.iter().filter(|(id, _)| !new.contains_key(id)).collect();
+    let modified: Vec<(&i64, &BackupNote, &BackupNote)> = new
+        .iter()
+        .filter_map(|(id, note)| old.get(id).map(|before| (id, before, note)))
+        .filter(|(_, before, after)| before.title != after.title || before.content != after.content)
+        .collect();
+
+    let untitled = t("export.untitled", &[]);
+    let mut markdown = format!(
+        "# {}\n\n{}\n",
+        t("backup.changelog_heading", &[]),
+        t(
+            "backup.changelog_range",
+            &[("old", file_name(&old_path)), ("new", file_name(&new_path))]
+        )
+    );
+
+    if !added.is_empty() {
+        markdown.push_str(&format!(
+            "\n## {} ({})\n\n",
+            t("backup.changelog_added", &[]),
+            added.len()
+        ));
+        for (id, note) in &added {
+            markdown.push_str(&format!(
+                "- [{}](note://{}) · {}\n",
+                title_or_untitled(&note.title, &untitled),
+                id,
+                t(
+                    "backup.changelog_lines",
+                    &[("count", note.content.lines().count().to_string())]
+                )
+            ));
+        }
+    }
+
+    if !removed.is_empty() {
+        markdown.push_str(&format!(
+            "\n## {} ({})\n\n",
+            t("backup.changelog_removed", &[]),
+            removed.len()
+        ));
+        for (_, note) in &removed {
+            markdown.push_str(&format!(
+                "- {}\n",
+                title_or_untitled(&note.title, &untitled)
+            ));
+        }
+    }
+
+    if !modified.is_empty() {
+        markdown.push_str(&format!(
+            "\n## {} ({})\n\n",
+            t("backup.changelog_modified", &[]),
+            modified.len()
+        ));
+        for (id, before, after) in &modified {
+            let mut line = format!(
+                "- [{}](note://{})",
+                title_or_untitled(&after.title, &untitled),
+                id
+            );
+            if before.title != after.title {
+                line.push_str(&format!(
+                    " · {}",
+                    t(
+                        "backup.changelog_renamed",
+                        &[("title", title_or_untitled(&before.title, &untitled))]
+                    )
+                ));
+            }
+            if before.content != after.content {
+                let (plus, minus) = line_changes(&before.content, &after.content);
+                line.push_str(&format!(" · +{} / -{}", plus, minus));
+            }
+            if !after.updated_at.is_empty() {
+                line.push_str(&format!(" · {}", after.updated_at));
+            }
+            line.push('\n');
+            markdown.push_str(&line);
+        }
+    }
+
+    if added.is_empty() && removed.is_empty() && modified.is_empty() {
+        markdown.push_str(&format!("\n{}\n", t("backup.changelog_no_changes", &[])));
+    }
+
+    fs::write(&file_path, markdown).map_err(wrap("export.failed"))?;
+    log::info!(
+        "已生成备份变更记录：新增 {}，删除 {}，修改 {}",
+        added.len(),
+        removed.len(),
+        modified.len()
+    );
+
+    Ok(BackupDiff {
+        added: added.len(),
+        removed: removed.len(),
+        modified: modified.len(),
+    })
+}
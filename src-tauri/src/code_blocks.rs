@@ -0,0 +1,310 @@
+use crate::i18n::t;
+use crate::outline::decode_entities;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+static HTML_CODE_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<pre(?:\s[^>]*)?>\s*<code([^>]*)>(.*?)</code>\s*</pre>").unwrap()
+});
+static HTML_LANGUAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)class\s*=\s*"[^"]*\blang(?:uage)?-([\w+-]+)"#).unwrap());
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+// 行尾的块标量指示符，如 "key: |"、"- >-"
+static BLOCK_SCALAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s:-])[|>][1-9]?[-+]?[1-9]?$").unwrap());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    // 没有标注语言或该语言没有检查器
+    NotValidated,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CodeBlockError {
+    pub message: String,
+    // 在整篇笔记中的行号，从 1 开始
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CodeBlockCheck {
+    pub language: Option<String>,
+    // 代码块开始所在行，从 1 开始
+    pub line: usize,
+    pub status: CheckStatus,
+    pub error: Option<CodeBlockError>,
+}
+
+// 错误信息及代码块内从 1 开始的行号、列号
+type CheckError = (String, Option<(usize, Option<usize>)>);
+
+struct CodeBlock {
+    language: Option<String>,
+    line: usize,
+    // 代码第一行所在行
+    code_line: usize,
+    code: String,
+    closed: bool,
+}
+
+// 开始围栏：最多 3 个空格缩进，3 个以上 ` 或 ~，返回围栏字符、长度和信息串
+fn open_fence(line: &str) -> Option<(char, usize, &str)> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let marker = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.len() - rest.trim_start_matches(marker).len();
+    let info = rest[len..].trim();
+    // 反引号围栏的信息串中不能再出现反引号
+    (len >= 3 && !(marker == '`' && info.contains('`'))).then_some((marker, len, info))
+}
+
+fn closes_fence(line: &str, marker: char, len: usize) -> bool {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return false;
+    }
+    let after = rest.trim_start_matches(marker);
+    rest.len() - after.len() >= len && after.trim().is_empty()
+}
+
+fn language(info: &str) -> Option<String> {
+    info.split_whitespace()
+        .next()
+        .map(|lang| lang.trim_start_matches('{').trim_start_matches('.'))
+        .filter(|lang| !lang.is_empty())
+        .map(str::to_lowercase)
+}
+
+// 收集 Markdown 围栏代码块和富文本编辑器保存的 <pre><code>，按出现顺序返回
+fn collect_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // 围栏代码块（含围栏行）内的行，其中的 HTML 不再识别
+    let mut fenced = Vec::new();
+    let mut open: Option<(char, usize, CodeBlock)> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        match open.take() {
+            Some((marker, len, block)) if closes_fence(line, marker, len) => {
+                blocks.push(CodeBlock {
+                    closed: true,
+                    ..block
+                });
+            }
+            Some((marker, len, mut block)) => {
+                block.code.push_str(line);
+                block.code.push('\n');
+                open = Some((marker, len, block));
+            }
+            None => {
+                if let Some((marker, len, info)) = open_fence(line) {
+                    open = Some((
+                        marker,
+                        len,
+                        CodeBlock {
+                            language: language(info),
+                            line: i + 1,
+                            code_line: i + 2,
+                            code: String::new(),
+                            closed: false,
+                        },
+                    ));
+                } else {
+                    fenced.push(false);
+                    continue;
+                }
+            }
+        }
+        fenced.push(true);
+    }
+    if let Some((_, _, block)) = open {
+        blocks.push(block);
+    }
+
+    for caps in HTML_CODE_BLOCK.captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        let line = content[..whole.start()].matches('\n').count() + 1;
+        if fenced.get(line - 1).copied().unwrap_or(false) {
+            continue;
+        }
+        let code = caps.get(2).unwrap();
+        blocks.push(CodeBlock {
+            language: HTML_LANGUAGE
+                .captures(&caps[1])
+                .map(|lang| lang[1].to_lowercase()),
+            line,
+            code_line: content[..code.start()].matches('\n').count() + 1,
+            // 代码高亮可能在代码中插入 <span>
+            code: decode_entities(&HTML_TAG.replace_all(code.as_str(), "")),
+            closed: true,
+        });
+    }
+
+    blocks.sort_by_key(|block| block.line);
+    blocks
+}
+
+// 字节偏移转为从 1 开始的行号和列号
+fn position(code: &str, offset: usize) -> (usize, usize) {
+    let before = &code[..offset.min(code.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+fn check_json(code: &str) -> Result<(), CheckError> {
+    serde_json::from_str::<serde_json::Value>(code)
+        .map(drop)
+        .map_err(|e| {
+            let message = e.to_string();
+            let suffix = format!(" at line {} column {}", e.line(), e.column());
+            let message = message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string();
+            (
+                message,
+                Some((e.line().max(1), Some(e.column()).filter(|c| *c > 0))),
+            )
+        })
+}
+
+fn check_toml(code: &str) -> Result<(), CheckError> {
+    code.parse::<toml::Table>().map(drop).map_err(|e| {
+        let (line, column) = e.span().map_or((1, None), |span| {
+            let (line, column) = position(code, span.start);
+            (line, Some(column))
+        });
+        // 多行信息合并为一行
+        let message = e.message().trim().lines().collect::<Vec<_>>().join(": ");
+        (message, Some((line, column)))
+    })
+}
+
+// 没有可用的 YAML 解析库，只检查常见的结构错误：Tab 缩进、未闭合的引号和不配对的 [] {}；
+// 块标量（| 和 >）的内容不检查
+fn check_yaml(code: &str) -> Result<(), CheckError> {
+    let mut brackets: Vec<(char, usize, usize)> = Vec::new();
+    let mut quote: Option<(char, usize, usize)> = None;
+    let mut block_scalar: Option<usize> = None;
+
+    for (i, line) in code.lines().enumerate() {
+        let line_no = i + 1;
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if let Some(parent) = block_scalar {
+            if line.trim().is_empty() || indent > parent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if quote.is_none() && line[..indent].contains('\t') && !line.trim().is_empty() {
+            return Err((t("code_block.yaml_tab", &[]), Some((line_no, Some(1)))));
+        }
+
+        let chars: Vec<(usize, char)> = line.char_indices().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let (offset, c) = chars[j];
+            let column = line[..offset].chars().count() + 1;
+            let prev = j.checked_sub(1).map(|k| chars[k].1);
+            match quote {
+                Some(('"', _, _)) if c == '\\' => j += 1,
+                Some(('\'', _, _)) if c == '\'' && chars.get(j + 1).map(|n| n.1) == Some('\'') => {
+                    j += 1
+                }
+                Some((open, _, _)) if c == open => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '#' if prev.is_none_or(char::is_whitespace) => break,
+                    // 只有出现在值开头的引号才是引号标量，如 it's 中的 ' 不算
+                    '"' | '\'' if prev.is_none_or(|p| p.is_whitespace() || "[{,:-".contains(p)) => {
+                        quote = Some((c, line_no, column))
+                    }
+                    '[' | '{' => brackets.push((c, line_no, column)),
+                    ']' | '}' => {
+                        let expected = if c == ']' { '[' } else { '{' };
+                        match brackets.pop() {
+                            Some((open, _, _)) if open == expected => {}
+                            _ => {
+                                return Err((
+                                    t(
+                                        "code_block.yaml_unexpected_bracket",
+                                        &[("bracket", c.to_string())],
+                                    ),
+                                    Some((line_no, Some(column))),
+                                ))
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+            }
+            j += 1;
+        }
+
+        let trimmed = line.split(" #").next().unwrap_or_default().trim_end();
+        if quote.is_none() && brackets.is_empty() && BLOCK_SCALAR.is_match(trimmed) {
+            block_scalar = Some(indent);
+        }
+    }
+
+    if let Some((_, line, column)) = quote {
+        return Err((
+            t("code_block.yaml_unclosed_quote", &[]),
+            Some((line, Some(column))),
+        ));
+    }
+    // 报告最外层未闭合的括号
+    if let Some(&(open, line, column)) = brackets.first() {
+        return Err((
+            t(
+                "code_block.yaml_unclosed_bracket",
+                &[("bracket", open.to_string())],
+            ),
+            Some((line, Some(column))),
+        ));
+    }
+    Ok(())
+}
+
+fn check(block: &CodeBlock) -> CodeBlockCheck {
+    let result = match block.language.as_deref() {
+        _ if !block.closed => Some(Err((t("code_block.unclosed_fence", &[]), None))),
+        Some("json") => Some(check_json(&block.code)),
+        Some("toml") => Some(check_toml(&block.code)),
+        Some("yaml" | "yml") => Some(check_yaml(&block.code)),
+        _ => None,
+    };
+
+    let (status, error) = match result {
+        None => (CheckStatus::NotValidated, None),
+        Some(Ok(())) => (CheckStatus::Passed, None),
+        Some(Err((message, position))) => (
+            CheckStatus::Failed,
+            Some(CodeBlockError {
+                message,
+                // 缺少结束围栏时指向开始围栏
+                line: position.map_or(block.line, |(line, _)| block.code_line + line - 1),
+                column: position.and_then(|(_, column)| column),
+            }),
+        ),
+    };
+    CodeBlockCheck {
+        language: block.language.clone(),
+        line: block.line,
+        status,
+        error,
+    }
+}
+
+// 发布前检查笔记中的代码块：JSON、TOML 完整解析，YAML 只做结构检查，其他语言返回 not_validated
+#[tauri::command]
+pub fn validate_code_blocks(content: String) -> Vec<CodeBlockCheck> {
+    collect_blocks(&content).iter().map(check).collect()
+}
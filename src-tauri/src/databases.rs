@@ -0,0 +1,126 @@
+use crate::archive;
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::read_only;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use tauri::{Emitter, Manager};
+
+// restore_database 生成的快照也是 .db 文件，不作为可切换的数据库列出
+pub const BACKUP_PREFIX: &str = "notes_backup_";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DatabaseInfo {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub active: bool,
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.chars().count() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && !name.starts_with(BACKUP_PREFIX)
+        && !archive::is_archive_stem(name);
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::new("database.invalid_name").with("name", name))
+    }
+}
+
+fn database_info(app: &tauri::AppHandle, name: &str) -> AppResult<DatabaseInfo> {
+    let path = portable::resolve_data_dir(app)?.join(format!("{}.db", name));
+    let metadata = fs::metadata(&path).map_err(wrap("database.info_failed"))?;
+
+    Ok(DatabaseInfo {
+        name: name.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        active: db::db_path(app)? == path,
+    })
+}
+
+#[tauri::command]
+pub fn list_databases(app: tauri::AppHandle) -> AppResult<Vec<DatabaseInfo>> {
+    let dir = portable::resolve_data_dir(&app)?;
+    let entries = fs::read_dir(&dir).map_err(wrap("database.list_failed"))?;
+
+    let mut databases = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "db") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if name.starts_with(BACKUP_PREFIX) || archive::is_archive_stem(name) {
+            continue;
+        }
+        databases.push(database_info(&app, name)?);
+    }
+    databases.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(databases)
+}
+
+// 前端通过该命令获取 SQL 插件应加载的数据库路径
+#[tauri::command]
+pub fn get_active_database(app: tauri::AppHandle) -> AppResult<DatabaseInfo> {
+    let name = db::active_database(&app);
+    let path = db::db_path(&app)?;
+
+    Ok(DatabaseInfo {
+        name,
+        path: path.to_string_lossy().into_owned(),
+        size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        modified: fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from),
+        active: true,
+    })
+}
+
+#[tauri::command]
+pub fn switch_database(app: tauri::AppHandle, name: String) -> AppResult<DatabaseInfo> {
+    validate_name(&name)?;
+    let path = portable::resolve_data_dir(&app)?.join(format!("{}.db", name));
+    if !path.exists() {
+        return Err(AppError::new("database.not_found").with("name", name));
+    }
+
+    // 关闭连接池时会合并 WAL，确保当前数据库的修改已落盘
+    app.state::<db::DbPool>().switch(path);
+    db::set_active_database(&app, &name)?;
+    log::info!("已切换数据库");
+
+    let info = database_info(&app, &name)?;
+    let _ = app.emit("database-switched", &info);
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn create_database(app: tauri::AppHandle, name: String) -> AppResult<DatabaseInfo> {
+    read_only::ensure_writable(&app)?;
+    validate_name(&name)?;
+    let path = portable::resolve_data_dir(&app)?.join(format!("{}.db", name));
+    if path.exists() {
+        return Err(AppError::new("database.exists").with("name", name));
+    }
+
+    let conn = db::open(&path).map_err(wrap("database.create_failed"))?;
+    db::migrate(&conn).map_err(wrap("database.init_failed"))?;
+    drop(conn);
+
+    database_info(&app, &name)
+}
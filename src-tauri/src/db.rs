@@ -0,0 +1,298 @@
+use crate::db_access;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::read_only;
+use crate::settings;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::Manager;
+
+// 标签在 GROUP_CONCAT 中使用的分隔符
+const TAG_SEPARATOR: char = '\u{1f}';
+
+const POOL_SIZE: u32 = 4;
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Rust 端共享的数据库连接池，所有功能都应通过 with_read_conn / with_write_conn 访问 notes.db
+pub struct DbPool {
+    path: RwLock<PathBuf>,
+    pool: RwLock<Option<Pool<SqliteConnectionManager>>>,
+    // 只读模式下以 SQLITE_OPEN_READ_ONLY 打开连接
+    read_only: RwLock<bool>,
+    // 同一时间只允许一个 Rust 端写连接，避免相互之间的锁竞争
+    write_lock: Mutex<()>,
+}
+
+impl DbPool {
+    pub fn new(path: PathBuf) -> Self {
+        DbPool {
+            path: RwLock::new(path),
+            pool: RwLock::new(None),
+            read_only: RwLock::new(false),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.path.read().unwrap().clone()
+    }
+
+    // 切换到另一个数据库文件，当前连接池会先关闭
+    pub fn switch(&self, path: PathBuf) {
+        self.close();
+        *self.path.write().unwrap() = path;
+    }
+
+    // 切换只读模式，当前连接池会先关闭
+    pub fn set_read_only(&self, read_only: bool) {
+        self.close();
+        *self.read_only.write().unwrap() = read_only;
+    }
+
+    // 按需创建连接池，关闭后再次访问会重新打开
+    fn get(&self) -> AppResult<Pool<SqliteConnectionManager>> {
+        if let Some(pool) = self.pool.read().unwrap().as_ref() {
+            return Ok(pool.clone());
+        }
+
+        let mut guard = self.pool.write().unwrap();
+        if let Some(pool) = guard.as_ref() {
+            return Ok(pool.clone());
+        }
+
+        let manager = if *self.read_only.read().unwrap() {
+            // 只读连接不能修改日志模式
+            SqliteConnectionManager::file(self.path())
+                .with_flags(
+                    OpenFlags::SQLITE_OPEN_READ_ONLY
+                        | OpenFlags::SQLITE_OPEN_URI
+                        | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )
+                .with_init(|conn| {
+                    conn.busy_timeout(BUSY_TIMEOUT)?;
+                    conn.execute_batch("PRAGMA query_only = ON;")
+                })
+        } else {
+            SqliteConnectionManager::file(self.path()).with_init(|conn| {
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+            })
+        };
+        let pool = Pool::builder()
+            .max_size(POOL_SIZE)
+            .min_idle(Some(0))
+            .build(manager)
+            .map_err(wrap("db.open_failed"))?;
+        *guard = Some(pool.clone());
+
+        Ok(pool)
+    }
+
+    // 关闭连接池（退出前、恢复或删除数据库文件前调用）
+    pub fn close(&self) {
+        if let Some(pool) = self.pool.write().unwrap().take() {
+            if let Ok(conn) = pool.get() {
+                let _ = conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);");
+            }
+        }
+    }
+}
+
+pub const DEFAULT_DATABASE: &str = "notes";
+
+// 读取上次选择的数据库名称，默认为 notes
+pub fn active_database(app: &tauri::AppHandle) -> String {
+    settings::get::<String>(app, settings::ACTIVE_DATABASE)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_DATABASE.to_string())
+}
+
+pub fn set_active_database(app: &tauri::AppHandle, name: &str) -> AppResult<()> {
+    settings::set(app, settings::ACTIVE_DATABASE, name)
+}
+
+// 当前使用的数据库文件路径
+pub fn db_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    if let Some(pool) = app.try_state::<DbPool>() {
+        return Ok(pool.path());
+    }
+    active_db_path(app)
+}
+
+// 按设置中选择的数据库计算路径，连接池尚未创建或切换配置时使用
+pub fn active_db_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(portable::resolve_data_dir(app)?.join(format!("{}.db", active_database(app))))
+}
+
+// 在连接池之外单独打开数据库，同样设置忙等待超时
+pub fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+// 与前端 createTables 保持一致的表结构
+pub fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS categories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT NOT NULL DEFAULT '#3B82F6',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT NOT NULL DEFAULT '#6B7280'
+        );
+        CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL DEFAULT '',
+            editor_type TEXT NOT NULL DEFAULT 'tiptap',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            category_id INTEGER,
+            is_pinned BOOLEAN DEFAULT FALSE,
+            is_favorited BOOLEAN DEFAULT FALSE,
+            FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+        );
+        CREATE TABLE IF NOT EXISTS note_tags (
+            note_id INTEGER,
+            tag_id INTEGER,
+            PRIMARY KEY (note_id, tag_id),
+            FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+        );",
+    )?;
+
+    let category_count: i64 =
+        conn.query_row("SELECT COUNT(*) FROM categories", [], |row| row.get(0))?;
+    if category_count == 0 {
+        for (name, color) in [
+            ("默认", "#3B82F6"),
+            ("工作", "#10B981"),
+            ("个人", "#F59E0B"),
+            ("学习", "#8B5CF6"),
+        ] {
+            conn.execute(
+                "INSERT OR IGNORE INTO categories (name, color) VALUES (?1, ?2)",
+                [name, color],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// 删除数据库对应的 -wal / -shm 文件
+pub fn remove_wal_files(db_path: &Path) -> AppResult<()> {
+    for suffix in ["-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        let path = PathBuf::from(path);
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(wrap("db.remove_wal_failed"))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn with_read_conn<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> AppResult<T> {
+    let state = app.state::<DbPool>();
+    if !state.path().exists() {
+        return Err(AppError::new("db.not_found"));
+    }
+
+    let conn = state.get()?.get().map_err(wrap("db.connection_failed"))?;
+    f(&conn).map_err(db_access::query_error(state.path()))
+}
+
+pub fn with_write_conn<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> AppResult<T> {
+    read_only::ensure_writable(app)?;
+    let state = app.state::<DbPool>();
+    if !state.path().exists() {
+        return Err(AppError::new("db.not_found"));
+    }
+
+    let _guard = state.write_lock.lock().unwrap();
+    let mut conn = state.get()?.get().map_err(wrap("db.connection_failed"))?;
+    f(&mut conn).map_err(db_access::query_error(state.path()))
+}
+
+// 读取全部笔记，结构与前端传入的 notes_json 一致
+pub fn load_notes(app: &tauri::AppHandle) -> AppResult<Vec<Value>> {
+    with_read_conn(app, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT n.id, n.title, n.content, n.editor_type, n.created_at, n.updated_at,
+                    n.category_id, n.is_pinned, n.is_favorited,
+                    (SELECT GROUP_CONCAT(t.name, char(31))
+                       FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+                      WHERE nt.note_id = n.id) AS tags
+               FROM notes n
+              ORDER BY n.id",
+        )?;
+
+        let notes = stmt
+            .query_map([], |row| {
+                let tags: Option<String> = row.get(9)?;
+                let tags: Vec<&str> = tags
+                    .as_deref()
+                    .map(|t| t.split(TAG_SEPARATOR).collect())
+                    .unwrap_or_default();
+                Ok(json!({
+                    "id": row.get::<_, i64>(0)?,
+                    "title": row.get::<_, String>(1)?,
+                    "content": row.get::<_, String>(2)?,
+                    "editor_type": row.get::<_, String>(3)?,
+                    "created_at": row.get::<_, Option<String>>(4)?,
+                    "updated_at": row.get::<_, Option<String>>(5)?,
+                    "category_id": row.get::<_, Option<i64>>(6)?,
+                    "is_pinned": row.get::<_, Option<bool>>(7)?.unwrap_or(false),
+                    "is_favorited": row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+                    "tags": tags,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(notes)
+    })
+}
+
+// 插入一条富文本笔记，返回与 load_notes 结构一致的数据
+pub fn insert_note(conn: &Connection, title: &str, content: &str) -> rusqlite::Result<Value> {
+    conn.execute(
+        "INSERT INTO notes (title, content, editor_type) VALUES (?1, ?2, 'tiptap')",
+        [title, content],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.query_row(
+        "SELECT created_at, updated_at FROM notes WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(json!({
+                "id": id,
+                "title": title,
+                "content": content,
+                "editor_type": "tiptap",
+                "created_at": row.get::<_, Option<String>>(0)?,
+                "updated_at": row.get::<_, Option<String>>(1)?,
+                "category_id": Value::Null,
+                "is_pinned": false,
+                "is_favorited": false,
+                "tags": Vec::<String>::new(),
+            }))
+        },
+    )
+}
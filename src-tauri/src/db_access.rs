@@ -0,0 +1,265 @@
+use crate::db;
+use crate::error::AppError;
+use crate::i18n::t;
+use crate::read_only;
+use rusqlite::ErrorCode;
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+
+// 数据库被占用时建议的重试间隔
+const RETRY_AFTER_SECONDS: u64 = 5;
+
+// 同步盘会在同步时短暂锁定文件，路径中出现这些目录名时提示用户
+const CLOUD_FOLDERS: &[(&str, &str)] = &[
+    ("onedrive", "OneDrive"),
+    ("dropbox", "Dropbox"),
+    ("icloud drive", "iCloud Drive"),
+    ("icloud~", "iCloud Drive"),
+    ("mobile documents", "iCloud Drive"),
+    ("google drive", "Google Drive"),
+    ("googledrive", "Google Drive"),
+    ("my drive", "Google Drive"),
+    ("box", "Box"),
+    ("pcloud", "pCloud"),
+    ("nutstore", "坚果云"),
+    ("坚果云", "坚果云"),
+    ("baidunetdisk", "百度网盘"),
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FileHolder {
+    pub pid: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessStatus {
+    Ok,
+    Busy,
+    ReadOnly,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessReport {
+    pub path: String,
+    pub status: AccessStatus,
+    pub read_latency_ms: Option<u64>,
+    pub write_latency_ms: Option<u64>,
+    pub cloud_folder: Option<String>,
+    // 打开了数据库或其 -wal / -shm 文件的其他进程，只在 Linux 和 macOS 上检测
+    pub holders: Vec<FileHolder>,
+    pub error: Option<String>,
+}
+
+pub fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+pub fn cloud_folder(path: &Path) -> Option<&'static str> {
+    path.components().find_map(|component| {
+        let Component::Normal(name) = component else {
+            return None;
+        };
+        let name = name.to_string_lossy().to_lowercase();
+        CLOUD_FOLDERS
+            .iter()
+            .find(|(folder, _)| name == *folder || name.starts_with(&format!("{} ", folder)))
+            .map(|(_, service)| *service)
+    })
+}
+
+fn related_files(db_path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![db_path.to_path_buf()];
+    for suffix in ["-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        paths.push(PathBuf::from(path));
+    }
+    paths
+        .into_iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect()
+}
+
+// 遍历 /proc/<pid>/fd 查找打开了这些文件的进程
+#[cfg(target_os = "linux")]
+fn holders(db_path: &Path) -> Vec<FileHolder> {
+    let files = related_files(db_path);
+    let own_pid = std::process::id();
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut holders = Vec::new();
+    for process in processes.flatten() {
+        let Some(pid) = process
+            .file_name()
+            .to_str()
+            .and_then(|pid| pid.parse().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        let open = fds
+            .flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| files.contains(&target)));
+        if open {
+            let name = std::fs::read_to_string(process.path().join("comm")).unwrap_or_default();
+            holders.push(FileHolder {
+                pid,
+                name: name.trim().to_string(),
+            });
+        }
+    }
+    holders
+}
+
+// lsof -F pc 按行输出 p<pid> 和 c<命令名>
+#[cfg(target_os = "macos")]
+fn holders(db_path: &Path) -> Vec<FileHolder> {
+    let files = related_files(db_path);
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let Ok(output) = std::process::Command::new("lsof")
+        .args(["-F", "pc", "--"])
+        .args(&files)
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    let own_pid = std::process::id();
+    let mut holders: Vec<FileHolder> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|pid| pid.parse().ok()) {
+            holders.push(FileHolder {
+                pid,
+                name: String::new(),
+            });
+        } else if let (Some(name), Some(holder)) = (line.strip_prefix('c'), holders.last_mut()) {
+            holder.name = name.to_string();
+        }
+    }
+    holders.retain(|holder| holder.pid != own_pid);
+    holders
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn holders(_db_path: &Path) -> Vec<FileHolder> {
+    Vec::new()
+}
+
+fn hints(db_path: &Path) -> String {
+    let mut hints = Vec::new();
+    let holders = holders(db_path);
+    if !holders.is_empty() {
+        let processes: Vec<String> = holders
+            .iter()
+            .map(|holder| format!("{} ({})", holder.name, holder.pid))
+            .collect();
+        hints.push(t(
+            "db.busy_hint_process",
+            &[("processes", processes.join(", "))],
+        ));
+    }
+    if let Some(service) = cloud_folder(db_path) {
+        hints.push(t("db.busy_hint_cloud", &[("service", service.to_string())]));
+    }
+    if hints.is_empty() {
+        hints.push(t("db.busy_hint_unknown", &[]));
+    }
+    hints.join(" ")
+}
+
+// 数据库被其他进程或同步盘锁定时给出可能的原因和重试建议，其他错误仍为 db.query_failed
+pub fn query_error(db_path: PathBuf) -> impl FnOnce(rusqlite::Error) -> AppError {
+    move |e| {
+        if is_busy(&e) {
+            log::warn!("数据库被占用: {}", e);
+            AppError::new("db.busy")
+                .with("retry_after", RETRY_AFTER_SECONDS)
+                .with("hint", hints(&db_path))
+        } else {
+            AppError::new("db.query_failed").with("error", e)
+        }
+    }
+}
+
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+// 设置中“排查问题”使用：分别测量读取和写入的耗时，写入在临时表中进行并回滚，
+// BEGIN IMMEDIATE 会获取主库的写锁，因此能发现其他进程的占用
+#[tauri::command]
+pub async fn diagnose_database_access(app: tauri::AppHandle) -> AccessReport {
+    let path = db::db_path(&app).unwrap_or_default();
+    let mut report = AccessReport {
+        path: path.to_string_lossy().into_owned(),
+        status: AccessStatus::Ok,
+        read_latency_ms: None,
+        write_latency_ms: None,
+        cloud_folder: cloud_folder(&path).map(str::to_string),
+        holders: holders(&path),
+        error: None,
+    };
+
+    let start = Instant::now();
+    let read = db::with_read_conn(&app, |conn| {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+    });
+    if let Err(e) = read {
+        report.status = if e.code == "db.busy" {
+            AccessStatus::Busy
+        } else {
+            AccessStatus::Error
+        };
+        report.error = Some(e.message());
+        return report;
+    }
+    report.read_latency_ms = Some(elapsed_ms(start));
+
+    if read_only::is_enabled(&app) {
+        report.status = AccessStatus::ReadOnly;
+        return report;
+    }
+
+    let start = Instant::now();
+    let write = db::with_write_conn(&app, |conn| {
+        conn.execute_batch("CREATE TEMP TABLE IF NOT EXISTS access_probe (value INTEGER)")?;
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        tx.execute("INSERT INTO temp.access_probe (value) VALUES (1)", [])?;
+        tx.rollback()
+    });
+    match write {
+        Ok(()) => report.write_latency_ms = Some(elapsed_ms(start)),
+        Err(e) => {
+            report.status = if e.code == "db.busy" {
+                AccessStatus::Busy
+            } else {
+                AccessStatus::Error
+            };
+            report.error = Some(e.message());
+        }
+    }
+    log::info!(
+        "数据库访问诊断: 读取 {:?} ms，写入 {:?} ms",
+        report.read_latency_ms,
+        report.write_latency_ms
+    );
+    report
+}
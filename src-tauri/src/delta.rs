@@ -0,0 +1,142 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::transfer::sha256_hex;
+use crate::{portable, updater};
+use base64::Engine;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri_plugin_updater::Update;
+
+// 发布清单中的一个补丁：bsdiff 格式并经 zstd 压缩，将 from 版本的安装包转换为 to 版本
+#[derive(Clone, Debug, Deserialize)]
+struct Patch {
+    from: String,
+    to: String,
+    url: String,
+    from_sha256: String,
+    to_sha256: String,
+}
+
+fn updates_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    let dir = portable::root_data_dir(app)?.join("updates");
+    fs::create_dir_all(&dir).map_err(wrap("update.dir_failed"))?;
+    Ok(dir)
+}
+
+// 安装后仍保留在磁盘上的更新包：Linux 上为 AppImage 本身（运行时的可执行文件在挂载目录中，不能使用）；
+// Windows 的 msi / nsis 安装程序和 macOS 的 .app.tar.gz 安装后不再保留，只能使用缓存
+fn installed_artifact() -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        std::env::var_os("APPIMAGE").map(PathBuf::from)
+    } else {
+        None
+    }
+}
+
+// 当前版本的安装包：优先使用上次更新时缓存的安装包；都没有时返回 None，直接完整下载
+fn current_artifact(app: &tauri::AppHandle, version: &str) -> Option<Vec<u8>> {
+    let cached = updates_dir(app).ok()?.join(format!("{}.artifact", version));
+    fs::read(cached)
+        .ok()
+        .or_else(|| installed_artifact().and_then(|path| fs::read(path).ok()))
+}
+
+// 缓存本次安装的安装包，作为下次打补丁的基础
+pub fn cache_artifact(app: &tauri::AppHandle, version: &str, bytes: &[u8]) -> AppResult<()> {
+    let dir = updates_dir(app)?;
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    fs::write(dir.join(format!("{}.artifact", version)), bytes).map_err(wrap("update.cache_failed"))
+}
+
+// 在发布清单中查找从当前版本到目标版本的补丁链
+fn patch_chain(update: &Update) -> Option<Vec<Patch>> {
+    let patches = update.raw_json["platforms"][&update.target]
+        .get("patches")
+        .or_else(|| update.raw_json.get("patches"))?;
+    let patches: Vec<Patch> = serde_json::from_value(patches.clone()).ok()?;
+
+    let mut chain = Vec::new();
+    let mut version = update.current_version.clone();
+    while version != update.version {
+        let next = patches.iter().find(|p| p.from == version)?;
+        if chain.len() >= patches.len() {
+            return None;
+        }
+        version = next.to.clone();
+        chain.push(next.clone());
+    }
+
+    (!chain.is_empty()).then_some(chain)
+}
+
+fn decode_base64(value: &str) -> AppResult<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(wrap("update.signature_invalid"))?;
+    String::from_utf8(bytes).map_err(wrap("update.signature_invalid"))
+}
+
+// 与完整下载相同，使用配置中的公钥校验补丁还原出的安装包
+fn verify_signature(app: &tauri::AppHandle, data: &[u8], signature: &str) -> AppResult<()> {
+    let pubkey = updater::pubkey(app).ok_or_else(|| AppError::new("update.pubkey_missing"))?;
+
+    let public_key = minisign_verify::PublicKey::decode(&decode_base64(&pubkey)?)
+        .map_err(wrap("update.signature_invalid"))?;
+    let signature = minisign_verify::Signature::decode(&decode_base64(signature)?)
+        .map_err(wrap("update.signature_invalid"))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(wrap("update.signature_invalid"))
+}
+
+async fn download_patch(url: &str) -> AppResult<Vec<u8>> {
+    let response = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(wrap("update.patch_download_failed"))?;
+    let compressed = response
+        .bytes()
+        .await
+        .map_err(wrap("update.patch_download_failed"))?;
+    zstd::decode_all(compressed.as_ref()).map_err(wrap("update.patch_decompress_failed"))
+}
+
+// 尝试通过补丁链得到新版本安装包；没有可用的补丁链或本机没有当前版本的安装包时返回 Ok(None)，
+// 由调用方直接完整下载，不算回退
+pub async fn try_download(
+    app: &tauri::AppHandle,
+    update: &Update,
+    mut on_progress: impl FnMut(usize, usize),
+) -> AppResult<Option<(Vec<u8>, usize)>> {
+    let Some(chain) = patch_chain(update) else {
+        return Ok(None);
+    };
+    let Some(mut artifact) = current_artifact(app, &update.current_version) else {
+        return Ok(None);
+    };
+
+    for (i, patch) in chain.iter().enumerate() {
+        if !sha256_hex(&artifact).eq_ignore_ascii_case(&patch.from_sha256) {
+            return Err(AppError::new("update.patch_base_mismatch").with("version", &patch.from));
+        }
+
+        let diff = download_patch(&patch.url).await?;
+        let mut patched = Vec::new();
+        bsdiff::patch(&artifact, &mut diff.as_slice(), &mut patched)
+            .map_err(wrap("update.patch_apply_failed"))?;
+
+        if !sha256_hex(&patched).eq_ignore_ascii_case(&patch.to_sha256) {
+            return Err(AppError::new("update.patch_result_mismatch").with("version", &patch.to));
+        }
+        artifact = patched;
+        on_progress(i + 1, chain.len());
+    }
+
+    verify_signature(app, &artifact, &update.signature)?;
+
+    Ok(Some((artifact, chain.len())))
+}
@@ -0,0 +1,244 @@
+use crate::error::{wrap, AppResult};
+use crate::portable;
+use crate::{db, logging};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use tauri::{Emitter, Manager};
+use zip::write::SimpleFileOptions;
+
+pub const LAST_PANIC_FILE: &str = "last_panic.txt";
+// 启动时检测到的崩溃报告改名保存，避免每次启动都重复提示
+const REPORTED_PANIC_FILE: &str = "last_panic.reported.txt";
+const SETTINGS_FILE: &str = "settings.json";
+const REDACTED: &str = "[REDACTED]";
+
+// 键名包含这些词的设置项一律打码，笔记正文和标题也不允许出现在诊断包中
+const SENSITIVE_KEYS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "key",
+    "auth",
+    "credential",
+    "cookie",
+    "content",
+    "title",
+    "note",
+];
+
+static SECRET_PAIR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b(token|secret|password|passwd|api[_-]?key|authorization|cookie|bearer)\b(["']?\s*[:=]?\s*)(?:bearer\s+)?("[^"]*"|'[^']*'|\S+)"#,
+    )
+    .unwrap()
+});
+static URL_CREDENTIALS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"://[^/\s:@]+:[^/\s@]+@").unwrap());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashReport {
+    pub time: String,
+    pub message: String,
+}
+
+// 上次运行崩溃时留下的报告，页面加载完成后通知前端一次
+#[derive(Default)]
+pub struct CrashState(pub Mutex<Option<CrashReport>>);
+
+pub fn redact_text(text: &str) -> String {
+    let text = SECRET_PAIR.replace_all(text, format!("$1$2{}", REDACTED).as_str());
+    URL_CREDENTIALS
+        .replace_all(&text, format!("://{}@", REDACTED).as_str())
+        .into_owned()
+}
+
+pub fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_KEYS.iter().any(|k| key.contains(k)) {
+                    *value = json!(REDACTED);
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        Value::String(s) => *s = redact_text(s),
+        _ => {}
+    }
+}
+
+// 由 panic hook 调用，进程退出前把崩溃信息写入日志目录
+pub fn write_panic_report(dir: &Path, message: &str, backtrace: &str) {
+    let report = format!(
+        "time: {}\nversion: {}\nmessage: {}\n\nbacktrace:\n{}\n",
+        chrono::Utc::now().to_rfc3339(),
+        env!("CARGO_PKG_VERSION"),
+        message,
+        backtrace
+    );
+    let _ = fs::create_dir_all(dir);
+    let _ = fs::write(dir.join(LAST_PANIC_FILE), report);
+}
+
+fn parse_panic_report(report: &str) -> CrashReport {
+    let field = |name: &str| {
+        report
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .unwrap_or("")
+            .to_string()
+    };
+    CrashReport {
+        time: field("time: "),
+        message: redact_text(&field("message: ")),
+    }
+}
+
+// 启动时检查上次运行是否崩溃
+pub fn detect_last_crash(app: &tauri::AppHandle) -> AppResult<()> {
+    let dir = logging::log_dir(app)?;
+    let path = dir.join(LAST_PANIC_FILE);
+    let Ok(report) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+
+    fs::rename(&path, dir.join(REPORTED_PANIC_FILE))
+        .map_err(wrap("diagnostics.save_crash_failed"))?;
+    log::warn!("检测到上次运行异常退出");
+    *app.state::<CrashState>().0.lock().unwrap() = Some(parse_panic_report(&report));
+
+    Ok(())
+}
+
+pub fn emit_last_crash(app: &tauri::AppHandle) {
+    let report = app.state::<CrashState>().0.lock().unwrap().take();
+    if let Some(report) = report {
+        let _ = app.emit("app-crashed-last-run", report);
+    }
+}
+
+// 只统计数量和文件信息，不读取任何笔记内容
+fn database_stats(app: &tauri::AppHandle) -> Value {
+    let path = match db::db_path(app) {
+        Ok(path) => path,
+        Err(e) => return json!({ "error": e }),
+    };
+    let size = fs::metadata(&path).map(|m| m.len()).ok();
+
+    let stats = db::with_read_conn(app, |conn| {
+        let count = |table: &str| -> rusqlite::Result<i64> {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+        };
+        let pragma = |name: &str| -> rusqlite::Result<Value> {
+            conn.query_row(&format!("PRAGMA {}", name), [], |row| {
+                Ok(match row.get_ref(0)? {
+                    rusqlite::types::ValueRef::Integer(i) => json!(i),
+                    rusqlite::types::ValueRef::Text(t) => json!(String::from_utf8_lossy(t)),
+                    _ => Value::Null,
+                })
+            })
+        };
+        Ok(json!({
+            "notes": count("notes")?,
+            "categories": count("categories")?,
+            "tags": count("tags")?,
+            "note_tags": count("note_tags")?,
+            "page_size": pragma("page_size")?,
+            "page_count": pragma("page_count")?,
+            "freelist_count": pragma("freelist_count")?,
+            "journal_mode": pragma("journal_mode")?,
+            "user_version": pragma("user_version")?,
+        }))
+    });
+
+    match stats {
+        Ok(mut stats) => {
+            stats["file_size"] = json!(size);
+            stats
+        }
+        Err(e) => json!({ "file_size": size, "error": e }),
+    }
+}
+
+fn system_info(app: &tauri::AppHandle) -> Value {
+    json!({
+        "app_name": app.package_info().name,
+        "app_version": app.package_info().version.to_string(),
+        "tauri_version": tauri::VERSION,
+        "os": tauri_plugin_os::platform(),
+        "os_type": tauri_plugin_os::type_().to_string(),
+        "os_version": tauri_plugin_os::version().to_string(),
+        "arch": tauri_plugin_os::arch(),
+        "locale": tauri_plugin_os::locale(),
+        "log_level": log::max_level().to_string(),
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[tauri::command]
+pub async fn create_diagnostic_bundle(
+    app: tauri::AppHandle,
+    dest_path: String,
+) -> AppResult<String> {
+    let file = File::create(&dest_path).map_err(wrap("diagnostics.create_failed"))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut add = |name: &str, content: &str| -> AppResult<()> {
+        zip.start_file(name, options)
+            .map_err(wrap("diagnostics.write_failed"))?;
+        zip.write_all(content.as_bytes())
+            .map_err(wrap("diagnostics.write_failed"))
+    };
+
+    // 日志文件逐行打码后写入
+    let log_dir = logging::log_dir(&app)?;
+    for name in logging::log_files() {
+        if let Ok(content) = fs::read_to_string(log_dir.join(&name)) {
+            let redacted: Vec<String> = content.lines().map(redact_text).collect();
+            add(&format!("logs/{}", name), &redacted.join("\n"))?;
+        }
+    }
+
+    let settings_path = portable::resolve_data_dir(&app)?.join(SETTINGS_FILE);
+    if let Ok(content) = fs::read_to_string(&settings_path) {
+        let settings = match serde_json::from_str::<Value>(&content) {
+            Ok(mut settings) => {
+                redact_json(&mut settings);
+                serde_json::to_string_pretty(&settings).unwrap_or_default()
+            }
+            // 无法解析时不写入原文，避免泄露
+            Err(_) => json!({ "error": "unparseable" }).to_string(),
+        };
+        add(SETTINGS_FILE, &settings)?;
+    }
+
+    let stats = serde_json::to_string_pretty(&database_stats(&app)).unwrap_or_default();
+    add("database_stats.json", &stats)?;
+
+    let system = serde_json::to_string_pretty(&system_info(&app)).unwrap_or_default();
+    add("system.json", &system)?;
+
+    let panic_report = [LAST_PANIC_FILE, REPORTED_PANIC_FILE]
+        .iter()
+        .find_map(|name| fs::read_to_string(log_dir.join(name)).ok());
+    if let Some(report) = panic_report {
+        add(LAST_PANIC_FILE, &redact_text(&report))?;
+    }
+
+    zip.finish().map_err(wrap("diagnostics.write_failed"))?;
+    log::info!("诊断包已生成");
+
+    Ok(dest_path)
+}
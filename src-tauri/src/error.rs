@@ -0,0 +1,53 @@
+use crate::i18n;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+// 后端错误：code 即消息表中的键，message 在展示或序列化时按当前语言生成
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppError {
+    pub code: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: &'static str) -> Self {
+        AppError {
+            code,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl fmt::Display) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub fn message(&self) -> String {
+        i18n::t(self.code, &self.params)
+    }
+}
+
+// 用于 map_err：把底层错误作为 {error} 参数包装成指定的错误码
+pub fn wrap<E: fmt::Display>(code: &'static str) -> impl FnOnce(E) -> AppError {
+    move |e| AppError::new(code).with("error", e)
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code)?;
+        state.serialize_field("message", &self.message())?;
+        state.end()
+    }
+}
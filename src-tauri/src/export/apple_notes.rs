@@ -0,0 +1,151 @@
+use super::directory::assign_slugs;
+use super::html::{escape_html, render_body};
+use super::mhtml::{local_image, IMG_SRC};
+use crate::attachment_protocol::content_type;
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(/?)h([1-6])(\s[^>]*)?>").unwrap());
+static CHECKBOX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)<input\b[^>]*type\s*=\s*"checkbox"[^>]*>\s*"#).unwrap());
+static CODE_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<pre><code[^>]*>(.*?)</code></pre>").unwrap());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AppleNotesExport {
+    pub notes: usize,
+    // 以 data: URI 内嵌的本地图片数
+    pub images: usize,
+    // 找不到的本地图片，保留原地址
+    pub missing: Vec<String>,
+}
+
+// 备忘录只有标题、标题、副标题三级：笔记标题用 h1，正文标题下移一级，三级以下都用 h3
+fn map_headings(body: &str) -> String {
+    HEADING
+        .replace_all(body, |caps: &Captures| {
+            let level = (caps[2].parse::<usize>().unwrap_or(1) + 1).min(3);
+            format!("<{}h{}>", &caps[1], level)
+        })
+        .into_owned()
+}
+
+// 任务列表的复选框导入后会丢失，改为 ☐ / ☑ 字符；代码改为等宽的 <tt>，删除线改为 <s>
+fn map_inline(body: &str) -> String {
+    let body = CHECKBOX.replace_all(body, |caps: &Captures| {
+        if caps[0].to_lowercase().contains("checked") {
+            "☑ "
+        } else {
+            "☐ "
+        }
+    });
+    let body = CODE_BLOCK.replace_all(&body, "<pre><tt>$1</tt></pre>");
+    body.replace("<code>", "<tt>")
+        .replace("</code>", "</tt>")
+        .replace("<del>", "<s>")
+        .replace("</del>", "</s>")
+}
+
+// 备忘录导入时不会读取相对路径的图片，本地附件转为 data: URI 内嵌
+fn embed_images(
+    body: &str,
+    data_dir: &Path,
+    cache: &mut HashMap<PathBuf, String>,
+    missing: &mut Vec<String>,
+) -> String {
+    IMG_SRC
+        .replace_all(body, |caps: &Captures| {
+            let src = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .map_or("", |m| m.as_str());
+            let path = match local_image(data_dir, src) {
+                Some(Ok(path)) => path,
+                Some(Err(_)) => {
+                    missing.push(src.to_string());
+                    return caps[0].to_string();
+                }
+                None => return caps[0].to_string(),
+            };
+            if let Some(uri) = cache.get(&path) {
+                return format!("{}\"{}\"", &caps[1], uri);
+            }
+            let Ok(data) = std::fs::read(&path) else {
+                missing.push(src.to_string());
+                return caps[0].to_string();
+            };
+            let uri = format!(
+                "data:{};base64,{}",
+                content_type(&path),
+                STANDARD.encode(data)
+            );
+            let replaced = format!("{}\"{}\"", &caps[1], uri);
+            cache.insert(path, uri);
+            replaced
+        })
+        .into_owned()
+}
+
+fn document(title: &str, notes: &[String]) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        notes.join("<hr>\n")
+    )
+}
+
+// 面向 macOS 备忘录“文件 > 导入到备忘录”的 HTML：备忘录把每个 HTML 文件导入为一条笔记，第一行作为标题。
+// path 为已存在的目录时每条笔记写成一个 <标题>.html，在导入对话框中选择该目录即可逐条导入；
+// 否则所有笔记写入同一个文件、以分隔线隔开，导入后为一条笔记，也可由快捷指令按 <hr> 拆分后逐条创建
+pub fn export(data_dir: &Path, notes: &[Value], path: &Path) -> AppResult<AppleNotesExport> {
+    let untitled = t("export.untitled", &[]);
+    let mut cache = HashMap::new();
+    let mut missing = Vec::new();
+    let rendered: Vec<(String, String)> = notes
+        .iter()
+        .map(|note| {
+            let title = note["title"]
+                .as_str()
+                .filter(|title| !title.trim().is_empty())
+                .unwrap_or(&untitled);
+            let body = render_body(note["content"].as_str().unwrap_or_default());
+            let body = map_inline(&map_headings(&body));
+            let body = embed_images(&body, data_dir, &mut cache, &mut missing);
+            (
+                title.to_string(),
+                format!("<h1>{}</h1>\n{}", escape_html(title), body),
+            )
+        })
+        .collect();
+
+    if path.is_dir() {
+        for ((title, html), slug) in rendered.iter().zip(assign_slugs(notes)) {
+            std::fs::write(
+                path.join(format!("{}.html", slug)),
+                document(title, std::slice::from_ref(html)),
+            )
+            .map_err(wrap("export.failed"))?;
+        }
+    } else {
+        let title = match rendered.as_slice() {
+            [(title, _)] => title.clone(),
+            _ => t("export.html_title", &[("count", notes.len().to_string())]),
+        };
+        let bodies: Vec<String> = rendered.into_iter().map(|(_, html)| html).collect();
+        std::fs::write(path, document(&title, &bodies)).map_err(wrap("export.failed"))?;
+    }
+
+    Ok(AppleNotesExport {
+        notes: notes.len(),
+        images: cache.len(),
+        missing,
+    })
+}
@@ -0,0 +1,91 @@
+use pdf_writer::{Buf, Content, Finish, Name, Pdf, Rect, Ref};
+
+// 骑马钉小册子的拼版顺序：页数补齐为 4 的倍数（None 为空白页），
+// 依次返回每张纸正面、反面的左右两页。双面打印时沿短边翻转，对折后即为原来的页序
+pub fn sides(pages: usize) -> Vec<[Option<usize>; 2]> {
+    let total = pages.max(1).div_ceil(4) * 4;
+    let page = |i: usize| (i < pages).then_some(i);
+    (0..total / 4)
+        .flat_map(|sheet| {
+            let front = [page(total - 1 - 2 * sheet), page(2 * sheet)];
+            let back = [page(2 * sheet + 1), page(total - 2 - 2 * sheet)];
+            [front, back]
+        })
+        .collect()
+}
+
+// 把每页内容写为表单对象，再按拼版顺序两页一组缩放、居中放到横向的纸面上，返回纸面页的 id。
+// 纸面与原页面同样大小，原页面为纵向时旋转为横向
+pub fn write_sheets(
+    pdf: &mut Pdf,
+    alloc: &mut impl FnMut() -> Ref,
+    contents: &[Buf],
+    page_size: (f32, f32),
+    page_tree_id: Ref,
+    font: (Name, Ref),
+) -> Vec<Ref> {
+    let (page_width, page_height) = page_size;
+    let sheet_width = page_width.max(page_height);
+    let sheet_height = page_width.min(page_height);
+    let half = sheet_width / 2.0;
+    let scale = (half / page_width).min(sheet_height / page_height);
+    let offset_x = (half - page_width * scale) / 2.0;
+    let offset_y = (sheet_height - page_height * scale) / 2.0;
+
+    let forms: Vec<Ref> = contents
+        .iter()
+        .map(|content| {
+            let id = alloc();
+            let mut form = pdf.form_xobject(id, content);
+            form.bbox(Rect::new(0.0, 0.0, page_width, page_height));
+            form.resources().fonts().pair(font.0, font.1);
+            form.finish();
+            id
+        })
+        .collect();
+    let names: Vec<Vec<u8>> = (0..forms.len())
+        .map(|i| format!("P{}", i + 1).into_bytes())
+        .collect();
+
+    let mut sheet_ids = Vec::new();
+    for side in sides(contents.len()) {
+        let sheet_id = alloc();
+        let content_id = alloc();
+        sheet_ids.push(sheet_id);
+
+        let mut content = Content::new();
+        for (slot, page) in side.iter().enumerate() {
+            let Some(page) = page else {
+                continue;
+            };
+            content
+                .save_state()
+                .transform([
+                    scale,
+                    0.0,
+                    0.0,
+                    scale,
+                    half * slot as f32 + offset_x,
+                    offset_y,
+                ])
+                .x_object(Name(&names[*page]))
+                .restore_state();
+        }
+        pdf.stream(content_id, &content.finish());
+
+        let mut sheet = pdf.page(sheet_id);
+        sheet
+            .media_box(Rect::new(0.0, 0.0, sheet_width, sheet_height))
+            .parent(page_tree_id)
+            .contents(content_id);
+        let mut resources = sheet.resources();
+        let mut x_objects = resources.x_objects();
+        for page in side.iter().flatten() {
+            x_objects.pair(Name(&names[*page]), forms[*page]);
+        }
+        x_objects.finish();
+        resources.finish();
+        sheet.finish();
+    }
+    sheet_ids
+}
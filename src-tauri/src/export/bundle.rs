@@ -0,0 +1,172 @@
+use super::{directory, RenderOptions};
+use crate::error::{wrap, AppError, AppResult};
+use crate::transfer;
+use crate::validation;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::{Captures, Regex};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::Path;
+use std::sync::LazyLock;
+
+static DATA_URI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"data:([\w.+-]+/[\w.+-]+);base64,([A-Za-z0-9+/]+=*)").unwrap());
+
+// 导出包的目录结构：
+//   manifest.json     格式和版本信息
+//   notes.json        完整的笔记数据，导入时使用
+//   notes/<slug>.md   每条笔记的 Markdown
+//   attachments/      从内容中提取出的内嵌附件
+const MANIFEST: &str = "manifest.json";
+const NOTES_JSON: &str = "notes.json";
+const NOTES_DIR: &str = "notes";
+const ATTACHMENTS_DIR: &str = "attachments";
+
+const BUNDLE_FORMAT: &str = "yue-notes-bundle";
+const BUNDLE_VERSION: u64 = 1;
+
+fn extension(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        _ => "bin",
+    }
+}
+
+// 把内容中内嵌的 data URI 提取为附件文件，Markdown 中改为相对路径引用；相同内容只保存一份
+fn extract_attachments(content: &str, attachments: &mut BTreeMap<String, Vec<u8>>) -> String {
+    DATA_URI
+        .replace_all(content, |caps: &Captures| {
+            let Ok(data) = STANDARD.decode(&caps[2]) else {
+                return caps[0].to_string();
+            };
+            let name = format!(
+                "{}.{}",
+                &transfer::sha256_hex(&data)[..16],
+                extension(&caps[1])
+            );
+            let path = format!("../{}/{}", ATTACHMENTS_DIR, name);
+            attachments.entry(name).or_insert(data);
+            path
+        })
+        .into_owned()
+}
+
+// 生成导出包中的全部文件，返回 (包内路径, 内容)
+pub fn entries(notes: &[Value]) -> AppResult<Vec<(String, Vec<u8>)>> {
+    let manifest = json!({
+        "format": BUNDLE_FORMAT,
+        "version": BUNDLE_VERSION,
+        "exported_at": Utc::now().to_rfc3339(),
+        "count": notes.len(),
+    });
+
+    let mut entries = vec![
+        (
+            MANIFEST.to_string(),
+            serde_json::to_vec_pretty(&manifest).map_err(wrap("export.serialize_failed"))?,
+        ),
+        (
+            NOTES_JSON.to_string(),
+            serde_json::to_vec_pretty(notes).map_err(wrap("export.serialize_failed"))?,
+        ),
+    ];
+
+    let mut attachments = BTreeMap::new();
+    for (slug, content) in directory::render_files(notes, true, &RenderOptions::default()) {
+        let content = extract_attachments(&content, &mut attachments);
+        entries.push((format!("{}/{}.md", NOTES_DIR, slug), content.into_bytes()));
+    }
+    for (name, data) in attachments {
+        entries.push((format!("{}/{}", ATTACHMENTS_DIR, name), data));
+    }
+
+    Ok(entries)
+}
+
+// 从导出包的 manifest.json 和 notes.json 中取出笔记
+pub fn notes_from_entries(
+    manifest: Option<&[u8]>,
+    notes_json: Option<&[u8]>,
+) -> AppResult<Vec<Value>> {
+    let manifest: Value = manifest
+        .and_then(|data| serde_json::from_slice(data).ok())
+        .ok_or_else(|| AppError::new("export.bundle_invalid"))?;
+    if manifest["format"] != BUNDLE_FORMAT {
+        return Err(AppError::new("export.bundle_invalid"));
+    }
+    let version = manifest["version"].as_u64().unwrap_or(0);
+    if version > BUNDLE_VERSION {
+        return Err(AppError::new("export.bundle_unsupported_version").with("version", version));
+    }
+
+    let notes_json = notes_json.ok_or_else(|| AppError::new("export.bundle_invalid"))?;
+    validation::parse_valid_notes(&String::from_utf8_lossy(notes_json))
+}
+
+pub fn write_tar_gz(notes: &[Value], path: &Path) -> AppResult<()> {
+    let file = File::create(path).map_err(wrap("export.failed"))?;
+    let encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    let mtime = Utc::now().timestamp().max(0) as u64;
+
+    for (name, data) in entries(notes)? {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, &name, data.as_slice())
+            .map_err(wrap("export.failed"))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(wrap("export.failed"))?;
+    Ok(())
+}
+
+pub fn read_tar_gz(path: &Path) -> AppResult<Vec<Value>> {
+    let file = File::open(path).map_err(wrap("export.bundle_read_failed"))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+
+    // 只读取需要的两个文件，其余内容（Markdown、附件）导入时不使用
+    let mut manifest = None;
+    let mut notes_json = None;
+    for entry in archive
+        .entries()
+        .map_err(wrap("export.bundle_read_failed"))?
+    {
+        let mut entry = entry.map_err(wrap("export.bundle_read_failed"))?;
+        let name = entry
+            .path()
+            .map_err(wrap("export.bundle_read_failed"))?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        let target = match name.as_str() {
+            MANIFEST => &mut manifest,
+            NOTES_JSON => &mut notes_json,
+            _ => continue,
+        };
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(wrap("export.bundle_read_failed"))?;
+        *target = Some(data);
+    }
+
+    notes_from_entries(manifest.as_deref(), notes_json.as_deref())
+}
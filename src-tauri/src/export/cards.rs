@@ -0,0 +1,296 @@
+use super::booklet;
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static MARKDOWN_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*(#{1,6}\s+|>\s?|[-*+]\s+(\[[ xX]\]\s+)?)").unwrap());
+
+pub const DEFAULT_CARDS_PER_PAGE: u32 = 3;
+pub const MAX_CARDS_PER_PAGE: u32 = 4;
+
+// 标准 5x3 英寸索引卡，单位为 pt
+const CARD_WIDTH: f32 = 360.0;
+const CARD_HEIGHT: f32 = 216.0;
+// Letter 纸张
+const PAGE_SHORT: f32 = 612.0;
+const PAGE_LONG: f32 = 792.0;
+
+const PADDING: f32 = 18.0;
+const TITLE_SIZE: f32 = 14.0;
+const BODY_SIZE: f32 = 10.0;
+const BODY_LEADING: f32 = 13.0;
+
+// 使用 PDF 阅读器内置的简体中文字体，无需嵌入字体文件
+const FONT_NAME: Name = Name(b"F1");
+const BASE_FONT: Name = Name(b"STSong-Light");
+
+// ASCII 字符按半角计算宽度，其余按全角
+fn char_width(c: char) -> f32 {
+    if c.is_ascii() {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().map(char_width).sum::<f32>() * size
+}
+
+// 字体编码为 UCS-2，超出基本多文种平面的字符用 '?' 代替
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .flat_map(|c| {
+            let code = u16::try_from(c as u32).unwrap_or(b'?' as u16);
+            code.to_be_bytes()
+        })
+        .collect()
+}
+
+// 去掉 HTML 标签和常见 Markdown 标记，压缩为单段纯文本
+fn plain_text(content: &str) -> String {
+    let text = HTML_TAG.replace_all(content, " ");
+    let text = MARKDOWN_PREFIX.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 按宽度折行，英文尽量在空格处断开；超出 max_lines 时在最后一行末尾加省略号
+pub fn wrap_lines(text: &str, width: f32, size: f32, max_lines: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines: Vec<String> = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        if lines.len() == max_lines {
+            if let Some(last) = lines.last_mut() {
+                while !last.is_empty() && text_width(last, size) + size > width {
+                    last.pop();
+                }
+                last.push('…');
+            }
+            break;
+        }
+
+        let mut end = start;
+        let mut line_width = 0.0;
+        let mut break_at = None;
+        while end < chars.len() {
+            let w = char_width(chars[end]) * size;
+            if end > start && line_width + w > width {
+                break;
+            }
+            line_width += w;
+            if chars[end] == ' ' || !chars[end].is_ascii() {
+                break_at = Some(end + 1);
+            }
+            end += 1;
+        }
+
+        // 截断在英文单词中间时退回到上一个断点
+        if end < chars.len()
+            && chars[end].is_ascii_alphanumeric()
+            && chars[end - 1].is_ascii_alphanumeric()
+        {
+            if let Some(at) = break_at {
+                end = at;
+            }
+        }
+
+        lines.push(
+            chars[start..end]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        );
+        start = end;
+        while start < chars.len() && chars[start] == ' ' {
+            start += 1;
+        }
+    }
+
+    lines
+}
+
+// 每页卡片的排列方式：不超过 3 张时纵向单列，4 张时横向 2x2
+fn layout(cards_per_page: u32) -> (f32, f32, u32, u32) {
+    if cards_per_page <= 3 {
+        (PAGE_SHORT, PAGE_LONG, 1, cards_per_page)
+    } else {
+        (PAGE_LONG, PAGE_SHORT, 2, cards_per_page.div_ceil(2))
+    }
+}
+
+fn draw_card(content: &mut Content, x: f32, y: f32, title: &str, body: &str) {
+    // 虚线裁切框
+    content
+        .save_state()
+        .set_stroke_gray(0.6)
+        .set_line_width(0.5)
+        .set_dash_pattern([3.0, 3.0], 0.0)
+        .rect(x, y, CARD_WIDTH, CARD_HEIGHT)
+        .stroke()
+        .restore_state();
+
+    let inner_width = CARD_WIDTH - PADDING * 2.0;
+    let title_y = y + CARD_HEIGHT - PADDING - TITLE_SIZE;
+    let rule_y = title_y - 6.0;
+
+    content
+        .save_state()
+        .set_stroke_gray(0.3)
+        .set_line_width(0.5)
+        .move_to(x + PADDING, rule_y)
+        .line_to(x + CARD_WIDTH - PADDING, rule_y)
+        .stroke()
+        .restore_state();
+
+    let title = wrap_lines(title, inner_width, TITLE_SIZE, 1);
+    let body_top = rule_y - 6.0 - BODY_SIZE;
+    let max_lines = ((body_top - (y + PADDING)) / BODY_LEADING) as usize + 1;
+    let body = wrap_lines(body, inner_width, BODY_SIZE, max_lines);
+
+    content.begin_text();
+    content
+        .set_font(FONT_NAME, TITLE_SIZE)
+        .next_line(x + PADDING, title_y);
+    if let Some(title) = title.first() {
+        content.show(Str(&encode(title)));
+    }
+    content.end_text();
+
+    content
+        .begin_text()
+        .set_font(FONT_NAME, BODY_SIZE)
+        .set_leading(BODY_LEADING)
+        .next_line(x + PADDING, body_top);
+    for (i, line) in body.iter().enumerate() {
+        if i > 0 {
+            content.next_line_using_leading();
+        }
+        content.show(Str(&encode(line)));
+    }
+    content.end_text();
+}
+
+// booklet 为 true 时按骑马钉小册子拼版，每张纸的一面排两页
+pub fn render(notes: &[Value], cards_per_page: u32, booklet: bool) -> AppResult<Vec<u8>> {
+    if !(1..=MAX_CARDS_PER_PAGE).contains(&cards_per_page) {
+        return Err(AppError::new("export.invalid_cards_per_page").with("max", MAX_CARDS_PER_PAGE));
+    }
+
+    let (page_width, page_height, columns, rows) = layout(cards_per_page);
+    let gap_x = (page_width - CARD_WIDTH * columns as f32) / (columns + 1) as f32;
+    let gap_y = (page_height - CARD_HEIGHT * rows as f32) / (rows + 1) as f32;
+
+    let mut pdf = Pdf::new();
+    let mut next_id = Ref::new(1);
+    let mut alloc = || next_id.bump();
+    let catalog_id = alloc();
+    let page_tree_id = alloc();
+    let font_id = alloc();
+    let cid_font_id = alloc();
+    let descriptor_id = alloc();
+
+    let untitled = t("export.untitled", &[]);
+    let mut contents = Vec::new();
+    // 没有笔记时也输出一页空白页，保证生成的是合法 PDF
+    let chunks: Vec<&[Value]> = if notes.is_empty() {
+        vec![&[]]
+    } else {
+        notes.chunks(cards_per_page as usize).collect()
+    };
+
+    for chunk in chunks {
+        let mut content = Content::new();
+        for (i, note) in chunk.iter().enumerate() {
+            let column = i as u32 % columns;
+            let row = i as u32 / columns;
+            let x = gap_x + (CARD_WIDTH + gap_x) * column as f32;
+            let y = page_height - (gap_y + CARD_HEIGHT) * (row + 1) as f32;
+            let title = note["title"].as_str().unwrap_or(&untitled);
+            let body = plain_text(note["content"].as_str().unwrap_or(""));
+            draw_card(&mut content, x, y, title, &body);
+        }
+        contents.push(content.finish());
+    }
+
+    let page_ids = if booklet {
+        booklet::write_sheets(
+            &mut pdf,
+            &mut alloc,
+            &contents,
+            (page_width, page_height),
+            page_tree_id,
+            (FONT_NAME, font_id),
+        )
+    } else {
+        let mut page_ids = Vec::new();
+        for content in &contents {
+            let page_id = alloc();
+            let content_id = alloc();
+            page_ids.push(page_id);
+            pdf.stream(content_id, content);
+
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, page_width, page_height))
+                .parent(page_tree_id)
+                .contents(content_id);
+            page.resources().fonts().pair(FONT_NAME, font_id);
+            page.finish();
+        }
+        page_ids
+    };
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .count(page_ids.len() as i32)
+        .kids(page_ids);
+
+    pdf.type0_font(font_id)
+        .base_font(BASE_FONT)
+        .encoding_predefined(Name(b"UniGB-UCS2-H"))
+        .descendant_font(cid_font_id);
+    let mut cid_font = pdf.cid_font(cid_font_id);
+    cid_font
+        .subtype(CidFontType::Type0)
+        .base_font(BASE_FONT)
+        .system_info(SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"GB1"),
+            supplement: 2,
+        })
+        .font_descriptor(descriptor_id)
+        .default_width(1000.0);
+    // Adobe-GB1 中 CID 1-95 为 ASCII 字符
+    cid_font.widths().same(1, 95, 500.0);
+    cid_font.finish();
+    pdf.font_descriptor(descriptor_id)
+        .name(BASE_FONT)
+        .flags(FontFlags::SYMBOLIC)
+        .bbox(Rect::new(-25.0, -254.0, 1000.0, 880.0))
+        .italic_angle(0.0)
+        .ascent(880.0)
+        .descent(-120.0)
+        .cap_height(880.0)
+        .stem_v(93.0);
+
+    Ok(pdf.finish())
+}
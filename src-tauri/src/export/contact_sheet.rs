@@ -0,0 +1,119 @@
+use super::cards::wrap_lines;
+use super::image::{rasterize, xml_escape};
+use crate::error::{wrap, AppError, AppResult};
+use base64::Engine;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// 缩略图最长边
+const THUMB_SIZE: u32 = 200;
+const COLUMNS: usize = 4;
+const GAP: f32 = 16.0;
+const CAPTION_HEIGHT: f32 = 28.0;
+const CAPTION_SIZE: f32 = 12.0;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ContactSheet {
+    pub images: usize,
+    // 不是图片或无法解码的附件
+    pub skipped: usize,
+}
+
+struct Thumbnail {
+    name: String,
+    width: u32,
+    height: u32,
+    png: Vec<u8>,
+}
+
+fn thumbnail(path: &Path) -> Option<Thumbnail> {
+    let image = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .decode()
+        .ok()?
+        .thumbnail(THUMB_SIZE, THUMB_SIZE);
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(Thumbnail {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        width: image.width(),
+        height: image.height(),
+        png,
+    })
+}
+
+// 缩略图居中放在固定大小的格子里，文件名显示在下方，过长时截断
+fn render_svg(thumbnails: &[Thumbnail]) -> (String, f32, f32) {
+    let cell = THUMB_SIZE as f32;
+    let columns = thumbnails.len().min(COLUMNS);
+    let rows = thumbnails.len().div_ceil(COLUMNS);
+    let width = GAP + columns as f32 * (cell + GAP);
+    let height = GAP + rows as f32 * (cell + CAPTION_HEIGHT + GAP);
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="100%" height="100%" fill="#ffffff"/>"##
+    );
+    for (i, thumb) in thumbnails.iter().enumerate() {
+        let x = GAP + (i % COLUMNS) as f32 * (cell + GAP);
+        let y = GAP + (i / COLUMNS) as f32 * (cell + CAPTION_HEIGHT + GAP);
+        svg.push_str(&format!(
+            r##"<rect x="{x}" y="{y}" width="{cell}" height="{cell}" fill="#f3f4f6"/>"##
+        ));
+        svg.push_str(&format!(
+            r#"<image x="{}" y="{}" width="{}" height="{}" href="data:image/png;base64,{}"/>"#,
+            x + (cell - thumb.width as f32) / 2.0,
+            y + (cell - thumb.height as f32) / 2.0,
+            thumb.width,
+            thumb.height,
+            base64::engine::general_purpose::STANDARD.encode(&thumb.png)
+        ));
+        let caption = wrap_lines(&thumb.name, cell, CAPTION_SIZE, 1)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        svg.push_str(&format!(
+            r##"<text x="{}" y="{}" text-anchor="middle" font-family="sans-serif" font-size="{CAPTION_SIZE}" fill="#374151" xml:space="preserve">{}</text>"##,
+            x + cell / 2.0,
+            y + cell + CAPTION_HEIGHT * 0.65,
+            xml_escape(&caption)
+        ));
+    }
+    svg.push_str("</svg>");
+    (svg, width, height)
+}
+
+// 把笔记附件目录中的图片排成缩略图网格并保存为 PNG，按文件名排序
+pub fn export(attachments_dir: &Path, path: &Path) -> AppResult<ContactSheet> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(attachments_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(AppError::new("export.failed").with("error", e)),
+    };
+    files.sort();
+
+    let thumbnails: Vec<Thumbnail> = files.iter().filter_map(|path| thumbnail(path)).collect();
+    let skipped = files.len() - thumbnails.len();
+    if thumbnails.is_empty() {
+        return Err(AppError::new("export.no_images"));
+    }
+
+    let (svg, width, height) = render_svg(&thumbnails);
+    let png = rasterize(&svg, width, height, 1.0)?;
+    std::fs::write(path, png).map_err(wrap("export.failed"))?;
+
+    Ok(ContactSheet {
+        images: thumbnails.len(),
+        skipped,
+    })
+}
@@ -0,0 +1,189 @@
+use super::{ExportFormat, RenderOptions};
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 外部导出脚本放在数据根目录的 exporters/<name>/ 中，由用户自行安装，应用不会下载
+const EXPORTERS_DIR: &str = "exporters";
+const MANIFEST_FILE: &str = "manifest.json";
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+const POLL: Duration = Duration::from_millis(50);
+// 错误中附带的 stderr 末尾字符数
+const STDERR_TAIL: usize = 2000;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    display_name: String,
+    // 传给脚本的格式：markdown 或 json
+    input: String,
+    // 输出文件扩展名，供前端的保存对话框使用
+    extension: String,
+    // 目录中可执行文件的名称
+    entry: String,
+    // 在目标路径之前传给脚本的参数
+    #[serde(default)]
+    args: Vec<String>,
+    timeout_seconds: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CustomExporter {
+    pub name: String,
+    pub display_name: String,
+    pub input: String,
+    pub extension: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CustomExportOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn exporters_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(portable::root_data_dir(app)?.join(EXPORTERS_DIR))
+}
+
+// 目录名即导出器名称；入口只能是目录中的文件，不能指向其他位置
+fn load(dir: &Path) -> AppResult<(Manifest, ExportFormat)> {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let invalid = |error: &dyn std::fmt::Display| {
+        AppError::new("export.custom_invalid_manifest")
+            .with("name", &name)
+            .with("error", error)
+    };
+
+    let content = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| invalid(&e))?;
+    let manifest: Manifest = serde_json::from_str(&content).map_err(|e| invalid(&e))?;
+    let format = manifest.input.parse::<ExportFormat>()?;
+    let entry = Path::new(&manifest.entry);
+    if entry.components().count() != 1 || !dir.join(entry).is_file() {
+        return Err(invalid(&manifest.entry));
+    }
+    Ok((manifest, format))
+}
+
+pub fn list(app: &tauri::AppHandle) -> AppResult<Vec<CustomExporter>> {
+    let Ok(entries) = fs::read_dir(exporters_dir(app)?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut exporters = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        match load(&dir) {
+            Ok((manifest, _)) => exporters.push(CustomExporter {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                display_name: manifest.display_name,
+                input: manifest.input,
+                extension: manifest.extension,
+            }),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    exporters.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(exporters)
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+fn tail(text: &str) -> String {
+    let chars: Vec<char> = text.trim_end().chars().collect();
+    chars[chars.len().saturating_sub(STDERR_TAIL)..]
+        .iter()
+        .collect()
+}
+
+// 以脚本所在目录为工作目录运行，笔记通过 stdin 传入，目标路径作为最后一个参数；
+// 超时后结束进程，退出码非 0 时错误中带上 stderr 的末尾部分
+pub fn run(
+    app: &tauri::AppHandle,
+    name: &str,
+    notes: &[Value],
+    file_path: &str,
+) -> AppResult<CustomExportOutput> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    let dir = exporters_dir(app)?.join(name);
+    if !valid_name || !dir.is_dir() {
+        return Err(AppError::new("export.custom_not_found").with("name", name));
+    }
+    let (manifest, format) = load(&dir)?;
+    let input = format.render(notes, &RenderOptions::default())?;
+    let timeout = Duration::from_secs(manifest.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+
+    let mut child = Command::new(dir.join(&manifest.entry))
+        .args(&manifest.args)
+        .arg(file_path)
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(wrap("export.custom_failed"))?;
+
+    // stdin 在单独的线程中写入，避免脚本先写满输出管道时相互等待
+    let stdin = child.stdin.take();
+    let writer = thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(wrap("export.custom_failed"))? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(POLL);
+    };
+    let _ = writer.join();
+    let output = CustomExportOutput {
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+
+    match status {
+        None => Err(AppError::new("export.custom_timeout").with("seconds", timeout.as_secs())),
+        Some(status) if !status.success() => Err(AppError::new("export.custom_exit")
+            .with(
+                "code",
+                status
+                    .code()
+                    .map_or_else(|| "-".to_string(), |code| code.to_string()),
+            )
+            .with("stderr", tail(&output.stderr))),
+        Some(_) => Ok(output),
+    }
+}
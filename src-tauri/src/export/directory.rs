@@ -0,0 +1,254 @@
+use super::RenderOptions;
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::LazyLock;
+
+static NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"note://(\d+)").unwrap());
+
+// 按出现顺序提取内容中引用的笔记 id（去重）
+pub fn note_links(content: &str) -> Vec<i64> {
+    let mut seen = HashSet::new();
+    NOTE_LINK
+        .captures_iter(content)
+        .filter_map(|c| c[1].parse::<i64>().ok())
+        .filter(|id| seen.insert(*id))
+        .collect()
+}
+
+// 将标题转换为文件名安全的 slug，保留中文等 Unicode 字母数字
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+// slug 最多保留的字符数，加上目录和扩展名后仍远低于 Windows 的路径长度限制
+const MAX_SLUG_CHARS: usize = 100;
+// Windows 保留的设备名，带扩展名（如 con.md）同样无法创建
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+fn is_reserved(slug: &str) -> bool {
+    RESERVED_NAMES.contains(&slug.to_lowercase().as_str())
+}
+
+// 过长时截断，Windows 保留名追加 -note，结尾的点和空格去掉
+fn safe_slug(slug: &str) -> String {
+    let truncated: String = slug.chars().take(MAX_SLUG_CHARS).collect();
+    let slug = truncated.trim_end_matches(['-', '.', ' ']);
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else if is_reserved(slug) {
+        format!("{}-note", slug)
+    } else {
+        slug.to_string()
+    }
+}
+
+// 为每条笔记分配唯一且可安全用作文件名的 slug，重名时追加序号
+pub fn assign_slugs(notes: &[Value]) -> Vec<String> {
+    let mut used = HashSet::new();
+    let untitled = t("export.untitled", &[]);
+    notes
+        .iter()
+        .map(|note| {
+            let base = safe_slug(&slugify(note["title"].as_str().unwrap_or(&untitled)));
+            let mut slug = base.clone();
+            let mut n = 2;
+            while !used.insert(slug.clone()) {
+                slug = format!("{}-{}", base, n);
+                n += 1;
+            }
+            slug
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FilenameIssue {
+    pub note_id: Option<i64>,
+    pub title: String,
+    // 直接由标题生成的文件名
+    pub file_name: String,
+    // collision、too_long、reserved_name、trailing_dot
+    pub problems: Vec<&'static str>,
+    // 导出时实际使用的文件名
+    pub suggestion: String,
+}
+
+// 检查由标题生成的文件名：与其他笔记重名、过长、Windows 保留名、以点或空格结尾。
+// 导出时会自动改用 suggestion，前端可据此提前提示用户修改标题
+pub fn validate_filenames(notes: &[Value]) -> Vec<FilenameIssue> {
+    let untitled = t("export.untitled", &[]);
+    let slugs = assign_slugs(notes);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let bases: Vec<String> = notes
+        .iter()
+        .map(|note| {
+            let base = slugify(note["title"].as_str().unwrap_or(&untitled));
+            *counts.entry(safe_slug(&base)).or_default() += 1;
+            base
+        })
+        .collect();
+
+    notes
+        .iter()
+        .zip(bases.iter().zip(&slugs))
+        .filter_map(|(note, (base, slug))| {
+            let mut problems = Vec::new();
+            if counts.get(&safe_slug(base)).is_some_and(|count| *count > 1) {
+                problems.push("collision");
+            }
+            if base.chars().count() > MAX_SLUG_CHARS {
+                problems.push("too_long");
+            }
+            if is_reserved(base) {
+                problems.push("reserved_name");
+            }
+            if base.ends_with(['.', ' ']) {
+                problems.push("trailing_dot");
+            }
+            if problems.is_empty() {
+                return None;
+            }
+            Some(FilenameIssue {
+                note_id: note["id"].as_i64(),
+                title: note["title"].as_str().unwrap_or(&untitled).to_string(),
+                file_name: format!("{}.md", base),
+                problems,
+                suggestion: format!("{}.md", slug),
+            })
+        })
+        .collect()
+}
+
+pub fn yaml_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+pub fn yaml_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|i| yaml_string(i)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+pub fn note_tags(note: &Value) -> Vec<String> {
+    note["tags"]
+        .as_array()
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|t| t.as_str().or_else(|| t["name"].as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// front-matter 中的基本字段：标题、创建和修改时间、标签，不含首尾的 ---
+pub fn front_matter_fields(note: &Value, title: &str, options: &RenderOptions) -> String {
+    let mut fields = format!("title: {}\n", yaml_string(title));
+    for field in ["created_at", "updated_at"] {
+        if let Some(value) = note[field].as_str() {
+            fields.push_str(&format!(
+                "{}: {}\n",
+                field,
+                yaml_string(&options.timestamp(value))
+            ));
+        }
+    }
+    fields.push_str(&format!("tags: {}\n", yaml_list(&note_tags(note))));
+    fields
+}
+
+// 生成每条笔记带 YAML front-matter 的 Markdown 文件，返回 (slug, 文件内容)
+pub fn render_files(
+    notes: &[Value],
+    include_links: bool,
+    options: &RenderOptions,
+) -> Vec<(String, String)> {
+    let slugs = assign_slugs(notes);
+    let slug_by_id: HashMap<i64, &str> = notes
+        .iter()
+        .zip(&slugs)
+        .filter_map(|(note, slug)| note["id"].as_i64().map(|id| (id, slug.as_str())))
+        .collect();
+
+    let links: Vec<Vec<String>> = notes
+        .iter()
+        .map(|note| {
+            note_links(note["content"].as_str().unwrap_or(""))
+                .into_iter()
+                .filter_map(|id| slug_by_id.get(&id).map(|s| s.to_string()))
+                .collect()
+        })
+        .collect();
+
+    let mut backlinks: HashMap<&str, Vec<String>> = HashMap::new();
+    for (slug, targets) in slugs.iter().zip(&links) {
+        for target in targets {
+            backlinks
+                .entry(target.as_str())
+                .or_default()
+                .push(slug.clone());
+        }
+    }
+
+    let untitled = t("export.untitled", &[]);
+    let mut files = Vec::with_capacity(notes.len());
+    for (i, note) in notes.iter().enumerate() {
+        let title = note["title"].as_str().unwrap_or(&untitled);
+        let content = note["content"].as_str().unwrap_or("");
+
+        let mut front_matter = String::from("---\n");
+        front_matter.push_str(&front_matter_fields(note, title, options));
+        if include_links {
+            let empty = Vec::new();
+            let note_backlinks = backlinks.get(slugs[i].as_str()).unwrap_or(&empty);
+            front_matter.push_str(&format!("links: {}\n", yaml_list(&links[i])));
+            front_matter.push_str(&format!("backlinks: {}\n", yaml_list(note_backlinks)));
+        }
+        front_matter.push_str("---\n\n");
+
+        files.push((slugs[i].clone(), format!("{}{}\n", front_matter, content)));
+    }
+
+    files
+}
+
+// 每条笔记导出为一个 Markdown 文件，返回写入的文件数
+pub fn export(
+    notes: &[Value],
+    dir: &Path,
+    include_links: bool,
+    options: &RenderOptions,
+) -> AppResult<usize> {
+    std::fs::create_dir_all(dir).map_err(wrap("export.create_dir_failed"))?;
+
+    for (slug, content) in render_files(notes, include_links, options) {
+        std::fs::write(dir.join(format!("{}.md", slug)), content).map_err(wrap("export.failed"))?;
+    }
+
+    Ok(notes.len())
+}
@@ -0,0 +1,84 @@
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+// 行内链接 [text](url "title")，图片（前面带 '!'）保持不变
+static INLINE_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\]]*)\]\(\s*<?([^()\s<>]+)>?(?:\s+(?:"[^"]*"|'[^']*'))?\s*\)"#).unwrap()
+});
+
+// 打印格式中行内链接的地址会丢失，把外部链接改为脚注引用，在文档末尾统一列出地址；
+// 整个文档共用一套编号，相同地址使用同一个脚注
+#[derive(Debug, Default)]
+pub struct Footnotes {
+    urls: Vec<String>,
+}
+
+// 笔记间的 note:// 链接和页内锚点在打印后没有意义，不转换
+fn is_external(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "ftp://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+impl Footnotes {
+    fn number(&mut self, url: &str) -> usize {
+        match self.urls.iter().position(|u| u == url) {
+            Some(i) => i + 1,
+            None => {
+                self.urls.push(url.to_string());
+                self.urls.len()
+            }
+        }
+    }
+
+    fn convert_text(&mut self, text: &str) -> String {
+        INLINE_LINK
+            .replace_all(text, |caps: &Captures| {
+                let url = &caps[3];
+                if !caps[1].is_empty() || !is_external(url) {
+                    return caps[0].to_string();
+                }
+                format!("{}[^{}]", &caps[2], self.number(url))
+            })
+            .into_owned()
+    }
+
+    // 跳过代码块和行内代码
+    pub fn convert(&mut self, content: &str) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut in_fence = false;
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                result.push_str(line);
+                continue;
+            }
+            if in_fence {
+                result.push_str(line);
+                continue;
+            }
+            for (i, part) in line.split('`').enumerate() {
+                if i > 0 {
+                    result.push('`');
+                }
+                if i % 2 == 0 {
+                    result.push_str(&self.convert_text(part));
+                } else {
+                    result.push_str(part);
+                }
+            }
+        }
+        result
+    }
+
+    // 脚注定义，没有脚注时为空字符串
+    pub fn render(&self) -> String {
+        self.urls
+            .iter()
+            .enumerate()
+            .map(|(i, url)| format!("[^{}]: {}\n", i + 1, url))
+            .collect()
+    }
+}
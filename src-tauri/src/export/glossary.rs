@@ -0,0 +1,247 @@
+use super::directory::{self, assign_slugs, slugify};
+use super::{target, RenderOptions};
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::LazyLock;
+
+// 已有的链接、图片、链接定义、HTML 标签（含 <url> 自动链接）和网址，其中的文字不再加链接
+static PROTECTED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?im)!?\[[^\]]*\]\([^)]*\)|!?\[[^\]]*\]\[[^\]]*\]|\[\[[^\]]*\]\]|^\s*\[[^\]]+\]:.*$|<a\b.*?</a>|<[^>]+>|\b(?:https?|ftp|note)://\S+",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GlossaryExport {
+    pub notes: usize,
+    // 插入的链接总数
+    pub links: usize,
+    // 指向的笔记不在本次导出中的术语，不加链接
+    pub unresolved: Vec<String>,
+}
+
+struct Term {
+    pattern: Regex,
+    // 术语所在的笔记，该笔记自身不加链接
+    note_id: Option<i64>,
+    href: String,
+}
+
+// 术语表为 { "术语": 笔记 id 或释义文本 }，返回 (术语, 值) 并按长度降序排列，较长的术语优先匹配
+fn parse(glossary_json: &str) -> AppResult<Vec<(String, Value)>> {
+    let glossary: serde_json::Map<String, Value> =
+        serde_json::from_str(glossary_json).map_err(wrap("export.parse_failed"))?;
+    let mut entries: Vec<(String, Value)> = glossary
+        .into_iter()
+        .map(|(term, value)| (term.trim().to_string(), value))
+        .filter(|(term, _)| !term.is_empty())
+        .collect();
+    for (term, value) in &entries {
+        let valid =
+            value.as_i64().is_some() || value.as_str().is_some_and(|text| !text.trim().is_empty());
+        if !valid {
+            return Err(AppError::new("export.invalid_glossary").with("term", term));
+        }
+    }
+    entries.sort_by(|a, b| {
+        b.0.chars()
+            .count()
+            .cmp(&a.0.chars().count())
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    Ok(entries)
+}
+
+// 可以插入链接的区间：跳过代码块、<pre> 块、行内代码和 PROTECTED 中的内容
+fn linkable_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut in_fence = false;
+    let mut in_pre = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        let lower = line.to_lowercase();
+        if in_pre || lower.contains("<pre") {
+            in_pre = !lower.contains("</pre>");
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut pos = start;
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 0 {
+                let mut last = 0;
+                for m in PROTECTED.find_iter(part) {
+                    ranges.push(pos + last..pos + m.start());
+                    last = m.end();
+                }
+                ranges.push(pos + last..pos + part.len());
+            }
+            pos += part.len() + 1;
+        }
+    }
+    ranges.retain(|range| !range.is_empty());
+    ranges
+}
+
+// 以英文字母或数字开头、结尾的术语需要完整匹配单词，中文术语不需要
+fn at_word_boundary(content: &str, range: &Range<usize>) -> bool {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let matched = &content[range.clone()];
+    let before = content[..range.start].chars().next_back();
+    let after = content[range.end..].chars().next();
+    let joined_before = matched.starts_with(is_word) && before.is_some_and(is_word);
+    let joined_after = matched.ends_with(is_word) && after.is_some_and(is_word);
+    !joined_before && !joined_after
+}
+
+// 每个术语只链接在正文中第一次出现的位置，返回新内容和插入的链接数
+fn link_terms(content: &str, terms: &[Term], note_id: Option<i64>) -> (String, usize) {
+    let ranges = linkable_ranges(content);
+    let mut links: Vec<(Range<usize>, &str)> = Vec::new();
+    for term in terms {
+        if term.note_id.is_some() && term.note_id == note_id {
+            continue;
+        }
+        let found = ranges.iter().find_map(|range| {
+            term.pattern
+                .find_iter(&content[range.clone()])
+                .map(|m| range.start + m.start()..range.start + m.end())
+                .find(|m| {
+                    at_word_boundary(content, m)
+                        && !links
+                            .iter()
+                            .any(|(link, _)| link.start < m.end && m.start < link.end)
+                })
+        });
+        if let Some(range) = found {
+            links.push((range, &term.href));
+        }
+    }
+
+    links.sort_by_key(|(range, _)| range.start);
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for (range, href) in &links {
+        result.push_str(&content[last..range.start]);
+        result.push_str(&format!("[{}]({})", &content[range.clone()], href));
+        last = range.end;
+    }
+    result.push_str(&content[last..]);
+    (result, links.len())
+}
+
+// 已被占用时依次加上 -2、-3 ……
+fn unique_slug(base: &str, taken: &HashSet<String>) -> String {
+    (1..)
+        .map(|i| {
+            if i == 1 {
+                base.to_string()
+            } else {
+                format!("{}-{}", base, i)
+            }
+        })
+        .find(|slug| !taken.contains(slug))
+        .unwrap()
+}
+
+// 在目录导出（见 directory.rs）的基础上，把每条笔记中第一次出现的术语链接到术语表：
+// 值为笔记 id 时链接到该笔记导出的文件，为释义文本时汇总到单独的术语表文件并链接到对应条目
+pub fn export(notes: &[Value], glossary_json: &str, dir: &Path) -> AppResult<GlossaryExport> {
+    let entries = parse(glossary_json)?;
+    let slugs = assign_slugs(notes);
+    let slug_by_id: HashMap<i64, &str> = notes
+        .iter()
+        .zip(&slugs)
+        .filter_map(|(note, slug)| note["id"].as_i64().map(|id| (id, slug.as_str())))
+        .collect();
+    // 术语表文件名不与笔记重复
+    let glossary_file = unique_slug("glossary", &slugs.iter().cloned().collect());
+
+    let mut terms = Vec::new();
+    let mut definitions = Vec::new();
+    let mut anchors = HashSet::new();
+    let mut unresolved = Vec::new();
+    for (term, value) in &entries {
+        let (note_id, href) = match value.as_i64() {
+            Some(id) => match slug_by_id.get(&id) {
+                Some(slug) => (Some(id), format!("{}.md", slug)),
+                None => {
+                    unresolved.push(term.clone());
+                    continue;
+                }
+            },
+            None => {
+                let base = Some(slugify(term))
+                    .filter(|slug| !slug.is_empty())
+                    .unwrap_or_else(|| "term".to_string());
+                let anchor = unique_slug(&base, &anchors);
+                anchors.insert(anchor.clone());
+                definitions.push((term, value.as_str().unwrap_or_default(), anchor.clone()));
+                (None, format!("{}.md#{}", glossary_file, anchor))
+            }
+        };
+        let pattern = RegexBuilder::new(&regex::escape(term))
+            .case_insensitive(true)
+            .build()
+            .map_err(wrap("export.failed"))?;
+        terms.push(Term {
+            pattern,
+            note_id,
+            href,
+        });
+    }
+
+    let mut links = 0;
+    let linked: Vec<Value> = notes
+        .iter()
+        .map(|note| {
+            let mut note = note.clone();
+            let (content, count) = link_terms(
+                note["content"].as_str().unwrap_or_default(),
+                &terms,
+                note["id"].as_i64(),
+            );
+            links += count;
+            note["content"] = Value::String(content);
+            note
+        })
+        .collect();
+    directory::export(&linked, dir, false, &RenderOptions::default())?;
+
+    if !definitions.is_empty() {
+        definitions.sort_by_key(|(term, _, _)| term.to_lowercase());
+        let mut document = format!("# {}\n", t("export.glossary_title", &[]));
+        for (term, definition, anchor) in definitions {
+            document.push_str(&format!(
+                "\n<a id=\"{}\"></a>\n\n## {}\n\n{}\n",
+                anchor,
+                term,
+                definition.trim()
+            ));
+        }
+        target::write_text(dir.join(format!("{}.md", glossary_file)), &document)?;
+    }
+
+    unresolved.sort();
+    Ok(GlossaryExport {
+        notes: notes.len(),
+        links,
+        unresolved,
+    })
+}
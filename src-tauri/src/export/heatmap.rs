@@ -0,0 +1,218 @@
+use super::image::{rasterize, xml_escape};
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+const CELL: f32 = 12.0;
+const GAP: f32 = 3.0;
+const LEFT: f32 = 36.0;
+const TOP: f32 = 48.0;
+const PADDING: f32 = 16.0;
+const LEGEND_HEIGHT: f32 = 28.0;
+const FONT_SIZE: f32 = 10.0;
+// 与 GitHub 贡献图相同的五级颜色，0 级为没有活动
+const LEVELS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Heatmap {
+    // 有创建或修改记录的天数
+    pub active_days: usize,
+    pub total: usize,
+    pub max_per_day: usize,
+    // 最长连续活动天数
+    pub longest_streak: usize,
+}
+
+// 数据库中的时间为 UTC，转换为本地（或指定时区）的日期
+fn local_date(value: &str, timezone: Option<Tz>) -> Option<NaiveDate> {
+    let utc = DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc())
+        })
+        .ok()?;
+    Some(match timezone {
+        Some(timezone) => utc.with_timezone(&timezone).date_naive(),
+        None => utc.with_timezone(&Local).date_naive(),
+    })
+}
+
+// 每条笔记在创建当天计一次，修改日期与创建日期不同时在修改当天再计一次
+fn daily_counts(notes: &[Value], year: i32, timezone: Option<Tz>) -> HashMap<NaiveDate, usize> {
+    let mut counts = HashMap::new();
+    for note in notes {
+        let created = note["created_at"]
+            .as_str()
+            .and_then(|value| local_date(value, timezone));
+        let updated = note["updated_at"]
+            .as_str()
+            .and_then(|value| local_date(value, timezone));
+        let days = [created, updated.filter(|day| Some(*day) != created)];
+        for day in days.into_iter().flatten().filter(|day| day.year() == year) {
+            *counts.entry(day).or_default() += 1;
+        }
+    }
+    counts
+}
+
+fn level(count: usize, max: usize) -> usize {
+    if count == 0 || max == 0 {
+        0
+    } else {
+        (count * 4).div_ceil(max).clamp(1, 4)
+    }
+}
+
+fn longest_streak(counts: &HashMap<NaiveDate, usize>, first: NaiveDate, last: NaiveDate) -> usize {
+    let (mut longest, mut current) = (0, 0);
+    for day in first.iter_days().take_while(|day| *day <= last) {
+        if counts.contains_key(&day) {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+// 按周排列，每列一周（周日在最上方），左侧标注周一、三、五，上方标注月份
+fn render_svg(
+    counts: &HashMap<NaiveDate, usize>,
+    year: i32,
+    first: NaiveDate,
+    last: NaiveDate,
+    max: usize,
+) -> (String, f32, f32) {
+    let offset = first.weekday().num_days_from_sunday() as i64;
+    let weeks = ((last - first).num_days() + offset) / 7 + 1;
+    let step = CELL + GAP;
+    let width = PADDING * 2.0 + LEFT + weeks as f32 * step;
+    let height = PADDING * 2.0 + TOP + 7.0 * step + LEGEND_HEIGHT;
+    let (left, top) = (PADDING + LEFT, PADDING + TOP);
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="100%" height="100%" fill="#ffffff"/>"##
+    );
+    let text = |svg: &mut String, x: f32, y: f32, size: f32, anchor: &str, content: &str| {
+        svg.push_str(&format!(
+            r##"<text x="{x}" y="{y}" text-anchor="{anchor}" font-family="sans-serif" font-size="{size}" fill="#57606a">{}</text>"##,
+            xml_escape(content)
+        ));
+    };
+    text(
+        &mut svg,
+        PADDING,
+        PADDING + 14.0,
+        14.0,
+        "start",
+        &t("export.heatmap_title", &[("year", year.to_string())]),
+    );
+
+    let months = t("export.heatmap_months", &[]);
+    for (month, label) in months.split(',').enumerate().take(12) {
+        let Some(day) = NaiveDate::from_ymd_opt(year, month as u32 + 1, 1) else {
+            continue;
+        };
+        let week = ((day - first).num_days() + offset) / 7;
+        text(
+            &mut svg,
+            left + week as f32 * step,
+            top - 6.0,
+            FONT_SIZE,
+            "start",
+            label.trim(),
+        );
+    }
+    let weekdays = t("export.heatmap_weekdays", &[]);
+    for (row, label) in [1, 3, 5].into_iter().zip(weekdays.split(',')) {
+        text(
+            &mut svg,
+            left - 6.0,
+            top + row as f32 * step + CELL - 2.0,
+            FONT_SIZE,
+            "end",
+            label.trim(),
+        );
+    }
+
+    for day in first.iter_days().take_while(|day| *day <= last) {
+        let index = (day - first).num_days() + offset;
+        let (week, row) = (index / 7, index % 7);
+        let count = counts.get(&day).copied().unwrap_or(0);
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" rx="2" fill="{}"><title>{} {}</title></rect>"#,
+            left + week as f32 * step,
+            top + row as f32 * step,
+            LEVELS[level(count, max)],
+            day.format("%Y-%m-%d"),
+            count
+        ));
+    }
+
+    // 图例：少 □□□□□ 多
+    let legend_y = top + 7.0 * step + 12.0;
+    let legend_right = width - PADDING;
+    let boxes_left = legend_right - 30.0 - LEVELS.len() as f32 * step;
+    text(
+        &mut svg,
+        boxes_left - 6.0,
+        legend_y + CELL - 2.0,
+        FONT_SIZE,
+        "end",
+        &t("export.heatmap_less", &[]),
+    );
+    for (i, color) in LEVELS.iter().enumerate() {
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{legend_y}" width="{CELL}" height="{CELL}" rx="2" fill="{color}"/>"#,
+            boxes_left + i as f32 * step
+        ));
+    }
+    text(
+        &mut svg,
+        boxes_left + LEVELS.len() as f32 * step + 3.0,
+        legend_y + CELL - 2.0,
+        FONT_SIZE,
+        "start",
+        &t("export.heatmap_more", &[]),
+    );
+
+    svg.push_str("</svg>");
+    (svg, width, height)
+}
+
+// 渲染一年的写作活动热力图，按文件扩展名保存为 SVG 或 PNG；未指定时区时按系统本地时间划分日期
+pub fn export(notes: &[Value], year: i32, timezone: Option<Tz>, path: &Path) -> AppResult<Heatmap> {
+    let (Some(first), Some(last)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return Err(AppError::new("export.invalid_year").with("year", year));
+    };
+
+    let counts = daily_counts(notes, year, timezone);
+    let max = counts.values().copied().max().unwrap_or(0);
+    let (svg, width, height) = render_svg(&counts, year, first, last, max);
+
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let data = if is_svg {
+        svg.into_bytes()
+    } else {
+        rasterize(&svg, width, height, 2.0)?
+    };
+    std::fs::write(path, data).map_err(wrap("export.failed"))?;
+
+    Ok(Heatmap {
+        active_days: counts.len(),
+        total: counts.values().sum(),
+        max_per_day: max,
+        longest_streak: longest_streak(&counts, first, last),
+    })
+}
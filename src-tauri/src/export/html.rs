@@ -0,0 +1,309 @@
+use super::directory::slugify;
+use super::numbering::HeadingNumbers;
+use crate::i18n::t;
+use pulldown_cmark::{html, Options, Parser};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+static IMG_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap());
+static SRC_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\ssrc\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<h([1-6])(\s[^>]*)?>(.*?)</h[1-6]\s*>").unwrap());
+static ID_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\sid\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static ALT_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\salt\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HtmlOptions {
+    // 图片没有替代文字时根据文件名生成，便于屏幕阅读器朗读；默认开启
+    pub auto_alt: bool,
+    // 左侧固定的目录栏，列出各级标题并随滚动高亮当前章节；默认关闭
+    pub sidebar_toc: bool,
+    // 正文标题加上多级编号（1、1.1、2 ……），目录中显示相同的编号；默认关闭
+    pub number_headings: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            auto_alt: true,
+            sidebar_toc: false,
+            number_headings: false,
+        }
+    }
+}
+
+// 目录栏样式和滚动高亮脚本，全部内联，离线打开即可使用
+const SIDEBAR_STYLE: &str = "body{margin:0;font-family:-apple-system,BlinkMacSystemFont,'Segoe UI','PingFang SC','Microsoft YaHei',sans-serif;line-height:1.6}
+nav.toc{position:fixed;top:0;bottom:0;left:0;width:260px;overflow-y:auto;box-sizing:border-box;padding:16px;border-right:1px solid #e5e7eb;background:#f9fafb;font-size:14px}
+nav.toc a{display:block;padding:2px 6px;border-radius:4px;color:#374151;text-decoration:none;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}
+nav.toc a:hover{background:#e5e7eb}
+nav.toc a.active{background:#dbeafe;color:#1d4ed8}
+main{position:fixed;top:0;bottom:0;left:260px;right:0;overflow-y:auto;box-sizing:border-box;padding:24px 40px}
+main img{max-width:100%}
+@media print{nav.toc{display:none}main{position:static;overflow:visible}}";
+const SIDEBAR_SCRIPT: &str = "(function(){var main=document.querySelector('main');var links=Array.prototype.slice.call(document.querySelectorAll('nav.toc a'));var targets=links.map(function(a){return document.getElementById(decodeURIComponent(a.getAttribute('href').slice(1)))});function update(){var top=main.getBoundingClientRect().top+8;var current=-1;for(var i=0;i<targets.length;i++){if(targets[i]&&targets[i].getBoundingClientRect().top<=top){current=i}}if(current<0){current=0}links.forEach(function(a,i){a.classList.toggle('active',i===current)});if(links[current]){var nav=links[current].parentNode;var r=links[current].getBoundingClientRect(),n=nav.getBoundingClientRect();if(r.top<n.top||r.bottom>n.bottom){links[current].scrollIntoView({block:'nearest'})}}}main.addEventListener('scroll',update);update()})();";
+
+struct TocEntry {
+    level: usize,
+    id: String,
+    // 已转义的 HTML 文本
+    text: String,
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// 由图片地址的文件名生成替代文字：去掉扩展名，分隔符换成空格，每个单词首字母大写，
+// 例如 attachments/3/team-photo_2024.png 为 "Team Photo 2024"；data: 地址和无法得到文字时返回 None
+pub fn alt_from_filename(src: &str) -> Option<String> {
+    if src.trim_start().to_lowercase().starts_with("data:") {
+        return None;
+    }
+    let path = src.split(['?', '#']).next().unwrap_or_default();
+    let name = percent_decode(path.rsplit(['/', '\\']).next().unwrap_or_default());
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name.as_str(),
+    };
+
+    let words: Vec<String> = stem
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.' | '+'))
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().map(|c| c.to_uppercase().to_string());
+            first.unwrap_or_default() + chars.as_str()
+        })
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+fn attr_value<'a>(caps: &'a Captures) -> &'a str {
+    caps.get(1)
+        .or_else(|| caps.get(2))
+        .or_else(|| caps.get(3))
+        .map_or("", |value| value.as_str())
+}
+
+// 为缺少替代文字（没有 alt 或 alt 为空）的图片补上由文件名生成的 alt，已有的替代文字不会改动
+pub fn add_missing_alt(html: &str) -> String {
+    IMG_TAG
+        .replace_all(html, |caps: &Captures| {
+            let tag = &caps[0];
+            let existing = ALT_ATTR.captures(tag);
+            if existing
+                .as_ref()
+                .is_some_and(|alt| !attr_value(alt).trim().is_empty())
+            {
+                return tag.to_string();
+            }
+            let Some(alt) = SRC_ATTR
+                .captures(tag)
+                .and_then(|src| alt_from_filename(attr_value(&src)))
+            else {
+                return tag.to_string();
+            };
+            let attr = format!(" alt=\"{}\"", escape_html(&alt));
+            match existing.and_then(|alt| alt.get(0)) {
+                Some(range) => format!("{}{}{}", &tag[..range.start()], attr, &tag[range.end()..]),
+                None => format!("<img{}{}", attr, &tag[4..]),
+            }
+        })
+        .into_owned()
+}
+
+pub fn render_body(content: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(content, options));
+    body
+}
+
+fn unique_id(base: &str, used: &mut HashSet<String>) -> String {
+    let mut id = base.to_string();
+    let mut n = 2;
+    while !used.insert(id.clone()) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+// 为标题补上 id（已有 id 的保留）并记录到目录中；目录层级相对于笔记标题下移一级
+fn add_heading_ids(body: &str, used: &mut HashSet<String>, toc: &mut Vec<TocEntry>) -> String {
+    HEADING
+        .replace_all(body, |caps: &Captures| {
+            let level: usize = caps[1].parse().unwrap_or(1);
+            let attrs = caps.get(2).map_or("", |attrs| attrs.as_str());
+            let text = HTML_TAG.replace_all(&caps[3], "").trim().to_string();
+            if text.is_empty() {
+                return caps[0].to_string();
+            }
+            if let Some(existing) = ID_ATTR.captures(attrs) {
+                let id = attr_value(&existing).to_string();
+                used.insert(id.clone());
+                toc.push(TocEntry {
+                    level: level + 1,
+                    id,
+                    text,
+                });
+                return caps[0].to_string();
+            }
+            let id = unique_id(&slugify(&decode_entities(&text)), used);
+            let heading = format!(
+                "<h{level} id=\"{}\"{}>{}</h{level}>",
+                escape_html(&id),
+                attrs,
+                &caps[3]
+            );
+            toc.push(TocEntry {
+                level: level + 1,
+                id,
+                text,
+            });
+            heading
+        })
+        .into_owned()
+}
+
+// 编号写在标题文字前，每条笔记单独编号
+fn number_headings(body: &str) -> String {
+    let mut numbers = HeadingNumbers::default();
+    HEADING
+        .replace_all(body, |caps: &Captures| {
+            if HTML_TAG.replace_all(&caps[3], "").trim().is_empty() {
+                return caps[0].to_string();
+            }
+            format!(
+                "<h{level}{}>{} {}</h{level}>",
+                caps.get(2).map_or("", |attrs| attrs.as_str()),
+                numbers.next(caps[1].parse().unwrap_or(1)),
+                &caps[3],
+                level = &caps[1]
+            )
+        })
+        .into_owned()
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn render_toc(toc: &[TocEntry]) -> String {
+    let mut nav = String::from("<nav class=\"toc\">\n");
+    for entry in toc {
+        nav.push_str(&format!(
+            "<a href=\"#{}\" style=\"padding-left:{}px\">{}</a>\n",
+            escape_html(&entry.id),
+            6 + (entry.level.min(6) - 1) * 12,
+            entry.text
+        ));
+    }
+    nav.push_str("</nav>\n");
+    nav
+}
+
+// 渲染为单个 HTML 文档，每条笔记一个 <article>；正文按 Markdown 解析，编辑器保存的 HTML 原样保留。
+// sidebar_toc 时笔记标题和正文中的各级标题列入左侧目录，正文放在可滚动的主区域
+pub fn render_notes(notes: &[Value], options: &HtmlOptions) -> String {
+    let mut articles = String::new();
+    let mut used = HashSet::new();
+    let mut toc = Vec::new();
+    for note in notes {
+        let title = note["title"].as_str().unwrap_or_default();
+        let title = if title.trim().is_empty() {
+            t("export.untitled", &[])
+        } else {
+            title.to_string()
+        };
+        let mut body = render_body(note["content"].as_str().unwrap_or_default());
+        if options.auto_alt {
+            body = add_missing_alt(&body);
+        }
+        if options.number_headings {
+            body = number_headings(&body);
+        }
+        let heading = if options.sidebar_toc {
+            let id = unique_id(&slugify(&title), &mut used);
+            toc.push(TocEntry {
+                level: 1,
+                id: id.clone(),
+                text: escape_html(&title),
+            });
+            body = add_heading_ids(&body, &mut used, &mut toc);
+            format!(
+                "<h1 id=\"{}\">{}</h1>",
+                escape_html(&id),
+                escape_html(&title)
+            )
+        } else {
+            format!("<h1>{}</h1>", escape_html(&title))
+        };
+        articles.push_str(&format!("<article>\n{}\n{}</article>\n", heading, body));
+    }
+
+    let document_title = match notes {
+        [note] => note["title"].as_str().unwrap_or_default().to_string(),
+        _ => t("export.html_title", &[("count", notes.len().to_string())]),
+    };
+    let (style, body) = if options.sidebar_toc {
+        (
+            format!("<style>\n{}\n</style>\n", SIDEBAR_STYLE),
+            format!(
+                "{}<main>\n{}</main>\n<script>\n{}\n</script>\n",
+                render_toc(&toc),
+                articles,
+                SIDEBAR_SCRIPT
+            ),
+        )
+    } else {
+        (String::new(), articles)
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&document_title),
+        style,
+        body
+    )
+}
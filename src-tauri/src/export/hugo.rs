@@ -0,0 +1,168 @@
+use super::directory::{assign_slugs, note_tags};
+use super::RenderOptions;
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use crate::markdown_import::percent_decode;
+use crate::screenshot::ATTACHMENTS_DIR;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+// 附件引用：相对路径 attachments/<id>/<name>，或编辑器中的 attachment:// 协议地址
+static ATTACHMENT_REF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:(?:attachment://localhost|https?://attachment\.localhost)/|(?:\.{0,2}/)?attachments/)(\d+)/([^\s"'()<>\[\]?#]+)"#,
+    )
+    .unwrap()
+});
+static NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"note://(\d+)").unwrap());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HugoExport {
+    pub pages: usize,
+    // 复制到页面目录的附件数
+    pub resources: usize,
+    // 引用了但在附件目录中找不到的文件，保留原链接
+    pub missing: Vec<String>,
+}
+
+fn toml_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// Hugo 的 TOML front-matter，date / lastmod 为 RFC 3339 时间
+fn front_matter(note: &Value, title: &str, options: &RenderOptions) -> String {
+    let mut fields = format!("+++\ntitle = {}\n", toml_string(title));
+    for (field, key) in [("created_at", "date"), ("updated_at", "lastmod")] {
+        if let Some(value) = note[field].as_str() {
+            fields.push_str(&format!(
+                "{} = {}\n",
+                key,
+                toml_string(&options.timestamp(value))
+            ));
+        }
+    }
+    let tags: Vec<String> = note_tags(note).iter().map(|tag| toml_string(tag)).collect();
+    fields.push_str(&format!("tags = [{}]\n+++\n\n", tags.join(", ")));
+    fields
+}
+
+// 把笔记引用的附件复制到页面目录作为页面资源，链接改为同目录下的文件名；
+// 引用其他笔记的同名附件时加上笔记 id 前缀避免覆盖
+fn copy_resources(
+    content: &str,
+    attachments_dir: &Path,
+    page_dir: &Path,
+    resources: &mut usize,
+    missing: &mut Vec<String>,
+) -> AppResult<String> {
+    // (笔记 id, 文件名) 到页面中使用的链接，链接保留原来的 URL 编码
+    let mut copied: HashMap<(String, String), String> = HashMap::new();
+    let mut used = HashSet::new();
+    let mut error = None;
+    let content = ATTACHMENT_REF.replace_all(content, |caps: &Captures| {
+        let (id, name) = (caps[1].to_string(), percent_decode(&caps[2]));
+        if let Some(link) = copied.get(&(id.clone(), name.clone())) {
+            return link.clone();
+        }
+        let source = attachments_dir.join(&id).join(&name);
+        if name.contains(['/', '\\']) || name == ".." || !source.is_file() {
+            missing.push(caps[0].to_string());
+            return caps[0].to_string();
+        }
+
+        let (file_name, link) = if name == "index.md" || used.contains(&name) {
+            (format!("{}-{}", id, name), format!("{}-{}", id, &caps[2]))
+        } else {
+            (name.clone(), caps[2].to_string())
+        };
+        if let Err(e) = fs::copy(&source, page_dir.join(&file_name)) {
+            error.get_or_insert(e);
+            return caps[0].to_string();
+        }
+        *resources += 1;
+        used.insert(file_name);
+        copied.insert((id, name), link.clone());
+        link
+    });
+    match error {
+        Some(e) => Err(wrap("export.failed")(e)),
+        None => Ok(content.into_owned()),
+    }
+}
+
+// 每条笔记导出为一个 Hugo 页面包：<slug>/index.md，引用的附件复制到同一目录；
+// 笔记正文可能包含 HTML，站点需要开启 markup.goldmark.renderer.unsafe 才会原样输出
+pub fn export(
+    notes: &[Value],
+    data_dir: &Path,
+    dir: &Path,
+    options: &RenderOptions,
+) -> AppResult<HugoExport> {
+    fs::create_dir_all(dir).map_err(wrap("export.create_dir_failed"))?;
+    let attachments_dir = data_dir.join(ATTACHMENTS_DIR);
+    let slugs = assign_slugs(notes);
+    let slug_by_id: HashMap<i64, &str> = notes
+        .iter()
+        .zip(&slugs)
+        .filter_map(|(note, slug)| note["id"].as_i64().map(|id| (id, slug.as_str())))
+        .collect();
+
+    let untitled = t("export.untitled", &[]);
+    let mut resources = 0;
+    let mut missing = Vec::new();
+    for (note, slug) in notes.iter().zip(&slugs) {
+        let page_dir = dir.join(slug);
+        fs::create_dir_all(&page_dir).map_err(wrap("export.create_dir_failed"))?;
+
+        let title = note["title"].as_str().unwrap_or(&untitled);
+        let content = copy_resources(
+            note["content"].as_str().unwrap_or(""),
+            &attachments_dir,
+            &page_dir,
+            &mut resources,
+            &mut missing,
+        )?;
+        // 指向已导出笔记的链接改为相对于页面的路径
+        let content = NOTE_LINK.replace_all(&content, |caps: &Captures| {
+            match caps[1]
+                .parse::<i64>()
+                .ok()
+                .and_then(|id| slug_by_id.get(&id))
+            {
+                Some(target) => format!("../{}/", target),
+                None => caps[0].to_string(),
+            }
+        });
+
+        fs::write(
+            page_dir.join("index.md"),
+            format!("{}{}\n", front_matter(note, title, options), content),
+        )
+        .map_err(wrap("export.failed"))?;
+    }
+
+    Ok(HugoExport {
+        pages: notes.len(),
+        resources,
+        missing,
+    })
+}
@@ -0,0 +1,592 @@
+use super::cards::wrap_lines;
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use crate::theme::Theme;
+use regex::Regex;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::fontdb::{Database, Language};
+use resvg::usvg::{Options, Tree};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+static HTML_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<(p|h[1-6]|li|blockquote|pre|ul|ol|div)\b").unwrap());
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static HTML_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<h([1-6])[^>]*>").unwrap());
+static HTML_LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<li[^>]*>").unwrap());
+static HTML_QUOTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<blockquote[^>]*>\s*(<p[^>]*>)?").unwrap());
+static HTML_PRE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<pre[^>]*>|</pre>").unwrap());
+static HTML_BREAK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)</(p|h[1-6]|li|blockquote|div)>|<br\s*/?>").unwrap());
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+static MARKDOWN_EMPHASIS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\*\*|__|~~|`").unwrap());
+
+// 常见系统自带的简体中文字体，按优先级排列
+const CJK_FAMILIES: &[&str] = &[
+    "PingFang SC",
+    "Hiragino Sans GB",
+    "Microsoft YaHei",
+    "Noto Sans CJK SC",
+    "Noto Sans SC",
+    "Source Han Sans SC",
+    "WenQuanYi Micro Hei",
+    "WenQuanYi Zen Hei",
+];
+
+// 系统字体库只加载一次；sans-serif 指向找到的中文字体，没有中文字体时为 None
+static FONTS: LazyLock<(Arc<Database>, Option<String>)> = LazyLock::new(|| {
+    let mut db = Database::new();
+    db.load_system_fonts();
+    let family = cjk_family(&db);
+    if let Some(family) = &family {
+        db.set_sans_serif_family(family.clone());
+    }
+    (Arc::new(db), family)
+});
+
+fn cjk_family(db: &Database) -> Option<String> {
+    let has_family = |name: &str| {
+        db.faces()
+            .any(|face| face.families.iter().any(|(family, _)| family == name))
+    };
+    if let Some(name) = CJK_FAMILIES.iter().find(|name| has_family(name)) {
+        return Some(name.to_string());
+    }
+
+    // 其次使用任意带有中文名称的字体
+    db.faces()
+        .find(|face| {
+            face.families.iter().any(|(_, language)| {
+                matches!(
+                    language,
+                    Language::Chinese_PeoplesRepublicOfChina
+                        | Language::Chinese_Taiwan
+                        | Language::Chinese_HongKongSAR
+                        | Language::Chinese_Singapore
+                        | Language::Chinese_MacaoSAR
+                )
+            })
+        })
+        .and_then(|face| {
+            face.families
+                .iter()
+                .find(|(_, language)| *language == Language::English_UnitedStates)
+                .or(face.families.first())
+                .map(|(family, _)| family.clone())
+        })
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x2E80..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ImageOptions {
+    // 图片宽度（CSS 像素），实际像素为 width * pixel_ratio
+    pub width: u32,
+    pub pixel_ratio: f32,
+    // light / dark，未指定时跟随当前主题
+    pub theme: Option<String>,
+    pub padding: u32,
+    // 超过该高度时拆分为多张图片，未指定时不拆分
+    pub max_height: Option<u32>,
+    // 底部显示应用名和导出日期
+    pub footer: bool,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            width: 750,
+            pixel_ratio: 2.0,
+            theme: None,
+            padding: 48,
+            max_height: None,
+            footer: true,
+        }
+    }
+}
+
+impl ImageOptions {
+    fn validate(&self) -> AppResult<()> {
+        let invalid = |name: &str| AppError::new("export.invalid_image_option").with("name", name);
+        if !(320..=3000).contains(&self.width) {
+            return Err(invalid("width"));
+        }
+        if !(0.5..=4.0).contains(&self.pixel_ratio) {
+            return Err(invalid("pixelRatio"));
+        }
+        if self.padding * 4 > self.width {
+            return Err(invalid("padding"));
+        }
+        if self.max_height.is_some_and(|height| height < 400) {
+            return Err(invalid("maxHeight"));
+        }
+        Ok(())
+    }
+}
+
+struct Palette {
+    background: &'static str,
+    text: &'static str,
+    muted: &'static str,
+    code_background: &'static str,
+    rule: &'static str,
+}
+
+fn palette(theme: Theme) -> Palette {
+    match theme {
+        Theme::Light => Palette {
+            background: "#ffffff",
+            text: "#1f2328",
+            muted: "#6e7781",
+            code_background: "#f6f8fa",
+            rule: "#d0d7de",
+        },
+        Theme::Dark => Palette {
+            background: "#0d1117",
+            text: "#e6edf3",
+            muted: "#8b949e",
+            code_background: "#161b22",
+            rule: "#30363d",
+        },
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Style {
+    Heading(usize),
+    Body,
+    ListItem,
+    Quote,
+    Code,
+}
+
+impl Style {
+    fn font_size(self) -> f32 {
+        match self {
+            Style::Heading(level) => [30.0, 26.0, 22.0, 19.0, 17.0, 16.0][level.clamp(1, 6) - 1],
+            Style::Code => 14.0,
+            _ => 16.0,
+        }
+    }
+
+    fn line_height(self) -> f32 {
+        self.font_size()
+            * if matches!(self, Style::Heading(_)) {
+                1.4
+            } else {
+                1.7
+            }
+    }
+
+    // 段落之后的间距
+    fn spacing(self) -> f32 {
+        match self {
+            Style::Heading(_) => 12.0,
+            Style::ListItem => 4.0,
+            _ => 14.0,
+        }
+    }
+
+    fn indent(self) -> f32 {
+        match self {
+            Style::ListItem | Style::Quote => 22.0,
+            Style::Code => 14.0,
+            _ => 0.0,
+        }
+    }
+}
+
+struct Block {
+    style: Style,
+    text: String,
+}
+
+// 编辑器保存的 HTML 先转为等价的 Markdown 行结构
+fn html_to_markdown(html: &str) -> String {
+    let text = HTML_HEADING.replace_all(html, |caps: &regex::Captures| {
+        format!("\n{} ", "#".repeat(caps[1].parse().unwrap_or(1)))
+    });
+    let text = HTML_LIST_ITEM.replace_all(&text, "\n- ");
+    let text = HTML_QUOTE.replace_all(&text, "\n> ");
+    let text = HTML_PRE.replace_all(&text, "\n```\n");
+    let text = HTML_BREAK.replace_all(&text, "\n");
+    HTML_TAG
+        .replace_all(&text, "")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn inline_text(text: &str) -> String {
+    let text = MARKDOWN_LINK.replace_all(text, "$1");
+    MARKDOWN_EMPHASIS.replace_all(&text, "").trim().to_string()
+}
+
+fn parse_blocks(title: &str, content: &str) -> Vec<Block> {
+    let markdown = if HTML_BLOCK.is_match(content) {
+        html_to_markdown(content)
+    } else {
+        content.to_string()
+    };
+
+    let mut blocks = vec![Block {
+        style: Style::Heading(1),
+        text: title.to_string(),
+    }];
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut in_code = false;
+
+    let flush = |paragraph: &mut Vec<String>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block {
+                style: Style::Body,
+                text: inline_text(&paragraph.join(" ")),
+            });
+            paragraph.clear();
+        }
+    };
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            blocks.push(Block {
+                style: Style::Code,
+                text: line.trim_end().replace('\t', "    "),
+            });
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        let block = if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            Some((Style::Heading(hashes), &trimmed[hashes..]))
+        } else if let Some(rest) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .or_else(|| trimmed.strip_prefix("+ "))
+        {
+            Some((Style::ListItem, rest))
+        } else {
+            trimmed.strip_prefix('>').map(|rest| (Style::Quote, rest))
+        };
+
+        match block {
+            Some((style, text)) => {
+                flush(&mut paragraph, &mut blocks);
+                let text = inline_text(text);
+                if !text.is_empty() {
+                    blocks.push(Block { style, text });
+                }
+            }
+            None => paragraph.push(trimmed.to_string()),
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+// 排版后的一行
+struct Row {
+    style: Style,
+    text: String,
+    height: f32,
+}
+
+fn layout(blocks: &[Block], text_width: f32) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        let style = block.style;
+        let width = text_width - style.indent() * 2.0;
+        let lines = if style == Style::Code {
+            // 代码按等宽字符估算宽度，过长的行直接截断
+            let max_chars = (width / (style.font_size() * 0.6)) as usize;
+            vec![block.text.chars().take(max_chars).collect()]
+        } else {
+            wrap_lines(&block.text, width, style.font_size(), usize::MAX)
+        };
+        for line in lines {
+            rows.push(Row {
+                style,
+                text: line,
+                height: style.line_height(),
+            });
+        }
+
+        // 连续的代码行之间不留间距
+        let next_is_code = blocks.get(i + 1).is_some_and(|b| b.style == Style::Code);
+        if !(style == Style::Code && next_is_code) {
+            if let Some(last) = rows.last_mut() {
+                last.height += style.spacing();
+            }
+        }
+    }
+    rows
+}
+
+fn paginate(rows: Vec<Row>, max: f32) -> Vec<Vec<Row>> {
+    let mut pages = vec![Vec::new()];
+    let mut height = 0.0;
+    for row in rows {
+        if height + row.height > max && !pages.last().is_some_and(Vec::is_empty) {
+            pages.push(Vec::new());
+            height = 0.0;
+        }
+        height += row.height;
+        pages.last_mut().unwrap().push(row);
+    }
+    pages
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const FOOTER_HEIGHT: f32 = 48.0;
+
+// fixed_height 为 None 时高度随内容变化
+fn render_svg(
+    rows: &[Row],
+    options: &ImageOptions,
+    colors: &Palette,
+    footer: Option<&str>,
+    fixed_height: Option<f32>,
+) -> (String, f32) {
+    let width = options.width as f32;
+    let padding = options.padding as f32;
+    let content_height: f32 = rows.iter().map(|row| row.height).sum();
+    let footer_height = if footer.is_some() { FOOTER_HEIGHT } else { 0.0 };
+    let height =
+        fixed_height.unwrap_or_else(|| (padding * 2.0 + content_height + footer_height).ceil());
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="100%" height="100%" fill="{}"/>"#,
+        colors.background
+    );
+
+    let mut y = padding;
+    for row in rows {
+        let style = row.style;
+        let line_height = style.line_height();
+        let x = padding + style.indent();
+        match style {
+            Style::Code => svg.push_str(&format!(
+                r#"<rect x="{padding}" y="{y}" width="{}" height="{}" fill="{}"/>"#,
+                width - padding * 2.0,
+                row.height,
+                colors.code_background
+            )),
+            Style::Quote => svg.push_str(&format!(
+                r#"<rect x="{padding}" y="{y}" width="4" height="{}" fill="{}"/>"#,
+                row.height, colors.rule
+            )),
+            Style::ListItem => svg.push_str(&format!(
+                r#"<circle cx="{}" cy="{}" r="3" fill="{}"/>"#,
+                padding + 8.0,
+                y + line_height / 2.0,
+                colors.text
+            )),
+            _ => {}
+        }
+
+        let (family, weight, fill) = match style {
+            Style::Heading(_) => ("sans-serif", "bold", colors.text),
+            Style::Code => ("monospace", "normal", colors.text),
+            Style::Quote => ("sans-serif", "normal", colors.muted),
+            _ => ("sans-serif", "normal", colors.text),
+        };
+        // 基线位于行高中间偏下
+        let baseline = y + (line_height + style.font_size() * 0.7) / 2.0;
+        svg.push_str(&format!(
+            r#"<text x="{x}" y="{baseline}" font-family="{family}" font-size="{}" font-weight="{weight}" fill="{fill}" xml:space="preserve">{}</text>"#,
+            style.font_size(),
+            xml_escape(&row.text)
+        ));
+        y += row.height;
+    }
+
+    if let Some(footer) = footer {
+        let rule_y = height - padding - FOOTER_HEIGHT + 16.0;
+        svg.push_str(&format!(
+            r#"<line x1="{padding}" y1="{rule_y}" x2="{}" y2="{rule_y}" stroke="{}" stroke-width="1"/>"#,
+            width - padding,
+            colors.rule
+        ));
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" text-anchor="end" font-family="sans-serif" font-size="13" fill="{}">{}</text>"#,
+            width - padding,
+            rule_y + 24.0,
+            colors.muted,
+            xml_escape(footer)
+        ));
+    }
+
+    svg.push_str("</svg>");
+    (svg, height)
+}
+
+pub fn rasterize(svg: &str, width: f32, height: f32, pixel_ratio: f32) -> AppResult<Vec<u8>> {
+    let options = Options {
+        fontdb: Arc::clone(&FONTS.0),
+        ..Options::default()
+    };
+    let tree = Tree::from_str(svg, &options).map_err(wrap("export.image_render_failed"))?;
+    let mut pixmap = Pixmap::new(
+        (width * pixel_ratio).ceil() as u32,
+        (height * pixel_ratio).ceil() as u32,
+    )
+    .ok_or_else(|| AppError::new("export.image_render_failed").with("error", "pixmap"))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(pixel_ratio, pixel_ratio),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(wrap("export.image_render_failed"))
+}
+
+// 拆分为多张时依次命名为 name-1.png、name-2.png ...
+fn page_path(path: &Path, page: usize, pages: usize) -> PathBuf {
+    if pages == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}-{}.png", stem, page))
+}
+
+// 单张图片的像素高度上限，超过时即使未设置 max_height 也会拆分
+const MAX_PIXEL_HEIGHT: f32 = 16384.0;
+
+pub fn export(
+    title: &str,
+    content: &str,
+    path: &Path,
+    options: &ImageOptions,
+    default_theme: Theme,
+) -> AppResult<Vec<String>> {
+    options.validate()?;
+    let theme = match options.theme.as_deref() {
+        None => default_theme,
+        Some("light") => Theme::Light,
+        Some("dark") => Theme::Dark,
+        Some(_) => return Err(AppError::new("export.invalid_image_option").with("name", "theme")),
+    };
+
+    // 没有可用的中文字体时直接报错，避免输出方块字
+    let has_cjk = title.chars().chain(content.chars()).any(is_cjk);
+    if has_cjk && FONTS.1.is_none() {
+        return Err(AppError::new("export.image_no_cjk_font"));
+    }
+
+    let padding = options.padding as f32;
+    let footer_height = if options.footer { FOOTER_HEIGHT } else { 0.0 };
+    let max_height = options
+        .max_height
+        .map_or(MAX_PIXEL_HEIGHT, |height| height as f32)
+        .min(MAX_PIXEL_HEIGHT / options.pixel_ratio);
+
+    let blocks = parse_blocks(title, content);
+    let rows = layout(&blocks, options.width as f32 - padding * 2.0);
+    let pages = paginate(rows, max_height - padding * 2.0 - footer_height);
+
+    let colors = palette(theme);
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut written = Vec::new();
+    for (i, rows) in pages.iter().enumerate() {
+        let footer = options.footer.then(|| {
+            let mut footer = t("export.image_footer", &[("date", date.clone())]);
+            if pages.len() > 1 {
+                footer.push_str(&format!(" · {}/{}", i + 1, pages.len()));
+            }
+            footer
+        });
+        let (svg, height) = render_svg(rows, options, &colors, footer.as_deref(), None);
+        let png = rasterize(&svg, options.width as f32, height, options.pixel_ratio)?;
+
+        let page_path = page_path(path, i + 1, pages.len());
+        std::fs::write(&page_path, png).map_err(wrap("export.failed"))?;
+        written.push(page_path.to_string_lossy().into_owned());
+    }
+
+    Ok(written)
+}
+
+// 缩略图按该宽度排版后整体缩放到目标尺寸，保证不同尺寸的缩略图版式一致
+const THUMBNAIL_LAYOUT: u32 = 320;
+const THUMBNAIL_PADDING: u32 = 20;
+
+// 正方形的笔记预览：标题和能放下的前几行，超出部分截掉；缺少中文字体时照常生成
+pub fn thumbnail(title: &str, content: &str, size: u32, theme: Theme) -> AppResult<Vec<u8>> {
+    let options = ImageOptions {
+        width: THUMBNAIL_LAYOUT,
+        pixel_ratio: size as f32 / THUMBNAIL_LAYOUT as f32,
+        theme: None,
+        padding: THUMBNAIL_PADDING,
+        max_height: None,
+        footer: false,
+    };
+    let side = THUMBNAIL_LAYOUT as f32;
+    let text_width = side - THUMBNAIL_PADDING as f32 * 2.0;
+    let rows = layout(&parse_blocks(title, content), text_width);
+    let mut pages = paginate(rows, text_width);
+    let (svg, _) = render_svg(
+        &pages.swap_remove(0),
+        &options,
+        &palette(theme),
+        None,
+        Some(side),
+    );
+    rasterize(&svg, side, side, options.pixel_ratio)
+}
+
+// 缓存条数上限，超出后整体清空
+const THUMBNAIL_CACHE_CAPACITY: usize = 500;
+
+// 内容哈希 -> base64 编码的 PNG
+#[derive(Default)]
+pub struct ThumbnailCache(Mutex<HashMap<String, String>>);
+
+impl ThumbnailCache {
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: String, thumbnail: String) {
+        let mut cache = self.0.lock().unwrap();
+        if cache.len() >= THUMBNAIL_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, thumbnail);
+    }
+
+    // (条数, 键和缩略图的字节数)
+    pub fn usage(&self) -> (usize, usize) {
+        let cache = self.0.lock().unwrap();
+        let bytes = cache.iter().map(|(k, v)| k.len() + v.len()).sum();
+        (cache.len(), bytes)
+    }
+}
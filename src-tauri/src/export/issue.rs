@@ -0,0 +1,35 @@
+use crate::tags;
+use std::collections::HashMap;
+
+// 行内 #标签 转为 issue 标签：label_map 中的键不区分大小写，映射为空字符串时丢弃该标签，未映射的原样保留
+pub fn labels(content: &str, label_map: &HashMap<String, String>) -> Vec<String> {
+    let label_map: HashMap<String, &str> = label_map
+        .iter()
+        .map(|(tag, label)| (tag.trim_start_matches('#').to_lowercase(), label.trim()))
+        .collect();
+
+    let mut labels: Vec<String> = Vec::new();
+    for tag in tags::find_inline_tags(content) {
+        let label = label_map
+            .get(&tag.name.to_lowercase())
+            .map_or(tag.name.as_str(), |label| *label);
+        if !label.is_empty() && !labels.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+            labels.push(label.to_string());
+        }
+    }
+    labels
+}
+
+// 标题、Labels 行和去掉行内标签后的正文，可直接粘贴或交给 gh issue create
+pub fn render(title: &str, content: &str, label_map: &HashMap<String, String>) -> String {
+    let labels = labels(content, label_map);
+    let body = tags::strip_inline_tags(content, &tags::find_inline_tags(content));
+
+    let mut markdown = format!("# {}\n\n", title.trim());
+    if !labels.is_empty() {
+        markdown.push_str(&format!("Labels: {}\n\n", labels.join(", ")));
+    }
+    markdown.push_str(body.trim());
+    markdown.push('\n');
+    markdown
+}
@@ -0,0 +1,226 @@
+use super::footnotes::Footnotes;
+use super::{markdown, ExportFormat, RenderOptions};
+use crate::error::{wrap, AppError, AppResult};
+use crate::shutdown;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// 结束后的任务保留一段时间供前端读取最终状态，之后被清理
+const JOB_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportJob {
+    pub id: String,
+    pub status: JobStatus,
+    // 已处理 / 总笔记数
+    pub processed: usize,
+    pub total: usize,
+    pub error: Option<AppError>,
+    #[serde(skip)]
+    cancel_requested: bool,
+    #[serde(skip)]
+    finished_at: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct ExportJobs(Mutex<HashMap<String, ExportJob>>);
+
+impl ExportJobs {
+    pub fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+// 清理过期的已结束任务
+fn sweep(jobs: &mut HashMap<String, ExportJob>) {
+    jobs.retain(|_, job| job.finished_at.is_none_or(|at| at.elapsed() < JOB_TTL));
+}
+
+fn update(app: &tauri::AppHandle, id: &str, f: impl FnOnce(&mut ExportJob)) {
+    if let Some(job) = app.state::<ExportJobs>().0.lock().unwrap().get_mut(id) {
+        f(job);
+    }
+}
+
+// 前端请求取消后返回 true，后台线程在处理每条笔记前检查
+fn cancel_requested(app: &tauri::AppHandle, id: &str) -> bool {
+    app.state::<ExportJobs>()
+        .0
+        .lock()
+        .unwrap()
+        .get(id)
+        .is_none_or(|job| job.cancel_requested)
+}
+
+// 先写入同目录下的临时文件，完成后再替换目标文件，取消或失败时不会留下不完整的导出
+fn run(
+    app: &tauri::AppHandle,
+    id: &str,
+    notes: &[Value],
+    format: ExportFormat,
+    file_path: &str,
+    options: &RenderOptions,
+) -> AppResult<bool> {
+    let part_path = PathBuf::from(format!("{}.part", file_path));
+    let result = write(app, id, notes, format, &part_path, options);
+    match result {
+        Ok(true) => fs::rename(&part_path, file_path)
+            .map(|_| true)
+            .map_err(wrap("export.failed")),
+        other => {
+            let _ = fs::remove_file(&part_path);
+            other
+        }
+    }
+}
+
+fn write(
+    app: &tauri::AppHandle,
+    id: &str,
+    notes: &[Value],
+    format: ExportFormat,
+    path: &Path,
+    options: &RenderOptions,
+) -> AppResult<bool> {
+    let mut writer = BufWriter::new(File::create(path).map_err(wrap("export.failed"))?);
+
+    match format {
+        // Markdown 逐条写入，进度按笔记更新
+        ExportFormat::Markdown => {
+            writer
+                .write_all(markdown::render_header(options).as_bytes())
+                .map_err(wrap("export.failed"))?;
+            let mut footnotes = Footnotes::default();
+            for (i, note) in notes.iter().enumerate() {
+                if cancel_requested(app, id) {
+                    return Ok(false);
+                }
+                writer
+                    .write_all(markdown::render_entry(note, options, &mut footnotes).as_bytes())
+                    .map_err(wrap("export.failed"))?;
+                update(app, id, |job| job.processed = i + 1);
+            }
+            writer
+                .write_all(markdown::render_footer(&footnotes).as_bytes())
+                .map_err(wrap("export.failed"))?;
+        }
+        // JSON 需要整体序列化，只在开始前检查取消
+        ExportFormat::Json => {
+            if cancel_requested(app, id) {
+                return Ok(false);
+            }
+            let content = format.render(notes, options)?;
+            writer
+                .write_all(content.as_bytes())
+                .map_err(wrap("export.failed"))?;
+            update(app, id, |job| job.processed = notes.len());
+        }
+    }
+
+    writer.flush().map_err(wrap("export.failed"))?;
+    Ok(true)
+}
+
+pub fn start(
+    app: &tauri::AppHandle,
+    notes: Vec<Value>,
+    format: ExportFormat,
+    file_path: String,
+    options: RenderOptions,
+) -> String {
+    let id = uuid::Uuid::new_v4().to_string();
+    {
+        let state = app.state::<ExportJobs>();
+        let mut jobs = state.0.lock().unwrap();
+        sweep(&mut jobs);
+        jobs.insert(
+            id.clone(),
+            ExportJob {
+                id: id.clone(),
+                status: JobStatus::Running,
+                processed: 0,
+                total: notes.len(),
+                error: None,
+                cancel_requested: false,
+                finished_at: None,
+            },
+        );
+    }
+
+    let app = app.clone();
+    let job_id = id.clone();
+    std::thread::spawn(move || {
+        // 应用正在退出时不再开始
+        let task = shutdown::begin_task(&app, "export_job");
+        let result = match task {
+            Some(_) => run(&app, &job_id, &notes, format, &file_path, &options),
+            None => Ok(false),
+        };
+        match &result {
+            Ok(true) => log::info!("导出任务 {} 已完成，共 {} 条笔记", job_id, notes.len()),
+            Ok(false) => log::info!("导出任务 {} 已取消", job_id),
+            Err(e) => log::error!("导出任务 {} 失败: {}", job_id, e),
+        }
+        update(&app, &job_id, |job| {
+            match result {
+                Ok(true) => job.status = JobStatus::Completed,
+                Ok(false) => job.status = JobStatus::Cancelled,
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+            job.finished_at = Some(Instant::now());
+        });
+    });
+
+    id
+}
+
+pub fn get(app: &tauri::AppHandle, id: &str) -> AppResult<ExportJob> {
+    let state = app.state::<ExportJobs>();
+    let mut jobs = state.0.lock().unwrap();
+    sweep(&mut jobs);
+    jobs.get(id)
+        .cloned()
+        .ok_or_else(|| AppError::new("export.job_not_found").with("id", id))
+}
+
+// 应用退出前取消所有正在运行的任务
+pub fn cancel_all(app: &tauri::AppHandle) {
+    let state = app.state::<ExportJobs>();
+    for job in state.0.lock().unwrap().values_mut() {
+        if job.status == JobStatus::Running {
+            job.cancel_requested = true;
+        }
+    }
+}
+
+// 已结束的任务无需取消，直接忽略
+pub fn cancel(app: &tauri::AppHandle, id: &str) -> AppResult<()> {
+    let state = app.state::<ExportJobs>();
+    let mut jobs = state.0.lock().unwrap();
+    let job = jobs
+        .get_mut(id)
+        .ok_or_else(|| AppError::new("export.job_not_found").with("id", id))?;
+    if job.status == JobStatus::Running {
+        job.cancel_requested = true;
+    }
+    Ok(())
+}
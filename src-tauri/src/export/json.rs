@@ -0,0 +1,112 @@
+use super::RenderOptions;
+use crate::error::{wrap, AppResult};
+use serde_json::{json, Value};
+
+// 与前端 JSON 备份保持相同的结构
+pub fn render_notes(notes: &[Value], options: &RenderOptions) -> AppResult<String> {
+    let mut export_data = json!({
+        "version": "1.0",
+        "notes": notes,
+    });
+    if !options.deterministic {
+        export_data["exportDate"] = json!(chrono::Utc::now().to_rfc3339());
+    }
+
+    serde_json::to_string_pretty(&export_data).map_err(wrap("export.serialize_failed"))
+}
+
+// 递归按键名排序，不依赖 serde_json 是否开启 preserve_order
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+// 便于用 Git 管理的规范化 JSON：结构与 render_notes 相同但不含导出时间，笔记按 id 排序、标签按名称排序、
+// 所有对象的键按字母顺序，两空格缩进并以换行结尾；数据不变时重新导出的内容逐字节相同
+pub fn render_canonical(notes: &[Value]) -> AppResult<String> {
+    let mut notes = notes.to_vec();
+    notes.sort_by_key(|note| note["id"].as_i64());
+    for note in &mut notes {
+        if let Some(tags) = note["tags"].as_array_mut() {
+            tags.sort_by(|a, b| {
+                let name = |tag: &Value| {
+                    tag.as_str()
+                        .or_else(|| tag["name"].as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                name(a).cmp(&name(b))
+            });
+        }
+    }
+    let export_data = sort_keys(json!({
+        "version": "1.0",
+        "notes": notes,
+    }));
+
+    let mut output =
+        serde_json::to_string_pretty(&export_data).map_err(wrap("export.serialize_failed"))?;
+    output.push('\n');
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: i64, title: &str, tags: &[&str]) -> Value {
+        json!({
+            "id": id,
+            "title": title,
+            "content": format!("<p>{}</p>", title),
+            "created_at": "2024-01-02 03:04:05",
+            "is_pinned": false,
+            "tags": tags,
+        })
+    }
+
+    // 同样的数据但键按相反顺序插入
+    fn reversed_keys(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .rev()
+                    .map(|(key, value)| (key.clone(), reversed_keys(value)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    #[test]
+    fn canonical_output_is_independent_of_row_order() {
+        let first = vec![
+            note(1, "Alpha", &["a", "b", "c"]),
+            note(2, "Beta", &["x"]),
+            note(3, "Gamma", &[]),
+        ];
+        let second = vec![
+            reversed_keys(&note(3, "Gamma", &[])),
+            reversed_keys(&note(1, "Alpha", &["c", "a", "b"])),
+            reversed_keys(&note(2, "Beta", &["x"])),
+        ];
+
+        let first = render_canonical(&first).unwrap();
+        let second = render_canonical(&second).unwrap();
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert!(first.ends_with('\n'));
+        assert!(!first.contains("exportDate"));
+    }
+}
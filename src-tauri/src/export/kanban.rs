@@ -0,0 +1,39 @@
+use super::directory::note_tags;
+use crate::i18n::t;
+use serde_json::Value;
+
+// 标签比较时忽略大小写和开头的 #
+fn normalize(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// 每个状态标签一列，笔记标题作为清单项；有多个状态标签的笔记放在靠前的那一列
+pub fn render(notes: &[Value], status_tags: &[String]) -> String {
+    let statuses: Vec<String> = status_tags.iter().map(|tag| normalize(tag)).collect();
+    let mut columns: Vec<Vec<&str>> = vec![Vec::new(); status_tags.len() + 1];
+
+    let untitled = t("export.untitled", &[]);
+    for note in notes {
+        let tags: Vec<String> = note_tags(note).iter().map(|tag| normalize(tag)).collect();
+        let column = statuses
+            .iter()
+            .position(|status| tags.contains(status))
+            .unwrap_or(status_tags.len());
+        columns[column].push(note["title"].as_str().unwrap_or(&untitled));
+    }
+
+    let no_status = t("export.kanban_no_status", &[]);
+    let names = status_tags
+        .iter()
+        .map(|tag| tag.trim().trim_start_matches('#'))
+        .chain([no_status.as_str()]);
+
+    let mut markdown = format!("# {}\n", t("export.kanban_heading", &[]));
+    for (name, titles) in names.zip(&columns) {
+        markdown.push_str(&format!("\n## {}\n\n", name));
+        for title in titles {
+            markdown.push_str(&format!("- [ ] {}\n", title.replace('\n', " ")));
+        }
+    }
+    markdown
+}
@@ -0,0 +1,188 @@
+use super::directory::{front_matter_fields, yaml_string};
+use super::footnotes::Footnotes;
+use super::numbering::number_markdown;
+use super::RenderOptions;
+use crate::i18n::t;
+use serde_json::Value;
+
+pub fn render_note(title: &str, content: &str, options: &RenderOptions) -> String {
+    let exported_at = options.now();
+    let mut footnotes = Footnotes::default();
+    let content = render_content(content, options, &mut footnotes);
+    let definitions = footnotes.render();
+    let definitions = if definitions.is_empty() {
+        definitions
+    } else {
+        format!("\n\n{}", definitions.trim_end())
+    };
+    format!(
+        "# {}\n\n{}{}\n\n---\n\n*{}*",
+        title,
+        content,
+        definitions,
+        t("export.exported_at", &[("time", exported_at)])
+    )
+}
+
+// 带 front-matter 的单条笔记：标题、时间、标签、置顶、收藏、分类和编辑器类型写在 front-matter 中，
+// 正文前不再重复标题，也不附加导出时间，重新导入时内容与原笔记一致
+pub fn render_note_with_front_matter(note: &Value, options: &RenderOptions) -> String {
+    let untitled = t("export.untitled", &[]);
+    let title = note["title"].as_str().unwrap_or(&untitled);
+    let mut footnotes = Footnotes::default();
+    let content = render_content(
+        note["content"].as_str().unwrap_or(""),
+        options,
+        &mut footnotes,
+    );
+    let definitions = footnotes.render();
+
+    let mut output = String::from("---\n");
+    output.push_str(&front_matter_fields(note, title, options));
+    for (field, key) in [("is_pinned", "pinned"), ("is_favorited", "favorited")] {
+        if note[field].as_bool().unwrap_or(false) {
+            output.push_str(&format!("{}: true\n", key));
+        }
+    }
+    if let Some(category_id) = note["category_id"].as_i64() {
+        output.push_str(&format!("category_id: {}\n", category_id));
+    }
+    if let Some(editor_type) = note["editor_type"].as_str() {
+        output.push_str(&format!("editor_type: {}\n", yaml_string(editor_type)));
+    }
+    output.push_str("---\n\n");
+    output.push_str(content.trim_end());
+    if !definitions.is_empty() {
+        output.push_str("\n\n");
+        output.push_str(definitions.trim_end());
+    }
+    output.push('\n');
+    output
+}
+
+// footnote_links 开启时把外部链接转为脚注，编号记录在 footnotes 中；number_headings 开启时给标题编号
+fn render_content(content: &str, options: &RenderOptions, footnotes: &mut Footnotes) -> String {
+    let content = if options.number_headings {
+        number_markdown(content)
+    } else {
+        content.to_string()
+    };
+    if options.footnote_links {
+        footnotes.convert(&content)
+    } else {
+        content
+    }
+}
+
+// 多条笔记导出的文件头：标题、导出时间和分隔线
+pub fn render_header(options: &RenderOptions) -> String {
+    let mut header = format!("# {}\n\n", t("export.heading", &[]));
+    if !options.deterministic {
+        let exported_at = options.now();
+        header.push_str(&format!(
+            "{}\n\n",
+            t("export.exported_at", &[("time", exported_at)])
+        ));
+    }
+    header.push_str("---\n\n");
+    header
+}
+
+pub fn render_entry(note: &Value, options: &RenderOptions, footnotes: &mut Footnotes) -> String {
+    let untitled = t("export.untitled", &[]);
+    let title = note["title"].as_str().unwrap_or(&untitled);
+    let content = render_content(note["content"].as_str().unwrap_or(""), options, footnotes);
+    let created_at = options.timestamp(note["created_at"].as_str().unwrap_or(""));
+
+    format!(
+        "## {}\n\n*{}*\n\n{}\n\n---\n\n",
+        title,
+        t("export.created_at", &[("time", created_at)]),
+        content
+    )
+}
+
+// 多条笔记导出的文件尾：所有笔记共用的脚注
+pub fn render_footer(footnotes: &Footnotes) -> String {
+    footnotes.render()
+}
+
+pub fn render_notes(notes: &[Value], options: &RenderOptions) -> String {
+    let mut markdown_content = render_header(options);
+    let mut footnotes = Footnotes::default();
+    for note in notes {
+        markdown_content.push_str(&render_entry(note, options, &mut footnotes));
+    }
+    markdown_content.push_str(&render_footer(&footnotes));
+
+    markdown_content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown_import::split_front_matter;
+    use serde_json::json;
+
+    fn round_trip(note: &Value) -> String {
+        render_note_with_front_matter(note, &RenderOptions::default())
+    }
+
+    #[test]
+    fn front_matter_round_trip() {
+        let note = json!({
+            "title": "He said \"hi\": part 2",
+            "content": "Body: with \"quotes\"",
+            "created_at": "2024-01-02 03:04:05",
+            "updated_at": "2024-02-03 04:05:06",
+            "tags": ["a: b", "x, y", "c\"d"],
+            "is_pinned": true,
+            "is_favorited": false,
+            "category_id": 7,
+            "editor_type": "custom",
+        });
+        let markdown = round_trip(&note);
+        let (front_matter, body) = split_front_matter(&markdown);
+        let front_matter = front_matter.expect("front-matter");
+
+        assert_eq!(
+            front_matter.title.as_deref(),
+            Some("He said \"hi\": part 2")
+        );
+        assert_eq!(
+            front_matter.created_at.as_deref(),
+            Some("2024-01-02 03:04:05")
+        );
+        assert_eq!(
+            front_matter.updated_at.as_deref(),
+            Some("2024-02-03 04:05:06")
+        );
+        assert_eq!(front_matter.tags, ["a: b", "x, y", "c\"d"]);
+        assert!(front_matter.pinned);
+        assert!(!front_matter.favorited);
+        assert_eq!(front_matter.category_id, Some(7));
+        assert_eq!(front_matter.editor_type.as_deref(), Some("custom"));
+        assert_eq!(body.trim(), "Body: with \"quotes\"");
+    }
+
+    #[test]
+    fn front_matter_round_trip_without_optional_fields() {
+        let note = json!({
+            "title": "key: value # not a comment \\ 'single'",
+            "content": "",
+            "category_id": null,
+        });
+        let markdown = round_trip(&note);
+        let (front_matter, _) = split_front_matter(&markdown);
+        let front_matter = front_matter.expect("front-matter");
+
+        assert_eq!(
+            front_matter.title.as_deref(),
+            Some("key: value # not a comment \\ 'single'")
+        );
+        assert_eq!(front_matter.created_at, None);
+        assert!(front_matter.tags.is_empty());
+        assert_eq!(front_matter.category_id, None);
+        assert_eq!(front_matter.editor_type, None);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::error::AppError;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use std::str::FromStr;
+
+// Telegram MarkdownV2 中需要转义的字符（代码和链接地址中另有规则）
+const TELEGRAM_SPECIAL: &str = "_*[]()~`>#+-=|{}.!\\";
+// WhatsApp 不支持转义，在格式符号后插入零宽空格，使其不再成对匹配
+const WHATSAPP_SPECIAL: &str = "*_~`";
+const ZERO_WIDTH_SPACE: char = '\u{200b}';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    Telegram,
+    WhatsApp,
+}
+
+impl FromStr for Platform {
+    type Err = AppError;
+
+    fn from_str(platform: &str) -> Result<Self, Self::Err> {
+        match platform.to_lowercase().as_str() {
+            "telegram" => Ok(Platform::Telegram),
+            "whatsapp" => Ok(Platform::WhatsApp),
+            other => Err(AppError::new("export.unsupported_platform").with("platform", other)),
+        }
+    }
+}
+
+impl Platform {
+    fn escape(self, text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match self {
+                Platform::Telegram if TELEGRAM_SPECIAL.contains(c) => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                Platform::WhatsApp if WHATSAPP_SPECIAL.contains(c) => {
+                    escaped.push(c);
+                    escaped.push(ZERO_WIDTH_SPACE);
+                }
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    // 代码中 Telegram 只需转义 ` 和 \，WhatsApp 原样保留
+    fn escape_code(self, text: &str) -> String {
+        match self {
+            Platform::Telegram => text.replace('\\', "\\\\").replace('`', "\\`"),
+            Platform::WhatsApp => text.to_string(),
+        }
+    }
+}
+
+struct Link {
+    url: String,
+    image: bool,
+}
+
+struct Renderer {
+    platform: Platform,
+    // 引用和链接文字先写入单独的缓冲区，结束时再加上前缀或包成链接
+    buffers: Vec<String>,
+    links: Vec<Link>,
+    // 每层列表的下一个序号，无序列表为 None
+    lists: Vec<Option<u64>>,
+    item_start: bool,
+    in_code_block: bool,
+    table_cell: usize,
+}
+
+impl Renderer {
+    fn new(platform: Platform) -> Self {
+        Renderer {
+            platform,
+            buffers: vec![String::new()],
+            links: Vec::new(),
+            lists: Vec::new(),
+            item_start: false,
+            in_code_block: false,
+            table_cell: 0,
+        }
+    }
+
+    fn out(&mut self) -> &mut String {
+        self.buffers.last_mut().unwrap()
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out().push_str(text);
+    }
+
+    // 块之间空一行，列表项和表格行之间只换行
+    fn block_break(&mut self, blank_line: bool) {
+        let separator = if blank_line { "\n\n" } else { "\n" };
+        let out = self.out();
+        if out.is_empty() {
+            return;
+        }
+        while !out.ends_with(separator) {
+            out.push('\n');
+        }
+    }
+
+    // 列表项的第一段紧跟在项目符号后面，之后的段落只换行
+    fn paragraph_break(&mut self) {
+        if !std::mem::take(&mut self.item_start) {
+            self.block_break(self.lists.is_empty());
+        }
+    }
+
+    fn start_link(&mut self, url: String, image: bool) {
+        self.links.push(Link { url, image });
+        self.buffers.push(String::new());
+    }
+
+    fn start(&mut self, tag: Tag) {
+        let platform = self.platform;
+        match tag {
+            Tag::Paragraph => self.paragraph_break(),
+            Tag::Heading { .. } => {
+                self.block_break(true);
+                self.push("*");
+            }
+            Tag::BlockQuote(_) => {
+                self.block_break(true);
+                self.buffers.push(String::new());
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_break(true);
+                self.in_code_block = true;
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) if platform == Platform::Telegram => {
+                        language.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    _ => String::new(),
+                };
+                self.push(&format!("```{}\n", language));
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block_break(true);
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.block_break(false);
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let marker = format!("{}.", number);
+                        *number += 1;
+                        platform.escape(&marker)
+                    }
+                    _ if platform == Platform::WhatsApp => "-".to_string(),
+                    _ => "•".to_string(),
+                };
+                self.push(&format!("{}{} ", "  ".repeat(depth), marker));
+                self.item_start = true;
+            }
+            Tag::Emphasis => self.push("_"),
+            Tag::Strong => self.push("*"),
+            Tag::Strikethrough => self.push("~"),
+            Tag::Link { dest_url, .. } => self.start_link(dest_url.to_string(), false),
+            Tag::Image { dest_url, .. } => self.start_link(dest_url.to_string(), true),
+            Tag::Table(_) => self.block_break(true),
+            Tag::TableHead | Tag::TableRow => {
+                self.block_break(false);
+                self.table_cell = 0;
+            }
+            Tag::TableCell => {
+                if self.table_cell > 0 {
+                    let separator = format!(" {} ", platform.escape("|"));
+                    self.push(&separator);
+                }
+                self.table_cell += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        let platform = self.platform;
+        match tag {
+            TagEnd::Heading(_) => self.push("*"),
+            TagEnd::BlockQuote(_) => {
+                let quote = self.buffers.pop().unwrap_or_default();
+                let prefix = match platform {
+                    Platform::Telegram => ">",
+                    Platform::WhatsApp => "> ",
+                };
+                let quoted: Vec<String> = quote
+                    .trim_end()
+                    .lines()
+                    .map(|line| format!("{}{}", prefix, line))
+                    .collect();
+                self.push(&quoted.join("\n"));
+            }
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                if !self.out().ends_with('\n') {
+                    self.push("\n");
+                }
+                self.push("```");
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::Item => self.item_start = false,
+            TagEnd::Emphasis => self.push("_"),
+            TagEnd::Strong => self.push("*"),
+            TagEnd::Strikethrough => self.push("~"),
+            TagEnd::Link | TagEnd::Image => {
+                let text = self.buffers.pop().unwrap_or_default();
+                let Some(link) = self.links.pop() else {
+                    return;
+                };
+                let rendered = match platform {
+                    // 链接地址中只需转义 ) 和 \
+                    Platform::Telegram => {
+                        let text = if text.is_empty() {
+                            platform.escape(&link.url)
+                        } else {
+                            text
+                        };
+                        let url = link.url.replace('\\', "\\\\").replace(')', "\\)");
+                        format!("[{}]({})", text, url)
+                    }
+                    // WhatsApp 不支持链接文字，地址原样附在后面由客户端识别
+                    Platform::WhatsApp if text.is_empty() || text == link.url => link.url,
+                    Platform::WhatsApp => format!("{} ({})", text, link.url),
+                };
+                let rendered = if link.image && platform == Platform::WhatsApp {
+                    format!("🖼 {}", rendered)
+                } else {
+                    rendered
+                };
+                self.push(&rendered);
+            }
+            _ => {}
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        let platform = self.platform;
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => {
+                let text = platform.escape_code(&text);
+                self.push(&text);
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                let text = platform.escape(&text);
+                self.push(&text);
+            }
+            Event::Code(code) => {
+                let code = format!("`{}`", platform.escape_code(&code));
+                self.push(&code);
+            }
+            Event::SoftBreak | Event::HardBreak => self.push("\n"),
+            Event::Rule => {
+                self.block_break(true);
+                self.push("——————");
+            }
+            Event::TaskListMarker(done) => self.push(if done { "☑ " } else { "☐ " }),
+            _ => {}
+        }
+    }
+}
+
+// 把 Markdown 转为聊天软件支持的格式：Telegram 使用 MarkdownV2，WhatsApp 使用 *粗体* / _斜体_ / ~删除线~；
+// 两者都不支持标题，标题转为粗体
+pub fn render(content: &str, platform: Platform) -> String {
+    let options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(platform);
+    for event in Parser::new_ext(content, options) {
+        renderer.event(event);
+    }
+    renderer.buffers.swap_remove(0).trim().to_string()
+}
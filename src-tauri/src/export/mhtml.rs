@@ -0,0 +1,183 @@
+use super::html::{render_notes, HtmlOptions};
+use crate::attachment_protocol::content_type;
+use crate::attachments;
+use crate::error::{wrap, AppResult};
+use crate::markdown_import::percent_decode;
+use crate::screenshot::ATTACHMENTS_DIR;
+use crate::transfer;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+pub static IMG_SRC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(<img\b[^>]*?\bsrc\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+
+// 编辑器中附件地址的前缀，去掉后为 <笔记 id>/<文件名>
+const ATTACHMENT_PREFIXES: &[&str] = &[
+    "attachment://localhost/",
+    "http://attachment.localhost/",
+    "https://attachment.localhost/",
+];
+// base64 每行的字符数，MIME 要求不超过 76
+const BASE64_LINE: usize = 76;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MhtmlExport {
+    pub images: usize,
+    // 找不到的本地图片，保留原地址；网络图片不下载
+    pub missing: Vec<String>,
+}
+
+struct Part {
+    content_id: String,
+    content_type: &'static str,
+    location: String,
+    data: Vec<u8>,
+}
+
+// 附件的相对路径或 attachment:// 地址对应的文件，其他地址返回 None
+pub fn local_image(data_dir: &Path, src: &str) -> Option<AppResult<PathBuf>> {
+    let relative = ATTACHMENT_PREFIXES
+        .iter()
+        .find_map(|prefix| src.strip_prefix(prefix))
+        .or_else(|| {
+            src.trim_start_matches("./")
+                .strip_prefix(ATTACHMENTS_DIR)
+                .and_then(|rest| rest.strip_prefix('/'))
+        })?;
+    let relative = relative.split(['?', '#']).next().unwrap_or_default();
+    Some(attachments::resolve(
+        data_dir,
+        &format!("{}/{}", ATTACHMENTS_DIR, percent_decode(relative)),
+    ))
+}
+
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let lines: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(BASE64_LINE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    lines.join("\r\n")
+}
+
+// 非 ASCII 标题按 RFC 2047 编码
+fn encoded_word(text: &str) -> String {
+    if text.is_ascii() && !text.contains(['\r', '\n']) {
+        text.to_string()
+    } else {
+        format!("=?utf-8?B?{}?=", STANDARD.encode(text))
+    }
+}
+
+// 按 RFC 2557 打包为 multipart/related：第一部分为 HTML，图片作为后续部分，
+// HTML 中的图片地址改为 cid: 引用，各部分同时带有 Content-Location
+pub fn render(data_dir: &Path, title: &str, content: &str) -> AppResult<(Vec<u8>, MhtmlExport)> {
+    let html = render_notes(
+        &[json!({ "title": title, "content": content })],
+        &HtmlOptions::default(),
+    );
+
+    let mut parts: Vec<Part> = Vec::new();
+    let mut by_path: HashMap<PathBuf, String> = HashMap::new();
+    let mut missing = Vec::new();
+    let html = IMG_SRC.replace_all(&html, |caps: &Captures| {
+        let src = caps
+            .get(2)
+            .or_else(|| caps.get(3))
+            .map_or("", |m| m.as_str());
+        let path = match local_image(data_dir, src) {
+            Some(Ok(path)) => path,
+            Some(Err(_)) => {
+                missing.push(src.to_string());
+                return caps[0].to_string();
+            }
+            None => return caps[0].to_string(),
+        };
+        if let Some(cid) = by_path.get(&path) {
+            return format!("{}\"cid:{}\"", &caps[1], cid);
+        }
+        let Ok(data) = std::fs::read(&path) else {
+            missing.push(src.to_string());
+            return caps[0].to_string();
+        };
+        let content_id = format!("image{}@yue-editor", parts.len() + 1);
+        let component = |path: Option<&Path>| {
+            path.and_then(Path::file_name)
+                .map(|name| {
+                    name.to_string_lossy()
+                        .replace('%', "%25")
+                        .replace(' ', "%20")
+                })
+                .unwrap_or_default()
+        };
+        let location = format!(
+            "file:///{}/{}/{}",
+            ATTACHMENTS_DIR,
+            component(path.parent()),
+            component(Some(&path))
+        );
+        parts.push(Part {
+            content_id: content_id.clone(),
+            content_type: content_type(&path),
+            location,
+            data,
+        });
+        by_path.insert(path, content_id.clone());
+        format!("{}\"cid:{}\"", &caps[1], content_id)
+    });
+
+    // base64 内容不会出现 "=_"，边界不会与正文冲突
+    let boundary = format!(
+        "----=_NextPart_{}",
+        &transfer::sha256_hex(html.as_bytes())[..24]
+    );
+    let mut out = String::new();
+    out.push_str("From: <Saved by Yue Editor>\r\n");
+    out.push_str(&format!("Subject: {}\r\n", encoded_word(title)));
+    out.push_str(&format!("Date: {}\r\n", chrono::Utc::now().to_rfc2822()));
+    out.push_str("MIME-Version: 1.0\r\n");
+    out.push_str(&format!(
+        "Content-Type: multipart/related;\r\n\ttype=\"text/html\";\r\n\tboundary=\"{}\"\r\n\r\n",
+        boundary
+    ));
+    out.push_str("This is a multi-part message in MIME format.\r\n\r\n");
+
+    out.push_str(&format!("--{}\r\n", boundary));
+    out.push_str("Content-Type: text/html; charset=\"utf-8\"\r\n");
+    out.push_str("Content-Transfer-Encoding: base64\r\n");
+    out.push_str("Content-Location: file:///index.html\r\n\r\n");
+    out.push_str(&base64_lines(html.as_bytes()));
+    out.push_str("\r\n\r\n");
+
+    for part in &parts {
+        out.push_str(&format!("--{}\r\n", boundary));
+        out.push_str(&format!("Content-Type: {}\r\n", part.content_type));
+        out.push_str("Content-Transfer-Encoding: base64\r\n");
+        out.push_str(&format!("Content-ID: <{}>\r\n", part.content_id));
+        out.push_str(&format!("Content-Location: {}\r\n\r\n", part.location));
+        out.push_str(&base64_lines(&part.data));
+        out.push_str("\r\n\r\n");
+    }
+    out.push_str(&format!("--{}--\r\n", boundary));
+
+    Ok((
+        out.into_bytes(),
+        MhtmlExport {
+            images: parts.len(),
+            missing,
+        },
+    ))
+}
+
+pub fn export(data_dir: &Path, title: &str, content: &str, path: &Path) -> AppResult<MhtmlExport> {
+    let (data, summary) = render(data_dir, title, content)?;
+    std::fs::write(path, data).map_err(wrap("export.failed"))?;
+    Ok(summary)
+}
@@ -0,0 +1,846 @@
+use crate::archive;
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::screenshot::ATTACHMENTS_DIR;
+use crate::transfer;
+use crate::validation;
+use crate::{i18n, tags, theme};
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tauri::Manager;
+
+mod apple_notes;
+mod booklet;
+mod bundle;
+mod cards;
+mod contact_sheet;
+mod custom;
+mod directory;
+mod footnotes;
+mod glossary;
+mod heatmap;
+mod html;
+mod hugo;
+mod image;
+mod issue;
+mod job;
+mod json;
+mod kanban;
+mod markdown;
+mod messaging;
+mod mhtml;
+mod notebook;
+mod numbering;
+mod parquet;
+mod sidecar;
+mod signing;
+mod spa;
+mod sqlite;
+mod subtitles;
+mod tag_summary;
+mod target;
+mod wiki;
+
+pub use image::ThumbnailCache;
+pub use job::{cancel_all as cancel_all_jobs, ExportJobs};
+
+// 导出选项，各导出格式按需读取
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    // 按 id 排序且不写入导出时间，相同数据每次导出的字节完全一致
+    pub deterministic: bool,
+    // 显示时间时转换到的时区，未指定时笔记时间原样输出、导出时间使用 UTC
+    pub timezone: Option<Tz>,
+    // Markdown 导出时把外部链接转为脚注，便于打印；默认关闭
+    pub footnote_links: bool,
+    // Markdown 导出时给正文标题加上多级编号（1、1.1、2 ……）；默认关闭
+    pub number_headings: bool,
+}
+
+impl RenderOptions {
+    pub fn with_timezone(timezone: Option<String>) -> AppResult<Self> {
+        let timezone = timezone
+            .map(|name| {
+                name.parse::<Tz>()
+                    .map_err(|_| AppError::new("export.invalid_timezone").with("timezone", &name))
+            })
+            .transpose()?;
+        Ok(RenderOptions {
+            timezone,
+            ..RenderOptions::default()
+        })
+    }
+
+    // 数据库中的时间为 UTC（SQLite CURRENT_TIMESTAMP 格式或 RFC 3339），无法解析时原样返回
+    pub fn timestamp(&self, value: &str) -> String {
+        let Some(timezone) = self.timezone else {
+            return value.to_string();
+        };
+        let utc = DateTime::parse_from_rfc3339(value)
+            .map(|time| time.with_timezone(&Utc))
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc())
+            });
+        match utc {
+            Ok(time) => time.with_timezone(&timezone).to_rfc3339(),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    pub fn now(&self) -> String {
+        let timezone = self.timezone.unwrap_or(Tz::UTC);
+        Utc::now()
+            .with_timezone(&timezone)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string()
+    }
+}
+
+// 导出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = AppError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(AppError::new("export.unsupported_format").with("format", other)),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn render(self, notes: &[Value], options: &RenderOptions) -> AppResult<String> {
+        let mut sorted;
+        let notes = if options.deterministic {
+            sorted = notes.to_vec();
+            sorted.sort_by_key(|note| note["id"].as_i64());
+            &sorted
+        } else {
+            notes
+        };
+
+        match self {
+            ExportFormat::Markdown => Ok(markdown::render_notes(notes, options)),
+            ExportFormat::Json => json::render_notes(notes, options),
+        }
+    }
+}
+
+// 先校验结构，避免格式错误的数据被默认值掩盖成“无标题”笔记
+pub fn parse_notes(notes_json: &str) -> AppResult<Vec<Value>> {
+    validation::parse_valid_notes(notes_json)
+}
+
+// 传入 note_json（完整笔记数据）时改为写入 YAML front-matter，可由 import_markdown_file 无损导入
+#[tauri::command]
+pub async fn export_note_to_markdown(
+    title: String,
+    content: String,
+    file_path: String,
+    timezone: Option<String>,
+    footnote_links: Option<bool>,
+    number_headings: Option<bool>,
+    note_json: Option<String>,
+) -> AppResult<()> {
+    let options = RenderOptions {
+        footnote_links: footnote_links.unwrap_or(false),
+        number_headings: number_headings.unwrap_or(false),
+        ..RenderOptions::with_timezone(timezone)?
+    };
+    let markdown_content = match note_json {
+        Some(note_json) => {
+            let mut note: Value =
+                serde_json::from_str(&note_json).map_err(wrap("export.parse_failed"))?;
+            note["title"] = Value::String(title);
+            note["content"] = Value::String(content);
+            markdown::render_note_with_front_matter(&note, &options)
+        }
+        None => markdown::render_note(&title, &content, &options),
+    };
+
+    target::write_text(&file_path, &markdown_content)?;
+
+    Ok(())
+}
+
+// notes_json 过大时，前端可先通过 begin_upload/append_chunk/finish_upload 上传，再传入 upload_handle
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_all_notes_to_markdown(
+    app: tauri::AppHandle,
+    notes_json: Option<String>,
+    upload_handle: Option<String>,
+    file_path: String,
+    timezone: Option<String>,
+    footnote_links: Option<bool>,
+    number_headings: Option<bool>,
+    include_archive: Option<bool>,
+) -> AppResult<()> {
+    let options = RenderOptions {
+        footnote_links: footnote_links.unwrap_or(false),
+        number_headings: number_headings.unwrap_or(false),
+        ..RenderOptions::with_timezone(timezone)?
+    };
+    let notes_json = transfer::resolve_input(&app, notes_json, upload_handle)?;
+    let mut notes = parse_notes(&notes_json)?;
+    // 归档的笔记默认不导出
+    if include_archive.unwrap_or(false) {
+        notes.extend(archive::load_notes(&app)?);
+    }
+    let markdown_content = markdown::render_notes(&notes, &options);
+
+    target::write_text(&file_path, &markdown_content)?;
+
+    Ok(())
+}
+
+// 目录导出前检查由标题生成的文件名是否重名或无法在某些系统上创建，返回有问题的笔记及导出时实际使用的文件名
+#[tauri::command]
+pub async fn validate_export_filenames(
+    notes_json: String,
+) -> AppResult<Vec<directory::FilenameIssue>> {
+    let notes = parse_notes(&notes_json)?;
+    Ok(directory::validate_filenames(&notes))
+}
+
+#[tauri::command]
+pub async fn export_notes_to_directory(
+    notes_json: String,
+    dir_path: String,
+    include_links: bool,
+    timezone: Option<String>,
+) -> AppResult<usize> {
+    let options = RenderOptions::with_timezone(timezone)?;
+    let notes = parse_notes(&notes_json)?;
+    directory::export(
+        &notes,
+        std::path::Path::new(&dir_path),
+        include_links,
+        &options,
+    )
+}
+
+// 按术语表（{ "术语": 笔记 id 或释义文本 }）导出目录，每条笔记中第一次出现的术语链接到对应的笔记或术语表条目，
+// 代码和已有链接中的文字不处理
+#[tauri::command]
+pub async fn export_with_glossary(
+    notes_json: String,
+    glossary_json: String,
+    dir_path: String,
+) -> AppResult<glossary::GlossaryExport> {
+    let notes = parse_notes(&notes_json)?;
+    glossary::export(&notes, &glossary_json, std::path::Path::new(&dir_path))
+}
+
+// 导出预览写入临时文件，避免通过 IPC 一次性返回大字符串
+#[tauri::command]
+pub async fn preview_export(
+    app: tauri::AppHandle,
+    notes_json: Option<String>,
+    upload_handle: Option<String>,
+    format: String,
+    timezone: Option<String>,
+) -> AppResult<transfer::TempResult> {
+    let format = format.parse::<ExportFormat>()?;
+    let options = RenderOptions::with_timezone(timezone)?;
+    let notes_json = transfer::resolve_input(&app, notes_json, upload_handle)?;
+    let notes = parse_notes(&notes_json)?;
+    let content = format.render(&notes, &options)?;
+
+    transfer::write_temp_result(&app, content.as_bytes())
+}
+
+// 只导出内容匹配正则表达式的笔记，支持 (?i) 内联标志或 case_insensitive 参数，返回导出的笔记数
+#[tauri::command]
+pub async fn export_notes_matching_regex(
+    notes_json: String,
+    pattern: String,
+    file_path: String,
+    format: String,
+    case_insensitive: Option<bool>,
+    timezone: Option<String>,
+) -> AppResult<usize> {
+    let format = format.parse::<ExportFormat>()?;
+    let options = RenderOptions::with_timezone(timezone)?;
+    let regex = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(case_insensitive.unwrap_or(false))
+        .build()
+        .map_err(wrap("export.invalid_regex"))?;
+
+    let notes: Vec<Value> = parse_notes(&notes_json)?
+        .into_iter()
+        .filter(|note| regex.is_match(note["content"].as_str().unwrap_or("")))
+        .collect();
+
+    let content = format.render(&notes, &options)?;
+    target::write_text(&file_path, &content)?;
+
+    Ok(notes.len())
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TagFilteredExport {
+    pub exported: usize,
+    pub excluded: usize,
+}
+
+// 标签比较忽略大小写和开头的 #
+fn tag_key(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// 导出前排除带有指定标签的笔记（例如 private、secret），用于公开分享；
+// 同时检查标签列表和正文中的 #标签
+#[tauri::command]
+pub async fn export_excluding_tags(
+    notes_json: String,
+    exclude: Vec<String>,
+    file_path: String,
+    format: String,
+    timezone: Option<String>,
+) -> AppResult<TagFilteredExport> {
+    let format = format.parse::<ExportFormat>()?;
+    let options = RenderOptions::with_timezone(timezone)?;
+    let exclude: HashSet<String> = exclude
+        .iter()
+        .map(|tag| tag_key(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    let (notes, excluded): (Vec<Value>, Vec<Value>) =
+        parse_notes(&notes_json)?.into_iter().partition(|note| {
+            let inline = tags::find_inline_tags(note["content"].as_str().unwrap_or(""));
+            !directory::note_tags(note)
+                .into_iter()
+                .chain(inline.into_iter().map(|tag| tag.name))
+                .any(|tag| exclude.contains(&tag_key(&tag)))
+        });
+
+    let content = format.render(&notes, &options)?;
+    target::write_text(&file_path, &content)?;
+    if !excluded.is_empty() {
+        log::info!("导出时排除了 {} 条带有敏感标签的笔记", excluded.len());
+    }
+
+    Ok(TagFilteredExport {
+        exported: notes.len(),
+        excluded: excluded.len(),
+    })
+}
+
+// 以确定性方式导出并返回输出内容的 SHA-256，同时写入同名的 .sha256 文件
+#[tauri::command]
+pub async fn export_with_checksum(
+    notes_json: String,
+    file_path: String,
+    format: String,
+    timezone: Option<String>,
+) -> AppResult<String> {
+    use std::path::Path;
+
+    let format = format.parse::<ExportFormat>()?;
+    let notes = parse_notes(&notes_json)?;
+    let options = RenderOptions {
+        deterministic: true,
+        ..RenderOptions::with_timezone(timezone)?
+    };
+    let content = format.render(&notes, &options)?;
+
+    target::write_text(&file_path, &content)?;
+
+    // 与写入的字节一致
+    let checksum = transfer::sha256_hex(target::without_bom(&content).as_bytes());
+    let file_name = Path::new(&file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target::write_text(
+        format!("{}.sha256", file_path),
+        &format!("{}  {}\n", checksum, file_name),
+    )
+    .map_err(wrap("export.checksum_write_failed"))?;
+
+    Ok(checksum)
+}
+
+// 导出后用 GPG 生成分离签名 <file>.sig，返回签名文件路径；key_id 为空时使用默认私钥
+#[tauri::command]
+pub async fn export_and_sign(
+    notes_json: String,
+    file_path: String,
+    key_id: Option<String>,
+    format: Option<String>,
+    timezone: Option<String>,
+) -> AppResult<String> {
+    let format = format
+        .as_deref()
+        .unwrap_or("markdown")
+        .parse::<ExportFormat>()?;
+    let notes = parse_notes(&notes_json)?;
+    let options = RenderOptions {
+        deterministic: true,
+        ..RenderOptions::with_timezone(timezone)?
+    };
+    let content = format.render(&notes, &options)?;
+    target::write_text(&file_path, &content)?;
+
+    let key_id = key_id.filter(|key| !key.trim().is_empty());
+    let sig_path = signing::sign(std::path::Path::new(&file_path), key_id.as_deref())?;
+    Ok(sig_path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub async fn verify_signature(
+    file_path: String,
+    sig_path: String,
+) -> AppResult<signing::SignatureInfo> {
+    signing::verify(
+        std::path::Path::new(&file_path),
+        std::path::Path::new(&sig_path),
+    )
+}
+
+// 导出为单个 HTML 文件，返回导出的笔记数；默认为缺少替代文字的图片按文件名生成 alt
+#[tauri::command]
+pub async fn export_notes_to_html(
+    notes_json: String,
+    file_path: String,
+    options: Option<html::HtmlOptions>,
+) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    let document = html::render_notes(&notes, &options.unwrap_or_default());
+    target::write_text(&file_path, &document)?;
+
+    Ok(notes.len())
+}
+
+// 每条笔记导出为一个 Hugo 页面包（<slug>/index.md），引用的附件作为页面资源复制到同一目录；
+// 未指定时区时 front-matter 中的时间按 UTC 输出
+#[tauri::command]
+pub async fn export_hugo_bundle(
+    app: tauri::AppHandle,
+    notes_json: String,
+    dir_path: String,
+    timezone: Option<String>,
+) -> AppResult<hugo::HugoExport> {
+    let mut options = RenderOptions::with_timezone(timezone)?;
+    options.timezone.get_or_insert(Tz::UTC);
+    let notes = parse_notes(&notes_json)?;
+    let summary = hugo::export(
+        &notes,
+        &portable::resolve_data_dir(&app)?,
+        std::path::Path::new(&dir_path),
+        &options,
+    )?;
+    log::info!(
+        "已导出 {} 个 Hugo 页面包，{} 个页面资源",
+        summary.pages,
+        summary.resources
+    );
+    Ok(summary)
+}
+
+// 每条笔记导出为一个 Wiki 页面文件，标签转为分类，另附索引页；目前只支持 MediaWiki
+#[tauri::command]
+pub async fn export_to_wiki(
+    notes_json: String,
+    dir_path: String,
+    dialect: String,
+) -> AppResult<usize> {
+    let dialect = dialect.parse::<wiki::WikiDialect>()?;
+    let notes = parse_notes(&notes_json)?;
+    wiki::export(&notes, std::path::Path::new(&dir_path), dialect)
+}
+
+// 按索引卡版式导出 PDF，每页默认 3 张卡片，返回导出的卡片数；
+// booklet 为 true 时按骑马钉小册子拼版（两页一面，页数补齐为 4 的倍数），双面打印后对折装订
+#[tauri::command]
+pub async fn export_index_cards(
+    notes_json: String,
+    file_path: String,
+    cards_per_page: Option<u32>,
+    booklet: Option<bool>,
+) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    let pdf = cards::render(
+        &notes,
+        cards_per_page.unwrap_or(cards::DEFAULT_CARDS_PER_PAGE),
+        booklet.unwrap_or(false),
+    )?;
+    target::write_bytes(&file_path, &pdf)?;
+
+    Ok(notes.len())
+}
+
+// 打包为 .tar.gz，目录结构见 bundle.rs，返回导出的笔记数
+#[tauri::command]
+pub async fn export_bundle_tar_gz(notes_json: String, file_path: String) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    bundle::write_tar_gz(&notes, std::path::Path::new(&file_path))?;
+
+    Ok(notes.len())
+}
+
+// 读取 export_bundle_tar_gz 生成的导出包，返回其中的笔记数据，由前端写入数据库
+#[tauri::command]
+pub async fn import_bundle_tar_gz(file_path: String) -> AppResult<Vec<Value>> {
+    bundle::read_tar_gz(std::path::Path::new(&file_path))
+}
+
+// 按状态标签分列导出为看板式 Markdown，返回导出的笔记数
+#[tauri::command]
+pub async fn export_kanban(
+    notes_json: String,
+    file_path: String,
+    status_tags: Vec<String>,
+) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    let markdown = kanban::render(&notes, &status_tags);
+    target::write_text(&file_path, &markdown)?;
+
+    Ok(notes.len())
+}
+
+// 导出按标签分组的索引文档，链接使用 note://id
+#[tauri::command]
+pub async fn export_tag_summary(notes_json: String, file_path: String) -> AppResult<()> {
+    let notes = parse_notes(&notes_json)?;
+    let markdown = tag_summary::render(&notes);
+    target::write_text(&file_path, &markdown)?;
+
+    Ok(())
+}
+
+// 导出一年的写作活动热力图（GitHub 贡献图样式），file_path 以 .svg 结尾时保存为 SVG，否则为 PNG；
+// 未指定时区时按系统本地时间划分日期
+#[tauri::command]
+pub async fn export_activity_heatmap(
+    notes_json: String,
+    file_path: String,
+    year: i32,
+    timezone: Option<String>,
+) -> AppResult<heatmap::Heatmap> {
+    let options = RenderOptions::with_timezone(timezone)?;
+    let notes = parse_notes(&notes_json)?;
+    heatmap::export(
+        &notes,
+        year,
+        options.timezone,
+        std::path::Path::new(&file_path),
+    )
+}
+
+// 导出为静态的单页阅读器（index.html、app.js 和 data.json），在浏览器中离线浏览和搜索笔记，返回导出的笔记数
+#[tauri::command]
+pub async fn export_spa_bundle(notes_json: String, dir_path: String) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    spa::export(&notes, std::path::Path::new(&dir_path))
+}
+
+// 导出为 macOS 备忘录可导入的 HTML（导入方式见 apple_notes.rs），file_path 为已存在的目录时每条笔记一个文件
+#[tauri::command]
+pub async fn export_for_apple_notes(
+    app: tauri::AppHandle,
+    notes_json: String,
+    file_path: String,
+) -> AppResult<apple_notes::AppleNotesExport> {
+    let notes = parse_notes(&notes_json)?;
+    apple_notes::export(
+        &portable::resolve_data_dir(&app)?,
+        &notes,
+        std::path::Path::new(&file_path),
+    )
+}
+
+// 导出为 MHTML 网页存档（.mht），引用的本地附件图片打包在同一文件中，返回打包的图片数和找不到的图片
+#[tauri::command]
+pub async fn export_note_to_mhtml(
+    app: tauri::AppHandle,
+    title: String,
+    content: String,
+    file_path: String,
+) -> AppResult<mhtml::MhtmlExport> {
+    let title = if title.trim().is_empty() {
+        i18n::t("export.untitled", &[])
+    } else {
+        title
+    };
+    mhtml::export(
+        &portable::resolve_data_dir(&app)?,
+        &title,
+        &content,
+        std::path::Path::new(&file_path),
+    )
+}
+
+// 把带时间戳的转写笔记导出为字幕，format 为 srt 或 vtt
+#[tauri::command]
+pub async fn export_to_subtitles(
+    content: String,
+    file_path: String,
+    format: String,
+) -> AppResult<subtitles::SubtitleExport> {
+    subtitles::export(&content, format.parse()?, std::path::Path::new(&file_path))
+}
+
+// 将单条笔记渲染为 PNG 图片，便于分享；笔记过长时按 max_height 拆分为多张，返回写入的文件路径
+#[tauri::command]
+pub async fn export_note_to_image(
+    app: tauri::AppHandle,
+    title: String,
+    content: String,
+    file_path: String,
+    options: Option<image::ImageOptions>,
+) -> AppResult<Vec<String>> {
+    let title = if title.trim().is_empty() {
+        i18n::t("export.untitled", &[])
+    } else {
+        title
+    };
+    image::export(
+        &title,
+        &content,
+        std::path::Path::new(&file_path),
+        &options.unwrap_or_default(),
+        theme::effective_theme(&app),
+    )
+}
+
+// 网格视图使用的正方形预览图，返回 base64 编码的 PNG；相同的内容、尺寸和主题直接返回缓存
+#[tauri::command]
+pub async fn generate_note_thumbnail(
+    app: tauri::AppHandle,
+    title: String,
+    content: String,
+    size: u32,
+) -> AppResult<String> {
+    if !(64..=1024).contains(&size) {
+        return Err(AppError::new("export.invalid_image_option").with("name", "size"));
+    }
+    let theme = theme::effective_theme(&app);
+    let key =
+        transfer::sha256_hex(format!("{}\0{}\0{}\0{:?}", size, title, content, theme).as_bytes());
+    let cache = app.state::<ThumbnailCache>();
+    if let Some(thumbnail) = cache.get(&key) {
+        return Ok(thumbnail);
+    }
+
+    let title = if title.trim().is_empty() {
+        i18n::t("export.untitled", &[])
+    } else {
+        title
+    };
+    let png = image::thumbnail(&title, &content, size, theme)?;
+    let thumbnail = base64::engine::general_purpose::STANDARD.encode(png);
+    cache.insert(key, thumbnail.clone());
+    Ok(thumbnail)
+}
+
+// 把笔记的图片附件排成带文件名的缩略图网格，保存为一张 PNG；非图片附件跳过并计数
+#[tauri::command]
+pub async fn export_attachment_contact_sheet(
+    app: tauri::AppHandle,
+    note_id: i64,
+    file_path: String,
+) -> AppResult<contact_sheet::ContactSheet> {
+    let dir = portable::resolve_data_dir(&app)?
+        .join(ATTACHMENTS_DIR)
+        .join(note_id.to_string());
+    let sheet = contact_sheet::export(&dir, std::path::Path::new(&file_path))?;
+    log::info!(
+        "已导出笔记 {} 的附件缩略图，{} 张图片，跳过 {} 个附件",
+        note_id,
+        sheet.images,
+        sheet.skipped
+    );
+    Ok(sheet)
+}
+
+#[tauri::command]
+pub fn list_notebooks(app: tauri::AppHandle) -> AppResult<Vec<notebook::NotebookSummary>> {
+    db::with_read_conn(&app, notebook::list)
+}
+
+// 按所选格式导出一个笔记本（分类）中的笔记，notebook 为笔记本名称或 id，返回导出的笔记数
+#[tauri::command]
+pub async fn export_notebook(
+    app: tauri::AppHandle,
+    notebook: String,
+    file_path: String,
+    format: String,
+) -> AppResult<usize> {
+    let format = format.parse::<ExportFormat>()?;
+    let notebook_id = db::with_read_conn(&app, |conn| notebook::find(conn, &notebook))?
+        .ok_or_else(|| AppError::new("export.notebook_not_found").with("id", &notebook))?;
+    let notes: Vec<Value> = db::load_notes(&app)?
+        .into_iter()
+        .filter(|note| note["category_id"].as_i64() == Some(notebook_id))
+        .collect();
+    let content = format.render(&notes, &RenderOptions::default())?;
+    target::write_text(&file_path, &content)?;
+
+    Ok(notes.len())
+}
+
+// 把一个笔记本（分类）导出为独立的数据库，可直接用于恢复；指向笔记本之外的链接改为纯文本并在结果中列出
+#[tauri::command]
+pub async fn export_notebook_to_db(
+    app: tauri::AppHandle,
+    notebook_id: i64,
+    file_path: String,
+) -> AppResult<notebook::NotebookExport> {
+    let summary = notebook::export(&app, notebook_id, std::path::Path::new(&file_path))?;
+    log::info!(
+        "已导出笔记本 {}，{} 条笔记，{} 个附件",
+        notebook_id,
+        summary.notes,
+        summary.attachments
+    );
+    Ok(summary)
+}
+
+// 列出数据目录 exporters 下的外部导出器，配置无效的跳过
+#[tauri::command]
+pub fn list_custom_exporters(app: tauri::AppHandle) -> AppResult<Vec<custom::CustomExporter>> {
+    custom::list(&app)
+}
+
+// 通过用户安装的外部脚本导出（如调用 pandoc），返回脚本的输出
+#[tauri::command]
+pub async fn export_with_custom_exporter(
+    app: tauri::AppHandle,
+    name: String,
+    notes_json: String,
+    file_path: String,
+) -> AppResult<custom::CustomExportOutput> {
+    let notes = parse_notes(&notes_json)?;
+    let output = custom::run(&app, &name, &notes, &file_path)?;
+    log::info!("已通过外部导出器 {} 导出 {} 条笔记", name, notes.len());
+    Ok(output)
+}
+
+// 在后台线程中导出，立即返回任务 id；前端通过 get_export_job 轮询进度，可同时运行多个任务
+#[tauri::command]
+pub async fn start_export_job(
+    app: tauri::AppHandle,
+    notes_json: String,
+    format: String,
+    file_path: String,
+    timezone: Option<String>,
+    footnote_links: Option<bool>,
+    number_headings: Option<bool>,
+) -> AppResult<String> {
+    let format = format.parse::<ExportFormat>()?;
+    let options = RenderOptions {
+        footnote_links: footnote_links.unwrap_or(false),
+        number_headings: number_headings.unwrap_or(false),
+        ..RenderOptions::with_timezone(timezone)?
+    };
+    let notes = parse_notes(&notes_json)?;
+
+    Ok(job::start(&app, notes, format, file_path, options))
+}
+
+#[tauri::command]
+pub fn get_export_job(app: tauri::AppHandle, id: String) -> AppResult<job::ExportJob> {
+    job::get(&app, &id)
+}
+
+#[tauri::command]
+pub fn cancel_export_job(app: tauri::AppHandle, id: String) -> AppResult<()> {
+    job::cancel(&app, &id)
+}
+
+// 按 GitHub issue 的习惯导出单条笔记：行内标签转为 Labels 行，label_map 可把标签改名为仓库中的标签
+#[tauri::command]
+pub async fn export_as_issue(
+    title: String,
+    content: String,
+    file_path: String,
+    label_map: Option<HashMap<String, String>>,
+) -> AppResult<()> {
+    let title = if title.trim().is_empty() {
+        i18n::t("export.untitled", &[])
+    } else {
+        title
+    };
+    let markdown = issue::render(&title, &content, &label_map.unwrap_or_default());
+    target::write_text(&file_path, &markdown)
+}
+
+// 为外部文件生成只含元数据的附属文件（如 report.pdf.meta.json），format 为 json 或 yaml
+#[tauri::command]
+pub async fn export_sidecar(
+    note_json: String,
+    file_path: String,
+    format: String,
+    timezone: Option<String>,
+) -> AppResult<()> {
+    let format = format.parse::<sidecar::SidecarFormat>()?;
+    let options = RenderOptions::with_timezone(timezone)?;
+    let note: Value = serde_json::from_str(&note_json).map_err(wrap("export.parse_failed"))?;
+    let content = sidecar::render(&note, format, &options);
+    target::write_text(&file_path, &content)
+}
+
+// 转为 Telegram、WhatsApp 等聊天软件支持的格式，返回可直接发送的文本
+#[tauri::command]
+pub async fn export_for_messaging(content: String, platform: String) -> AppResult<String> {
+    let platform = platform.parse::<messaging::Platform>()?;
+    Ok(messaging::render(&content, platform))
+}
+
+// 导出为键名排序、笔记按 id 排序的规范化 JSON，适合整个数据集放在 Git 中管理，返回导出的笔记数
+#[tauri::command]
+pub async fn export_canonical_json(notes_json: String, file_path: String) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    let content = json::render_canonical(&notes)?;
+    target::write_text(&file_path, &content)?;
+
+    Ok(notes.len())
+}
+
+// 导出前检查目标路径：是否可写、已有文件的编码及是否带 BOM；导出总是写为不带 BOM 的 UTF-8，
+// warnings 不为空时前端应在覆盖前提示
+#[tauri::command]
+pub async fn check_export_target(file_path: String) -> AppResult<target::ExportTarget> {
+    target::inspect(std::path::Path::new(&file_path))
+}
+
+// 导出为 Parquet 文件（列结构见 parquet.rs），供 pandas、DuckDB 等工具分析，返回导出的笔记数
+#[tauri::command]
+pub async fn export_to_parquet(notes_json: String, file_path: String) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    parquet::export(&notes, std::path::Path::new(&file_path))?;
+
+    Ok(notes.len())
+}
+
+// 导出为结构精简的 SQLite 文件（结构见 sqlite.rs），便于分享可查询的数据集，返回导出的笔记数
+#[tauri::command]
+pub async fn export_to_clean_sqlite(notes_json: String, file_path: String) -> AppResult<usize> {
+    let notes = parse_notes(&notes_json)?;
+    sqlite::export(&notes, std::path::Path::new(&file_path))?;
+
+    Ok(notes.len())
+}
@@ -0,0 +1,376 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::screenshot::ATTACHMENTS_DIR;
+use regex::{Captures, Regex};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+// 指向其他笔记的链接：Markdown 链接、HTML 链接和裸地址
+static MARKDOWN_NOTE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\(note://(\d+)\)").unwrap());
+static HTML_NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*href=["']note://(\d+)["'][^>]*>(.*?)</a\s*>"#).unwrap()
+});
+static BARE_NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"note://(\d+)").unwrap());
+
+struct Category {
+    id: i64,
+    name: String,
+    color: String,
+    created_at: Option<String>,
+}
+
+struct Note {
+    id: i64,
+    title: String,
+    content: String,
+    editor_type: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    is_pinned: Option<bool>,
+    is_favorited: Option<bool>,
+    // (id, name, color)
+    tags: Vec<(i64, String, String)>,
+}
+
+struct Notebook {
+    category: Category,
+    notes: Vec<Note>,
+    // 全部笔记的标题，用于把笔记本之外的链接改写为标题
+    titles: HashMap<i64, String>,
+}
+
+// 指向笔记本之外的链接，导出时改为纯文本
+#[derive(Clone, Debug, Serialize)]
+pub struct UnlinkedReference {
+    pub note_id: i64,
+    pub note_title: String,
+    pub target_id: i64,
+    // 目标笔记已删除时为空
+    pub target_title: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotebookExport {
+    pub notes: usize,
+    pub tags: usize,
+    pub attachments: usize,
+    pub unlinked: Vec<UnlinkedReference>,
+    pub manifest_path: String,
+    // 没有附件时为空
+    pub attachments_dir: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotebookSummary {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub note_count: usize,
+}
+
+// 笔记本即分类，未分类的笔记不属于任何笔记本
+pub fn list(conn: &Connection) -> rusqlite::Result<Vec<NotebookSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.name, c.color, COUNT(n.id)
+           FROM categories c LEFT JOIN notes n ON n.category_id = c.id
+          GROUP BY c.id ORDER BY c.id",
+    )?;
+    let notebooks = stmt
+        .query_map([], |row| {
+            Ok(NotebookSummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                note_count: row.get::<_, i64>(3)? as usize,
+            })
+        })?
+        .collect();
+    notebooks
+}
+
+// 按名称查找笔记本，找不到同名笔记本时按 id 解析
+pub fn find(conn: &Connection, notebook: &str) -> rusqlite::Result<Option<i64>> {
+    let by_name = conn
+        .query_row(
+            "SELECT id FROM categories WHERE name = ?1",
+            [notebook.trim()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if by_name.is_some() {
+        return Ok(by_name);
+    }
+    let Ok(id) = notebook.trim().parse::<i64>() else {
+        return Ok(None);
+    };
+    conn.query_row("SELECT id FROM categories WHERE id = ?1", [id], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+// 与数据库同名的附件目录和清单文件，例如 course.db 对应 course.attachments/ 和 course.manifest.json；
+// 附件目录内的结构与数据目录下的 attachments 相同，接收方放入数据目录即可
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+fn load(conn: &Connection, notebook_id: i64) -> rusqlite::Result<Option<Notebook>> {
+    let category = conn
+        .query_row(
+            "SELECT id, name, color, created_at FROM categories WHERE id = ?1",
+            [notebook_id],
+            |row| {
+                Ok(Category {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+    let Some(category) = category else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT id, title, content, editor_type, created_at, updated_at, is_pinned, is_favorited
+           FROM notes WHERE category_id = ?1 ORDER BY id",
+    )?;
+    let mut notes = stmt
+        .query_map([notebook_id], |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                editor_type: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                is_pinned: row.get(6)?,
+                is_favorited: row.get(7)?,
+                tags: Vec::new(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<Note>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.name, t.color FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+          WHERE nt.note_id = ?1",
+    )?;
+    for note in &mut notes {
+        note.tags = stmt
+            .query_map([note.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+    }
+
+    let mut stmt = conn.prepare("SELECT id, title FROM notes")?;
+    let titles = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<HashMap<i64, String>>>()?;
+
+    Ok(Some(Notebook {
+        category,
+        notes,
+        titles,
+    }))
+}
+
+// 改写指向笔记本之外的链接：有链接文字时保留文字，裸地址换成目标笔记标题
+fn unlink(
+    note: &Note,
+    exported: &HashSet<i64>,
+    titles: &HashMap<i64, String>,
+    unlinked: &mut Vec<UnlinkedReference>,
+) -> String {
+    let mut record = |target: &str| -> Option<i64> {
+        let target = target.parse::<i64>().ok()?;
+        if exported.contains(&target) {
+            return None;
+        }
+        unlinked.push(UnlinkedReference {
+            note_id: note.id,
+            note_title: note.title.clone(),
+            target_id: target,
+            target_title: titles.get(&target).cloned(),
+        });
+        Some(target)
+    };
+
+    let content =
+        MARKDOWN_NOTE_LINK.replace_all(&note.content, |caps: &Captures| match record(&caps[2]) {
+            Some(_) => caps[1].to_string(),
+            None => caps[0].to_string(),
+        });
+    let content = HTML_NOTE_LINK.replace_all(&content, |caps: &Captures| match record(&caps[1]) {
+        Some(_) => caps[2].to_string(),
+        None => caps[0].to_string(),
+    });
+    BARE_NOTE_LINK
+        .replace_all(&content, |caps: &Captures| match record(&caps[1]) {
+            Some(target) => titles.get(&target).cloned().unwrap_or_default(),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+// 完整表结构由 db::migrate 建立，保留原 id，附件路径 attachments/<id>/ 仍然有效
+fn write(
+    conn: &mut Connection,
+    category: &Category,
+    notes: &[(&Note, String)],
+) -> rusqlite::Result<usize> {
+    db::migrate(conn)?;
+    let tx = conn.transaction()?;
+    // 去掉 migrate 插入的默认分类，只保留导出的笔记本
+    tx.execute("DELETE FROM categories", [])?;
+    tx.execute(
+        "INSERT INTO categories (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            category.id,
+            category.name,
+            category.color,
+            category.created_at
+        ],
+    )?;
+
+    let mut tag_ids = HashSet::new();
+    {
+        let mut insert_note = tx.prepare(
+            "INSERT INTO notes (id, title, content, editor_type, created_at, updated_at,
+                                category_id, is_pinned, is_favorited)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        let mut insert_tag =
+            tx.prepare("INSERT OR IGNORE INTO tags (id, name, color) VALUES (?1, ?2, ?3)")?;
+        let mut insert_note_tag =
+            tx.prepare("INSERT OR IGNORE INTO note_tags (note_id, tag_id) VALUES (?1, ?2)")?;
+
+        for (note, content) in notes {
+            insert_note.execute(params![
+                note.id,
+                note.title,
+                content,
+                note.editor_type,
+                note.created_at,
+                note.updated_at,
+                category.id,
+                note.is_pinned.unwrap_or(false),
+                note.is_favorited.unwrap_or(false),
+            ])?;
+            for (id, name, color) in &note.tags {
+                insert_tag.execute(params![id, name, color])?;
+                insert_note_tag.execute(params![note.id, id])?;
+                tag_ids.insert(*id);
+            }
+        }
+    }
+    tx.commit()?;
+
+    // 确认导出的文件完整，接收方可以直接用来恢复
+    let check: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+            Some(check),
+        ));
+    }
+    Ok(tag_ids.len())
+}
+
+// 复制笔记的附件目录，返回复制的文件数
+fn copy_attachments(data_dir: &Path, target: &Path, note_ids: &[i64]) -> AppResult<usize> {
+    let mut copied = 0;
+    for id in note_ids {
+        let source = data_dir.join(ATTACHMENTS_DIR).join(id.to_string());
+        let Ok(entries) = fs::read_dir(&source) else {
+            continue;
+        };
+        let dir = target.join(id.to_string());
+        for entry in entries.flatten().filter(|entry| entry.path().is_file()) {
+            fs::create_dir_all(&dir).map_err(wrap("export.create_dir_failed"))?;
+            fs::copy(entry.path(), dir.join(entry.file_name())).map_err(wrap("export.failed"))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+// 把一个笔记本（分类）导出为独立的数据库文件，附件复制到同名的 .attachments 目录，并写入 .manifest.json
+pub fn export(app: &tauri::AppHandle, notebook_id: i64, path: &Path) -> AppResult<NotebookExport> {
+    let part_path = PathBuf::from(format!("{}.part", path.display()));
+    let _ = fs::remove_file(&part_path);
+
+    let Notebook {
+        category,
+        notes,
+        titles,
+    } = db::with_read_conn(app, |conn| load(conn, notebook_id))?
+        .ok_or_else(|| AppError::new("export.notebook_not_found").with("id", notebook_id))?;
+    let exported: HashSet<i64> = notes.iter().map(|note| note.id).collect();
+    let mut unlinked = Vec::new();
+    let rewritten: Vec<(&Note, String)> = notes
+        .iter()
+        .map(|note| (note, unlink(note, &exported, &titles, &mut unlinked)))
+        .collect();
+
+    let result = db::open(&part_path)
+        .and_then(|mut conn| write(&mut conn, &category, &rewritten))
+        .map_err(wrap("export.failed"))
+        .and_then(|tags| {
+            fs::rename(&part_path, path).map_err(wrap("export.failed"))?;
+            Ok(tags)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&part_path);
+    }
+    let tags = result?;
+
+    let note_ids: Vec<i64> = notes.iter().map(|note| note.id).collect();
+    let attachments_dir = sibling(path, ATTACHMENTS_DIR);
+    let attachments = copy_attachments(
+        &portable::resolve_data_dir(app)?,
+        &attachments_dir,
+        &note_ids,
+    )?;
+
+    let manifest_path = sibling(path, "manifest.json");
+    let manifest = json!({
+        "app_version": app.package_info().version.to_string(),
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "notebook": { "id": category.id, "name": category.name, "color": category.color },
+        "database": path.file_name().map(|name| name.to_string_lossy().into_owned()),
+        "notes": note_ids,
+        "tags": tags,
+        "attachments": attachments,
+        "attachments_dir": (attachments > 0).then(|| {
+            attachments_dir.file_name().map(|name| name.to_string_lossy().into_owned())
+        }),
+        "unlinked": unlinked,
+    });
+    fs::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest).unwrap_or_default(),
+    )
+    .map_err(wrap("export.failed"))?;
+
+    Ok(NotebookExport {
+        notes: notes.len(),
+        tags,
+        attachments,
+        unlinked,
+        manifest_path: manifest_path.to_string_lossy().into_owned(),
+        attachments_dir: (attachments > 0).then(|| attachments_dir.to_string_lossy().into_owned()),
+    })
+}
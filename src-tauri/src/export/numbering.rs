@@ -0,0 +1,88 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+static ATX_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^( {0,3})(#{1,6})[ \t]+(\S.*)$").unwrap());
+static FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(```|~~~)").unwrap());
+
+// 多级标题编号（1、1.1、1.2、2 ……），每条笔记重新开始。
+// 跳级的标题只占一层编号：# 之后直接出现 ### 编为 1.1 而不是 1.0.1，之后的 ## 接着编为 1.2
+#[derive(Debug, Default)]
+pub struct HeadingNumbers {
+    // (标题级别, 该层当前序号)，级别从外到内递增
+    stack: Vec<(usize, usize)>,
+}
+
+impl HeadingNumbers {
+    pub fn next(&mut self, level: usize) -> String {
+        // 退出比当前更深的层级，被退出的同层序号由当前标题接续
+        let mut sibling = 0;
+        while let Some(&(top, count)) = self.stack.last() {
+            if top <= level {
+                break;
+            }
+            sibling = count;
+            self.stack.pop();
+        }
+        match self.stack.last_mut() {
+            Some((top, count)) if *top == level => *count += 1,
+            _ => self.stack.push((level, sibling + 1)),
+        }
+        self.stack
+            .iter()
+            .map(|(_, count)| count.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+// 给 Markdown 正文中的 ATX 标题加上编号，代码块中的 # 不处理
+pub fn number_markdown(content: &str) -> String {
+    let mut numbers = HeadingNumbers::default();
+    let mut in_fence: Option<&str> = None;
+    let mut lines = Vec::new();
+    for line in content.split('\n') {
+        if let Some(caps) = FENCE.captures(line) {
+            let marker = caps.get(1).map_or("", |m| m.as_str());
+            match in_fence {
+                None => in_fence = Some(marker),
+                Some(open) if open == marker => in_fence = None,
+                _ => {}
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        match ATX_HEADING.captures(line) {
+            Some(caps) if in_fence.is_none() => {
+                let number = numbers.next(caps[2].len());
+                lines.push(format!("{}{} {} {}", &caps[1], &caps[2], number, &caps[3]));
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_levels_take_one_number() {
+        let mut numbers = HeadingNumbers::default();
+        assert_eq!(numbers.next(1), "1");
+        assert_eq!(numbers.next(3), "1.1");
+        assert_eq!(numbers.next(2), "1.2");
+        assert_eq!(numbers.next(1), "2");
+    }
+
+    #[test]
+    fn headings_in_code_blocks_stay_unnumbered() {
+        let content =
+            "# Intro\n```sh\n# comment\n```\n### Detail\n~~~\n## not a heading\n~~~\n## Next";
+        assert_eq!(
+            number_markdown(content),
+            "# 1 Intro\n```sh\n# comment\n```\n### 1.1 Detail\n~~~\n## not a heading\n~~~\n## 1.2 Next"
+        );
+    }
+}
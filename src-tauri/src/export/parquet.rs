@@ -0,0 +1,482 @@
+use super::directory::note_tags;
+use crate::error::{wrap, AppResult};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::Value;
+use std::path::Path;
+
+// 供 pandas / DuckDB 等分析工具读取的 Parquet 文件：单个 row group，不压缩，PLAIN 编码。列结构：
+//   id            INT64 NOT NULL
+//   title         STRING NOT NULL
+//   content       STRING NOT NULL
+//   created_at    TIMESTAMP(MICROS, UTC)，无法解析时为 NULL
+//   updated_at    TIMESTAMP(MICROS, UTC)，无法解析时为 NULL
+//   category_id   INT64，未分类时为 NULL
+//   is_pinned     BOOLEAN NOT NULL
+//   is_favorited  BOOLEAN NOT NULL
+//   tags          LIST<STRING NOT NULL> NOT NULL，没有标签时为空列表
+const MAGIC: &[u8] = b"PAR1";
+const CREATED_BY: &str = "yue-editor";
+
+// 物理类型
+const BOOLEAN: i32 = 0;
+const INT64: i32 = 2;
+const BYTE_ARRAY: i32 = 6;
+// 重复类型
+const REQUIRED: i32 = 0;
+const OPTIONAL: i32 = 1;
+const REPEATED: i32 = 2;
+// converted_type，供只认识旧式注解的读取器使用
+const UTF8: i32 = 0;
+const LIST: i32 = 3;
+const TIMESTAMP_MICROS: i32 = 10;
+// 编码
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+
+// Thrift compact protocol 的类型编号
+const T_TRUE: u8 = 1;
+const T_FALSE: u8 = 2;
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Parquet 的页头和文件元数据使用 Thrift compact protocol 编码，这里只实现用到的部分
+#[derive(Default)]
+struct Thrift {
+    buf: Vec<u8>,
+    // 外层结构体最后写入的字段编号，字段头按与上一字段的差值编码
+    parents: Vec<i16>,
+    field: i16,
+}
+
+impl Thrift {
+    fn varint(&mut self, value: u64) {
+        varint(&mut self.buf, value);
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn header(&mut self, id: i16, kind: u8) {
+        let delta = id - self.field;
+        if (1..=15).contains(&delta) {
+            self.buf.push((delta as u8) << 4 | kind);
+        } else {
+            self.buf.push(kind);
+            self.zigzag(id as i64);
+        }
+        self.field = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.header(id, T_I32);
+        self.zigzag(value as i64);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.header(id, T_I64);
+        self.zigzag(value);
+    }
+
+    fn bool(&mut self, id: i16, value: bool) {
+        self.header(id, if value { T_TRUE } else { T_FALSE });
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn string(&mut self, id: i16, value: &str) {
+        self.header(id, T_BINARY);
+        self.bytes(value.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.header(id, T_LIST);
+        if len < 15 {
+            self.buf.push((len as u8) << 4 | kind);
+        } else {
+            self.buf.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    // 作为字段的结构体；列表中的结构体元素直接调用 enter
+    fn begin(&mut self, id: i16) {
+        self.header(id, T_STRUCT);
+        self.enter();
+    }
+
+    fn enter(&mut self) {
+        self.parents.push(self.field);
+        self.field = 0;
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.field = self.parents.pop().unwrap_or(0);
+    }
+
+    // 没有字段的结构体，用于 LogicalType 中的 StringType、ListType 等
+    fn empty(&mut self, id: i16) {
+        self.begin(id);
+        self.end();
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Logical {
+    None,
+    String,
+    Timestamp,
+    List,
+}
+
+struct SchemaNode {
+    name: &'static str,
+    kind: Option<i32>,
+    repetition: Option<i32>,
+    children: usize,
+    logical: Logical,
+}
+
+impl SchemaNode {
+    fn leaf(name: &'static str, kind: i32, repetition: i32, logical: Logical) -> Self {
+        SchemaNode {
+            name,
+            kind: Some(kind),
+            repetition: Some(repetition),
+            children: 0,
+            logical,
+        }
+    }
+
+    fn group(
+        name: &'static str,
+        repetition: Option<i32>,
+        children: usize,
+        logical: Logical,
+    ) -> Self {
+        SchemaNode {
+            name,
+            kind: None,
+            repetition,
+            children,
+            logical,
+        }
+    }
+
+    fn write(&self, thrift: &mut Thrift) {
+        thrift.enter();
+        if let Some(kind) = self.kind {
+            thrift.i32(1, kind);
+        }
+        if let Some(repetition) = self.repetition {
+            thrift.i32(3, repetition);
+        }
+        thrift.string(4, self.name);
+        if self.children > 0 {
+            thrift.i32(5, self.children as i32);
+        }
+        let converted = match self.logical {
+            Logical::None => None,
+            Logical::String => Some(UTF8),
+            Logical::Timestamp => Some(TIMESTAMP_MICROS),
+            Logical::List => Some(LIST),
+        };
+        if let Some(converted) = converted {
+            thrift.i32(6, converted);
+        }
+        match self.logical {
+            Logical::None => {}
+            Logical::String => {
+                thrift.begin(10);
+                thrift.empty(1);
+                thrift.end();
+            }
+            Logical::List => {
+                thrift.begin(10);
+                thrift.empty(3);
+                thrift.end();
+            }
+            Logical::Timestamp => {
+                thrift.begin(10);
+                thrift.begin(8);
+                thrift.bool(1, true);
+                thrift.begin(2);
+                thrift.empty(2);
+                thrift.end();
+                thrift.end();
+                thrift.end();
+            }
+        }
+        thrift.end();
+    }
+}
+
+// 一列的全部数据：重复级别、定义级别和 PLAIN 编码的非空值；级别只有 0 和 1 两种
+struct Column {
+    path: &'static [&'static str],
+    kind: i32,
+    repeated: bool,
+    nullable: bool,
+    repetition_levels: Vec<u8>,
+    definition_levels: Vec<u8>,
+    values: Vec<u8>,
+    booleans: Vec<bool>,
+    num_values: usize,
+}
+
+impl Column {
+    fn new(path: &'static [&'static str], kind: i32, nullable: bool) -> Self {
+        Column {
+            path,
+            kind,
+            repeated: false,
+            nullable,
+            repetition_levels: Vec::new(),
+            definition_levels: Vec::new(),
+            values: Vec::new(),
+            booleans: Vec::new(),
+            num_values: 0,
+        }
+    }
+
+    fn push_level(&mut self, present: bool) {
+        self.num_values += 1;
+        if self.nullable {
+            self.definition_levels.push(present as u8);
+        }
+    }
+
+    fn push_i64(&mut self, value: Option<i64>) {
+        self.push_level(value.is_some());
+        if let Some(value) = value {
+            self.values.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn push_str(&mut self, value: &str) {
+        self.push_level(true);
+        self.push_bytes(value);
+    }
+
+    fn push_bytes(&mut self, value: &str) {
+        self.values
+            .extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.values.extend_from_slice(value.as_bytes());
+    }
+
+    fn push_bool(&mut self, value: bool) {
+        self.push_level(true);
+        self.booleans.push(value);
+    }
+
+    // 列表列：空列表记一个定义级别为 0 的条目，第二个及之后的元素重复级别为 1
+    fn push_list(&mut self, items: &[String]) {
+        if items.is_empty() {
+            self.num_values += 1;
+            self.repetition_levels.push(0);
+            self.definition_levels.push(0);
+            return;
+        }
+        for (i, item) in items.iter().enumerate() {
+            self.num_values += 1;
+            self.repetition_levels.push((i > 0) as u8);
+            self.definition_levels.push(1);
+            self.push_bytes(item);
+        }
+    }
+
+    // 数据页（v1）：重复级别、定义级别（各自带 4 字节长度前缀），然后是值
+    fn page(&self) -> Vec<u8> {
+        let mut page = Vec::new();
+        if self.repeated {
+            page.extend(encode_levels(&self.repetition_levels));
+        }
+        if self.nullable || self.repeated {
+            page.extend(encode_levels(&self.definition_levels));
+        }
+        if self.kind == BOOLEAN {
+            let mut bits = vec![0u8; self.booleans.len().div_ceil(8)];
+            for (i, _) in self
+                .booleans
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value)
+            {
+                bits[i / 8] |= 1 << (i % 8);
+            }
+            page.extend(bits);
+        } else {
+            page.extend_from_slice(&self.values);
+        }
+        page
+    }
+}
+
+// 位宽为 1 的 RLE / bit-packing 混合编码，全部使用 RLE 段：段长左移一位，后跟一个字节的值
+fn encode_levels(levels: &[u8]) -> Vec<u8> {
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < levels.len() {
+        let run = levels[i..]
+            .iter()
+            .take_while(|level| **level == levels[i])
+            .count();
+        varint(&mut runs, (run as u64) << 1);
+        runs.push(levels[i]);
+        i += run;
+    }
+    let mut encoded = (runs.len() as u32).to_le_bytes().to_vec();
+    encoded.extend(runs);
+    encoded
+}
+
+// 数据库中的时间为 UTC（SQLite CURRENT_TIMESTAMP 格式或 RFC 3339）
+fn timestamp_micros(value: &Value) -> Option<i64> {
+    let value = value.as_str()?;
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc())
+        })
+        .ok()
+        .map(|time| time.timestamp_micros())
+}
+
+fn schema() -> Vec<SchemaNode> {
+    vec![
+        SchemaNode::group("schema", None, 9, Logical::None),
+        SchemaNode::leaf("id", INT64, REQUIRED, Logical::None),
+        SchemaNode::leaf("title", BYTE_ARRAY, REQUIRED, Logical::String),
+        SchemaNode::leaf("content", BYTE_ARRAY, REQUIRED, Logical::String),
+        SchemaNode::leaf("created_at", INT64, OPTIONAL, Logical::Timestamp),
+        SchemaNode::leaf("updated_at", INT64, OPTIONAL, Logical::Timestamp),
+        SchemaNode::leaf("category_id", INT64, OPTIONAL, Logical::None),
+        SchemaNode::leaf("is_pinned", BOOLEAN, REQUIRED, Logical::None),
+        SchemaNode::leaf("is_favorited", BOOLEAN, REQUIRED, Logical::None),
+        SchemaNode::group("tags", Some(REQUIRED), 1, Logical::List),
+        SchemaNode::group("list", Some(REPEATED), 1, Logical::None),
+        SchemaNode::leaf("element", BYTE_ARRAY, REQUIRED, Logical::String),
+    ]
+}
+
+fn columns(notes: &[Value]) -> Vec<Column> {
+    let mut id = Column::new(&["id"], INT64, false);
+    let mut title = Column::new(&["title"], BYTE_ARRAY, false);
+    let mut content = Column::new(&["content"], BYTE_ARRAY, false);
+    let mut created_at = Column::new(&["created_at"], INT64, true);
+    let mut updated_at = Column::new(&["updated_at"], INT64, true);
+    let mut category_id = Column::new(&["category_id"], INT64, true);
+    let mut is_pinned = Column::new(&["is_pinned"], BOOLEAN, false);
+    let mut is_favorited = Column::new(&["is_favorited"], BOOLEAN, false);
+    let mut tags = Column::new(&["tags", "list", "element"], BYTE_ARRAY, false);
+    tags.repeated = true;
+
+    for note in notes {
+        id.push_i64(Some(note["id"].as_i64().unwrap_or(0)));
+        title.push_str(note["title"].as_str().unwrap_or(""));
+        content.push_str(note["content"].as_str().unwrap_or(""));
+        created_at.push_i64(timestamp_micros(&note["created_at"]));
+        updated_at.push_i64(timestamp_micros(&note["updated_at"]));
+        category_id.push_i64(note["category_id"].as_i64());
+        is_pinned.push_bool(note["is_pinned"].as_bool().unwrap_or(false));
+        is_favorited.push_bool(note["is_favorited"].as_bool().unwrap_or(false));
+        tags.push_list(&note_tags(note));
+    }
+    vec![
+        id,
+        title,
+        content,
+        created_at,
+        updated_at,
+        category_id,
+        is_pinned,
+        is_favorited,
+        tags,
+    ]
+}
+
+fn render(notes: &[Value]) -> Vec<u8> {
+    let mut file = MAGIC.to_vec();
+    // (列, 数据页偏移, 页头加数据的字节数)
+    let mut chunks = Vec::new();
+    for column in columns(notes) {
+        let page = column.page();
+        let mut header = Thrift::default();
+        header.i32(1, 0); // DATA_PAGE
+        header.i32(2, page.len() as i32);
+        header.i32(3, page.len() as i32);
+        header.begin(5);
+        header.i32(1, column.num_values as i32);
+        header.i32(2, PLAIN);
+        header.i32(3, RLE);
+        header.i32(4, RLE);
+        header.end();
+        header.end();
+
+        let offset = file.len();
+        file.extend(&header.buf);
+        file.extend(&page);
+        chunks.push((column, offset, header.buf.len() + page.len()));
+    }
+
+    let mut meta = Thrift::default();
+    meta.i32(1, 1);
+    let schema = schema();
+    meta.list(2, T_STRUCT, schema.len());
+    for node in &schema {
+        node.write(&mut meta);
+    }
+    meta.i64(3, notes.len() as i64);
+    meta.list(4, T_STRUCT, 1);
+    meta.enter();
+    meta.list(1, T_STRUCT, chunks.len());
+    for (column, offset, size) in &chunks {
+        meta.enter();
+        meta.i64(2, *offset as i64);
+        meta.begin(3);
+        meta.i32(1, column.kind);
+        meta.list(2, T_I32, 2);
+        meta.zigzag(PLAIN as i64);
+        meta.zigzag(RLE as i64);
+        meta.list(3, T_BINARY, column.path.len());
+        for part in column.path {
+            meta.bytes(part.as_bytes());
+        }
+        meta.i32(4, 0); // UNCOMPRESSED
+        meta.i64(5, column.num_values as i64);
+        meta.i64(6, *size as i64);
+        meta.i64(7, *size as i64);
+        meta.i64(9, *offset as i64);
+        meta.end();
+        meta.end();
+    }
+    meta.i64(2, chunks.iter().map(|(_, _, size)| *size as i64).sum());
+    meta.i64(3, notes.len() as i64);
+    meta.end();
+    meta.string(6, CREATED_BY);
+    meta.end();
+
+    file.extend(&meta.buf);
+    file.extend((meta.buf.len() as u32).to_le_bytes());
+    file.extend(MAGIC);
+    file
+}
+
+pub fn export(notes: &[Value], path: &Path) -> AppResult<()> {
+    std::fs::write(path, render(notes)).map_err(wrap("export.failed"))
+}
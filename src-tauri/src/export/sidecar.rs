@@ -0,0 +1,66 @@
+use super::directory::{note_tags, yaml_list, yaml_string};
+use super::RenderOptions;
+use crate::error::AppError;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SidecarFormat {
+    Json,
+    Yaml,
+}
+
+impl FromStr for SidecarFormat {
+    type Err = AppError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(SidecarFormat::Json),
+            "yaml" | "yml" => Ok(SidecarFormat::Yaml),
+            other => Err(AppError::new("export.unsupported_format").with("format", other)),
+        }
+    }
+}
+
+// 只包含 id、标题、标签和时间，不含正文；缺少的字段不输出
+pub fn render(note: &Value, format: SidecarFormat, options: &RenderOptions) -> String {
+    let timestamps: Vec<(&str, String)> = ["created_at", "updated_at"]
+        .into_iter()
+        .filter_map(|field| {
+            note[field]
+                .as_str()
+                .map(|value| (field, options.timestamp(value)))
+        })
+        .collect();
+    let title = note["title"].as_str().unwrap_or("");
+    let tags = note_tags(note);
+
+    match format {
+        SidecarFormat::Json => {
+            let mut meta = Map::new();
+            if let Some(id) = note["id"].as_i64() {
+                meta.insert("id".to_string(), json!(id));
+            }
+            meta.insert("title".to_string(), json!(title));
+            meta.insert("tags".to_string(), json!(tags));
+            for (field, value) in timestamps {
+                meta.insert(field.to_string(), json!(value));
+            }
+            let mut content = serde_json::to_string_pretty(&meta).unwrap_or_default();
+            content.push('\n');
+            content
+        }
+        SidecarFormat::Yaml => {
+            let mut content = String::new();
+            if let Some(id) = note["id"].as_i64() {
+                content.push_str(&format!("id: {}\n", id));
+            }
+            content.push_str(&format!("title: {}\n", yaml_string(title)));
+            content.push_str(&format!("tags: {}\n", yaml_list(&tags)));
+            for (field, value) in timestamps {
+                content.push_str(&format!("{}: {}\n", field, yaml_string(&value)));
+            }
+            content
+        }
+    }
+}
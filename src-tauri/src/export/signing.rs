@@ -0,0 +1,115 @@
+use crate::error::{AppError, AppResult};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SignatureInfo {
+    pub valid: bool,
+    // 签名使用的密钥 id 与用户 id（GOODSIG / BADSIG 中给出）
+    pub key_id: Option<String>,
+    pub signer: Option<String>,
+    // 签名有效时给出主密钥指纹
+    pub fingerprint: Option<String>,
+}
+
+// 调用系统中的 gpg，--batch 避免在终端中询问；口令仍由 gpg-agent 的 pinentry 输入
+fn gpg(args: &[&str]) -> AppResult<Output> {
+    Command::new("gpg")
+        .arg("--batch")
+        .args(args)
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AppError::new("export.gpg_not_found")
+            } else {
+                AppError::new("export.sign_failed").with("error", e)
+            }
+        })
+}
+
+fn stderr_tail(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().rev().take(5).collect();
+    lines.into_iter().rev().collect::<Vec<_>>().join("\n")
+}
+
+// 签名前确认有可用的私钥；key_id 为空时使用 gpg 的默认密钥（第一把私钥）
+fn ensure_secret_key(key_id: Option<&str>) -> AppResult<()> {
+    let mut args = vec!["--with-colons", "--list-secret-keys"];
+    args.extend(key_id);
+    let output = gpg(&args)?;
+    let has_key = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.starts_with("sec:"));
+    if has_key {
+        Ok(())
+    } else {
+        Err(AppError::new("export.no_signing_key").with("key", key_id.unwrap_or("default")))
+    }
+}
+
+// 为文件生成分离签名 <file>.sig，返回签名文件路径
+pub fn sign(file_path: &Path, key_id: Option<&str>) -> AppResult<PathBuf> {
+    ensure_secret_key(key_id)?;
+
+    let mut sig_path = file_path.as_os_str().to_owned();
+    sig_path.push(".sig");
+    let sig_path = PathBuf::from(sig_path);
+    let sig = sig_path.to_string_lossy();
+    let file = file_path.to_string_lossy();
+
+    let mut args = vec!["--yes"];
+    if let Some(key_id) = key_id {
+        args.extend(["--local-user", key_id]);
+    }
+    args.extend(["--output", &sig, "--detach-sign", &file]);
+    let output = gpg(&args)?;
+    if !output.status.success() {
+        return Err(AppError::new("export.sign_failed").with("error", stderr_tail(&output)));
+    }
+    Ok(sig_path)
+}
+
+// 通过 --status-fd 输出的机器可读状态行判断签名结果
+pub fn verify(file_path: &Path, sig_path: &Path) -> AppResult<SignatureInfo> {
+    let sig = sig_path.to_string_lossy();
+    let file = file_path.to_string_lossy();
+    let output = gpg(&["--status-fd", "1", "--verify", &sig, &file])?;
+
+    let mut info = SignatureInfo {
+        valid: false,
+        key_id: None,
+        signer: None,
+        fingerprint: None,
+    };
+    let mut recognized = false;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some(status) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let mut parts = status.splitn(3, ' ');
+        let keyword = parts.next().unwrap_or_default();
+        let key_id = parts.next().map(str::to_string);
+        let rest = parts.next().map(str::to_string);
+        match keyword {
+            "GOODSIG" | "BADSIG" => {
+                recognized = true;
+                info.valid = keyword == "GOODSIG";
+                info.key_id = key_id;
+                info.signer = rest;
+            }
+            "VALIDSIG" => info.fingerprint = key_id,
+            "NO_PUBKEY" => {
+                return Err(AppError::new("export.signature_unknown_key")
+                    .with("key", key_id.unwrap_or_default()))
+            }
+            _ => {}
+        }
+    }
+
+    if !recognized {
+        return Err(AppError::new("export.verify_failed").with("error", stderr_tail(&output)));
+    }
+    Ok(info)
+}
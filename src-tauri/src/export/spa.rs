@@ -0,0 +1,174 @@
+use super::directory::note_tags;
+use super::html::{escape_html, render_body};
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use crate::readability;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+static NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"note://(\d+)").unwrap());
+
+const STYLE: &str = "body{margin:0;font-family:-apple-system,BlinkMacSystemFont,'Segoe UI','PingFang SC','Microsoft YaHei',sans-serif;line-height:1.6;color:#1f2937}
+aside{position:fixed;top:0;bottom:0;left:0;width:280px;display:flex;flex-direction:column;box-sizing:border-box;border-right:1px solid #e5e7eb;background:#f9fafb}
+#search{margin:12px;padding:6px 10px;border:1px solid #d1d5db;border-radius:6px;font-size:14px}
+#list{flex:1;overflow-y:auto;padding:0 8px 12px}
+#list a{display:block;padding:4px 8px;border-radius:4px;color:#374151;text-decoration:none;overflow:hidden;text-overflow:ellipsis;white-space:nowrap;font-size:14px}
+#list a:hover{background:#e5e7eb}
+#list a.active{background:#dbeafe;color:#1d4ed8}
+#list .empty{padding:4px 8px;color:#9ca3af;font-size:14px}
+main{margin-left:280px;padding:24px 40px;max-width:860px}
+main img{max-width:100%}
+main pre{overflow-x:auto;padding:12px;background:#f3f4f6;border-radius:6px}
+main .tags{color:#6b7280;font-size:14px}
+@media (max-width:700px){aside{position:static;width:auto;max-height:40vh}main{margin-left:0;padding:16px}}";
+
+// 只用浏览器内置的 DOM 接口；data.js 在 file:// 下也能加载，fetch 读取 data.json 仅作为后备
+const APP_SCRIPT: &str = r#"(function () {
+  var list = document.getElementById('list');
+  var search = document.getElementById('search');
+  var view = document.getElementById('note');
+  var notes = [];
+
+  function load(callback) {
+    if (window.NOTES_DATA) {
+      callback(window.NOTES_DATA);
+      return;
+    }
+    fetch('data.json').then(function (response) {
+      return response.json();
+    }).then(callback);
+  }
+
+  // 所有关键词都出现在标题、标签或正文中才算匹配
+  function matches(note, terms) {
+    var haystack = (note.title + '\n' + note.tags.join(' ') + '\n' + note.text).toLowerCase();
+    return terms.every(function (term) {
+      return haystack.indexOf(term) >= 0;
+    });
+  }
+
+  function renderList(current) {
+    var terms = search.value.toLowerCase().split(/\s+/).filter(Boolean);
+    var shown = notes.filter(function (note) {
+      return matches(note, terms);
+    });
+    list.innerHTML = '';
+    shown.forEach(function (note) {
+      var link = document.createElement('a');
+      link.href = '#note-' + note.id;
+      link.textContent = note.title;
+      if (current && note.id === current.id) {
+        link.className = 'active';
+      }
+      list.appendChild(link);
+    });
+    if (!shown.length) {
+      var empty = document.createElement('p');
+      empty.className = 'empty';
+      empty.textContent = list.getAttribute('data-empty');
+      list.appendChild(empty);
+    }
+  }
+
+  function current() {
+    var id = Number(location.hash.replace('#note-', ''));
+    return notes.filter(function (note) {
+      return note.id === id;
+    })[0] || notes[0];
+  }
+
+  function show() {
+    var note = current();
+    view.innerHTML = '';
+    renderList(note);
+    if (!note) {
+      return;
+    }
+    document.title = note.title;
+    var heading = document.createElement('h1');
+    heading.textContent = note.title;
+    view.appendChild(heading);
+    if (note.tags.length) {
+      var tags = document.createElement('p');
+      tags.className = 'tags';
+      tags.textContent = note.tags.map(function (tag) {
+        return '#' + tag;
+      }).join(' ');
+      view.appendChild(tags);
+    }
+    var body = document.createElement('div');
+    body.innerHTML = note.html;
+    view.appendChild(body);
+    window.scrollTo(0, 0);
+  }
+
+  load(function (data) {
+    notes = data.notes;
+    search.addEventListener('input', function () {
+      renderList(current());
+    });
+    window.addEventListener('hashchange', show);
+    show();
+  });
+})();
+"#;
+
+fn index_html(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<aside>\n<input id=\"search\" type=\"search\" placeholder=\"{}\">\n<nav id=\"list\" data-empty=\"{}\"></nav>\n</aside>\n<main id=\"note\"></main>\n<script src=\"data.js\"></script>\n<script src=\"app.js\"></script>\n</body>\n</html>\n",
+        escape_html(title),
+        STYLE,
+        escape_html(&t("export.spa_search", &[])),
+        escape_html(&t("export.spa_empty", &[]))
+    )
+}
+
+// 可直接部署的静态阅读器：data.json 为笔记数据（正文已渲染为 HTML，另附用于搜索的纯文本），
+// data.js 为同样内容的脚本版本，使 index.html 直接从本地打开时也能读取；笔记间的 note:// 链接改为页内锚点，
+// 附件不复制
+pub fn export(notes: &[Value], dir: &Path) -> AppResult<usize> {
+    fs::create_dir_all(dir).map_err(wrap("export.create_dir_failed"))?;
+
+    let untitled = t("export.untitled", &[]);
+    let entries: Vec<Value> = notes
+        .iter()
+        .map(|note| {
+            let content = note["content"].as_str().unwrap_or_default();
+            let html = render_body(content);
+            let html = NOTE_LINK.replace_all(&html, "#note-$1");
+            json!({
+                "id": note["id"],
+                "title": note["title"]
+                    .as_str()
+                    .filter(|title| !title.trim().is_empty())
+                    .unwrap_or(&untitled),
+                "tags": note_tags(note),
+                "created_at": note["created_at"],
+                "updated_at": note["updated_at"],
+                "html": html,
+                "text": readability::text_blocks(content).join("\n"),
+            })
+        })
+        .collect();
+    let title = t("export.html_title", &[("count", notes.len().to_string())]);
+    let data = serde_json::to_string(&json!({
+        "version": "1.0",
+        "title": title,
+        "notes": entries,
+    }))
+    .map_err(wrap("export.serialize_failed"))?;
+
+    for (name, content) in [
+        ("data.json", data.clone()),
+        ("data.js", format!("window.NOTES_DATA = {};\n", data)),
+        ("app.js", APP_SCRIPT.to_string()),
+        ("index.html", index_html(&title)),
+    ] {
+        fs::write(dir.join(name), content).map_err(wrap("export.failed"))?;
+    }
+
+    Ok(notes.len())
+}
@@ -0,0 +1,74 @@
+use super::directory::note_tags;
+use crate::error::{wrap, AppResult};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+// 对外公开的精简结构，不包含编辑器类型、分类 id 等应用内部字段；修改时同步增加 user_version
+//   notes     (id, title, content, created_at, updated_at, is_pinned, is_favorited)
+//   note_tags (note_id, tag)
+const SCHEMA_VERSION: i64 = 1;
+const SCHEMA: &str = "
+    CREATE TABLE notes (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at TEXT,
+        updated_at TEXT,
+        is_pinned INTEGER NOT NULL DEFAULT 0,
+        is_favorited INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE note_tags (
+        note_id INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (note_id, tag)
+    );
+    CREATE INDEX idx_notes_created_at ON notes (created_at);
+    CREATE INDEX idx_note_tags_tag ON note_tags (tag);";
+
+fn write(conn: &mut Connection, notes: &[Value]) -> rusqlite::Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+    let tx = conn.transaction()?;
+    {
+        let mut insert_note = tx.prepare(
+            "INSERT INTO notes (id, title, content, created_at, updated_at, is_pinned, is_favorited)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut insert_tag =
+            tx.prepare("INSERT OR IGNORE INTO note_tags (note_id, tag) VALUES (?1, ?2)")?;
+
+        for note in notes {
+            insert_note.execute(params![
+                note["id"].as_i64(),
+                note["title"].as_str().unwrap_or(""),
+                note["content"].as_str().unwrap_or(""),
+                note["created_at"].as_str(),
+                note["updated_at"].as_str(),
+                note["is_pinned"].as_bool().unwrap_or(false),
+                note["is_favorited"].as_bool().unwrap_or(false),
+            ])?;
+            let id = tx.last_insert_rowid();
+            for tag in note_tags(note) {
+                insert_tag.execute(params![id, tag])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+// 先写入临时文件再替换目标文件，确保得到的是全新的数据库而不是追加到已有文件
+pub fn export(notes: &[Value], path: &Path) -> AppResult<()> {
+    let part_path = PathBuf::from(format!("{}.part", path.display()));
+    let _ = std::fs::remove_file(&part_path);
+
+    let result = crate::db::open(&part_path)
+        .and_then(|mut conn| write(&mut conn, notes))
+        .map_err(wrap("export.failed"))
+        .and_then(|_| std::fs::rename(&part_path, path).map_err(wrap("export.failed")));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&part_path);
+    }
+    result
+}
@@ -0,0 +1,176 @@
+use crate::error::{wrap, AppError, AppResult};
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::LazyLock;
+
+// [HH:MM:SS] 或 [MM:SS]，秒后可带 .mmm 毫秒，行首允许列表标记
+static CUE_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[-*+]\s+)?\[([^\]]*)\]\s*(.*)$").unwrap());
+static TIMESTAMP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{1,2}):(\d{2})(?::(\d{2}))?(?:[.,](\d{1,3}))?$").unwrap());
+
+// 最后一条字幕没有下一条的开始时间作为结束，默认显示的时长
+const LAST_CUE_MS: u64 = 5000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl std::str::FromStr for SubtitleFormat {
+    type Err = AppError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "srt" => Ok(SubtitleFormat::Srt),
+            "vtt" | "webvtt" => Ok(SubtitleFormat::Vtt),
+            other => Err(AppError::new("export.unsupported_format").with("format", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MalformedLine {
+    // 从 1 开始的行号
+    pub line: usize,
+    pub text: String,
+    // missing_timestamp / invalid_timestamp / out_of_order / empty_text
+    pub reason: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SubtitleExport {
+    pub cues: usize,
+    // 被跳过的非空行，不会写入字幕文件
+    pub malformed: Vec<MalformedLine>,
+}
+
+struct Cue {
+    start: u64,
+    text: String,
+}
+
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let caps = TIMESTAMP.captures(value.trim())?;
+    let number = |i: usize| {
+        caps.get(i)
+            .map_or(0, |m| m.as_str().parse::<u64>().unwrap_or(0))
+    };
+    // 只有两段时为 MM:SS
+    let (hours, minutes, seconds) = if caps.get(3).is_some() {
+        (number(1), number(2), number(3))
+    } else {
+        (0, number(1), number(2))
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    // .5 表示 500 毫秒
+    let millis = caps.get(4).map_or(0, |m| {
+        m.as_str().parse::<u64>().unwrap_or(0) * 10u64.pow(3 - m.as_str().len() as u32)
+    });
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+fn parse_cues(content: &str) -> (Vec<Cue>, Vec<MalformedLine>) {
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut malformed = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut report = |reason| {
+            malformed.push(MalformedLine {
+                line: i + 1,
+                text: line.to_string(),
+                reason,
+            })
+        };
+        let Some(caps) = CUE_LINE.captures(line) else {
+            report("missing_timestamp");
+            continue;
+        };
+        let Some(start) = parse_timestamp(&caps[1]) else {
+            report("invalid_timestamp");
+            continue;
+        };
+        let text = caps[2].trim();
+        if text.is_empty() {
+            report("empty_text");
+            continue;
+        }
+        // 时间必须递增，否则上一条字幕的结束时间会早于开始时间
+        if cues.last().is_some_and(|last| start <= last.start) {
+            report("out_of_order");
+            continue;
+        }
+        cues.push(Cue {
+            start,
+            text: text.to_string(),
+        });
+    }
+    (cues, malformed)
+}
+
+fn format_time(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn render(cues: &[Cue], format: SubtitleFormat) -> String {
+    let mut output = match format {
+        SubtitleFormat::Srt => String::new(),
+        SubtitleFormat::Vtt => "WEBVTT\n\n".to_string(),
+    };
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+    for (i, cue) in cues.iter().enumerate() {
+        let end = cues
+            .get(i + 1)
+            .map_or(cue.start + LAST_CUE_MS, |next| next.start);
+        let text = match format {
+            SubtitleFormat::Srt => cue.text.clone(),
+            // WebVTT 中 & 和 < 有特殊含义
+            SubtitleFormat::Vtt => cue
+                .text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+        };
+        if format == SubtitleFormat::Srt {
+            output.push_str(&format!("{}\n", i + 1));
+        }
+        output.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_time(cue.start, separator),
+            format_time(end, separator),
+            text
+        ));
+    }
+    output
+}
+
+// 把 [HH:MM:SS] 文本 格式的行转换为字幕，每条字幕显示到下一条开始，最后一条显示 5 秒；
+// 无法解析的行跳过并在结果中列出，没有任何有效行时返回 export.no_cues
+pub fn export(content: &str, format: SubtitleFormat, path: &Path) -> AppResult<SubtitleExport> {
+    let (cues, malformed) = parse_cues(content);
+    if cues.is_empty() {
+        return Err(AppError::new("export.no_cues"));
+    }
+    std::fs::write(path, render(&cues, format)).map_err(wrap("export.failed"))?;
+    Ok(SubtitleExport {
+        cues: cues.len(),
+        malformed,
+    })
+}
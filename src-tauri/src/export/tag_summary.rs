@@ -0,0 +1,48 @@
+use super::directory::note_tags;
+use crate::i18n::t;
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn link(note: &Value, untitled: &str) -> String {
+    let title = note["title"]
+        .as_str()
+        .unwrap_or(untitled)
+        .replace('\n', " ");
+    match note["id"].as_i64() {
+        Some(id) => format!("- [{}](note://{})\n", title, id),
+        None => format!("- {}\n", title),
+    }
+}
+
+// 每个标签一节，按笔记数从多到少排列，没有标签的笔记放在最后
+pub fn render(notes: &[Value]) -> String {
+    let mut by_tag: HashMap<String, Vec<&Value>> = HashMap::new();
+    let mut untagged = Vec::new();
+    for note in notes {
+        let mut tags = note_tags(note);
+        tags.dedup();
+        if tags.is_empty() {
+            untagged.push(note);
+        }
+        for tag in tags {
+            by_tag.entry(tag).or_default().push(note);
+        }
+    }
+
+    let mut tags: Vec<(String, Vec<&Value>)> = by_tag.into_iter().collect();
+    tags.sort_by(|(a, a_notes), (b, b_notes)| b_notes.len().cmp(&a_notes.len()).then(a.cmp(b)));
+
+    let untitled = t("export.untitled", &[]);
+    let mut markdown = format!("# {}\n", t("export.tag_summary_heading", &[]));
+    let untagged_section = (t("export.untagged", &[]), untagged);
+    for (tag, notes) in tags.iter().chain([&untagged_section]) {
+        if notes.is_empty() {
+            continue;
+        }
+        markdown.push_str(&format!("\n## {} ({})\n\n", tag, notes.len()));
+        for note in notes {
+            markdown.push_str(&link(note, &untitled));
+        }
+    }
+    markdown
+}
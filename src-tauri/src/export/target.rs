@@ -0,0 +1,134 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const BOM: char = '\u{FEFF}';
+// 只读取开头一段判断编码
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportTarget {
+    pub exists: bool,
+    pub writable: bool,
+    pub read_only: bool,
+    pub has_bom: bool,
+    // utf-8、utf-8-bom、utf-16le、utf-16be、utf-32le、utf-32be、binary 或 unknown（如 GBK 等本地编码），文件不存在时为 None
+    pub encoding: Option<String>,
+    // 覆盖前需要提示用户的问题
+    pub warnings: Vec<String>,
+}
+
+fn detect_encoding(sample: &[u8]) -> &'static str {
+    match sample {
+        [0xEF, 0xBB, 0xBF, ..] => "utf-8-bom",
+        [0xFF, 0xFE, 0x00, 0x00, ..] => "utf-32le",
+        [0x00, 0x00, 0xFE, 0xFF, ..] => "utf-32be",
+        [0xFF, 0xFE, ..] => "utf-16le",
+        [0xFE, 0xFF, ..] => "utf-16be",
+        _ => match std::str::from_utf8(sample) {
+            Ok(text) if !text.contains('\0') => "utf-8",
+            // 采样截断在多字节字符中间
+            Err(e) if e.error_len().is_none() => "utf-8",
+            _ if sample.contains(&0) => "binary",
+            _ => "unknown",
+        },
+    }
+}
+
+// 文件不存在时检查所在目录能否写入
+fn dir_writable(path: &Path) -> bool {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::metadata(dir).is_ok_and(|meta| meta.is_dir() && !meta.permissions().readonly())
+}
+
+pub fn inspect(path: &Path) -> AppResult<ExportTarget> {
+    let Ok(metadata) = fs::metadata(path) else {
+        let writable = dir_writable(path);
+        return Ok(ExportTarget {
+            exists: false,
+            writable,
+            read_only: false,
+            has_bom: false,
+            encoding: None,
+            warnings: if writable {
+                Vec::new()
+            } else {
+                vec![t("export.target_not_writable", &[])]
+            },
+        });
+    };
+
+    let read_only = metadata.permissions().readonly();
+    // 以写方式打开但不截断，能发现权限位之外的限制（如文件被其他程序独占）
+    let writable =
+        metadata.is_file() && !read_only && OpenOptions::new().write(true).open(path).is_ok();
+    let encoding = if metadata.is_file() {
+        let mut sample = Vec::new();
+        File::open(path)
+            .and_then(|file| file.take(SAMPLE_SIZE).read_to_end(&mut sample))
+            .map_err(wrap("export.failed"))?;
+        Some(detect_encoding(&sample))
+    } else {
+        None
+    };
+    // UTF-16/32 只能靠 BOM 识别
+    let has_bom = matches!(
+        encoding,
+        Some("utf-8-bom" | "utf-16le" | "utf-16be" | "utf-32le" | "utf-32be")
+    );
+
+    let mut warnings = Vec::new();
+    if read_only {
+        warnings.push(t("export.target_read_only", &[]));
+    } else if !writable {
+        warnings.push(t("export.target_not_writable", &[]));
+    }
+    match encoding {
+        Some("utf-8-bom") => warnings.push(t("export.target_bom", &[])),
+        Some(encoding @ ("utf-16le" | "utf-16be" | "utf-32le" | "utf-32be" | "unknown")) => {
+            warnings.push(t(
+                "export.target_encoding",
+                &[("encoding", encoding.to_string())],
+            ))
+        }
+        _ => {}
+    }
+
+    Ok(ExportTarget {
+        exists: true,
+        writable,
+        read_only,
+        has_bom,
+        encoding: encoding.map(str::to_string),
+        warnings,
+    })
+}
+
+// 文本导出统一写为不带 BOM 的 UTF-8；从带 BOM 的文件导入的笔记正文开头可能残留 U+FEFF，写入前去掉
+pub fn without_bom(content: &str) -> &str {
+    content.strip_prefix(BOM).unwrap_or(content)
+}
+
+pub fn write_text(path: impl AsRef<Path>, content: &str) -> AppResult<()> {
+    write_bytes(path, without_bom(content).as_bytes())
+}
+
+// 先写到同目录的临时文件再重命名，写入中断时不会留下不完整的导出文件
+pub fn write_bytes(path: impl AsRef<Path>, bytes: &[u8]) -> AppResult<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, bytes)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            AppError::new("export.failed").with("error", e)
+        })
+}
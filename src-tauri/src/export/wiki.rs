@@ -0,0 +1,392 @@
+use super::directory::note_tags;
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+static NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^note://(\d+)$").unwrap());
+
+// 页面名中 MediaWiki 不允许的字符，以及文件名中不能使用的字符；导入脚本以文件名作为页面名
+const FORBIDDEN: &str = "#<>[]|{}/\\:*?\"";
+// 文本中含这些字符或字符序列时包进 <nowiki>，避免被当作 Wiki 标记（'' 粗斜体、~~~ 签名、__TOC__ 等魔术字）
+const WIKI_SPECIAL: &str = "[]{}|<>&";
+const WIKI_SEQUENCES: [&str; 3] = ["''", "~~~", "__"];
+// 行首的这些字符在 MediaWiki 中有含义（列表、缩进、标题、预格式化）
+const LINE_START_SPECIAL: &str = "*#:;= ";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WikiDialect {
+    MediaWiki,
+}
+
+impl FromStr for WikiDialect {
+    type Err = AppError;
+
+    fn from_str(dialect: &str) -> Result<Self, Self::Err> {
+        match dialect.to_lowercase().as_str() {
+            "mediawiki" => Ok(WikiDialect::MediaWiki),
+            other => Err(AppError::new("export.unsupported_dialect").with("dialect", other)),
+        }
+    }
+}
+
+impl WikiDialect {
+    pub fn extension(self) -> &'static str {
+        match self {
+            WikiDialect::MediaWiki => "wiki",
+        }
+    }
+}
+
+fn page_name(title: &str) -> String {
+    let name: String = title
+        .trim()
+        .chars()
+        .map(|c| {
+            if FORBIDDEN.contains(c) || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let name = name.trim_matches(['-', ' ', '.']).to_string();
+    if name.is_empty() {
+        t("export.untitled", &[])
+    } else {
+        name
+    }
+}
+
+fn nowiki(text: &str) -> String {
+    format!(
+        "<nowiki>{}</nowiki>",
+        text.replace("</nowiki>", "&lt;/nowiki&gt;")
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+struct Link {
+    url: String,
+    image: bool,
+}
+
+struct Renderer<'a> {
+    // 笔记 id 到页面名，链接到未导出的笔记时只保留文字
+    pages: &'a HashMap<i64, String>,
+    buffers: Vec<String>,
+    links: Vec<Link>,
+    // 每层列表是否为有序列表
+    lists: Vec<bool>,
+    code_block: Option<&'static str>,
+    table_head: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn out(&mut self) -> &mut String {
+        self.buffers.last_mut().unwrap()
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out().push_str(text);
+    }
+
+    fn block_break(&mut self, blank_line: bool) {
+        let separator = if blank_line { "\n\n" } else { "\n" };
+        let out = self.out();
+        if out.is_empty() {
+            return;
+        }
+        while !out.ends_with(separator) {
+            out.push('\n');
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let at_line_start = self.out().is_empty() || self.out().ends_with('\n');
+        let special = text.contains(|c| WIKI_SPECIAL.contains(c))
+            || WIKI_SEQUENCES.iter().any(|seq| text.contains(seq))
+            || (at_line_start && text.starts_with(|c| LINE_START_SPECIAL.contains(c)));
+        if special {
+            self.push(&nowiki(text));
+        } else {
+            self.push(text);
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph if self.lists.is_empty() => self.block_break(true),
+            Tag::Heading { level, .. } => {
+                self.block_break(true);
+                self.push(&format!("{} ", "=".repeat(level as usize)));
+            }
+            Tag::BlockQuote(_) => {
+                self.block_break(true);
+                self.push("<blockquote>\n");
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_break(true);
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => {
+                        language.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                // 有语言时使用 SyntaxHighlight 扩展，内容原样保留；<pre> 中需要转义 HTML
+                if language.is_empty() {
+                    self.push("<pre>\n");
+                    self.code_block = Some("</pre>");
+                } else {
+                    self.push(&format!("<syntaxhighlight lang=\"{}\">\n", language));
+                    self.code_block = Some("</syntaxhighlight>");
+                }
+            }
+            Tag::List(start) => {
+                self.block_break(self.lists.is_empty());
+                self.lists.push(start.is_some());
+            }
+            Tag::Item => {
+                self.block_break(false);
+                let marker: String = self
+                    .lists
+                    .iter()
+                    .map(|ordered| if *ordered { '#' } else { '*' })
+                    .collect();
+                self.push(&format!("{} ", marker));
+            }
+            Tag::Emphasis => self.push("''"),
+            Tag::Strong => self.push("'''"),
+            Tag::Strikethrough => self.push("<s>"),
+            Tag::Link { dest_url, .. } => {
+                self.links.push(Link {
+                    url: dest_url.to_string(),
+                    image: false,
+                });
+                self.buffers.push(String::new());
+            }
+            Tag::Image { dest_url, .. } => {
+                self.links.push(Link {
+                    url: dest_url.to_string(),
+                    image: true,
+                });
+                self.buffers.push(String::new());
+            }
+            Tag::Table(_) => {
+                self.block_break(true);
+                self.push("{| class=\"wikitable\"\n");
+            }
+            Tag::TableHead => {
+                self.table_head = true;
+                self.push("|-\n");
+            }
+            Tag::TableRow => self.push("|-\n"),
+            Tag::TableCell => self.push(if self.table_head { "! " } else { "| " }),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(level) => {
+                let marker = format!(" {}", "=".repeat(level as usize));
+                self.push(&marker);
+            }
+            TagEnd::BlockQuote(_) => {
+                self.block_break(false);
+                self.push("</blockquote>");
+            }
+            TagEnd::CodeBlock => {
+                if !self.out().ends_with('\n') {
+                    self.push("\n");
+                }
+                if let Some(close) = self.code_block.take() {
+                    self.push(close);
+                }
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.push("''"),
+            TagEnd::Strong => self.push("'''"),
+            TagEnd::Strikethrough => self.push("</s>"),
+            TagEnd::Link | TagEnd::Image => {
+                let text = self.buffers.pop().unwrap_or_default();
+                let Some(link) = self.links.pop() else {
+                    return;
+                };
+                let rendered = self.link(&link, text);
+                self.push(&rendered);
+            }
+            TagEnd::Table => {
+                self.block_break(false);
+                self.push("|}");
+            }
+            TagEnd::TableHead => {
+                self.table_head = false;
+                self.block_break(false);
+            }
+            TagEnd::TableRow => self.block_break(false),
+            TagEnd::TableCell => self.push("\n"),
+            _ => {}
+        }
+    }
+
+    // 笔记链接转为 [[页面名|文字]]，外部链接转为 [地址 文字]，本地图片转为 [[File:文件名]]
+    fn link(&self, link: &Link, text: String) -> String {
+        let external = ["http://", "https://", "mailto:", "ftp://"]
+            .iter()
+            .any(|scheme| link.url.starts_with(scheme));
+        if let Some(caps) = NOTE_LINK.captures(&link.url) {
+            let page = caps[1]
+                .parse::<i64>()
+                .ok()
+                .and_then(|id| self.pages.get(&id));
+            return match page {
+                Some(page) if text.is_empty() || text == *page => format!("[[{}]]", page),
+                Some(page) => format!("[[{}|{}]]", page, text),
+                None => text,
+            };
+        }
+        if link.image && !external {
+            let file = link.url.rsplit(['/', '\\']).next().unwrap_or(&link.url);
+            return if text.is_empty() {
+                format!("[[File:{}]]", file)
+            } else {
+                format!("[[File:{}|{}]]", file, text)
+            };
+        }
+        if external {
+            let url = link.url.replace(' ', "%20");
+            return if text.is_empty() {
+                format!("[{}]", url)
+            } else {
+                format!("[{} {}]", url, text)
+            };
+        }
+        text
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.code_block == Some("</pre>") => {
+                self.push(&escape_html(&text));
+            }
+            Event::Text(text) if self.code_block.is_some() => self.push(&text),
+            Event::Text(text) => self.text(&text),
+            // MediaWiki 本身接受部分 HTML 标签，原样保留
+            Event::Html(html) | Event::InlineHtml(html) => self.push(&html),
+            Event::Code(code) => {
+                let code = format!("<code>{}</code>", nowiki(&code));
+                self.push(&code);
+            }
+            // 单个换行在列表项中会结束该项，软换行改为空格
+            Event::SoftBreak => self.push(" "),
+            Event::HardBreak => self.push("<br />"),
+            Event::Rule => {
+                self.block_break(true);
+                self.push("----");
+            }
+            Event::TaskListMarker(done) => self.push(if done { "☑ " } else { "☐ " }),
+            _ => {}
+        }
+    }
+}
+
+// 转换为 MediaWiki 标记，支持的子集：标题、段落、粗体 / 斜体 / 删除线、行内代码和代码块、
+// 有序 / 无序 / 嵌套列表、任务列表（转为 ☑ / ☐）、引用、表格、分隔线、硬换行、
+// 外部链接、笔记链接和图片；脚注、定义列表和数学公式不转换
+fn render(content: &str, pages: &HashMap<i64, String>) -> String {
+    let options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer {
+        pages,
+        buffers: vec![String::new()],
+        links: Vec::new(),
+        lists: Vec::new(),
+        code_block: None,
+        table_head: false,
+    };
+    for event in Parser::new_ext(content, options) {
+        renderer.event(event);
+    }
+    renderer.buffers.swap_remove(0).trim().to_string()
+}
+
+fn category(tag: &str) -> String {
+    format!("[[Category:{}]]", page_name(tag))
+}
+
+// 每条笔记写入一个 <页面名>.wiki 文件，标签转为分类，另生成列出全部页面和分类的索引页；
+// 页面名与文件名一致，可直接用 importTextFiles.php 导入。返回写入的笔记页数
+pub fn export(notes: &[Value], dir: &Path, dialect: WikiDialect) -> AppResult<usize> {
+    std::fs::create_dir_all(dir).map_err(wrap("export.create_dir_failed"))?;
+
+    // 页面名不区分大小写地去重，避免在不区分大小写的文件系统上互相覆盖
+    let index_name = t("export.wiki_index", &[]);
+    let mut used: HashSet<String> = HashSet::from([index_name.to_lowercase()]);
+    let untitled = t("export.untitled", &[]);
+    let names: Vec<String> = notes
+        .iter()
+        .map(|note| {
+            let base = page_name(note["title"].as_str().unwrap_or(&untitled));
+            let mut name = base.clone();
+            let mut n = 2;
+            while !used.insert(name.to_lowercase()) {
+                name = format!("{} ({})", base, n);
+                n += 1;
+            }
+            name
+        })
+        .collect();
+    let pages: HashMap<i64, String> = notes
+        .iter()
+        .zip(&names)
+        .filter_map(|(note, name)| note["id"].as_i64().map(|id| (id, name.clone())))
+        .collect();
+
+    let extension = dialect.extension();
+    let mut categories = BTreeSet::new();
+    for (note, name) in notes.iter().zip(&names) {
+        let mut page = render(note["content"].as_str().unwrap_or(""), &pages);
+        let tags = note_tags(note);
+        if !tags.is_empty() {
+            page.push_str("\n\n");
+            let lines: Vec<String> = tags.iter().map(|tag| category(tag)).collect();
+            page.push_str(&lines.join("\n"));
+        }
+        page.push('\n');
+        categories.extend(tags);
+        std::fs::write(dir.join(format!("{}.{}", name, extension)), page)
+            .map_err(wrap("export.failed"))?;
+    }
+
+    let mut sorted: Vec<&String> = names.iter().collect();
+    sorted.sort();
+    let mut index = String::new();
+    for name in sorted {
+        index.push_str(&format!("* [[{}]]\n", name));
+    }
+    if !categories.is_empty() {
+        index.push_str(&format!("\n== {} ==\n", t("export.wiki_categories", &[])));
+        for tag in &categories {
+            index.push_str(&format!("* [[:Category:{}]]\n", page_name(tag)));
+        }
+    }
+    std::fs::write(dir.join(format!("{}.{}", index_name, extension)), index)
+        .map_err(wrap("export.failed"))?;
+
+    Ok(notes.len())
+}
@@ -0,0 +1,493 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use crate::link_preview::{self, Validators};
+use crate::web_clip::{self, ClipMode};
+use crate::{db, read_only, settings, shutdown};
+use chrono::{DateTime, Utc};
+use feed_rs::model::Entry;
+use reqwest::Url;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Emitter;
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_FEED_SIZE: usize = 10 * 1024 * 1024;
+// 首次抓取或长时间未抓取时，每个订阅最多导入的条目数
+const MAX_ITEMS_PER_FETCH: usize = 50;
+const FEED_ACCEPT: &str =
+    "application/rss+xml, application/atom+xml, application/xml;q=0.9, text/xml;q=0.9, */*;q=0.8";
+
+// 订阅和已导入的条目；条目按 guid 或链接去重，删除笔记后也不会重新导入
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS feeds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        url TEXT NOT NULL UNIQUE,
+        title TEXT,
+        category_id INTEGER,
+        mode TEXT NOT NULL,
+        etag TEXT,
+        last_modified TEXT,
+        last_fetched_at DATETIME,
+        last_error TEXT,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE IF NOT EXISTS feed_items (
+        feed_id INTEGER NOT NULL,
+        guid TEXT NOT NULL,
+        link TEXT,
+        note_id INTEGER,
+        fetched_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (feed_id, guid)
+    );
+    CREATE INDEX IF NOT EXISTS idx_feed_items_link ON feed_items (feed_id, link);";
+
+// 同一时间只进行一轮抓取
+static FETCHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedMode {
+    // 每个条目一条笔记
+    ItemPerNote,
+    // 每次抓取的新条目合并为一条摘要笔记
+    DigestNotePerFetch,
+}
+
+impl FeedMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            FeedMode::ItemPerNote => "item_per_note",
+            FeedMode::DigestNotePerFetch => "digest_note_per_fetch",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Feed {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub category_id: Option<i64>,
+    pub mode: FeedMode,
+    pub last_fetched_at: Option<String>,
+    pub last_error: Option<String>,
+    pub items: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FeedFetchResult {
+    pub feed_id: i64,
+    // 新导入的条目数
+    pub added: usize,
+    pub error: Option<String>,
+}
+
+struct FeedItem {
+    guid: String,
+    link: Option<String>,
+    title: String,
+    published: Option<DateTime<Utc>>,
+    markdown: String,
+    images: Vec<Url>,
+}
+
+fn load_feeds(conn: &Connection) -> rusqlite::Result<Vec<Feed>> {
+    conn.execute_batch(SCHEMA)?;
+    let mut stmt = conn.prepare(
+        "SELECT f.id, f.url, f.title, f.category_id, f.mode, f.last_fetched_at, f.last_error,
+                (SELECT COUNT(*) FROM feed_items i WHERE i.feed_id = f.id)
+           FROM feeds f ORDER BY f.id",
+    )?;
+    let feeds = stmt
+        .query_map([], |row| {
+            let mode: String = row.get(4)?;
+            Ok(Feed {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get(2)?,
+                category_id: row.get(3)?,
+                mode: serde_json::from_value(serde_json::Value::String(mode))
+                    .unwrap_or(FeedMode::ItemPerNote),
+                last_fetched_at: row.get(5)?,
+                last_error: row.get(6)?,
+                items: row.get(7)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(feeds)
+}
+
+// 笔记正文：来源和发布时间写入 frontmatter，与网页剪藏一致
+fn item_content(item: &FeedItem) -> String {
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    let mut frontmatter = String::from("---\n");
+    if let Some(link) = &item.link {
+        frontmatter.push_str(&format!("source: {}\n", quote(link)));
+    }
+    if let Some(published) = item.published {
+        frontmatter.push_str(&format!(
+            "published_at: {}\n",
+            quote(&published.to_rfc3339())
+        ));
+    }
+    frontmatter.push_str("---\n\n");
+    format!("{}{}", frontmatter, item.markdown)
+}
+
+fn digest_content(items: &[FeedItem]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let heading = match &item.link {
+                Some(link) => format!("## [{}]({})", item.title, link),
+                None => format!("## {}", item.title),
+            };
+            format!("{}\n\n{}", heading, item.markdown)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// 条目内容优先取全文，其次取摘要；HTML 经网页剪藏的转换器清理为 Markdown
+fn convert_entry(entry: Entry, base: &Url) -> FeedItem {
+    let link = entry.links.first().map(|link| link.href.clone());
+    let title = entry
+        .title
+        .map(|title| title.content.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .or_else(|| link.clone())
+        .unwrap_or_else(|| t("export.untitled", &[]));
+    let html = entry
+        .content
+        .and_then(|content| content.body)
+        .or_else(|| entry.summary.map(|summary| summary.content))
+        .unwrap_or_default();
+    let item_base = link
+        .as_deref()
+        .and_then(|link| Url::parse(link).ok())
+        .unwrap_or_else(|| base.clone());
+    let (markdown, images) = web_clip::convert(&html, &item_base, ClipMode::Full);
+    FeedItem {
+        guid: if entry.id.is_empty() {
+            link.clone().unwrap_or_else(|| title.clone())
+        } else {
+            entry.id
+        },
+        link,
+        title,
+        published: entry.published.or(entry.updated),
+        markdown,
+        images,
+    }
+}
+
+fn record_error(app: &tauri::AppHandle, feed_id: i64, error: &str) {
+    let result = db::with_write_conn(app, |conn| {
+        conn.execute(
+            "UPDATE feeds SET last_error = ?1, last_fetched_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![error, feed_id],
+        )
+    });
+    if let Err(e) = result {
+        log::warn!("{}", e);
+    }
+}
+
+// 保存新条目为笔记并记录抓取状态，返回 (笔记 id, 条目) 以便之后下载图片
+fn save_items(
+    conn: &mut Connection,
+    feed: &Feed,
+    feed_title: Option<&str>,
+    items: Vec<FeedItem>,
+    validators: &Validators,
+) -> rusqlite::Result<Vec<(i64, FeedItem)>> {
+    let tx = conn.transaction()?;
+    let mut saved: Vec<(i64, FeedItem)> = Vec::new();
+    let insert = |title: &str, content: &str| -> rusqlite::Result<i64> {
+        let note = db::insert_note(&tx, title, content)?;
+        let id = note["id"].as_i64().unwrap_or_default();
+        // 目标分类已删除时放在未分类中
+        tx.execute(
+            "UPDATE notes SET category_id = (SELECT id FROM categories WHERE id = ?1)
+              WHERE id = ?2",
+            params![feed.category_id, id],
+        )?;
+        Ok(id)
+    };
+
+    match feed.mode {
+        FeedMode::ItemPerNote => {
+            for item in items {
+                let id = insert(&item.title, &item_content(&item))?;
+                saved.push((id, item));
+            }
+        }
+        FeedMode::DigestNotePerFetch if !items.is_empty() => {
+            let title = t(
+                "feed.digest_title",
+                &[
+                    ("feed", feed_title.unwrap_or(&feed.url).to_string()),
+                    ("date", chrono::Local::now().format("%Y-%m-%d").to_string()),
+                ],
+            );
+            let id = insert(&title, &digest_content(&items))?;
+            saved.extend(items.into_iter().map(|item| (id, item)));
+        }
+        FeedMode::DigestNotePerFetch => {}
+    }
+
+    for (note_id, item) in &saved {
+        tx.execute(
+            "INSERT OR IGNORE INTO feed_items (feed_id, guid, link, note_id) VALUES (?1, ?2, ?3, ?4)",
+            params![feed.id, item.guid, item.link, note_id],
+        )?;
+    }
+    tx.execute(
+        "UPDATE feeds SET title = COALESCE(?1, title), etag = ?2, last_modified = ?3,
+                last_fetched_at = CURRENT_TIMESTAMP, last_error = NULL
+          WHERE id = ?4",
+        params![
+            feed_title,
+            validators.etag,
+            validators.last_modified,
+            feed.id
+        ],
+    )?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+async fn fetch_feed(app: &tauri::AppHandle, feed: &Feed) -> AppResult<usize> {
+    let url = link_preview::parse_url(&feed.url)?;
+    let validators = db::with_read_conn(app, |conn| {
+        conn.query_row(
+            "SELECT etag, last_modified FROM feeds WHERE id = ?1",
+            [feed.id],
+            |row| {
+                Ok(Validators {
+                    etag: row.get(0)?,
+                    last_modified: row.get(1)?,
+                })
+            },
+        )
+    })?;
+
+    let Some(download) = link_preview::download_if_modified(
+        &url,
+        FEED_ACCEPT,
+        FEED_TIMEOUT,
+        MAX_FEED_SIZE,
+        &validators,
+    )
+    .await?
+    else {
+        // 304：内容未变化
+        db::with_write_conn(app, |conn| {
+            conn.execute(
+                "UPDATE feeds SET last_fetched_at = CURRENT_TIMESTAMP, last_error = NULL
+                  WHERE id = ?1",
+                [feed.id],
+            )
+        })?;
+        return Ok(0);
+    };
+    if download.truncated {
+        return Err(AppError::new("feed.too_large").with("max", MAX_FEED_SIZE / 1024 / 1024));
+    }
+    let parsed =
+        feed_rs::parser::parse(download.body.as_slice()).map_err(wrap("feed.parse_failed"))?;
+    let feed_title = parsed
+        .title
+        .map(|title| title.content.trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    let (guids, links) = db::with_read_conn(app, |conn| {
+        let mut stmt = conn.prepare("SELECT guid, link FROM feed_items WHERE feed_id = ?1")?;
+        let mut guids = HashSet::new();
+        let mut links = HashSet::new();
+        let rows = stmt.query_map([feed.id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+        for row in rows {
+            let (guid, link) = row?;
+            guids.insert(guid);
+            links.extend(link);
+        }
+        Ok((guids, links))
+    })?;
+
+    // 订阅源通常从新到旧排列，导入时从旧到新，让新条目的笔记排在后面
+    let mut items: Vec<FeedItem> = parsed
+        .entries
+        .into_iter()
+        .take(MAX_ITEMS_PER_FETCH)
+        .map(|entry| convert_entry(entry, &download.url))
+        .filter(|item| {
+            !guids.contains(&item.guid)
+                && item.link.as_ref().is_none_or(|link| !links.contains(link))
+        })
+        .collect();
+    items.reverse();
+    let new_validators = Validators {
+        etag: download.etag,
+        last_modified: download.last_modified,
+    };
+    let saved = db::with_write_conn(app, |conn| {
+        save_items(conn, feed, feed_title.as_deref(), items, &new_validators)
+    })?;
+
+    // 图片在笔记创建后下载到对应的附件目录，失败的保留远程地址
+    if settings::get::<bool>(app, settings::FEED_IMAGES).unwrap_or(false) {
+        let mut note_ids: Vec<i64> = saved.iter().map(|(id, _)| *id).collect();
+        note_ids.dedup();
+        for note_id in note_ids {
+            let images: Vec<Url> = saved
+                .iter()
+                .filter(|(id, _)| *id == note_id)
+                .flat_map(|(_, item)| item.images.iter().cloned())
+                .collect();
+            if images.is_empty() {
+                continue;
+            }
+            let content = db::with_read_conn(app, |conn| {
+                conn.query_row(
+                    "SELECT content FROM notes WHERE id = ?1",
+                    [note_id],
+                    |row| row.get::<_, String>(0),
+                )
+            })?;
+            let updated = web_clip::download_images(app, note_id, &content, &images).await?;
+            if updated != content {
+                db::with_write_conn(app, |conn| {
+                    conn.execute(
+                        "UPDATE notes SET content = ?1 WHERE id = ?2",
+                        params![updated, note_id],
+                    )
+                })?;
+            }
+        }
+    }
+
+    Ok(saved.len())
+}
+
+#[tauri::command]
+pub async fn add_feed(
+    app: tauri::AppHandle,
+    url: String,
+    target_notebook: Option<i64>,
+    mode: FeedMode,
+) -> AppResult<Feed> {
+    let url = link_preview::parse_url(&url)?;
+    let id = db::with_write_conn(&app, |conn| {
+        conn.execute_batch(SCHEMA)?;
+        if let Some(category_id) = target_notebook {
+            let exists = conn
+                .query_row(
+                    "SELECT 1 FROM categories WHERE id = ?1",
+                    [category_id],
+                    |_| Ok(()),
+                )
+                .optional()?;
+            if exists.is_none() {
+                return Ok(Err(
+                    AppError::new("feed.notebook_not_found").with("id", category_id)
+                ));
+            }
+        }
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO feeds (url, category_id, mode) VALUES (?1, ?2, ?3)",
+            params![url.as_str(), target_notebook, mode.as_str()],
+        )?;
+        if inserted == 0 {
+            return Ok(Err(AppError::new("feed.exists").with("url", &url)));
+        }
+        Ok(Ok(conn.last_insert_rowid()))
+    })??;
+
+    log::info!("已添加订阅 {}", url);
+    let feeds = db::with_read_conn(&app, load_feeds)?;
+    feeds
+        .into_iter()
+        .find(|feed| feed.id == id)
+        .ok_or_else(|| AppError::new("feed.not_found").with("id", id))
+}
+
+#[tauri::command]
+pub async fn list_feeds(app: tauri::AppHandle) -> AppResult<Vec<Feed>> {
+    if read_only::is_enabled(&app) {
+        // 只读连接不能建表，尚未使用过订阅时没有 feeds 表
+        return db::with_read_conn(&app, |conn| load_feeds(conn).or_else(|_| Ok(Vec::new())));
+    }
+    db::with_write_conn(&app, |conn| load_feeds(conn))
+}
+
+// 删除订阅及其抓取记录，已导入的笔记保留
+#[tauri::command]
+pub async fn remove_feed(app: tauri::AppHandle, id: i64) -> AppResult<()> {
+    let removed = db::with_write_conn(&app, |conn| {
+        conn.execute_batch(SCHEMA)?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM feed_items WHERE feed_id = ?1", [id])?;
+        let removed = tx.execute("DELETE FROM feeds WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(removed)
+    })?;
+    if removed == 0 {
+        return Err(AppError::new("feed.not_found").with("id", id));
+    }
+    log::info!("已删除订阅 {}", id);
+    Ok(())
+}
+
+// 在后台抓取全部订阅，立即返回；完成后发送 feeds-fetched 事件，单个订阅失败不影响其他订阅
+#[tauri::command]
+pub fn fetch_feeds_now(app: tauri::AppHandle) -> AppResult<()> {
+    read_only::ensure_writable(&app)?;
+    let feeds = db::with_write_conn(&app, |conn| load_feeds(conn))?;
+    if feeds.is_empty() || FETCHING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let Some(task) = shutdown::begin_task(&app, "fetch_feeds") else {
+        FETCHING.store(false, Ordering::SeqCst);
+        return Ok(());
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let _task = task;
+        let mut results = Vec::with_capacity(feeds.len());
+        for feed in &feeds {
+            let result = match fetch_feed(&app, feed).await {
+                Ok(added) => {
+                    if added > 0 {
+                        log::info!("订阅 {} 导入了 {} 个新条目", feed.url, added);
+                    }
+                    FeedFetchResult {
+                        feed_id: feed.id,
+                        added,
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    log::warn!("抓取订阅 {} 失败: {}", feed.url, e);
+                    let error = e.message();
+                    record_error(&app, feed.id, &error);
+                    FeedFetchResult {
+                        feed_id: feed.id,
+                        added: 0,
+                        error: Some(error),
+                    }
+                }
+            };
+            results.push(result);
+        }
+        FETCHING.store(false, Ordering::SeqCst);
+        if let Err(e) = app.emit("feeds-fetched", &results) {
+            log::warn!("{}", e);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use crate::error::{AppError, AppResult};
+use crate::{db, i18n, tray};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const TICK: std::time::Duration = std::time::Duration::from_secs(1);
+const MAX_MINUTES: u32 = 24 * 60;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS focus_sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        note_id INTEGER REFERENCES notes (id) ON DELETE SET NULL,
+        started_at TEXT NOT NULL,
+        ended_at TEXT NOT NULL,
+        planned_minutes INTEGER NOT NULL,
+        focused_seconds INTEGER NOT NULL,
+        completed INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_focus_sessions_started_at ON focus_sessions (started_at);";
+
+// 计时基于系统时间差而不是累计 tick，系统休眠期间同样计时
+struct FocusSession {
+    note_id: Option<i64>,
+    planned: Duration,
+    started_at: DateTime<Utc>,
+    // 此前各运行段累计的时长
+    elapsed: Duration,
+    // 当前运行段的开始时间，暂停时为 None
+    resumed_at: Option<DateTime<Utc>>,
+}
+
+impl FocusSession {
+    fn focused(&self, now: DateTime<Utc>) -> Duration {
+        let running = self.resumed_at.map_or(Duration::zero(), |at| now - at);
+        (self.elapsed + running).min(self.planned)
+    }
+
+    fn status(&self, now: DateTime<Utc>) -> FocusStatus {
+        FocusStatus {
+            note_id: self.note_id,
+            planned_minutes: self.planned.num_minutes() as u32,
+            remaining_seconds: (self.planned - self.focused(now)).num_seconds(),
+            paused: self.resumed_at.is_none(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FocusState(Mutex<Option<FocusSession>>);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FocusStatus {
+    pub note_id: Option<i64>,
+    pub planned_minutes: u32,
+    pub remaining_seconds: i64,
+    pub paused: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct FocusRange {
+    // 本地日期，包含两端
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DayFocus {
+    pub date: NaiveDate,
+    pub minutes: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteFocus {
+    pub note_id: Option<i64>,
+    pub title: Option<String>,
+    pub minutes: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FocusStats {
+    pub total_minutes: i64,
+    pub sessions: usize,
+    pub days: Vec<DayFocus>,
+    pub notes: Vec<NoteFocus>,
+}
+
+// 记录结束的会话；提前停止且不足一分钟的不记录
+fn log_session(
+    app: &tauri::AppHandle,
+    session: &FocusSession,
+    now: DateTime<Utc>,
+    completed: bool,
+) -> AppResult<()> {
+    let focused = session.focused(now);
+    if !completed && focused.num_minutes() < 1 {
+        return Ok(());
+    }
+    db::with_write_conn(app, |conn| {
+        conn.execute_batch(SCHEMA)?;
+        conn.execute(
+            "INSERT INTO focus_sessions
+                (note_id, started_at, ended_at, planned_minutes, focused_seconds, completed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                session.note_id,
+                session.started_at.to_rfc3339(),
+                now.to_rfc3339(),
+                session.planned.num_minutes(),
+                focused.num_seconds(),
+                completed,
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+fn tray_status(status: &FocusStatus) -> String {
+    let remaining = status.remaining_seconds.max(0);
+    let time = format!("{:02}:{:02}", remaining / 60, remaining % 60);
+    if status.paused {
+        i18n::t("focus.tray_paused", &[("time", time)])
+    } else {
+        i18n::t("focus.tray_running", &[("time", time)])
+    }
+}
+
+#[tauri::command]
+pub fn start_focus_session(
+    app: tauri::AppHandle,
+    minutes: u32,
+    note_id: Option<i64>,
+) -> AppResult<FocusStatus> {
+    if minutes == 0 || minutes > MAX_MINUTES {
+        return Err(AppError::new("focus.invalid_duration").with("max", MAX_MINUTES));
+    }
+
+    let state = app.state::<FocusState>();
+    let mut session = state.0.lock().unwrap();
+    if session.is_some() {
+        return Err(AppError::new("focus.already_running"));
+    }
+    let now = Utc::now();
+    let started = FocusSession {
+        note_id,
+        planned: Duration::minutes(minutes as i64),
+        started_at: now,
+        elapsed: Duration::zero(),
+        resumed_at: Some(now),
+    };
+    let status = started.status(now);
+    *session = Some(started);
+    log::info!("开始专注 {} 分钟", minutes);
+
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn pause_focus_session(state: tauri::State<'_, FocusState>) -> AppResult<FocusStatus> {
+    let mut session = state.0.lock().unwrap();
+    let session = session
+        .as_mut()
+        .ok_or_else(|| AppError::new("focus.not_running"))?;
+    let now = Utc::now();
+    if let Some(resumed_at) = session.resumed_at.take() {
+        session.elapsed += now - resumed_at;
+    }
+    Ok(session.status(now))
+}
+
+#[tauri::command]
+pub fn resume_focus_session(state: tauri::State<'_, FocusState>) -> AppResult<FocusStatus> {
+    let mut session = state.0.lock().unwrap();
+    let session = session
+        .as_mut()
+        .ok_or_else(|| AppError::new("focus.not_running"))?;
+    let now = Utc::now();
+    session.resumed_at.get_or_insert(now);
+    Ok(session.status(now))
+}
+
+// 提前结束当前会话，专注满一分钟的部分仍计入统计
+#[tauri::command]
+pub fn stop_focus_session(app: tauri::AppHandle) -> AppResult<()> {
+    let session = app.state::<FocusState>().0.lock().unwrap().take();
+    let Some(session) = session else {
+        return Ok(());
+    };
+    tray::set_status(&app, None)?;
+    log_session(&app, &session, Utc::now(), false)
+}
+
+#[tauri::command]
+pub fn get_focus_session(state: tauri::State<'_, FocusState>) -> Option<FocusStatus> {
+    let session = state.0.lock().unwrap();
+    session.as_ref().map(|session| session.status(Utc::now()))
+}
+
+// 按本地日期和笔记汇总专注时长
+#[tauri::command]
+pub async fn get_focus_stats(app: tauri::AppHandle, range: FocusRange) -> AppResult<FocusStats> {
+    let rows = db::with_read_conn(&app, |conn| {
+        conn.execute_batch(SCHEMA)?;
+        let mut stmt = conn.prepare(
+            "SELECT f.note_id, n.title, f.started_at, f.focused_seconds
+               FROM focus_sessions f LEFT JOIN notes n ON n.id = f.note_id
+              ORDER BY f.started_at",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Option<i64>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    })?;
+
+    let mut sessions = 0;
+    let mut total = 0;
+    let mut days: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut notes: BTreeMap<Option<i64>, (Option<String>, i64)> = BTreeMap::new();
+    for (note_id, title, started_at, seconds) in rows {
+        let Ok(started_at) = DateTime::parse_from_rfc3339(&started_at) else {
+            continue;
+        };
+        let date = started_at.with_timezone(&Local).date_naive();
+        if date < range.from || date > range.to {
+            continue;
+        }
+        sessions += 1;
+        total += seconds;
+        *days.entry(date).or_default() += seconds;
+        notes.entry(note_id).or_insert((title, 0)).1 += seconds;
+    }
+
+    let mut notes: Vec<NoteFocus> = notes
+        .into_iter()
+        .map(|(note_id, (title, seconds))| NoteFocus {
+            note_id,
+            title,
+            minutes: seconds / 60,
+        })
+        .collect();
+    notes.sort_by_key(|note| std::cmp::Reverse(note.minutes));
+
+    Ok(FocusStats {
+        total_minutes: total / 60,
+        sessions,
+        days: days
+            .into_iter()
+            .map(|(date, seconds)| DayFocus {
+                date,
+                minutes: seconds / 60,
+            })
+            .collect(),
+        notes,
+    })
+}
+
+// 到时的会话：记录、通知并通知前端
+fn finish(app: &tauri::AppHandle, session: FocusSession, now: DateTime<Utc>) {
+    let minutes = session.planned.num_minutes();
+    if let Err(e) = log_session(app, &session, now, true) {
+        log::error!("记录专注会话失败: {}", e);
+    }
+    let _ = app
+        .notification()
+        .builder()
+        .title(i18n::t("notification.focus_title", &[]))
+        .body(i18n::t(
+            "notification.focus_body",
+            &[("minutes", minutes.to_string())],
+        ))
+        .show();
+    let _ = app.emit("focus-session-finished", session.note_id);
+    log::info!("专注会话结束，共 {} 分钟", minutes);
+}
+
+// 启动计时线程，每秒刷新托盘中的剩余时间
+pub fn spawn(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let mut shown: Option<String> = None;
+        loop {
+            thread::sleep(TICK);
+
+            let now = Utc::now();
+            let (status, finished) = {
+                let state = app.state::<FocusState>();
+                let mut session = state.0.lock().unwrap();
+                match session.as_ref().map(|s| s.status(now)) {
+                    Some(status) if status.remaining_seconds <= 0 => (None, session.take()),
+                    status => (status, None),
+                }
+            };
+
+            if let Some(session) = finished {
+                finish(&app, session, now);
+            }
+            let text = status.as_ref().map(tray_status);
+            if text != shown {
+                if let Err(e) = tray::set_status(&app, text.as_deref()) {
+                    log::error!("{}", e);
+                }
+                shown = text;
+            }
+        }
+    });
+}
@@ -0,0 +1,358 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::read_only;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::LazyLock;
+
+// 超过该节点数时只返回度数最高的部分
+const MAX_NODES: usize = 2000;
+const DEFAULT_DEPTH: usize = 1;
+
+static NOTE_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"note://(\d+)").unwrap());
+
+// 笔记链接索引：note_links 记录每条笔记引用其他笔记的次数，note_links_indexed 记录已建立索引的笔记。
+// 前端直接写数据库，因此用触发器在内容修改或删除时让索引失效，读取前只需重新解析失效的笔记
+const INDEX_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS note_links (
+        source_id INTEGER NOT NULL,
+        target_id INTEGER NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (source_id, target_id)
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS idx_note_links_target ON note_links (target_id);
+    CREATE TABLE IF NOT EXISTS note_links_indexed (note_id INTEGER PRIMARY KEY);
+    CREATE TRIGGER IF NOT EXISTS note_links_on_update AFTER UPDATE OF content ON notes BEGIN
+        DELETE FROM note_links_indexed WHERE note_id = NEW.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS note_links_on_delete AFTER DELETE ON notes BEGIN
+        DELETE FROM note_links WHERE source_id = OLD.id;
+        DELETE FROM note_links_indexed WHERE note_id = OLD.id;
+    END;";
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GraphScope {
+    // 整个工作区
+    Workspace,
+    // 以某条笔记为中心、depth 跳以内的邻居
+    Note { id: i64 },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphNode {
+    pub id: i64,
+    pub title: String,
+    pub tag_colors: Vec<String>,
+    // 在所选范围内（截断前）与该笔记相连的边数
+    pub degree: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphEdge {
+    pub from: i64,
+    pub to: i64,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub truncated: bool,
+}
+
+// 重新解析索引失效的笔记，返回更新的笔记数
+pub fn refresh_links(conn: &mut Connection) -> rusqlite::Result<usize> {
+    conn.execute_batch(INDEX_SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let stale = {
+        let mut stmt = tx.prepare(
+            "SELECT id, content FROM notes
+              WHERE id NOT IN (SELECT note_id FROM note_links_indexed)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    for (id, content) in &stale {
+        let mut counts: HashMap<i64, i64> = HashMap::new();
+        for caps in NOTE_LINK.captures_iter(content) {
+            if let Ok(target) = caps[1].parse::<i64>() {
+                *counts.entry(target).or_default() += 1;
+            }
+        }
+
+        tx.execute("DELETE FROM note_links WHERE source_id = ?1", [id])?;
+        for (target, count) in counts {
+            tx.execute(
+                "INSERT INTO note_links (source_id, target_id, count) VALUES (?1, ?2, ?3)",
+                params![id, target, count],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO note_links_indexed (note_id) VALUES (?1)",
+            [id],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(stale.len())
+}
+
+fn load_titles(conn: &Connection) -> rusqlite::Result<HashMap<i64, String>> {
+    let mut stmt = conn.prepare("SELECT id, title FROM notes")?;
+    let titles = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(titles)
+}
+
+fn load_tag_colors(conn: &Connection) -> rusqlite::Result<HashMap<i64, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT nt.note_id, t.color FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+          ORDER BY nt.note_id, t.name",
+    )?;
+    let mut colors: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+        let (id, color) = row?;
+        colors.entry(id).or_default().push(color);
+    }
+    Ok(colors)
+}
+
+// 只保留两端都存在的边，自引用不计
+fn load_edges(conn: &Connection) -> rusqlite::Result<Vec<GraphEdge>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.source_id, l.target_id, l.count FROM note_links l
+           JOIN notes n ON n.id = l.target_id
+          WHERE l.source_id != l.target_id",
+    )?;
+    let edges = stmt
+        .query_map([], |row| {
+            Ok(GraphEdge {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                count: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(edges)
+}
+
+// 不区分方向，从 center 出发最多 depth 跳
+fn neighborhood(center: i64, depth: usize, edges: &[GraphEdge]) -> HashSet<i64> {
+    let mut adjacent: HashMap<i64, Vec<i64>> = HashMap::new();
+    for edge in edges {
+        adjacent.entry(edge.from).or_default().push(edge.to);
+        adjacent.entry(edge.to).or_default().push(edge.from);
+    }
+
+    let mut visited = HashSet::from([center]);
+    let mut queue = VecDeque::from([(center, 0)]);
+    while let Some((id, distance)) = queue.pop_front() {
+        if distance == depth {
+            continue;
+        }
+        for &next in adjacent.get(&id).into_iter().flatten() {
+            if visited.insert(next) {
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    visited
+}
+
+struct GraphData {
+    titles: HashMap<i64, String>,
+    colors: HashMap<i64, Vec<String>>,
+    edges: Vec<GraphEdge>,
+}
+
+fn load(conn: &Connection) -> rusqlite::Result<GraphData> {
+    Ok(GraphData {
+        titles: load_titles(conn)?,
+        colors: load_tag_colors(conn)?,
+        edges: load_edges(conn)?,
+    })
+}
+
+fn build(
+    data: GraphData,
+    scope: GraphScope,
+    depth: usize,
+    include_orphans: bool,
+) -> AppResult<NoteGraph> {
+    let GraphData {
+        titles,
+        colors,
+        mut edges,
+    } = data;
+    let center = match scope {
+        GraphScope::Workspace => None,
+        GraphScope::Note { id } => Some(id),
+    };
+
+    let mut members: HashSet<i64> = match center {
+        None => titles.keys().copied().collect(),
+        Some(id) if titles.contains_key(&id) => neighborhood(id, depth, &edges),
+        Some(id) => return Err(AppError::new("graph.note_not_found").with("id", id)),
+    };
+    edges.retain(|edge| members.contains(&edge.from) && members.contains(&edge.to));
+
+    let mut degrees: HashMap<i64, usize> = HashMap::new();
+    for edge in &edges {
+        *degrees.entry(edge.from).or_default() += 1;
+        *degrees.entry(edge.to).or_default() += 1;
+    }
+    let degree = |id: &i64| degrees.get(id).copied().unwrap_or(0);
+
+    if !include_orphans {
+        members.retain(|id| degree(id) > 0 || center == Some(*id));
+    }
+
+    // 按度数从高到低排列，超出上限时截断；以笔记为中心时中心节点始终保留
+    let mut ids: Vec<i64> = members.into_iter().collect();
+    ids.sort_by_key(|id| (center != Some(*id), std::cmp::Reverse(degree(id)), *id));
+    let truncated = ids.len() > MAX_NODES;
+    if truncated {
+        ids.truncate(MAX_NODES);
+        let kept: HashSet<i64> = ids.iter().copied().collect();
+        edges.retain(|edge| kept.contains(&edge.from) && kept.contains(&edge.to));
+    }
+
+    let nodes = ids
+        .into_iter()
+        .map(|id| GraphNode {
+            id,
+            title: titles.get(&id).cloned().unwrap_or_default(),
+            tag_colors: colors.get(&id).cloned().unwrap_or_default(),
+            degree: degree(&id),
+        })
+        .collect();
+
+    Ok(NoteGraph {
+        nodes,
+        edges,
+        truncated,
+    })
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CycleNote {
+    pub id: i64,
+    pub title: String,
+}
+
+// 两端笔记都存在的链接，包括自引用，按 id 排序使结果稳定
+fn load_adjacency(conn: &Connection) -> rusqlite::Result<BTreeMap<i64, Vec<i64>>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.source_id, l.target_id FROM note_links l
+           JOIN notes s ON s.id = l.source_id
+           JOIN notes t ON t.id = l.target_id
+          ORDER BY l.source_id, l.target_id",
+    )?;
+    let mut adjacent: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+        let (from, to) = row?;
+        adjacent.entry(from).or_default().push(to);
+    }
+    Ok(adjacent)
+}
+
+// 迭代式 DFS，每条指向栈上节点的回边对应一个环（栈中从目标到当前节点的路径），自引用为长度 1 的环。
+// 时间为 O(节点 + 边)；不枚举全部简单环（数量可能呈指数增长），但每个存在环的强连通分量至少报告一个
+fn find_cycles(adjacent: &BTreeMap<i64, Vec<i64>>) -> Vec<Vec<i64>> {
+    let mut cycles = Vec::new();
+    let mut finished: HashSet<i64> = HashSet::new();
+    // 当前路径上的节点及其在路径中的位置
+    let mut on_path: HashMap<i64, usize> = HashMap::new();
+    let mut path: Vec<i64> = Vec::new();
+    let mut stack: Vec<(i64, usize)> = Vec::new();
+
+    for &root in adjacent.keys() {
+        if finished.contains(&root) {
+            continue;
+        }
+        on_path.insert(root, 0);
+        path.push(root);
+        stack.push((root, 0));
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let targets = adjacent.get(&node).map_or(&[][..], Vec::as_slice);
+            let Some(&target) = targets.get(*next) else {
+                stack.pop();
+                path.pop();
+                on_path.remove(&node);
+                finished.insert(node);
+                continue;
+            };
+            *next += 1;
+
+            if let Some(&start) = on_path.get(&target) {
+                cycles.push(path[start..].to_vec());
+            } else if !finished.contains(&target) {
+                on_path.insert(target, path.len());
+                path.push(target);
+                stack.push((target, 0));
+            }
+        }
+    }
+    cycles
+}
+
+// 查找笔记链接中的循环引用，每个环按链接方向给出笔记列表（最后一条链接回到第一条笔记）
+#[tauri::command]
+pub async fn find_link_cycles(app: tauri::AppHandle) -> AppResult<Vec<Vec<CycleNote>>> {
+    // 只读模式下无法更新索引，使用已有的索引
+    if !read_only::is_enabled(&app) {
+        db::with_write_conn(&app, refresh_links)?;
+    }
+    let (adjacent, titles) =
+        db::with_read_conn(&app, |conn| Ok((load_adjacency(conn)?, load_titles(conn)?)))?;
+
+    let cycles = find_cycles(&adjacent)
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|id| CycleNote {
+                    id,
+                    title: titles.get(&id).cloned().unwrap_or_default(),
+                })
+                .collect()
+        })
+        .collect();
+    Ok(cycles)
+}
+
+// 笔记关系图数据；depth 只对以笔记为中心的范围有效，默认 1 跳
+#[tauri::command]
+pub async fn get_note_graph(
+    app: tauri::AppHandle,
+    scope: GraphScope,
+    depth: Option<usize>,
+    include_orphans: Option<bool>,
+) -> AppResult<NoteGraph> {
+    if !read_only::is_enabled(&app) {
+        let refreshed = db::with_write_conn(&app, refresh_links)?;
+        if refreshed > 0 {
+            log::info!("已更新 {} 条笔记的链接索引", refreshed);
+        }
+    }
+
+    let data = db::with_read_conn(&app, load)?;
+    build(
+        data,
+        scope,
+        depth.unwrap_or(DEFAULT_DEPTH),
+        include_orphans.unwrap_or(true),
+    )
+}
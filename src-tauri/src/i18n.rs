@@ -0,0 +1,116 @@
+use crate::error::{AppError, AppResult};
+use crate::settings;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+use tauri::Manager;
+
+static ZH_CN: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| serde_json::from_str(include_str!("../locales/zh-CN.json")).unwrap());
+static EN_US: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| serde_json::from_str(include_str!("../locales/en-US.json")).unwrap());
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::ZhCn);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    ZhCn,
+    EnUs,
+}
+
+impl Locale {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::ZhCn => "zh-CN",
+            Locale::EnUs => "en-US",
+        }
+    }
+
+    fn table(self) -> &'static HashMap<String, String> {
+        match self {
+            Locale::ZhCn => &ZH_CN,
+            Locale::EnUs => &EN_US,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = AppError;
+
+    // 只看语言部分，zh-TW、en-GB 等也能落到已有的消息表
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or("").to_lowercase();
+        match language.as_str() {
+            "zh" => Ok(Locale::ZhCn),
+            "en" => Ok(Locale::EnUs),
+            _ => Err(AppError::new("locale.unsupported").with("locale", s)),
+        }
+    }
+}
+
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap()
+}
+
+// 按当前语言查找消息并替换 {name} 形式的参数；缺失时依次回退到中文和键名本身
+pub fn t(key: &str, params: &[(&str, String)]) -> String {
+    let template = locale()
+        .table()
+        .get(key)
+        .or_else(|| ZH_CN.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+
+    params
+        .iter()
+        .fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+// 设为该值时不保存具体语言，始终跟随系统
+pub const SYSTEM_LOCALE: &str = "system";
+
+// 系统语言不在支持范围内时使用中文
+pub fn system_locale() -> Locale {
+    tauri_plugin_os::locale()
+        .and_then(|locale| Locale::from_str(locale.trim()).ok())
+        .unwrap_or(Locale::ZhCn)
+}
+
+// 启动时恢复上次设置的语言，没有设置过则跟随系统
+pub fn init(app: &tauri::AppHandle) {
+    let locale = settings::get::<String>(app, settings::LOCALE)
+        .and_then(|locale| Locale::from_str(locale.trim()).ok())
+        .unwrap_or_else(system_locale);
+    *LOCALE.write().unwrap() = locale;
+}
+
+// 返回系统语言对应的消息表，供前端显示“跟随系统”选项
+#[tauri::command]
+pub fn get_system_locale() -> &'static str {
+    system_locale().as_str()
+}
+
+// locale 为 system 时清除保存的语言，恢复跟随系统
+#[tauri::command]
+pub fn set_backend_locale(app: tauri::AppHandle, locale: String) -> AppResult<()> {
+    let locale = if locale == SYSTEM_LOCALE {
+        settings::set(&app, settings::LOCALE, Option::<String>::None)?;
+        system_locale()
+    } else {
+        let locale = Locale::from_str(&locale)?;
+        settings::set(&app, settings::LOCALE, locale.as_str())?;
+        locale
+    };
+    *LOCALE.write().unwrap() = locale;
+    log::info!("界面语言已设置为 {}", locale.as_str());
+
+    // 托盘菜单和窗口标题立即切换到新语言
+    crate::tray::refresh(&app)?;
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_title(&t("app.title", &[]));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::backup::BackupState;
+use crate::error::{AppError, AppResult};
+use crate::periodic::{self, Schedule};
+use crate::{db, settings};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{Emitter, Manager};
+
+// PRAGMA integrity_check 最多返回的问题条数
+const MAX_PROBLEMS: u32 = 100;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    // integrity_check 报告的问题，无法打开或读取数据库时为错误信息
+    pub problems: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IntegrityStatus {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_report: Option<IntegrityReport>,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+pub struct IntegrityState(Mutex<IntegrityStatus>);
+
+impl IntegrityStatus {
+    fn schedule(&self) -> Schedule {
+        Schedule {
+            enabled: self.enabled,
+            interval_hours: self.interval_hours,
+            last_run: self.last_run,
+        }
+    }
+}
+
+fn run_check(app: &tauri::AppHandle) -> IntegrityReport {
+    let result = db::with_read_conn(app, |conn| {
+        let mut stmt = conn.prepare(&format!("PRAGMA integrity_check({})", MAX_PROBLEMS))?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>();
+        rows
+    });
+    let problems = match result {
+        Ok(rows) => rows.into_iter().filter(|row| row != "ok").collect(),
+        Err(e) => vec![e.to_string()],
+    };
+    IntegrityReport {
+        ok: problems.is_empty(),
+        problems,
+        checked_at: Utc::now(),
+    }
+}
+
+// 检查期间持有备份锁，避免与备份或恢复同时进行；wait 为 false 时若备份正在进行则返回 backup.in_progress
+fn check(app: &tauri::AppHandle, wait: bool) -> AppResult<IntegrityReport> {
+    let state = app.state::<BackupState>();
+    let _guard = state.acquire(wait)?;
+    let report = run_check(app);
+
+    let state = app.state::<IntegrityState>();
+    let mut status = state.0.lock().unwrap();
+    status.last_run = Some(report.checked_at);
+    status.last_report = Some(report.clone());
+    Ok(report)
+}
+
+// 检查当前数据库的完整性，有备份正在进行时等待其完成
+#[tauri::command]
+pub async fn check_database_integrity(app: tauri::AppHandle) -> AppResult<IntegrityReport> {
+    check(&app, true)
+}
+
+#[tauri::command]
+pub fn set_integrity_check_schedule(
+    app: tauri::AppHandle,
+    enabled: bool,
+    interval_hours: u32,
+) -> AppResult<IntegrityStatus> {
+    if enabled && interval_hours == 0 {
+        return Err(AppError::new("integrity.invalid_interval"));
+    }
+    let last_run = app.state::<IntegrityState>().0.lock().unwrap().last_run;
+    let schedule = Schedule {
+        enabled,
+        interval_hours,
+        last_run,
+    };
+    settings::set(&app, settings::INTEGRITY_CHECK, &schedule)?;
+
+    let state = app.state::<IntegrityState>();
+    let mut status = state.0.lock().unwrap();
+    status.enabled = enabled;
+    status.interval_hours = interval_hours;
+    status.next_run = schedule.next_run();
+    Ok(status.clone())
+}
+
+// 从设置备份恢复时经过 set_integrity_check_schedule，只取开关和间隔，上次检查时间保持本机的记录
+pub fn restore_schedule(app: &tauri::AppHandle, value: Value) -> AppResult<()> {
+    let schedule = serde_json::from_value::<Option<Schedule>>(value)
+        .map_err(|_| {
+            AppError::new("settings.invalid_value").with("key", settings::INTEGRITY_CHECK)
+        })?
+        .unwrap_or_default();
+    set_integrity_check_schedule(app.clone(), schedule.enabled, schedule.interval_hours).map(drop)
+}
+
+#[tauri::command]
+pub fn get_integrity_check_status(state: tauri::State<'_, IntegrityState>) -> IntegrityStatus {
+    state.0.lock().unwrap().clone()
+}
+
+struct IntegrityCheck;
+
+impl periodic::Task for IntegrityCheck {
+    const NAME: &'static str = "integrity_check";
+    const SETTING: &'static str = settings::INTEGRITY_CHECK;
+    type Output = IntegrityReport;
+    type Saved = Schedule;
+
+    fn schedule(app: &tauri::AppHandle) -> Schedule {
+        app.state::<IntegrityState>().0.lock().unwrap().schedule()
+    }
+
+    // 备份正在进行时推迟到下一个 tick
+    fn run(app: &tauri::AppHandle) -> Option<IntegrityReport> {
+        check(app, false).ok()
+    }
+
+    fn finish(app: &tauri::AppHandle, output: thread::Result<IntegrityReport>) -> Schedule {
+        let report = output.unwrap_or_else(|_| IntegrityReport {
+            ok: false,
+            problems: vec![AppError::new("integrity.panicked").to_string()],
+            checked_at: Utc::now(),
+        });
+        if report.ok {
+            log::info!("数据库完整性检查通过");
+        } else {
+            log::error!("数据库完整性检查失败: {}", report.problems.join("; "));
+            let _ = app.emit("database-integrity-warning", &report);
+        }
+
+        let state = app.state::<IntegrityState>();
+        let mut status = state.0.lock().unwrap();
+        status.last_run = Some(report.checked_at);
+        status.last_report = Some(report);
+        let schedule = status.schedule();
+        status.next_run = schedule.next_run();
+        schedule
+    }
+}
+
+// 按保存的配置启动后台定时检查；检查失败时记录日志并发送 database-integrity-warning 事件，由前端提示用户从备份恢复
+pub fn spawn(app: tauri::AppHandle) {
+    let schedule = settings::get::<Schedule>(&app, settings::INTEGRITY_CHECK).unwrap_or_default();
+    {
+        let state = app.state::<IntegrityState>();
+        let mut status = state.0.lock().unwrap();
+        status.enabled = schedule.enabled;
+        status.interval_hours = schedule.interval_hours;
+        status.last_run = schedule.last_run;
+        status.next_run = schedule.next_run();
+    }
+    periodic::spawn::<IntegrityCheck>(app);
+}
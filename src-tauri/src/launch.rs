@@ -0,0 +1,158 @@
+use crate::markdown_import::{self, MarkdownImport};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+
+const DEEP_LINK_SCHEME: &str = "yue://";
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FailedFile {
+    pub path: String,
+    pub error: String,
+}
+
+// 一次启动参数中所有文件的导入结果，只发送一个事件
+#[derive(Clone, Debug, Serialize)]
+pub struct OpenedFiles {
+    pub imported: Vec<MarkdownImport>,
+    pub failed: Vec<FailedFile>,
+}
+
+#[derive(Default)]
+struct LaunchArgs {
+    files: Vec<PathBuf>,
+    links: Vec<String>,
+}
+
+// 跳过程序路径和选项；相对路径按发起启动的进程的工作目录解析
+fn parse_args(argv: &[String], cwd: &Path) -> LaunchArgs {
+    let mut args = LaunchArgs::default();
+    let mut argv = argv.iter().skip(1);
+    while let Some(arg) = argv.next() {
+        if arg.starts_with(DEEP_LINK_SCHEME) {
+            args.links.push(arg.clone());
+        } else if arg == "--profile" {
+            // 配置只在启动时选择，已运行的实例忽略该参数及其值
+            argv.next();
+        } else if arg.starts_with('-') {
+            continue;
+        } else {
+            let path = cwd.join(arg);
+            let is_markdown = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    MARKDOWN_EXTENSIONS
+                        .iter()
+                        .any(|m| m.eq_ignore_ascii_case(ext))
+                });
+            if is_markdown {
+                args.files.push(path);
+            } else {
+                log::warn!("忽略不支持的启动参数: {}", arg);
+            }
+        }
+    }
+    args
+}
+
+// yue://note/{id} 中的笔记 id（见 qr::deep_link）
+fn deep_link_note(link: &str) -> Option<i64> {
+    let path = link.strip_prefix(DEEP_LINK_SCHEME)?.trim_end_matches('/');
+    path.strip_prefix("note/")?.parse().ok()
+}
+
+// yue:// 链接的路由，目前只有打开笔记
+fn route_deep_link(app: &tauri::AppHandle, link: &str) {
+    match deep_link_note(link) {
+        Some(id) => {
+            let _ = app.emit("deep-link-open-note", id);
+        }
+        None => log::warn!("无法识别的链接: {}", link),
+    }
+}
+
+fn open_files(app: &tauri::AppHandle, files: Vec<PathBuf>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut opened = OpenedFiles {
+            imported: Vec::new(),
+            failed: Vec::new(),
+        };
+        for path in files {
+            match markdown_import::import(&app, &path, None, true) {
+                Ok(import) => opened.imported.push(import),
+                Err(e) => {
+                    log::warn!("打开文件 {} 失败: {}", path.display(), e);
+                    opened.failed.push(FailedFile {
+                        path: path.display().to_string(),
+                        error: e.message(),
+                    });
+                }
+            }
+        }
+        let _ = app.emit("launch-files-opened", opened);
+    });
+}
+
+// 已有实例在运行时，新启动的进程把参数和工作目录转发过来后退出。
+// Markdown 文件导入为笔记，yue:// 链接交给链接路由，并显示、聚焦主窗口
+pub fn on_second_instance(app: &tauri::AppHandle, argv: Vec<String>, cwd: String) {
+    log::info!("收到另一个实例的启动参数: {:?}", argv);
+    crate::show_main_window(app.clone());
+
+    let args = parse_args(&argv, Path::new(&cwd));
+    for link in &args.links {
+        route_deep_link(app, link);
+    }
+    if !args.files.is_empty() {
+        open_files(app, args.files);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_collects_files_and_links() {
+        let cwd = Path::new("/home/user/docs");
+        let args = parse_args(
+            &argv(&[
+                "/usr/bin/yue",
+                "--profile",
+                "work.md",
+                "-v",
+                "notes/a.md",
+                "B.MARKDOWN",
+                "image.png",
+                "yue://note/3",
+            ]),
+            cwd,
+        );
+        assert_eq!(args.files, [cwd.join("notes/a.md"), cwd.join("B.MARKDOWN")]);
+        assert_eq!(args.links, ["yue://note/3"]);
+    }
+
+    #[test]
+    fn parse_args_skips_program_path() {
+        let args = parse_args(&argv(&["/opt/yue/readme.md"]), Path::new("/"));
+        assert!(args.files.is_empty());
+        assert!(args.links.is_empty());
+        assert!(parse_args(&[], Path::new("/")).files.is_empty());
+    }
+
+    #[test]
+    fn deep_link_note_ids() {
+        assert_eq!(deep_link_note("yue://note/42"), Some(42));
+        assert_eq!(deep_link_note("yue://note/42/"), Some(42));
+        assert_eq!(deep_link_note("yue://note/abc"), None);
+        assert_eq!(deep_link_note("yue://tag/1"), None);
+        assert_eq!(deep_link_note("https://note/1"), None);
+    }
+}
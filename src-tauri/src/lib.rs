@@ -1,17 +1,70 @@
 use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
     tray::{TrayIconBuilder, TrayIconEvent},
-    Manager,
+    webview::PageLoadEvent,
+    Emitter, Manager, WindowEvent,
 };
 use tauri::{TitleBarStyle, WebviewUrl, WebviewWindowBuilder};
 
+mod active_note;
+mod archive;
+mod attachment_archive;
+mod attachment_protocol;
+mod attachments;
+mod backup;
+mod code_blocks;
+mod databases;
+mod db;
+mod db_access;
+mod delta;
+mod diagnostics;
+mod error;
+mod export;
+mod feeds;
+mod focus;
+mod graph;
+mod i18n;
+mod integrity;
+mod launch;
+mod link_preview;
+mod logging;
+mod markdown_import;
+mod memory;
+mod normalize;
+mod notebooks;
+mod outline;
+mod pdf_import;
+mod periodic;
+mod portable;
+mod profiles;
+mod qr;
+mod read_only;
+mod readability;
+mod replace;
+mod scheduler;
+mod screenshot;
+mod selection;
+mod settings;
+mod shutdown;
+mod similarity;
+mod spellcheck;
+mod tags;
+mod tasks;
+mod theme;
+mod transfer;
+mod tray;
+mod tts;
+mod updater;
+mod validation;
+mod versions;
+mod web_clip;
+mod window_effect;
+mod window_state;
+mod word_frequency;
+mod zoom;
+
 #[tauri::command]
 fn show_main_window(app: tauri::AppHandle) {
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.show();
-        let _ = window.set_focus();
-        let _ = window.unminimize();
-    }
+    tray::show_main_window(&app);
 }
 
 #[tauri::command]
@@ -21,161 +74,258 @@ fn hide_main_window(app: tauri::AppHandle) {
     }
 }
 
-#[tauri::command]
-async fn export_note_to_markdown(
-    title: String,
-    content: String,
-    file_path: String,
-) -> Result<(), String> {
-    use std::fs;
-
-    let markdown_content = format!(
-        "# {}\n\n{}\n\n---\n\n*导出时间: {}*",
-        title,
-        content,
-        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-    );
-
-    fs::write(&file_path, markdown_content).map_err(|e| format!("导出失败: {}", e))?;
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn export_all_notes_to_markdown(notes_json: String, file_path: String) -> Result<(), String> {
-    use serde_json::Value;
-    use std::fs;
-
-    let notes: Vec<Value> =
-        serde_json::from_str(&notes_json).map_err(|e| format!("解析笔记数据失败: {}", e))?;
-
-    let mut markdown_content = String::new();
-    markdown_content.push_str("# 笔记导出\n\n");
-    markdown_content.push_str(&format!(
-        "导出时间: {}\n\n",
-        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-    ));
-    markdown_content.push_str("---\n\n");
-
-    for note in notes {
-        let title = note["title"].as_str().unwrap_or("无标题");
-        let content = note["content"].as_str().unwrap_or("");
-        let created_at = note["created_at"].as_str().unwrap_or("");
-
-        markdown_content.push_str(&format!("## {}\n\n", title));
-        markdown_content.push_str(&format!("*创建时间: {}*\n\n", created_at));
-        markdown_content.push_str(&format!("{}\n\n", content));
-        markdown_content.push_str("---\n\n");
-    }
-
-    fs::write(&file_path, markdown_content).map_err(|e| format!("导出失败: {}", e))?;
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn backup_database(app: tauri::AppHandle, file_path: String) -> Result<(), String> {
-    use std::fs;
-
-    // 获取应用数据目录中的数据库文件路径
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("无法获取应用数据目录: {}", e))?;
-    let db_path = app_data_dir.join("notes.db");
-
-    if !db_path.exists() {
-        return Err("数据库文件不存在".to_string());
-    }
-
-    fs::copy(&db_path, &file_path).map_err(|e| format!("备份数据库失败: {}", e))?;
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn restore_database(app: tauri::AppHandle, file_path: String) -> Result<(), String> {
-    use std::fs;
-
-    if !std::path::Path::new(&file_path).exists() {
-        return Err("备份文件不存在".to_string());
-    }
-
-    // 获取应用数据目录中的数据库文件路径
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("无法获取应用数据目录: {}", e))?;
-    let db_path = app_data_dir.join("notes.db");
-
-    // 备份当前数据库（如果存在）
-    if db_path.exists() {
-        let backup_path = app_data_dir.join(format!(
-            "notes_backup_{}.db",
-            chrono::Utc::now().format("%Y%m%d_%H%M%S")
-        ));
-        fs::copy(&db_path, &backup_path).map_err(|e| format!("备份当前数据库失败: {}", e))?;
-    }
-
-    // 恢复数据库
-    fs::copy(&file_path, &db_path).map_err(|e| format!("恢复数据库失败: {}", e))?;
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn delete_database(app: tauri::AppHandle) -> Result<(), String> {
-    use std::fs;
-
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("无法获取应用数据目录: {}", e))?;
-    
-    let db_path = app_data_dir.join("notes.db");
-
-    if db_path.exists() {
-        fs::remove_file(&db_path)
-            .map_err(|e| format!("无法删除数据库文件: {}", e))?;
-    }
-
-    // 重启应用以重新生成数据库
-    app.restart();
-
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let invoke_handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        show_main_window,
+        hide_main_window,
+        active_note::set_active_note,
+        active_note::get_last_active_note,
+        export::export_note_to_markdown,
+        export::export_all_notes_to_markdown,
+        export::validate_export_filenames,
+        export::check_export_target,
+        export::export_notes_to_directory,
+        export::export_with_glossary,
+        export::export_notes_to_html,
+        export::export_hugo_bundle,
+        export::export_spa_bundle,
+        export::export_to_wiki,
+        export::preview_export,
+        export::export_notes_matching_regex,
+        export::export_excluding_tags,
+        export::export_with_checksum,
+        export::export_and_sign,
+        export::verify_signature,
+        export::export_index_cards,
+        export::export_bundle_tar_gz,
+        export::import_bundle_tar_gz,
+        export::export_kanban,
+        export::export_tag_summary,
+        export::export_activity_heatmap,
+        export::export_note_to_mhtml,
+        export::export_for_apple_notes,
+        export::export_to_subtitles,
+        export::export_note_to_image,
+        export::generate_note_thumbnail,
+        export::export_attachment_contact_sheet,
+        export::list_custom_exporters,
+        export::export_with_custom_exporter,
+        export::export_as_issue,
+        export::export_for_messaging,
+        export::export_sidecar,
+        export::export_canonical_json,
+        export::export_to_parquet,
+        export::export_to_clean_sqlite,
+        export::list_notebooks,
+        export::export_notebook,
+        export::export_notebook_to_db,
+        notebooks::rename_notebook,
+        notebooks::delete_notebook,
+        export::start_export_job,
+        export::get_export_job,
+        export::cancel_export_job,
+        i18n::set_backend_locale,
+        i18n::get_system_locale,
+        backup::backup_database,
+        backup::is_backup_running,
+        backup::get_last_backup_info,
+        integrity::check_database_integrity,
+        integrity::set_integrity_check_schedule,
+        integrity::get_integrity_check_status,
+        backup::restore_database,
+        backup::test_restore,
+        backup::diff_backups,
+        backup::delete_database,
+        archive::archive_notes,
+        archive::unarchive_note,
+        archive::list_archived_notes,
+        archive::search_archive,
+        databases::list_databases,
+        databases::get_active_database,
+        databases::switch_database,
+        databases::create_database,
+        memory::get_memory_usage,
+        diagnostics::create_diagnostic_bundle,
+        db_access::diagnose_database_access,
+        scheduler::set_auto_export,
+        scheduler::get_auto_export_status,
+        screenshot::capture_screenshot,
+        attachments::audit_attachments,
+        attachments::delete_orphan_attachments,
+        attachments::relink_attachment,
+        attachment_archive::archive_old_attachments,
+        attachment_archive::restore_archived_attachment,
+        link_preview::fetch_url_metadata,
+        markdown_import::import_markdown_file,
+        normalize::normalize_note_text,
+        normalize::normalize_all_notes,
+        replace::preview_find_and_replace,
+        replace::find_and_replace_all,
+        similarity::find_similar_notes,
+        web_clip::clip_url_to_note,
+        feeds::add_feed,
+        feeds::list_feeds,
+        feeds::remove_feed,
+        feeds::fetch_feeds_now,
+        graph::get_note_graph,
+        graph::find_link_cycles,
+        readability::analyze_readability,
+        word_frequency::analyze_word_frequency,
+        tts::speak_note,
+        tts::pause_speech,
+        tts::resume_speech,
+        tts::stop_speech,
+        tts::list_tts_voices,
+        focus::start_focus_session,
+        focus::pause_focus_session,
+        focus::resume_focus_session,
+        focus::stop_focus_session,
+        focus::get_focus_session,
+        focus::get_focus_stats,
+        window_effect::set_window_effect,
+        window_effect::clear_window_effect,
+        window_effect::get_supported_window_effects,
+        zoom::set_zoom_level,
+        zoom::zoom_in,
+        zoom::zoom_out,
+        zoom::zoom_reset,
+        selection::set_selection_to_note,
+        shutdown::prepare_for_shutdown,
+        tasks::extract_tasks,
+        tasks::update_note_tasks,
+        tasks::toggle_task,
+        versions::list_note_versions,
+        spellcheck::spellcheck_text,
+        spellcheck::spellcheck_paragraphs,
+        spellcheck::add_word_to_dictionary,
+        spellcheck::remove_word_from_dictionary,
+        settings::get_setting,
+        settings::set_setting,
+        settings::get_all_settings,
+        settings::migrate_local_settings,
+        settings::backup_settings,
+        settings::restore_settings,
+        logging::get_recent_logs,
+        logging::open_log_folder,
+        logging::set_log_level,
+        outline::extract_outline,
+        code_blocks::validate_code_blocks,
+        pdf_import::import_pdf,
+        portable::get_portable_status,
+        portable::convert_to_portable,
+        profiles::list_profiles,
+        profiles::create_profile,
+        profiles::switch_profile,
+        profiles::delete_profile,
+        qr::generate_note_qr,
+        qr::save_note_qr,
+        read_only::set_read_only,
+        read_only::get_read_only,
+        validation::validate_notes_json,
+        tags::extract_inline_tags,
+        tags::rename_tag,
+        tags::merge_tags,
+        tags::delete_unused_tags,
+        tags::get_tag_report,
+        theme::get_system_theme,
+        theme::get_effective_theme,
+        transfer::read_temp_result,
+        transfer::begin_upload,
+        transfer::append_chunk,
+        transfer::finish_upload,
+        updater::check_for_updates,
+        updater::download_update,
+        updater::install_update_and_restart,
+        updater::set_auto_update_check,
+        updater::get_auto_update_status
+    ];
+
     tauri::Builder::default()
+        // 需最先注册：第二个实例启动时把参数转发给已运行的实例后直接退出
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            launch::on_second_instance(app, argv, cwd);
+        }))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_os::init())
-        // .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        // 读取文件和生成缩略图在后台线程中进行，不阻塞 webview
+        .register_asynchronous_uri_scheme_protocol(
+            attachment_protocol::SCHEME,
+            |ctx, request, responder| {
+                let app = ctx.app_handle().clone();
+                std::thread::spawn(move || {
+                    responder.respond(attachment_protocol::handle(&app, &request));
+                });
+            },
+        )
+        .manage(backup::BackupState::default())
+        .manage(integrity::IntegrityState::default())
+        .manage(scheduler::AutoExportState::default())
+        .manage(transfer::UploadState::default())
+        .manage(tray::TrayState::default())
+        .manage(updater::UpdaterState::default())
+        .manage(diagnostics::CrashState::default())
+        .manage(theme::ThemeState::default())
+        .manage(spellcheck::SpellState::default())
+        .manage(export::ExportJobs::default())
+        .manage(export::ThumbnailCache::default())
+        .manage(link_preview::LinkPreviewState::default())
+        .manage(focus::FocusState::default())
+        .manage(tts::TtsState::default())
+        .manage(selection::SelectionState::default())
+        .manage(shutdown::ShutdownState::default())
+        .manage(read_only::ReadOnlyState::default())
+        .manage(active_note::ActiveNoteState::default())
         .setup(|app| {
-            // 创建托盘菜单
-            let show_item = MenuItemBuilder::with_id("show", "显示窗口").build(app)?;
-            let hide_item = MenuItemBuilder::with_id("hide", "隐藏窗口").build(app)?;
-            let separator = tauri::menu::PredefinedMenuItem::separator(app)?;
-            let quit_item = MenuItemBuilder::with_id("quit", "退出").build(app)?;
+            // 设置保存在当前配置的目录中；日志级别、语言和当前数据库都保存在设置中，需最先加载
+            profiles::init(app.handle())?;
+            app.manage(settings::SettingsStore::load(app.handle())?);
+            if let Err(e) = logging::init(app.handle()) {
+                eprintln!("{}", e);
+            }
+            log::info!("应用启动，版本 {}", app.package_info().version);
+            i18n::init(app.handle());
+            if let Err(e) = diagnostics::detect_last_crash(app.handle()) {
+                log::error!("{}", e);
+            }
+
+            // 数据库路径确定后创建共享连接池
+            app.manage(db::DbPool::new(db::db_path(app.handle())?));
+            read_only::init(app.handle());
+            active_note::init(app.handle());
+
+            // 保存了窗口效果时创建透明窗口，否则效果不可见
+            let transparent = window_effect::saved(app.handle()).is_some();
+            app.manage(window_effect::WindowEffectState::new(transparent));
 
             let win_builder = WebviewWindowBuilder::new(app, "main", WebviewUrl::default())
-                .title("本地笔记")
-                .inner_size(800.0, 600.0)
+                .title(i18n::t("app.title", &[]))
                 .decorations(false)
+                .transparent(transparent)
                 .resizable(true);
+            let (win_builder, maximized) = window_state::restore(app, win_builder);
 
             // set transparent title bar only when building for macOS
             #[cfg(target_os = "macos")]
             let win_builder = win_builder.title_bar_style(TitleBarStyle::Transparent);
 
             let window = win_builder.build().unwrap();
+            if maximized {
+                let _ = window.maximize();
+            }
+            theme::init(app.handle());
+            window_effect::init(app.handle());
+            zoom::init(app.handle());
+            shutdown::init(app.handle());
 
-            let menu = MenuBuilder::new(app)
-                .items(&[&show_item, &hide_item, &separator, &quit_item])
-                .build()?;
+            // 创建托盘菜单
+            let menu = tray::build_menu(app.handle())?;
 
             // set background color only when building for macOS
             #[cfg(target_os = "macos")]
@@ -196,43 +346,69 @@ pub fn run() {
                 }
             }
             // 创建系统托盘
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(tray::TRAY_ID)
                 .menu(&menu)
-                .icon(app.default_window_icon().unwrap().clone())
-                .tooltip("本地笔记")
+                .icon(
+                    tray::themed_icon(app.handle(), theme::effective_theme(app.handle()))
+                        .unwrap_or_else(|| app.default_window_icon().unwrap().clone()),
+                )
+                .tooltip(i18n::t("app.title", &[]))
                 .on_menu_event(|app, event| match event.id.as_ref() {
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.unminimize();
-                        }
-                    }
+                    "show" => tray::show_main_window(app),
                     "hide" => {
                         if let Some(window) = app.get_webview_window("main") {
                             let _ = window.hide();
                         }
                     }
+                    "update" => {
+                        tray::show_main_window(app);
+                        let version = app
+                            .state::<tray::TrayState>()
+                            .update_version
+                            .lock()
+                            .unwrap()
+                            .clone();
+                        let _ = app.emit("update-available", version);
+                    }
                     "quit" => {
                         app.exit(0);
                     }
-                    _ => {}
+                    id => {
+                        if let Some(name) = id.strip_prefix(tray::PROFILE_PREFIX) {
+                            if let Err(e) = profiles::switch_profile(app.clone(), name.to_string())
+                            {
+                                log::error!("{}", e);
+                            }
+                            // 勾选状态由菜单自行切换，重新生成以保持与当前配置一致
+                            let _ = tray::refresh(app);
+                        }
+                    }
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click { .. } = event {
-                        if let Some(app) = tray.app_handle().get_webview_window("main") {
-                            if app.is_visible().unwrap_or(false) {
-                                let _ = app.hide();
+                        let app = tray.app_handle();
+                        if let Some(window) = app.get_webview_window("main") {
+                            if window.is_visible().unwrap_or(false) {
+                                let _ = window.hide();
                             } else {
-                                let _ = app.show();
-                                let _ = app.set_focus();
-                                let _ = app.unminimize();
+                                tray::show_main_window(app);
                             }
                         }
                     }
                 })
                 .build(app)?;
 
+            // 启动自动导出定时器
+            scheduler::spawn(app.handle().clone());
+            // 启动数据库完整性定时检查
+            integrity::spawn(app.handle().clone());
+            // 启动后台更新检查
+            updater::spawn(app.handle().clone());
+            // 启动专注计时
+            focus::spawn(app.handle().clone());
+            // 注册“选中文本创建笔记”快捷键
+            selection::init(app.handle());
+
             // 注册全局快捷键
             // 注意：Tauri 2.0的全局快捷键API有变化，暂时注释掉
             // use tauri_plugin_global_shortcut::GlobalShortcutExt;
@@ -279,15 +455,48 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            show_main_window,
-            hide_main_window,
-            export_note_to_markdown,
-            export_all_notes_to_markdown,
-            backup_database,
-            restore_database,
-            delete_database
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        // 系统主题变化时重新检测，Linux 上与轮询结果保持一致
+        .on_window_event(|window, event| match event {
+            WindowEvent::ThemeChanged(_) => {
+                let app = window.app_handle();
+                theme::system_changed(app, theme::detect_system_theme(app));
+            }
+            // 窗口销毁后无法再读取位置，关闭时先保存
+            WindowEvent::CloseRequested { .. } => {
+                if let Err(e) = window_state::save(window.app_handle()) {
+                    log::warn!("{}", e);
+                }
+            }
+            _ => {}
+        })
+        // 页面加载完成后前端才能收到事件，此时再提示上次运行崩溃
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                diagnostics::emit_last_crash(webview.app_handle());
+            }
+        })
+        .invoke_handler(move |invoke| {
+            logging::log_command(invoke.message.command());
+            invoke_handler(invoke)
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // 先阻止退出，在后台线程中完成收尾（有时限）后再退出
+            tauri::RunEvent::ExitRequested { api, code, .. } if !shutdown::is_prepared(app) => {
+                api.prevent_exit();
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    shutdown::prepare(&app);
+                    app.exit(code.unwrap_or(0));
+                });
+            }
+            // 退出前关闭连接池，确保 WAL 已合并
+            tauri::RunEvent::Exit => {
+                if let Some(pool) = app.try_state::<db::DbPool>() {
+                    pool.close();
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,408 @@
+use crate::error::{wrap, AppError, AppResult};
+use encoding_rs::{Encoding, UTF_8};
+use regex::Regex;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+// 只读取页面开头部分，<head> 中的元数据一般都在这里
+const MAX_BODY_SIZE: usize = 512 * 1024;
+const MAX_REDIRECTS: usize = 5;
+const USER_AGENT: &str = concat!("yue-editor/", env!("CARGO_PKG_VERSION"));
+
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title(?:\s[^>]*)?>(.*?)</title\s*>").unwrap());
+static META: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap());
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<link\s[^>]*>").unwrap());
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)([a-z][\w:-]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+static CHARSET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)charset\s*=\s*["']?([\w.:-]+)"#).unwrap());
+static ENTITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#[xX][0-9a-fA-F]+|#\d+|[a-zA-Z]+);").unwrap());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UrlMetadata {
+    // 跟随重定向后的最终地址
+    pub url: String,
+    pub title: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub favicon: Option<String>,
+}
+
+// 本次会话内按 URL 缓存成功的结果，重复粘贴同一链接时不再请求
+#[derive(Default)]
+pub struct LinkPreviewState(Mutex<HashMap<String, UrlMetadata>>);
+
+impl LinkPreviewState {
+    // (条数, 近似字节数)
+    pub fn usage(&self) -> (usize, usize) {
+        let cache = self.0.lock().unwrap();
+        let bytes = cache
+            .iter()
+            .map(|(url, metadata)| {
+                let fields = [
+                    &metadata.title,
+                    &metadata.og_title,
+                    &metadata.og_description,
+                    &metadata.favicon,
+                ];
+                url.len()
+                    + metadata.url.len()
+                    + fields
+                        .iter()
+                        .filter_map(|f| f.as_ref())
+                        .map(String::len)
+                        .sum::<usize>()
+            })
+            .sum();
+        (cache.len(), bytes)
+    }
+}
+
+// 回环、内网、链路本地等地址不允许访问，避免粘贴的链接被用来探测本机或局域网服务
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                // 100.64.0.0/10 运营商级 NAT
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // fc00::/7 唯一本地地址，fe80::/10 链路本地地址
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// 域名解析后再过滤地址，重定向到的域名同样经过这里
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = tauri::async_runtime::spawn_blocking(move || {
+                (host.as_str(), 0).to_socket_addrs().map(|addrs| {
+                    addrs
+                        .filter(|addr| is_public(addr.ip()))
+                        .collect::<Vec<SocketAddr>>()
+                })
+            })
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)??;
+            if addrs.is_empty() {
+                return Err(Box::new(AppError::new("link.blocked_address"))
+                    as Box<dyn std::error::Error + Send + Sync>);
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+// 只允许 http(s)；IP 地址形式的主机不经过解析器，需要在这里检查
+fn check_url(url: &Url) -> AppResult<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::new("link.unsupported_scheme").with("scheme", url.scheme()));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| AppError::new("link.invalid_url").with("url", url))?;
+    let blocked = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => !is_public(ip),
+        Err(_) => host.eq_ignore_ascii_case("localhost"),
+    };
+    if blocked {
+        return Err(AppError::new("link.blocked_address"));
+    }
+    Ok(())
+}
+
+fn redirect_policy(attempt: Attempt) -> reqwest::redirect::Action {
+    if attempt.previous().len() > MAX_REDIRECTS {
+        return attempt.error(AppError::new("link.too_many_redirects"));
+    }
+    match check_url(attempt.url()) {
+        Ok(()) => attempt.follow(),
+        Err(e) => attempt.error(e),
+    }
+}
+
+fn client(timeout: Duration) -> AppResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(USER_AGENT)
+        .redirect(Policy::custom(redirect_policy))
+        // 经代理访问时目标域名由代理解析，地址过滤会失效
+        .no_proxy()
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+        .map_err(wrap("link.fetch_failed"))
+}
+
+// 先看响应头，其次看页面中的 <meta charset>，都没有时按 UTF-8 解码
+pub fn decode(body: &[u8], content_type: &str) -> String {
+    let head = String::from_utf8_lossy(&body[..body.len().min(2048)]);
+    let encoding = Encoding::for_bom(body)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| {
+            CHARSET
+                .captures(content_type)
+                .or_else(|| CHARSET.captures(&head))
+                .and_then(|caps| Encoding::for_label(caps[1].as_bytes()))
+        })
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+// 解码实体并合并空白，空字符串视为没有
+fn clean(text: &str) -> Option<String> {
+    let text = decode_entities(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn attributes(tag: &str) -> HashMap<String, String> {
+    ATTRIBUTE
+        .captures_iter(tag)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .or_else(|| caps.get(4))
+                .map_or("", |m| m.as_str());
+            (caps[1].to_lowercase(), value.to_string())
+        })
+        .collect()
+}
+
+pub fn extract(html: &str, url: &Url) -> UrlMetadata {
+    let mut og_title = None;
+    let mut og_description = None;
+    for tag in META.find_iter(html) {
+        let attrs = attributes(tag.as_str());
+        let key = attrs.get("property").or_else(|| attrs.get("name"));
+        let content = attrs.get("content").and_then(|c| clean(c));
+        match key.map(|key| key.to_lowercase()).as_deref() {
+            Some("og:title") if og_title.is_none() => og_title = content,
+            Some("og:description") if og_description.is_none() => og_description = content,
+            _ => {}
+        }
+    }
+
+    // 优先使用 rel 为 icon 的链接，没有时退回站点根目录下的 favicon.ico
+    let favicon = LINK
+        .find_iter(html)
+        .map(|tag| attributes(tag.as_str()))
+        .find(|attrs| {
+            attrs.get("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("icon"))
+            })
+        })
+        .and_then(|attrs| {
+            attrs
+                .get("href")
+                .and_then(|href| url.join(href.trim()).ok())
+        })
+        .or_else(|| url.join("/favicon.ico").ok())
+        .map(String::from);
+
+    UrlMetadata {
+        url: url.to_string(),
+        title: TITLE.captures(html).and_then(|caps| clean(&caps[1])),
+        og_title,
+        og_description,
+        favicon,
+    }
+}
+
+// 下载结果：跟随重定向后的地址、小写的 Content-Type，以及最多 max_size 字节的内容
+pub struct Download {
+    pub url: Url,
+    pub content_type: String,
+    pub body: Vec<u8>,
+    pub truncated: bool,
+    // 用于下次条件请求的 ETag / Last-Modified
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+// 条件请求的校验值，来自上次下载的响应头
+#[derive(Clone, Debug, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Download {
+    pub fn mime(&self) -> &str {
+        self.content_type.split(';').next().unwrap_or("").trim()
+    }
+
+    pub fn is_html(&self) -> bool {
+        matches!(self.mime(), "text/html" | "application/xhtml+xml")
+    }
+}
+
+pub fn parse_url(url: &str) -> AppResult<Url> {
+    let parsed =
+        Url::parse(url.trim()).map_err(|_| AppError::new("link.invalid_url").with("url", url))?;
+    check_url(&parsed)?;
+    Ok(parsed)
+}
+
+// 经过地址过滤下载；服务器返回错误状态码时错误中带上状态码，便于前端说明原因（如需要登录）
+pub async fn download(
+    url: &Url,
+    accept: &str,
+    timeout: Duration,
+    max_size: usize,
+) -> AppResult<Download> {
+    download_if_modified(url, accept, timeout, max_size, &Validators::default())
+        .await?
+        .ok_or_else(|| AppError::new("link.http_status").with("status", 304))
+}
+
+// 带 If-None-Match / If-Modified-Since 的下载，内容未变化（304）时返回 None
+pub async fn download_if_modified(
+    url: &Url,
+    accept: &str,
+    timeout: Duration,
+    max_size: usize,
+    validators: &Validators,
+) -> AppResult<Option<Download>> {
+    let mut request = client(timeout)?
+        .get(url.clone())
+        .header(reqwest::header::ACCEPT, accept);
+    if let Some(etag) = &validators.etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+    let mut response = request.send().await.map_err(wrap("link.fetch_failed"))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(AppError::new("link.http_status").with("status", status.as_u16()));
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    let final_url = response.url().clone();
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await.map_err(wrap("link.fetch_failed"))? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= max_size {
+            truncated = body.len() > max_size;
+            body.truncate(max_size);
+            break;
+        }
+    }
+
+    Ok(Some(Download {
+        url: final_url,
+        content_type,
+        body,
+        truncated,
+        etag,
+        last_modified,
+    }))
+}
+
+async fn fetch(url: &Url) -> AppResult<UrlMetadata> {
+    // 只需要 <head> 中的内容，超出部分直接截断
+    let page = download(
+        url,
+        "text/html,application/xhtml+xml",
+        TIMEOUT,
+        MAX_BODY_SIZE,
+    )
+    .await?;
+    if !page.is_html() {
+        return Err(AppError::new("link.not_html").with("type", page.mime()));
+    }
+
+    Ok(extract(&decode(&page.body, &page.content_type), &page.url))
+}
+
+// 获取网页标题等元数据，由前端决定如何使用（例如把粘贴的链接替换为 [标题](url)）
+#[tauri::command]
+pub async fn fetch_url_metadata(
+    state: tauri::State<'_, LinkPreviewState>,
+    url: String,
+) -> AppResult<UrlMetadata> {
+    if let Some(metadata) = state.0.lock().unwrap().get(&url) {
+        return Ok(metadata.clone());
+    }
+
+    let metadata = fetch(&parse_url(&url)?).await?;
+
+    state.0.lock().unwrap().insert(url, metadata.clone());
+    Ok(metadata)
+}
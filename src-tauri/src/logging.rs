@@ -0,0 +1,203 @@
+use crate::diagnostics;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::settings;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tauri_plugin_opener::OpenerExt;
+
+const LOG_FILE: &str = "yue.log";
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+// 包括当前日志在内最多保留的文件数
+const KEEP_FILES: usize = 5;
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    // yue.log -> yue.1.log -> ... -> yue.4.log，最旧的文件被丢弃
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for i in (1..KEEP_FILES).rev() {
+            let from = if i == 1 {
+                self.dir.join(LOG_FILE)
+            } else {
+                self.dir.join(format!("yue.{}.log", i - 1))
+            };
+            let to = self.dir.join(format!("yue.{}.log", i));
+            if from.exists() {
+                fs::rename(from, to)?;
+            }
+        }
+        *self = RotatingFile::open(&self.dir)?;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_LOG_SIZE {
+            let _ = self.rotate();
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+}
+
+struct FileLogger {
+    file: Mutex<RotatingFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if cfg!(debug_assertions) {
+            eprint!("{}", line);
+        }
+        self.file.lock().unwrap().write_line(&line);
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap().file.flush();
+    }
+}
+
+pub fn log_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(portable::root_data_dir(app)?.join("logs"))
+}
+
+// 从新到旧排列的日志文件名
+pub fn log_files() -> Vec<String> {
+    (0..KEEP_FILES)
+        .map(|i| {
+            if i == 0 {
+                LOG_FILE.to_string()
+            } else {
+                format!("yue.{}.log", i)
+            }
+        })
+        .collect()
+}
+
+// 初始化日志：写入数据根目录下的 logs/yue.log（按大小滚动），调试构建同时输出到 stderr
+pub fn init(app: &tauri::AppHandle) -> AppResult<()> {
+    let dir = log_dir(app)?;
+    let file = RotatingFile::open(&dir).map_err(wrap("log.open_failed"))?;
+
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        file: Mutex::new(file),
+    });
+    log::set_logger(logger).map_err(wrap("log.init_failed"))?;
+
+    apply_level(app);
+
+    // panic 时将信息和调用栈写入日志，并单独保存一份崩溃报告供下次启动检测
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        log::error!("panic: {}\n{}", info, backtrace);
+        diagnostics::write_panic_report(&dir, &info.to_string(), &backtrace.to_string());
+        log::logger().flush();
+        default_hook(info);
+    }));
+
+    Ok(())
+}
+
+// 按设置中的日志级别过滤
+pub fn apply_level(app: &tauri::AppHandle) {
+    let level = settings::get::<String>(app, settings::LOG_LEVEL)
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(DEFAULT_LEVEL);
+    log::set_max_level(level);
+}
+
+// 命令入口日志，只记录命令名，不记录参数以免泄露笔记内容
+pub fn log_command(command: &str) {
+    log::debug!(target: "command", "{}", command);
+}
+
+#[tauri::command]
+pub fn get_recent_logs(app: tauri::AppHandle, lines: usize) -> AppResult<Vec<String>> {
+    let dir = log_dir(&app)?;
+    let mut result: Vec<String> = Vec::new();
+
+    for name in log_files() {
+        if result.len() >= lines {
+            break;
+        }
+        let Ok(content) = fs::read_to_string(dir.join(name)) else {
+            break;
+        };
+
+        let needed = lines - result.len();
+        let mut chunk: Vec<String> = content
+            .lines()
+            .rev()
+            .take(needed)
+            .map(str::to_string)
+            .collect();
+        chunk.reverse();
+        chunk.append(&mut result);
+        result = chunk;
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn open_log_folder(app: tauri::AppHandle) -> AppResult<()> {
+    let dir = log_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(wrap("log.create_dir_failed"))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(wrap("log.open_dir_failed"))
+}
+
+#[tauri::command]
+pub fn set_log_level(app: tauri::AppHandle, level: String) -> AppResult<()> {
+    let level = LevelFilter::from_str(&level)
+        .map_err(|_| AppError::new("log.invalid_level").with("level", &level))?;
+    log::set_max_level(level);
+
+    settings::set(&app, settings::LOG_LEVEL, level.to_string().to_lowercase())?;
+    log::info!("日志级别已设置为 {}", level);
+
+    Ok(())
+}
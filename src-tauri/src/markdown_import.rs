@@ -0,0 +1,468 @@
+use crate::attachment_protocol::content_type;
+use crate::db;
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use crate::portable;
+use crate::screenshot::ATTACHMENTS_DIR;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use regex::{Captures, Regex};
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static HTML_IMAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(<img\b[^>]*?\bsrc\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+
+const EDITOR_TYPES: [&str; 2] = ["tiptap", "custom"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MarkdownImport {
+    pub note: Value,
+    // 复制到附件目录的图片数
+    pub images: usize,
+    // 找不到或无法复制的图片，保留原链接
+    pub missing_images: Vec<String>,
+}
+
+// 把相对路径引用的图片复制到笔记的附件目录，链接改为与 capture_screenshot 相同的相对路径
+struct ImageRebase {
+    base_dir: PathBuf,
+    target_dir: PathBuf,
+    relative_dir: String,
+    copied: HashMap<PathBuf, String>,
+    used_names: HashSet<String>,
+    missing: Vec<String>,
+}
+
+// 带协议的地址（http:、data: 等）、协议相对地址和页内锚点不是本地文件
+fn is_external(src: &str) -> bool {
+    src.is_empty()
+        || src.starts_with('#')
+        || src.starts_with("//")
+        || src
+            .split_once(':')
+            .is_some_and(|(scheme, _)| scheme.len() > 1 && !scheme.contains(['/', '\\']))
+}
+
+pub fn percent_decode(src: &str) -> String {
+    let bytes = src.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl ImageRebase {
+    fn report_missing(&mut self, src: &str) {
+        if !self.missing.iter().any(|m| m == src) {
+            self.missing.push(src.to_string());
+        }
+    }
+
+    // 重名时在文件名后追加序号
+    fn unique_name(&mut self, path: &Path) -> String {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "image".to_string());
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let mut name = format!("{}{}", stem, extension);
+        let mut n = 2;
+        while !self.used_names.insert(name.clone()) {
+            name = format!("{}-{}{}", stem, n, extension);
+            n += 1;
+        }
+        name
+    }
+
+    // 返回改写后的链接；不是本地图片时返回 None，保留原链接
+    fn rebase(&mut self, src: &str) -> Option<String> {
+        if is_external(src) {
+            return None;
+        }
+        let path = self.base_dir.join(percent_decode(src));
+        let Ok(path) = path.canonicalize().and_then(|path| {
+            path.is_file()
+                .then_some(path)
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }) else {
+            self.report_missing(src);
+            return None;
+        };
+        // 只复制 base_dir 之内的图片，防止 ../ 或绝对路径把任意本地文件带进附件目录
+        let inside = self
+            .base_dir
+            .canonicalize()
+            .is_ok_and(|base| path.starts_with(base));
+        if !inside || !content_type(&path).starts_with("image/") {
+            log::warn!("跳过导入目录之外或不是图片的文件: {}", src);
+            return None;
+        }
+        if let Some(link) = self.copied.get(&path) {
+            return Some(link.clone());
+        }
+
+        let name = self.unique_name(&path);
+        let copied = std::fs::create_dir_all(&self.target_dir)
+            .and_then(|_| std::fs::copy(&path, self.target_dir.join(&name)));
+        if let Err(e) = copied {
+            log::warn!("复制图片 {} 失败: {}", path.display(), e);
+            self.report_missing(src);
+            return None;
+        }
+
+        let link = format!("{}/{}", self.relative_dir, name);
+        self.copied.insert(path, link.clone());
+        Some(link)
+    }
+
+    fn rebase_html(&mut self, html: &str) -> String {
+        HTML_IMAGE
+            .replace_all(html, |caps: &Captures| {
+                let src = caps
+                    .get(2)
+                    .or_else(|| caps.get(3))
+                    .map_or("", |m| m.as_str());
+                match self.rebase(src) {
+                    Some(link) => format!("{}\"{}\"", &caps[1], link),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+// export_note_to_markdown 等写入的 front-matter 字段，同时兼容其他工具常用的别名
+#[derive(Debug, Default)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub favorited: bool,
+    pub category_id: Option<i64>,
+    pub editor_type: Option<String>,
+}
+
+// 双引号字符串按导出时的转义规则还原，单引号中 '' 表示一个单引号，其余原样去掉首尾空白
+fn yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut result = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        }
+        return result;
+    }
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+    value.to_string()
+}
+
+// 行内列表 ["a", "b"]，逗号可以出现在引号内
+fn yaml_inline_list(value: &str) -> Vec<String> {
+    let inner = value.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in inner.chars() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => {
+                items.push(yaml_scalar(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(yaml_scalar(&current));
+    items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+// 转为数据库使用的 UTC 时间格式（SQLite CURRENT_TIMESTAMP），无法识别时返回 None
+fn db_timestamp(value: &str) -> Option<String> {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+    let value = value.trim();
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&chrono::Utc).format(FORMAT).to_string());
+    }
+    if let Ok(time) = chrono::NaiveDateTime::parse_from_str(value, FORMAT) {
+        return Some(time.format(FORMAT).to_string());
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|date| format!("{} 00:00:00", date))
+}
+
+// 文件开头由 --- 包围的 YAML front-matter，只解析 key: value、行内列表和 "- item" 形式的块列表；
+// 返回解析结果和去掉 front-matter 后的正文，没有 front-matter 时原样返回
+pub fn split_front_matter(markdown: &str) -> (Option<FrontMatter>, &str) {
+    let text = markdown.trim_start_matches('\u{feff}');
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, markdown);
+    };
+
+    let mut offset = 0;
+    let mut end = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            end = Some((offset, offset + line.len()));
+            break;
+        }
+        offset += line.len();
+    }
+    let Some((yaml_end, body_start)) = end else {
+        return (None, markdown);
+    };
+
+    let mut front_matter = FrontMatter::default();
+    let mut list_key: Option<String> = None;
+    for line in rest[..yaml_end].lines() {
+        let trimmed = line.trim();
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if list_key.as_deref() == Some("tags") {
+                front_matter.tags.push(yaml_scalar(item));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        list_key = value.is_empty().then(|| key.clone());
+        match key.as_str() {
+            "title" => front_matter.title = Some(yaml_scalar(value)),
+            "created_at" | "created" | "date" => {
+                front_matter.created_at = db_timestamp(&yaml_scalar(value))
+            }
+            "updated_at" | "updated" | "modified" => {
+                front_matter.updated_at = db_timestamp(&yaml_scalar(value))
+            }
+            "tags" if value.starts_with('[') => front_matter.tags = yaml_inline_list(value),
+            "tags" if !value.is_empty() => {
+                front_matter.tags = yaml_scalar(value)
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            }
+            "pinned" | "is_pinned" => front_matter.pinned = yaml_scalar(value) == "true",
+            "favorited" | "is_favorited" => front_matter.favorited = yaml_scalar(value) == "true",
+            "category_id" => front_matter.category_id = yaml_scalar(value).parse().ok(),
+            // 只接受编辑器支持的类型，两种编辑器都保存 HTML，正文同样转换
+            "editor_type" => {
+                front_matter.editor_type = Some(yaml_scalar(value))
+                    .filter(|editor_type| EDITOR_TYPES.contains(&editor_type.as_str()))
+            }
+            _ => {}
+        }
+    }
+
+    (Some(front_matter), &rest[body_start..])
+}
+
+// 正文以与标题相同的一级标题开头时去掉该标题，避免导入后标题重复出现在正文中
+fn strip_title_heading<'a>(body: &'a str, title: &str) -> &'a str {
+    let trimmed = body.trim_start();
+    let (first, rest) = trimmed.split_once('\n').unwrap_or((trimmed, ""));
+    match first.trim_end().strip_prefix("# ") {
+        Some(heading) if heading.trim() == title.trim() => rest.trim_start_matches(['\r', '\n']),
+        _ => body,
+    }
+}
+
+// 转换为编辑器使用的 HTML；rebase 为 None 时图片链接保持不变
+fn render(markdown: &str, mut rebase: Option<&mut ImageRebase>) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(markdown, options).map(|event| {
+        let Some(rebase) = rebase.as_deref_mut() else {
+            return event;
+        };
+        match event {
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: rebase.rebase(&dest_url).map_or(dest_url, CowStr::from),
+                title,
+                id,
+            }),
+            Event::Html(html) => Event::Html(rebase.rebase_html(&html).into()),
+            Event::InlineHtml(html) => Event::InlineHtml(rebase.rebase_html(&html).into()),
+            event => event,
+        }
+    });
+
+    let mut content = String::new();
+    html::push_html(&mut content, events);
+    content
+}
+
+// 导入 Markdown 文件为新笔记，相对路径的图片按 base_dir（默认为文件所在目录）解析，只复制该目录之内的图片
+pub fn import(
+    app: &tauri::AppHandle,
+    path: &Path,
+    base_dir: Option<PathBuf>,
+    copy_images: bool,
+) -> AppResult<MarkdownImport> {
+    let markdown = std::fs::read_to_string(path).map_err(wrap("markdown.read_failed"))?;
+    let (front_matter, body) = split_front_matter(&markdown);
+    let front_matter = front_matter.unwrap_or_default();
+    let title = front_matter
+        .title
+        .clone()
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| t("export.untitled", &[]));
+    let body = strip_title_heading(body, &title);
+    let base_dir = base_dir
+        .or_else(|| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let attachments_dir = portable::resolve_data_dir(app)?.join(ATTACHMENTS_DIR);
+
+    // 附件目录按笔记 id 划分，先插入笔记拿到 id，再在同一事务中写入转换后的内容
+    let (note, images, missing_images) = db::with_write_conn(app, |conn| {
+        let tx = conn.transaction()?;
+        let mut note = db::insert_note(&tx, &title, "")?;
+        let id = note["id"].as_i64().unwrap_or_default();
+
+        let mut rebase = ImageRebase {
+            base_dir,
+            target_dir: attachments_dir.join(id.to_string()),
+            relative_dir: format!("{}/{}", ATTACHMENTS_DIR, id),
+            copied: HashMap::new(),
+            used_names: HashSet::new(),
+            missing: Vec::new(),
+        };
+        let content = render(body, copy_images.then_some(&mut rebase));
+
+        tx.execute(
+            "UPDATE notes SET content = ?1,
+                    created_at = COALESCE(?2, created_at),
+                    updated_at = COALESCE(?3, updated_at),
+                    is_pinned = ?4, is_favorited = ?5,
+                    editor_type = COALESCE(?6, editor_type),
+                    category_id = (SELECT id FROM categories WHERE id = ?7)
+              WHERE id = ?8",
+            params![
+                content,
+                front_matter.created_at,
+                front_matter.updated_at,
+                front_matter.pinned,
+                front_matter.favorited,
+                front_matter.editor_type,
+                front_matter.category_id,
+                id
+            ],
+        )?;
+        // 分类可能已在当前数据库中删除，此时导入为未分类
+        let category_id: Option<i64> =
+            tx.query_row("SELECT category_id FROM notes WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })?;
+        for tag in &front_matter.tags {
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![id, tag],
+            )?;
+        }
+        tx.commit()?;
+        note["content"] = Value::String(content);
+        if let Some(created_at) = &front_matter.created_at {
+            note["created_at"] = Value::String(created_at.clone());
+        }
+        if let Some(updated_at) = &front_matter.updated_at {
+            note["updated_at"] = Value::String(updated_at.clone());
+        }
+        note["is_pinned"] = Value::Bool(front_matter.pinned);
+        note["is_favorited"] = Value::Bool(front_matter.favorited);
+        note["category_id"] = serde_json::json!(category_id);
+        if let Some(editor_type) = &front_matter.editor_type {
+            note["editor_type"] = Value::String(editor_type.clone());
+        }
+        note["tags"] = serde_json::json!(front_matter.tags);
+        Ok((note, rebase.copied.len(), rebase.missing))
+    })?;
+
+    log::info!(
+        "已导入 Markdown 文件 {}，复制图片 {} 张，缺失 {} 张",
+        path.display(),
+        images,
+        missing_images.len()
+    );
+
+    Ok(MarkdownImport {
+        note,
+        images,
+        missing_images,
+    })
+}
+
+// copy_images 默认开启
+#[tauri::command]
+pub async fn import_markdown_file(
+    app: tauri::AppHandle,
+    file_path: String,
+    base_dir: Option<String>,
+    copy_images: Option<bool>,
+) -> AppResult<MarkdownImport> {
+    import(
+        &app,
+        Path::new(&file_path),
+        base_dir.map(PathBuf::from),
+        copy_images.unwrap_or(true),
+    )
+}
@@ -0,0 +1,76 @@
+use crate::export::{ExportJobs, ThumbnailCache};
+use crate::link_preview::LinkPreviewState;
+use crate::spellcheck::SpellState;
+use crate::transfer::UploadState;
+use serde::Serialize;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::Manager;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CacheUsage {
+    pub name: &'static str,
+    pub entries: usize,
+    // 只统计缓存中的字符串数据，不含容器本身的开销；无法估算时为空
+    pub bytes: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryUsage {
+    // 常驻内存和虚拟内存（字节），系统不提供时为空
+    pub rss: Option<u64>,
+    pub virtual_memory: Option<u64>,
+    // SQLite 自身分配的内存（页缓存等）
+    pub sqlite: i64,
+    pub caches: Vec<CacheUsage>,
+}
+
+// 只刷新当前进程的内存信息，不枚举其他进程
+fn process_memory() -> Option<(u64, u64)> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    let process = system.process(pid)?;
+    Some((process.memory(), process.virtual_memory()))
+}
+
+fn cache(name: &'static str, (entries, bytes): (usize, usize)) -> CacheUsage {
+    CacheUsage {
+        name,
+        entries,
+        bytes: Some(bytes),
+    }
+}
+
+fn count(name: &'static str, entries: usize) -> CacheUsage {
+    CacheUsage {
+        name,
+        entries,
+        bytes: None,
+    }
+}
+
+// 开销很小，可供前端定期轮询以观察长时间运行时的内存增长
+#[tauri::command]
+pub fn get_memory_usage(app: tauri::AppHandle) -> MemoryUsage {
+    let (rss, virtual_memory) = process_memory().unzip();
+    let spell = app.state::<SpellState>();
+    let caches = vec![
+        cache("thumbnails", app.state::<ThumbnailCache>().usage()),
+        cache("link_previews", app.state::<LinkPreviewState>().usage()),
+        cache("spellcheck", spell.cache_usage()),
+        count("spellcheck_dictionaries", spell.dictionaries_loaded()),
+        count("export_jobs", app.state::<ExportJobs>().count()),
+        count("uploads", app.state::<UploadState>().count()),
+    ];
+
+    MemoryUsage {
+        rss,
+        virtual_memory,
+        sqlite: unsafe { rusqlite::ffi::sqlite3_memory_used() },
+        caches,
+    }
+}
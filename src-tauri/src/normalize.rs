@@ -0,0 +1,307 @@
+use crate::db;
+use crate::error::AppResult;
+use crate::versions;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+// 连续空行最多保留一行
+const MAX_BLANK_LINES: usize = 1;
+// 零宽空格、词连接符和 BOM；零宽连接符 / 非连接符用于 emoji 组合和部分文字的书写，不删除
+const INVISIBLE: [char; 3] = ['\u{200b}', '\u{2060}', '\u{feff}'];
+const NBSP: char = '\u{a0}';
+
+// 各项整理默认全部开启
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NormalizeOptions {
+    // CRLF 和单独的 CR 统一为 LF
+    pub line_endings: bool,
+    // Unicode NFC 规范化（例如 macOS 上复制来的分解形式）
+    pub unicode_nfc: bool,
+    // 去掉行尾空白，Markdown 硬换行保留为两个空格
+    pub trailing_whitespace: bool,
+    // 合并多余的空行并去掉首尾空行
+    pub collapse_blank_lines: bool,
+    // 删除零宽字符和 BOM
+    pub remove_invisible: bool,
+    // 不换行空格替换为普通空格，tiptap 笔记中保留
+    pub replace_nbsp: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            line_endings: true,
+            unicode_nfc: true,
+            trailing_whitespace: true,
+            collapse_blank_lines: true,
+            remove_invisible: true,
+            replace_nbsp: true,
+        }
+    }
+}
+
+// 每项整理修改的次数：换行符、字符按个数计，其余按行计
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NormalizeChanges {
+    pub line_endings: usize,
+    pub unicode_nfc: usize,
+    pub trailing_whitespace: usize,
+    pub blank_lines: usize,
+    pub invisible: usize,
+    pub nbsp: usize,
+}
+
+impl NormalizeChanges {
+    fn total(&self) -> usize {
+        self.line_endings
+            + self.unicode_nfc
+            + self.trailing_whitespace
+            + self.blank_lines
+            + self.invisible
+            + self.nbsp
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NormalizedText {
+    pub content: String,
+    pub changes: NormalizeChanges,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteNormalization {
+    pub note_id: i64,
+    pub title: String,
+    pub changes: NormalizeChanges,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NormalizeReport {
+    pub dry_run: bool,
+    pub notes: Vec<NoteNormalization>,
+    pub total_changes: usize,
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim_end_matches('\r').trim().is_empty()
+}
+
+// 该行结束时是否仍在 <pre> 块中
+fn in_pre_after(line: &str, in_pre: bool) -> bool {
+    let lower = line.to_lowercase();
+    match (lower.rfind("<pre"), lower.rfind("</pre")) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => in_pre,
+    }
+}
+
+// 按选项整理笔记内容。空白相关的整理（行尾空白、空行、不换行空格）不会修改代码块内的内容；
+// html 为 true 时（tiptap 笔记）同样跳过 <pre> 块所在的行，并保留不换行空格（&nbsp; 在 HTML 中有意义）；
+// 不统一换行符时保留每行原有的 \r
+pub fn normalize_content(content: &str, options: &NormalizeOptions, html: bool) -> NormalizedText {
+    let mut changes = NormalizeChanges::default();
+    let content = if options.line_endings {
+        changes.line_endings = content.matches('\r').count();
+        content.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        content.to_string()
+    };
+    let lines: Vec<&str> = content.split('\n').collect();
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut fence: Option<&str> = None;
+    let mut in_pre = false;
+    let mut blank = 0;
+    for (i, raw) in lines.iter().enumerate() {
+        let (line, cr) = match raw.strip_suffix('\r') {
+            Some(line) => (line, "\r"),
+            None => (*raw, ""),
+        };
+        let mut line = line.to_string();
+        if options.remove_invisible {
+            let before = line.chars().count();
+            line.retain(|c| !INVISIBLE.contains(&c));
+            changes.invisible += before - line.chars().count();
+        }
+        if options.unicode_nfc {
+            let composed: String = line.nfc().collect();
+            if composed != line {
+                changes.unicode_nfc += 1;
+                line = composed;
+            }
+        }
+
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        if let Some(open) = fence {
+            output.push(format!("{}{}", line, cr));
+            if marker == Some(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if marker.is_some() {
+            fence = marker;
+        }
+        if html {
+            let pre = in_pre || line.to_lowercase().contains("<pre");
+            in_pre = in_pre_after(&line, in_pre);
+            if pre {
+                blank = 0;
+                output.push(format!("{}{}", line, cr));
+                continue;
+            }
+        }
+
+        if options.replace_nbsp && !html && line.contains(NBSP) {
+            changes.nbsp += line.matches(NBSP).count();
+            line = line.replace(NBSP, " ");
+        }
+
+        if options.collapse_blank_lines && line.trim().is_empty() {
+            blank += 1;
+            if blank > MAX_BLANK_LINES {
+                changes.blank_lines += 1;
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+
+        if options.trailing_whitespace {
+            let stripped = line.trim_end();
+            if stripped.len() != line.len() {
+                let next_has_text = lines.get(i + 1).is_some_and(|next| !is_blank(next));
+                let hard_break = fence.is_none() && line.ends_with("  ") && next_has_text;
+                let kept = if hard_break {
+                    format!("{}  ", stripped)
+                } else {
+                    stripped.to_string()
+                };
+                if kept != line {
+                    changes.trailing_whitespace += 1;
+                    line = kept;
+                }
+            }
+        }
+        output.push(format!("{}{}", line, cr));
+    }
+
+    if options.collapse_blank_lines {
+        let start = output.iter().position(|line| !is_blank(line));
+        let end = output.iter().rposition(|line| !is_blank(line));
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                changes.blank_lines += start + (output.len() - 1 - end);
+                output.truncate(end + 1);
+                output.drain(..start);
+            }
+            _ => {
+                changes.blank_lines += output.iter().filter(|line| !line.is_empty()).count();
+                output.clear();
+            }
+        }
+    }
+
+    let mut content = output.join("\n");
+    // 去掉末尾空行后，最后一行残留的 \r 已没有对应的 \n
+    if content.ends_with('\r') && !lines.last().is_some_and(|line| line.ends_with('\r')) {
+        content.pop();
+    }
+    NormalizedText { content, changes }
+}
+
+// editor_type 为 tiptap 时按 HTML 内容整理
+#[tauri::command]
+pub fn normalize_note_text(
+    content: String,
+    options: Option<NormalizeOptions>,
+    editor_type: Option<String>,
+) -> NormalizedText {
+    normalize_content(
+        &content,
+        &options.unwrap_or_default(),
+        editor_type.as_deref() == Some("tiptap"),
+    )
+}
+
+// 内容会变化的笔记及整理后的内容
+fn changed_notes(
+    conn: &Connection,
+    options: &NormalizeOptions,
+) -> rusqlite::Result<Vec<(NoteNormalization, String)>> {
+    let mut stmt = conn.prepare("SELECT id, title, content, editor_type FROM notes")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?.as_deref() == Some("tiptap"),
+        ))
+    })?;
+    let mut changed = Vec::new();
+    for row in rows {
+        let (id, title, content, html) = row?;
+        let normalized = normalize_content(&content, options, html);
+        if normalized.content != content {
+            let note = NoteNormalization {
+                note_id: id,
+                title,
+                changes: normalized.changes,
+            };
+            changed.push((note, normalized.content));
+        }
+    }
+    Ok(changed)
+}
+
+// 对所有笔记执行 normalize_content，返回内容会变化的笔记及各项修改次数；dry_run 时只读取统计，只读模式下也可使用。
+// 修改在同一事务中完成，开启了历史版本时先保存修改前的版本
+#[tauri::command]
+pub async fn normalize_all_notes(
+    app: tauri::AppHandle,
+    options: Option<NormalizeOptions>,
+    dry_run: bool,
+) -> AppResult<NormalizeReport> {
+    let options = options.unwrap_or_default();
+    let changed = if dry_run {
+        db::with_read_conn(&app, |conn| changed_notes(conn, &options))?
+    } else {
+        let keep_versions = versions::enabled(&app);
+        db::with_write_conn(&app, |conn| {
+            let tx = conn.transaction()?;
+            let changed = changed_notes(&tx, &options)?;
+            for (note, content) in &changed {
+                if keep_versions {
+                    versions::snapshot(&tx, note.note_id)?;
+                }
+                tx.execute(
+                    "UPDATE notes SET content = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                    params![content, note.note_id],
+                )?;
+            }
+            tx.commit()?;
+            Ok(changed)
+        })?
+    };
+    let notes = changed
+        .into_iter()
+        .map(|(note, _)| note)
+        .collect::<Vec<_>>();
+
+    if !dry_run && !notes.is_empty() {
+        log::info!("已整理 {} 条笔记的文本", notes.len());
+    }
+    let total_changes = notes.iter().map(|note| note.changes.total()).sum();
+    Ok(NormalizeReport {
+        dry_run,
+        notes,
+        total_changes,
+    })
+}
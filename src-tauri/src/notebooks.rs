@@ -0,0 +1,166 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, OptionalExtension, Transaction};
+use serde::Serialize;
+use tauri::Emitter;
+
+// 笔记本即分类（notes.category_id）
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotebookChange {
+    pub affected_notes: usize,
+    // rename_notebook 合并到已有笔记本时为 true
+    pub merged: bool,
+}
+
+enum NotebookError {
+    NotFound(String),
+    Exists(String),
+    Sql(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for NotebookError {
+    fn from(e: rusqlite::Error) -> Self {
+        NotebookError::Sql(e)
+    }
+}
+
+fn notebook_id(tx: &Transaction, name: &str) -> rusqlite::Result<Option<i64>> {
+    tx.query_row(
+        "SELECT id FROM categories WHERE name = ?1",
+        [name.trim()],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn find(tx: &Transaction, name: &str) -> Result<i64, NotebookError> {
+    notebook_id(tx, name)?.ok_or_else(|| NotebookError::NotFound(name.to_string()))
+}
+
+// 在一个事务中修改笔记本，成功后通知前端刷新分类列表
+fn mutate<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&Transaction) -> Result<T, NotebookError>,
+) -> AppResult<T> {
+    let mut failure = None;
+    let result = db::with_write_conn(app, |conn| {
+        let tx = conn.transaction()?;
+        match f(&tx) {
+            Ok(value) => {
+                tx.commit()?;
+                Ok(Some(value))
+            }
+            Err(NotebookError::Sql(e)) => Err(e),
+            Err(e) => {
+                failure = Some(e);
+                Ok(None)
+            }
+        }
+    })?;
+
+    match (result, failure) {
+        (Some(value), _) => {
+            let _ = app.emit("notebooks-changed", ());
+            Ok(value)
+        }
+        (None, Some(NotebookError::Exists(name))) => {
+            Err(AppError::new("notebook.already_exists").with("name", name))
+        }
+        (None, Some(NotebookError::NotFound(name))) => {
+            Err(AppError::new("notebook.not_found").with("name", name))
+        }
+        (None, _) => Err(AppError::new("notebook.not_found")),
+    }
+}
+
+// 重命名笔记本；新名称已被其他笔记本使用时拒绝，merge 为 true 时把笔记移入该笔记本并删除原笔记本
+#[tauri::command]
+pub async fn rename_notebook(
+    app: tauri::AppHandle,
+    old: String,
+    new: String,
+    merge: Option<bool>,
+) -> AppResult<NotebookChange> {
+    let new = new.trim().to_string();
+    if new.is_empty() {
+        return Err(AppError::new("notebook.empty_name"));
+    }
+    let change = mutate(&app, |tx| {
+        let source = find(tx, &old)?;
+        match notebook_id(tx, &new)? {
+            Some(target) if target != source => {
+                if !merge.unwrap_or(false) {
+                    return Err(NotebookError::Exists(new.clone()));
+                }
+                let affected_notes = tx.execute(
+                    "UPDATE notes SET category_id = ?1 WHERE category_id = ?2",
+                    params![target, source],
+                )?;
+                tx.execute("DELETE FROM categories WHERE id = ?1", [source])?;
+                Ok(NotebookChange {
+                    affected_notes,
+                    merged: true,
+                })
+            }
+            _ => {
+                tx.execute(
+                    "UPDATE categories SET name = ?1 WHERE id = ?2",
+                    params![new, source],
+                )?;
+                let affected_notes: i64 = tx.query_row(
+                    "SELECT COUNT(*) FROM notes WHERE category_id = ?1",
+                    [source],
+                    |row| row.get(0),
+                )?;
+                Ok(NotebookChange {
+                    affected_notes: affected_notes as usize,
+                    merged: false,
+                })
+            }
+        }
+    })?;
+    log::info!(
+        "已将笔记本 {} 重命名为 {}，涉及 {} 条笔记",
+        old,
+        new,
+        change.affected_notes
+    );
+    Ok(change)
+}
+
+// 删除笔记本：指定 move_to 时把笔记移到该笔记本，否则连同笔记一起删除（附件目录可用 delete_orphan_attachments 清理）。
+// 返回移动或删除的笔记数
+#[tauri::command]
+pub async fn delete_notebook(
+    app: tauri::AppHandle,
+    name: String,
+    move_to: Option<String>,
+) -> AppResult<usize> {
+    if move_to
+        .as_deref()
+        .is_some_and(|target| target.trim() == name.trim())
+    {
+        return Err(AppError::new("notebook.same_target").with("name", &name));
+    }
+    let affected = mutate(&app, |tx| {
+        let source = find(tx, &name)?;
+        let affected = match &move_to {
+            Some(target) => {
+                let target = find(tx, target)?;
+                tx.execute(
+                    "UPDATE notes SET category_id = ?1 WHERE category_id = ?2",
+                    params![target, source],
+                )?
+            }
+            None => tx.execute("DELETE FROM notes WHERE category_id = ?1", [source])?,
+        };
+        tx.execute("DELETE FROM categories WHERE id = ?1", [source])?;
+        Ok(affected)
+    })?;
+    match &move_to {
+        Some(target) => log::info!("已删除笔记本 {}，{} 条笔记移到 {}", name, affected, target),
+        None => log::info!("已删除笔记本 {} 及其中的 {} 条笔记", name, affected),
+    }
+    Ok(affected)
+}
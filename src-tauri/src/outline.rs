@@ -0,0 +1,151 @@
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+static HTML_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<h([1-6])(?:\s[^>]*)?>(.*?)</h[1-6]\s*>").unwrap());
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineNode {
+    pub text: String,
+    pub level: u8,
+    // 从 1 开始的行号
+    pub line: usize,
+    pub children: Vec<OutlineNode>,
+}
+
+// Markdown ATX 标题：最多 3 个空格缩进，1-6 个 '#'，后接空白或行尾
+fn markdown_heading(line: &str) -> Option<(u8, String)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &rest[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // 去掉结尾可选的 '#' 序列
+    let mut text = rest.trim();
+    let stripped = text.trim_end_matches('#');
+    if stripped.is_empty() || stripped.ends_with([' ', '\t']) {
+        text = stripped.trim_end();
+    }
+    if text.is_empty() {
+        return None;
+    }
+    Some((level as u8, text.to_string()))
+}
+
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// 按出现顺序收集标题，同时支持 Markdown 和富文本编辑器保存的 HTML
+fn collect_headings(content: &str) -> Vec<(u8, String, usize)> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    // 代码块（含围栏行）内的行，HTML 标题同样需要跳过
+    let mut fenced = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            fenced.push(true);
+            continue;
+        }
+        fenced.push(in_fence);
+        if in_fence {
+            continue;
+        }
+
+        if let Some((level, text)) = markdown_heading(line) {
+            headings.push((level, text, i + 1));
+        }
+    }
+
+    for caps in HTML_HEADING.captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        let line = content[..whole.start()].matches('\n').count() + 1;
+        if fenced.get(line - 1).copied().unwrap_or(false) {
+            continue;
+        }
+        let level = caps[1].parse().unwrap_or(1);
+        let text = decode_entities(HTML_TAG.replace_all(&caps[2], "").trim());
+        if !text.is_empty() {
+            headings.push((level, text, line));
+        }
+    }
+
+    // 同一行内保持出现顺序（sort_by_key 是稳定排序）
+    headings.sort_by_key(|(_, _, line)| *line);
+    headings
+}
+
+// 按级别嵌套：每个标题挂在前面最近一个级别更高的标题下，跳级（如 h1 下直接 h3）也按此规则处理
+pub fn build_outline(content: &str) -> Vec<OutlineNode> {
+    let mut roots: Vec<OutlineNode> = Vec::new();
+    // 当前路径上各节点在其父节点 children 中的下标
+    let mut path: Vec<usize> = Vec::new();
+
+    for (level, text, line) in collect_headings(content) {
+        let node = OutlineNode {
+            text,
+            level,
+            line,
+            children: Vec::new(),
+        };
+
+        // 回退到级别比当前标题更高的祖先
+        while let Some(parent) = node_at(&roots, &path) {
+            if parent.level < level {
+                break;
+            }
+            path.pop();
+        }
+
+        let siblings = match node_at_mut(&mut roots, &path) {
+            Some(parent) => &mut parent.children,
+            None => &mut roots,
+        };
+        siblings.push(node);
+        path.push(siblings.len() - 1);
+    }
+
+    roots
+}
+
+fn node_at<'a>(roots: &'a [OutlineNode], path: &[usize]) -> Option<&'a OutlineNode> {
+    let (first, rest) = path.split_first()?;
+    let mut node = &roots[*first];
+    for &i in rest {
+        node = &node.children[i];
+    }
+    Some(node)
+}
+
+fn node_at_mut<'a>(roots: &'a mut [OutlineNode], path: &[usize]) -> Option<&'a mut OutlineNode> {
+    let (first, rest) = path.split_first()?;
+    let mut node = &mut roots[*first];
+    for &i in rest {
+        node = &mut node.children[i];
+    }
+    Some(node)
+}
+
+#[tauri::command]
+pub fn extract_outline(content: String) -> Vec<OutlineNode> {
+    build_outline(&content)
+}
@@ -0,0 +1,206 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lopdf::Document;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+// 单页文本提取的最长时间，超时的页面跳过
+const PAGE_TIMEOUT: Duration = Duration::from_secs(10);
+// 作为附件内嵌到笔记中的 PDF 大小上限
+const MAX_ATTACHMENT_SIZE: u64 = 20 * 1024 * 1024;
+
+// 以这些字符结尾的行视为段落结束
+const PARAGRAPH_END: &[char] = &['.', '!', '?', ':', '。', '！', '？', '：', '”', '」'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportMode {
+    // 笔记内容为提取出的文本
+    Text,
+    // PDF 内嵌为笔记附件，提取出的文本附在后面用于搜索
+    Attachment,
+}
+
+impl FromStr for ImportMode {
+    type Err = AppError;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "text" => Ok(ImportMode::Text),
+            "attachment" => Ok(ImportMode::Attachment),
+            other => Err(AppError::new("pdf.invalid_mode").with("mode", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PdfImport {
+    pub note: Value,
+    pub pages: usize,
+    // 提取超时而跳过的页码
+    pub skipped_pages: Vec<u32>,
+    // 没有文本层（扫描件），需要 OCR 才能得到文字
+    pub needs_ocr: bool,
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn load(path: &Path) -> AppResult<Document> {
+    let document = Document::load(path).map_err(|e| match e {
+        lopdf::Error::Decryption(_) | lopdf::Error::InvalidPassword => {
+            AppError::new("pdf.encrypted")
+        }
+        e => AppError::new("pdf.open_failed").with("error", e),
+    })?;
+    // 无法用空密码解密的文件仍处于加密状态，提取出的只会是乱码
+    if document.is_encrypted() {
+        return Err(AppError::new("pdf.encrypted"));
+    }
+    Ok(document)
+}
+
+// 在后台线程中提取单页文本，超时返回 None
+fn extract_page(document: &Arc<Document>, page: u32) -> Option<String> {
+    let (tx, rx) = mpsc::channel();
+    let document = Arc::clone(document);
+    thread::spawn(move || {
+        let _ = tx.send(document.extract_text(&[page]));
+    });
+    match rx.recv_timeout(PAGE_TIMEOUT) {
+        Ok(text) => Some(text.unwrap_or_default()),
+        Err(_) => None,
+    }
+}
+
+// PDF 中的换行多为排版折行：连续的行合并为段落，遇到空行或句末标点时分段；中文行之间不加空格
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let needs_space = current.chars().last().is_some_and(|c| c.is_ascii())
+            && line.chars().next().is_some_and(|c| c.is_ascii());
+        if needs_space {
+            current.push(' ');
+        }
+        current.push_str(line);
+        if line.ends_with(PARAGRAPH_END) {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+}
+
+// 笔记内容为编辑器使用的 HTML，每页一个二级标题
+fn render_pages(pages: &[(u32, Option<String>)]) -> String {
+    let mut html = String::new();
+    for (number, text) in pages {
+        html.push_str(&format!(
+            "<h2>{}</h2>",
+            html_escape(&t("pdf.page_heading", &[("page", number.to_string())]))
+        ));
+        match text {
+            Some(text) => {
+                for paragraph in paragraphs(text) {
+                    html.push_str(&format!("<p>{}</p>", html_escape(&paragraph)));
+                }
+            }
+            None => html.push_str(&format!("<p><em>{}</em></p>", t("pdf.page_timeout", &[]))),
+        }
+    }
+    html
+}
+
+fn attachment_link(path: &Path, file_name: &str) -> AppResult<String> {
+    let size = std::fs::metadata(path)
+        .map_err(wrap("pdf.open_failed"))?
+        .len();
+    if size > MAX_ATTACHMENT_SIZE {
+        return Err(AppError::new("pdf.too_large").with("max", MAX_ATTACHMENT_SIZE / 1024 / 1024));
+    }
+    let data = std::fs::read(path).map_err(wrap("pdf.open_failed"))?;
+    Ok(format!(
+        "<p><a href=\"data:application/pdf;base64,{}\" download=\"{}\">{}</a></p>",
+        STANDARD.encode(data),
+        html_escape(file_name),
+        html_escape(file_name)
+    ))
+}
+
+// 导入 PDF 为新笔记并返回该笔记；mode 为 text 或 attachment
+#[tauri::command]
+pub async fn import_pdf(
+    app: tauri::AppHandle,
+    file_path: String,
+    mode: String,
+) -> AppResult<PdfImport> {
+    let mode = mode.parse::<ImportMode>()?;
+    let path = Path::new(&file_path);
+    let document = Arc::new(load(path)?);
+
+    let pages: Vec<(u32, Option<String>)> = document
+        .get_pages()
+        .keys()
+        .map(|&number| (number, extract_page(&document, number)))
+        .collect();
+    let skipped_pages: Vec<u32> = pages
+        .iter()
+        .filter(|(_, text)| text.is_none())
+        .map(|(number, _)| *number)
+        .collect();
+    let needs_ocr = pages
+        .iter()
+        .all(|(_, text)| text.as_deref().is_none_or(|text| text.trim().is_empty()));
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| t("export.untitled", &[]));
+
+    let mut content = match mode {
+        ImportMode::Text => String::new(),
+        ImportMode::Attachment => attachment_link(path, &file_name)?,
+    };
+    if !needs_ocr {
+        content.push_str(&render_pages(&pages));
+    }
+
+    let note = db::with_write_conn(&app, |conn| db::insert_note(conn, &title, &content))?;
+    log::info!(
+        "已导入 PDF {}，共 {} 页，跳过 {} 页",
+        file_name,
+        pages.len(),
+        skipped_pages.len()
+    );
+
+    Ok(PdfImport {
+        note,
+        pages: pages.len(),
+        skipped_pages,
+        needs_ocr,
+    })
+}
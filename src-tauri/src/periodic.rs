@@ -0,0 +1,119 @@
+use crate::{settings, shutdown};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+// 定时器线程的检查间隔
+const TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+// 保存在设置文件中的定时配置，last_run 用于重启后按原间隔继续
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Schedule {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+impl Schedule {
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.enabled.then(|| {
+            self.last_run.unwrap_or_else(Utc::now) + Duration::hours(self.interval_hours as i64)
+        })
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run().is_some_and(|next_run| next_run <= now)
+    }
+}
+
+// 由 spawn 驱动的定时任务，运行状态保存在各模块自己的 State 中
+pub trait Task {
+    // shutdown::begin_task 和日志使用的任务名
+    const NAME: &'static str;
+    // 保存配置的设置项
+    const SETTING: &'static str;
+    type Output;
+    type Saved: Serialize;
+
+    fn schedule(app: &tauri::AppHandle) -> Schedule;
+    // 返回 None 表示暂时不能执行（如备份正在进行），下一个 tick 再试
+    fn run(app: &tauri::AppHandle) -> Option<Self::Output>;
+    // 记录本次结果和运行时间，返回要保存的配置；output 为 Err 表示任务 panic
+    fn finish(app: &tauri::AppHandle, output: thread::Result<Self::Output>) -> Self::Saved;
+}
+
+// 启动后台定时器线程，任务在该线程中执行，不阻塞界面
+pub fn spawn<T: Task>(app: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+
+        if !T::schedule(&app).is_due(Utc::now()) {
+            continue;
+        }
+        // 应用正在退出时不再开始新的任务
+        let Some(_task) = shutdown::begin_task(&app, T::NAME) else {
+            continue;
+        };
+        // 任务中的 panic 不能让定时器线程退出
+        let output = match panic::catch_unwind(AssertUnwindSafe(|| T::run(&app))) {
+            Ok(Some(output)) => Ok(output),
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("定时任务 {} 异常退出", T::NAME);
+                Err(e)
+            }
+        };
+
+        let saved = T::finish(&app, output);
+        if let Err(e) = settings::set(&app, T::SETTING, &saved) {
+            log::warn!("保存定时任务 {} 的运行时间失败: {}", T::NAME, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_schedule_never_runs() {
+        let schedule = Schedule {
+            enabled: false,
+            interval_hours: 1,
+            last_run: None,
+        };
+        assert_eq!(schedule.next_run(), None);
+        assert!(!schedule.is_due(Utc::now() + Duration::days(365)));
+    }
+
+    #[test]
+    fn next_run_follows_last_run() {
+        let last_run = Utc::now() - Duration::hours(3);
+        let schedule = Schedule {
+            enabled: true,
+            interval_hours: 2,
+            last_run: Some(last_run),
+        };
+        assert_eq!(schedule.next_run(), Some(last_run + Duration::hours(2)));
+        assert!(schedule.is_due(Utc::now()));
+        assert!(!schedule.is_due(last_run + Duration::hours(1)));
+    }
+
+    #[test]
+    fn schedule_round_trips_through_settings_json() {
+        let schedule = Schedule {
+            enabled: true,
+            interval_hours: 24,
+            last_run: None,
+        };
+        let value = serde_json::to_value(&schedule).unwrap();
+        assert_eq!(value["intervalHours"], 24);
+        assert_eq!(serde_json::from_value::<Schedule>(value).unwrap(), schedule);
+        assert_eq!(
+            serde_json::from_value::<Schedule>(serde_json::json!({})).unwrap(),
+            Schedule::default()
+        );
+    }
+}
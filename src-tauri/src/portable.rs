@@ -0,0 +1,119 @@
+use crate::backup;
+use crate::error::{wrap, AppError, AppResult};
+use crate::profiles;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tauri::Manager;
+
+// 可执行文件旁存在该文件或 data 目录时进入便携模式，数据保存在 data 目录中
+const FLAG_FILE: &str = "portable.flag";
+const DATA_DIR: &str = "data";
+// 更新缓存与安装位置有关，不随数据复制
+const SKIPPED_DIRS: &[&str] = &["updates"];
+
+// 启动时确定，运行期间不变；转换后需重启才会生效
+static PORTABLE_DIR: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    (dir.join(FLAG_FILE).is_file() || dir.join(DATA_DIR).is_dir()).then(|| dir.join(DATA_DIR))
+});
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PortableStatus {
+    pub portable: bool,
+    pub data_dir: String,
+}
+
+pub fn is_portable() -> bool {
+    PORTABLE_DIR.is_some()
+}
+
+// 数据根目录，保存日志、更新缓存等与配置无关的数据
+pub fn root_data_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    if let Some(dir) = PORTABLE_DIR.as_ref() {
+        return Ok(dir.clone());
+    }
+    app.path()
+        .app_data_dir()
+        .map_err(wrap("app.data_dir_unavailable"))
+}
+
+// 当前配置的数据目录：数据库、附件、设置和备份都应通过这里取得路径
+pub fn resolve_data_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(profiles::profile_dir(
+        &root_data_dir(app)?,
+        &profiles::active(),
+    ))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+#[tauri::command]
+pub fn get_portable_status(app: tauri::AppHandle) -> AppResult<PortableStatus> {
+    Ok(PortableStatus {
+        portable: is_portable(),
+        data_dir: resolve_data_dir(&app)?.display().to_string(),
+    })
+}
+
+// 把当前数据复制到 target_dir/data 并写入标记文件；把程序复制到 target_dir 后即可便携运行。
+// 目标 data 目录已有内容时拒绝，避免覆盖另一份便携数据
+#[tauri::command]
+pub async fn convert_to_portable(app: tauri::AppHandle, target_dir: String) -> AppResult<String> {
+    let target = Path::new(&target_dir);
+    let data_dir = target.join(DATA_DIR);
+    let occupied = fs::read_dir(&data_dir).is_ok_and(|mut entries| entries.next().is_some());
+    if occupied {
+        return Err(AppError::new("portable.target_not_empty").with("path", data_dir.display()));
+    }
+
+    // 先把 WAL 合并回数据库文件，复制出的数据库才是完整的
+    if let Err(e) = backup::checkpoint(&app) {
+        log::warn!("转换便携模式前合并 WAL 失败: {}", e);
+    }
+
+    // 复制整个数据根目录，包括所有配置
+    let source = root_data_dir(&app)?;
+    fs::create_dir_all(&data_dir).map_err(wrap("portable.convert_failed"))?;
+    let mut copied = 0;
+    for entry in fs::read_dir(&source).map_err(wrap("portable.convert_failed"))? {
+        let entry = entry.map_err(wrap("portable.convert_failed"))?;
+        let name = entry.file_name();
+        if SKIPPED_DIRS.iter().any(|dir| name == *dir) {
+            continue;
+        }
+        let path = entry.path();
+        let target = data_dir.join(&name);
+        copied += if path.is_dir() {
+            copy_dir(&path, &target)
+        } else {
+            fs::copy(&path, &target).map(|_| 1)
+        }
+        .map_err(wrap("portable.convert_failed"))?;
+    }
+    fs::write(target.join(FLAG_FILE), "").map_err(wrap("portable.convert_failed"))?;
+
+    log::info!(
+        "已将 {} 个数据文件复制到便携目录 {}",
+        copied,
+        data_dir.display()
+    );
+    Ok(data_dir.display().to_string())
+}
@@ -0,0 +1,202 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::settings::SettingsStore;
+use crate::{
+    active_note, databases, db, i18n, logging, portable, read_only, selection, spellcheck, theme,
+    tray, window_effect, zoom,
+};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{Emitter, Manager};
+
+// 默认配置直接使用数据根目录，保持与引入配置之前的数据位置一致；其他配置位于 profiles/<name>
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+// 设置属于各配置自身，当前配置只能记录在数据根目录下的单独文件中
+const ACTIVE_FILE: &str = "active_profile";
+const CLI_FLAG: &str = "--profile";
+
+static ACTIVE: RwLock<String> = RwLock::new(String::new());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub path: String,
+    pub active: bool,
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.chars().count() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::new("profile.invalid_name").with("name", name))
+    }
+}
+
+pub fn active() -> String {
+    let active = ACTIVE.read().unwrap();
+    if active.is_empty() {
+        DEFAULT_PROFILE.to_string()
+    } else {
+        active.clone()
+    }
+}
+
+pub fn profile_dir(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join(PROFILES_DIR).join(name)
+    }
+}
+
+// --profile <name> 或 --profile=<name>
+pub fn cli_profile(argv: &[String]) -> Option<String> {
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == CLI_FLAG {
+            return args.next().cloned();
+        }
+        if let Some(name) = arg.strip_prefix(CLI_FLAG).and_then(|s| s.strip_prefix('=')) {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+// 启动时最先调用（早于设置加载）：命令行参数只影响本次启动，否则使用上次切换到的配置；
+// 配置不存在时回到默认配置
+pub fn init(app: &tauri::AppHandle) -> AppResult<()> {
+    let root = portable::root_data_dir(app)?;
+    let args: Vec<String> = std::env::args().collect();
+    let name = cli_profile(&args)
+        .or_else(|| fs::read_to_string(root.join(ACTIVE_FILE)).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| validate_name(name).is_ok() && profile_dir(&root, name).is_dir())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    *ACTIVE.write().unwrap() = name;
+    Ok(())
+}
+
+fn profile_info(root: &Path, name: &str) -> ProfileInfo {
+    ProfileInfo {
+        name: name.to_string(),
+        path: profile_dir(root, name).to_string_lossy().into_owned(),
+        active: name == active(),
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles(app: tauri::AppHandle) -> AppResult<Vec<ProfileInfo>> {
+    let root = portable::root_data_dir(&app)?;
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Ok(entries) = fs::read_dir(root.join(PROFILES_DIR)) {
+        let mut others: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name != DEFAULT_PROFILE && validate_name(name).is_ok())
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+    Ok(names.iter().map(|name| profile_info(&root, name)).collect())
+}
+
+#[tauri::command]
+pub fn create_profile(app: tauri::AppHandle, name: String) -> AppResult<ProfileInfo> {
+    read_only::ensure_writable(&app)?;
+    validate_name(&name)?;
+    let root = portable::root_data_dir(&app)?;
+    let dir = profile_dir(&root, &name);
+    if name == DEFAULT_PROFILE || dir.exists() {
+        return Err(AppError::new("profile.exists").with("name", name));
+    }
+
+    fs::create_dir_all(&dir).map_err(wrap("profile.create_failed"))?;
+    let path = dir.join(format!("{}.db", db::DEFAULT_DATABASE));
+    let conn = db::open(&path).map_err(wrap("profile.create_failed"))?;
+    db::migrate(&conn).map_err(wrap("database.init_failed"))?;
+    drop(conn);
+
+    log::info!("已创建配置 {}", name);
+    let _ = tray::refresh(&app);
+    Ok(profile_info(&root, &name))
+}
+
+// 依赖数据目录或设置的状态在切换后重新加载
+fn rebind(app: &tauri::AppHandle) -> AppResult<()> {
+    app.state::<SettingsStore>().reload(app)?;
+    app.state::<db::DbPool>().switch(db::active_db_path(app)?);
+    spellcheck::reset(app);
+    i18n::init(app);
+    logging::apply_level(app);
+    window_effect::init(app);
+    zoom::init(app);
+    active_note::init(app);
+    if let Err(e) = selection::refresh(app) {
+        log::warn!("{}", e);
+    }
+    if let Err(e) = theme::refresh(app) {
+        log::warn!("{}", e);
+    }
+    tray::refresh(app)
+}
+
+// 关闭当前数据库连接池（会合并 WAL），切换到新配置的数据目录；同时发送 database-switched，
+// 前端据此关闭 SQL 插件的旧连接并加载新配置中的数据库，收到 profile-switched 后重新加载其他状态
+#[tauri::command]
+pub fn switch_profile(app: tauri::AppHandle, name: String) -> AppResult<ProfileInfo> {
+    validate_name(&name)?;
+    let root = portable::root_data_dir(&app)?;
+    if !profile_dir(&root, &name).is_dir() {
+        return Err(AppError::new("profile.not_found").with("name", name));
+    }
+    if name == active() {
+        return Ok(profile_info(&root, &name));
+    }
+
+    app.state::<db::DbPool>().close();
+    *ACTIVE.write().unwrap() = name.clone();
+    fs::write(root.join(ACTIVE_FILE), &name).map_err(wrap("profile.switch_failed"))?;
+    rebind(&app)?;
+    log::info!("已切换到配置 {}", name);
+
+    match databases::get_active_database(app.clone()) {
+        Ok(database) => {
+            let _ = app.emit("database-switched", &database);
+        }
+        Err(e) => log::warn!("{}", e),
+    }
+    let info = profile_info(&root, &name);
+    let _ = app.emit("profile-switched", &info);
+    Ok(info)
+}
+
+// 需要再次输入配置名称确认；默认配置和当前配置不能删除
+#[tauri::command]
+pub fn delete_profile(app: tauri::AppHandle, name: String, confirmation: String) -> AppResult<()> {
+    read_only::ensure_writable(&app)?;
+    validate_name(&name)?;
+    if confirmation != name {
+        return Err(AppError::new("profile.confirmation_mismatch"));
+    }
+    if name == DEFAULT_PROFILE || name == active() {
+        return Err(AppError::new("profile.in_use").with("name", name));
+    }
+    let dir = profile_dir(&portable::root_data_dir(&app)?, &name);
+    if !dir.is_dir() {
+        return Err(AppError::new("profile.not_found").with("name", name));
+    }
+
+    fs::remove_dir_all(&dir).map_err(wrap("profile.delete_failed"))?;
+    log::info!("已删除配置 {}", name);
+    let _ = tray::refresh(&app);
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use crate::error::{wrap, AppError, AppResult};
+use qrcode::render::svg;
+use qrcode::types::QrError;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+// 每个模块在 PNG 中占用的像素数，以及四周留白的模块数
+const PNG_SCALE: usize = 8;
+const QUIET_ZONE: usize = 4;
+const SVG_MIN_SIZE: u32 = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorCorrection(EcLevel);
+
+impl Default for ErrorCorrection {
+    fn default() -> Self {
+        ErrorCorrection(EcLevel::M)
+    }
+}
+
+impl FromStr for ErrorCorrection {
+    type Err = AppError;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.to_uppercase().as_str() {
+            "L" => Ok(ErrorCorrection(EcLevel::L)),
+            "M" => Ok(ErrorCorrection(EcLevel::M)),
+            "Q" => Ok(ErrorCorrection(EcLevel::Q)),
+            "H" => Ok(ErrorCorrection(EcLevel::H)),
+            _ => Err(AppError::new("qr.invalid_error_correction").with("level", level)),
+        }
+    }
+}
+
+impl ErrorCorrection {
+    // 版本 40 二维码在字节模式下的最大容量
+    pub fn max_bytes(self) -> usize {
+        match self.0 {
+            EcLevel::L => 2953,
+            EcLevel::M => 2331,
+            EcLevel::Q => 1663,
+            EcLevel::H => 1273,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteQr {
+    pub svg: String,
+    pub png: Vec<u8>,
+    // 内容过长时编码的是笔记的深度链接而不是内容本身
+    pub deep_link: bool,
+}
+
+pub fn deep_link(note_id: i64) -> String {
+    format!("yue://note/{}", note_id)
+}
+
+// 内容超出容量时，有笔记 id 则改为编码深度链接，否则返回带最大容量的错误供前端截断后重试
+fn encode(
+    content: &str,
+    level: ErrorCorrection,
+    note_id: Option<i64>,
+) -> AppResult<(QrCode, bool)> {
+    match QrCode::with_error_correction_level(content.as_bytes(), level.0) {
+        Ok(code) => Ok((code, false)),
+        Err(QrError::DataTooLong) => match note_id {
+            Some(id) => QrCode::with_error_correction_level(deep_link(id).as_bytes(), level.0)
+                .map(|code| (code, true))
+                .map_err(wrap("qr.render_failed")),
+            None => Err(AppError::new("qr.too_large")
+                .with("size", content.len())
+                .with("max", level.max_bytes())),
+        },
+        Err(e) => Err(AppError::new("qr.render_failed").with("error", e)),
+    }
+}
+
+fn render_svg(code: &QrCode) -> String {
+    code.render::<svg::Color>()
+        .min_dimensions(SVG_MIN_SIZE, SVG_MIN_SIZE)
+        .quiet_zone(true)
+        .build()
+}
+
+// 8 位灰度 PNG，黑色模块为 0，白色为 255
+fn render_png(code: &QrCode) -> AppResult<Vec<u8>> {
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + QUIET_ZONE * 2) * PNG_SCALE;
+
+    let mut pixels = vec![255u8; size * size];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x0 = (i % modules + QUIET_ZONE) * PNG_SCALE;
+        let y0 = (i / modules + QUIET_ZONE) * PNG_SCALE;
+        for y in y0..y0 + PNG_SCALE {
+            pixels[y * size + x0..y * size + x0 + PNG_SCALE].fill(0);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(wrap("qr.render_failed"))?;
+    Ok(png)
+}
+
+fn parse_level(error_correction: Option<String>) -> AppResult<ErrorCorrection> {
+    error_correction
+        .map(|level| level.parse())
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+#[tauri::command]
+pub fn generate_note_qr(
+    content: String,
+    error_correction: Option<String>,
+    note_id: Option<i64>,
+) -> AppResult<NoteQr> {
+    let level = parse_level(error_correction)?;
+    let (code, deep_link) = encode(&content, level, note_id)?;
+
+    Ok(NoteQr {
+        svg: render_svg(&code),
+        png: render_png(&code)?,
+        deep_link,
+    })
+}
+
+// 按扩展名保存为 SVG 或 PNG（默认）
+#[tauri::command]
+pub fn save_note_qr(
+    content: String,
+    file_path: String,
+    error_correction: Option<String>,
+    note_id: Option<i64>,
+) -> AppResult<()> {
+    let level = parse_level(error_correction)?;
+    let (code, _) = encode(&content, level, note_id)?;
+
+    let path = Path::new(&file_path);
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let data = if is_svg {
+        render_svg(&code).into_bytes()
+    } else {
+        render_png(&code)?
+    };
+    std::fs::write(path, data).map_err(wrap("qr.save_failed"))
+}
@@ -0,0 +1,55 @@
+use crate::error::{AppError, AppResult};
+use crate::{db, tray};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+const CLI_FLAG: &str = "--read-only";
+
+// 只读模式：数据库以只读方式打开，修改笔记和附件的命令都会返回 app.read_only，前端的数据库连接也重新以只读方式打开；
+// 导出、搜索和备份等只读取数据的功能，以及窗口状态、缩放、语言等界面偏好的保存不受影响
+#[derive(Default)]
+pub struct ReadOnlyState(Mutex<bool>);
+
+pub fn is_enabled(app: &tauri::AppHandle) -> bool {
+    *app.state::<ReadOnlyState>().0.lock().unwrap()
+}
+
+// 修改数据前调用
+pub fn ensure_writable(app: &tauri::AppHandle) -> AppResult<()> {
+    if is_enabled(app) {
+        return Err(AppError::new("app.read_only"));
+    }
+    Ok(())
+}
+
+// 连接池创建后调用，命令行带 --read-only 时以只读模式启动
+pub fn init(app: &tauri::AppHandle) {
+    if std::env::args().skip(1).any(|arg| arg == CLI_FLAG) {
+        apply(app, true);
+        log::info!("以只读模式启动");
+    }
+}
+
+fn apply(app: &tauri::AppHandle, enabled: bool) {
+    *app.state::<ReadOnlyState>().0.lock().unwrap() = enabled;
+    app.state::<db::DbPool>().set_read_only(enabled);
+    if let Err(e) = tray::refresh(app) {
+        log::warn!("{}", e);
+    }
+}
+
+// 切换只读模式，连接池会以新的方式重新打开；前端收到 read-only-changed 后禁用或恢复编辑
+#[tauri::command]
+pub fn set_read_only(app: tauri::AppHandle, enabled: bool) {
+    if is_enabled(&app) == enabled {
+        return;
+    }
+    apply(&app, enabled);
+    log::info!("只读模式已{}", if enabled { "开启" } else { "关闭" });
+    let _ = app.emit("read-only-changed", enabled);
+}
+
+#[tauri::command]
+pub fn get_read_only(app: tauri::AppHandle) -> bool {
+    is_enabled(&app)
+}
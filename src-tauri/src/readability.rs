@@ -0,0 +1,228 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+static HTML_BLOCK_END: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</(?:p|h[1-6]|li|blockquote|div|td|th)\s*>|<br\s*/?>").unwrap()
+});
+static HTML_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<pre\b.*?</pre\s*>").unwrap());
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z]+(?:['’][A-Za-z]+)*").unwrap());
+// 句末标点后跟空白或结尾时断句，允许标点后紧跟引号或括号
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[.!?]+["'”’)\]]*(?:\s+|$)"#).unwrap());
+
+// 拉丁字母以外的文字占比超过该值时，分数没有参考意义
+const NON_LATIN_THRESHOLD: f64 = 0.5;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Readability {
+    // 没有英文单词时为 None
+    pub flesch_reading_ease: Option<f64>,
+    pub flesch_kincaid_grade: Option<f64>,
+    pub words: usize,
+    pub sentences: usize,
+    pub syllables: usize,
+    // 内容以中日韩等非拉丁文字为主，前端应隐藏分数
+    pub mostly_non_latin: bool,
+}
+
+// 富文本编辑器保存的 HTML 先按块级标签换行并去掉标签，代码块整体跳过
+fn html_to_text(content: &str) -> String {
+    let text = HTML_CODE.replace_all(content, "\n\n");
+    let text = HTML_BLOCK_END.replace_all(&text, "\n\n");
+    HTML_TAG
+        .replace_all(&text, "")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// 去掉 Markdown 标记，按块返回纯文本；代码块和 HTML 片段不计入
+pub fn text_blocks(content: &str) -> Vec<String> {
+    let content = if HTML_TAG.is_match(content) {
+        html_to_text(content)
+    } else {
+        content.to_string()
+    };
+
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+    for event in Parser::new(&content) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Text(text) if !in_code => current.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => current.push(' '),
+            Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell,
+            ) => {
+                if !current.trim().is_empty() {
+                    blocks.push(std::mem::take(&mut current));
+                }
+                current.clear();
+            }
+            _ => {}
+        }
+    }
+    if !current.trim().is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+// 英文音节数的近似：元音字母组数，去掉词尾不发音的 e，至少 1 个
+fn syllables(word: &str) -> usize {
+    let word = word.to_ascii_lowercase();
+    let word = word.trim_end_matches(['\'', '’', 's']);
+    let word = match word.strip_suffix('e') {
+        Some(stem) if !stem.ends_with('l') && stem.len() > 2 => stem,
+        _ => word,
+    };
+
+    let mut count = 0;
+    let mut prev_vowel = false;
+    for c in word.chars() {
+        let vowel = matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+        if vowel && !prev_vowel {
+            count += 1;
+        }
+        prev_vowel = vowel;
+    }
+    count.max(1)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+// Flesch 易读度与 Flesch-Kincaid 年级，只适用于英文：
+// 音节按拼写估算，缩写、数字等会带来偏差；中日韩文字没有空格分词，也没有音节的对应概念，
+// 这类文字不参与计数，内容以其为主时通过 mostly_non_latin 提示前端隐藏分数
+pub fn analyze(content: &str) -> Readability {
+    let blocks = text_blocks(content);
+
+    let mut words = 0;
+    let mut sentences = 0;
+    let mut syllable_count = 0;
+    let mut latin = 0usize;
+    let mut non_latin = 0usize;
+    for block in &blocks {
+        for c in block.chars().filter(|c| c.is_alphabetic()) {
+            // 拉丁字母及其扩展区
+            if (c as u32) < 0x250 {
+                latin += 1;
+            } else {
+                non_latin += 1;
+            }
+        }
+        // 块末尾没有句末标点（如标题、列表项）时，整块算一句
+        for sentence in SENTENCE_END.split(block) {
+            let mut has_word = false;
+            for word in WORD.find_iter(sentence) {
+                words += 1;
+                syllable_count += syllables(word.as_str());
+                has_word = true;
+            }
+            if has_word {
+                sentences += 1;
+            }
+        }
+    }
+
+    let (ease, grade) = if words == 0 {
+        (None, None)
+    } else {
+        let words_per_sentence = words as f64 / sentences as f64;
+        let syllables_per_word = syllable_count as f64 / words as f64;
+        (
+            Some(round(
+                206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            )),
+            Some(round(
+                0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            )),
+        )
+    };
+
+    Readability {
+        flesch_reading_ease: ease,
+        flesch_kincaid_grade: grade,
+        words,
+        sentences,
+        syllables: syllable_count,
+        mostly_non_latin: latin + non_latin > 0
+            && non_latin as f64 / (latin + non_latin) as f64 > NON_LATIN_THRESHOLD,
+    }
+}
+
+#[tauri::command]
+pub fn analyze_readability(content: String) -> Readability {
+    analyze(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input_has_no_score() {
+        for content in ["", "   \n\n", "<p></p>", "```\ncode only\n```"] {
+            let result = analyze(content);
+            assert_eq!(result.words, 0);
+            assert_eq!(result.sentences, 0);
+            assert_eq!(result.flesch_reading_ease, None);
+            assert_eq!(result.flesch_kincaid_grade, None);
+            assert!(!result.mostly_non_latin);
+        }
+    }
+
+    #[test]
+    fn english_sample() {
+        let result = analyze("The cat sat on the mat. It was happy!\n\n# A short title");
+        assert_eq!(result.words, 12);
+        assert_eq!(result.sentences, 3);
+        assert!(!result.mostly_non_latin);
+        let ease = result.flesch_reading_ease.unwrap();
+        assert!(ease > 90.0, "{}", ease);
+        assert!(result.flesch_kincaid_grade.unwrap() < 2.0);
+    }
+
+    #[test]
+    fn html_is_treated_like_markdown() {
+        let markdown = analyze("The cat sat on the mat.\n\nIt was happy.");
+        let html = analyze("<p>The cat sat on the mat.</p><p>It was happy.</p><pre>x = 1</pre>");
+        assert_eq!(html.words, markdown.words);
+        assert_eq!(html.sentences, markdown.sentences);
+        assert_eq!(html.flesch_reading_ease, markdown.flesch_reading_ease);
+    }
+
+    #[test]
+    fn cjk_sample() {
+        let result = analyze("今天天气很好。我们去公园散步吧！");
+        assert_eq!(result.words, 0);
+        assert_eq!(result.flesch_reading_ease, None);
+        assert!(result.mostly_non_latin);
+
+        let mixed = analyze("这是一个关于 Rust 的中文笔记。");
+        assert_eq!(mixed.words, 1);
+        assert!(mixed.mostly_non_latin);
+        assert!(mixed.flesch_reading_ease.is_some());
+    }
+
+    #[test]
+    fn syllable_estimates() {
+        assert_eq!(syllables("cat"), 1);
+        assert_eq!(syllables("make"), 1);
+        assert_eq!(syllables("table"), 2);
+        assert_eq!(syllables("reading"), 2);
+        assert_eq!(syllables("happy"), 2);
+    }
+}
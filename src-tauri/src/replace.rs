@@ -0,0 +1,329 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::transfer;
+use crate::versions;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+// 富文本编辑器保存的 HTML 中的代码
+static HTML_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<pre\b.*?</pre\s*>|<code\b.*?</code\s*>").unwrap());
+// HTML 的标签和字符实体，富文本笔记中只替换文本节点
+static HTML_MARKUP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<[^>]*>|&(?:#[0-9]+|#[xX][0-9a-fA-F]+|[A-Za-z][A-Za-z0-9]*);").unwrap()
+});
+
+// 每条笔记在预览中最多展示的示例行数和每行长度
+const MAX_SAMPLES: usize = 3;
+const MAX_SAMPLE_CHARS: usize = 200;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReplaceOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    // 正则模式下替换文本可以用 $1、${name} 引用捕获组
+    pub regex: bool,
+    pub skip_code_blocks: bool,
+    // 范围：按标签名、分类（笔记本）或笔记 id 限定，都为空时为全部笔记
+    pub tag: Option<String>,
+    pub category_id: Option<i64>,
+    pub note_ids: Option<Vec<i64>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SampleLine {
+    // 从 1 开始的行号
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteMatches {
+    pub note_id: i64,
+    pub title: String,
+    pub matches: usize,
+    pub samples: Vec<SampleLine>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplacePreview {
+    pub notes: Vec<NoteMatches>,
+    pub total_matches: usize,
+    // 执行替换时需要传回，预览之后笔记或参数有变化时拒绝执行
+    pub token: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplaceResult {
+    pub notes: usize,
+    pub replacements: usize,
+}
+
+struct Replacer<'a> {
+    regex: Regex,
+    replacement: &'a str,
+    options: &'a ReplaceOptions,
+}
+
+struct ScopedNote {
+    id: i64,
+    title: String,
+    content: String,
+    // tiptap 笔记的内容为 HTML
+    html: bool,
+}
+
+struct Change {
+    note_id: i64,
+    title: String,
+    content: String,
+    replaced: String,
+    matches: usize,
+}
+
+impl<'a> Replacer<'a> {
+    fn new(query: &str, replacement: &'a str, options: &'a ReplaceOptions) -> AppResult<Self> {
+        if query.is_empty() {
+            return Err(AppError::new("replace.empty_query"));
+        }
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let pattern = if options.whole_word {
+            format!(r"\b(?:{})\b", pattern)
+        } else {
+            pattern
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(wrap("replace.invalid_regex"))?;
+        Ok(Replacer {
+            regex,
+            replacement,
+            options,
+        })
+    }
+
+    // 返回替换后的内容和替换次数；空匹配、位于代码中的匹配以及 HTML 中与标签或实体重叠的匹配跳过
+    fn apply(&self, content: &str, html: bool) -> (String, usize) {
+        let mut excluded = if self.options.skip_code_blocks {
+            code_ranges(content)
+        } else {
+            Vec::new()
+        };
+        if html {
+            excluded.extend(HTML_MARKUP.find_iter(content).map(|m| m.range()));
+        }
+
+        let mut output = String::with_capacity(content.len());
+        let mut last = 0;
+        let mut count = 0;
+        for caps in self.regex.captures_iter(content) {
+            let m = caps.get(0).unwrap();
+            if m.is_empty()
+                || excluded
+                    .iter()
+                    .any(|range| m.start() < range.end && range.start < m.end())
+            {
+                continue;
+            }
+            output.push_str(&content[last..m.start()]);
+            if self.options.regex {
+                caps.expand(self.replacement, &mut output);
+            } else {
+                output.push_str(self.replacement);
+            }
+            last = m.end();
+            count += 1;
+        }
+        output.push_str(&content[last..]);
+        (output, count)
+    }
+}
+
+// Markdown 的代码块、行内代码以及 HTML 中的 <pre> / <code>
+fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Parser::new(content)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::CodeBlock(_)) | Event::Code(_)))
+        .map(|(_, range)| range)
+        .collect();
+    ranges.extend(HTML_CODE.find_iter(content).map(|m| m.range()));
+    ranges
+}
+
+fn truncate(line: &str) -> String {
+    let mut chars = line.trim().chars();
+    let mut text: String = chars.by_ref().take(MAX_SAMPLE_CHARS).collect();
+    if chars.next().is_some() {
+        text.push('…');
+    }
+    text
+}
+
+// 按行对比替换前后的内容，取前几处变化的行作为示例
+fn samples(before: &str, after: &str) -> Vec<SampleLine> {
+    let before_lines: Vec<&str> = before.lines().collect();
+    let after_lines: Vec<&str> = after.lines().collect();
+    // 替换文本含换行时行数会变化，只能按顺序对齐到第一处不同的行
+    before_lines
+        .iter()
+        .zip(after_lines.iter())
+        .enumerate()
+        .filter(|(_, (b, a))| b != a)
+        .take(MAX_SAMPLES)
+        .map(|(i, (b, a))| SampleLine {
+            line: i + 1,
+            before: truncate(b),
+            after: truncate(a),
+        })
+        .collect()
+}
+
+fn scoped_notes(conn: &Connection, options: &ReplaceOptions) -> rusqlite::Result<Vec<ScopedNote>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.title, n.content, n.editor_type FROM notes n
+          WHERE (?1 IS NULL OR n.category_id = ?1)
+            AND (?2 IS NULL OR EXISTS (
+                    SELECT 1 FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+                     WHERE nt.note_id = n.id AND t.name = ?2))
+          ORDER BY n.id",
+    )?;
+    let ids: Option<HashSet<i64>> = options
+        .note_ids
+        .as_ref()
+        .map(|ids| ids.iter().copied().collect());
+    let notes = stmt
+        .query_map(params![options.category_id, options.tag], |row| {
+            Ok(ScopedNote {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                html: row.get::<_, Option<String>>(3)?.as_deref() == Some("tiptap"),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<ScopedNote>>>()?;
+    Ok(notes
+        .into_iter()
+        .filter(|note| ids.as_ref().is_none_or(|ids| ids.contains(&note.id)))
+        .collect())
+}
+
+fn changes(
+    conn: &Connection,
+    replacer: &Replacer,
+    options: &ReplaceOptions,
+) -> rusqlite::Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    for note in scoped_notes(conn, options)? {
+        let (replaced, matches) = replacer.apply(&note.content, note.html);
+        if matches > 0 && replaced != note.content {
+            changes.push(Change {
+                note_id: note.id,
+                title: note.title,
+                content: note.content,
+                replaced,
+                matches,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+// 由参数和每条笔记替换前后的内容计算，任何一项变化都会使预览失效
+fn token(query: &str, replacement: &str, options: &ReplaceOptions, changes: &[Change]) -> String {
+    let mut input = serde_json::json!([query, replacement, options]).to_string();
+    for change in changes {
+        input.push('\0');
+        input.push_str(&change.note_id.to_string());
+        input.push('\0');
+        input.push_str(&transfer::sha256_hex(change.content.as_bytes()));
+        input.push_str(&transfer::sha256_hex(change.replaced.as_bytes()));
+    }
+    transfer::sha256_hex(input.as_bytes())
+}
+
+// 预览各笔记的匹配数和示例行，不修改任何内容
+#[tauri::command]
+pub async fn preview_find_and_replace(
+    app: tauri::AppHandle,
+    query: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+) -> AppResult<ReplacePreview> {
+    let options = options.unwrap_or_default();
+    let replacer = Replacer::new(&query, &replacement, &options)?;
+    let changes = db::with_read_conn(&app, |conn| changes(conn, &replacer, &options))?;
+
+    let token = token(&query, &replacement, &options, &changes);
+    let notes: Vec<NoteMatches> = changes
+        .into_iter()
+        .map(|change| NoteMatches {
+            samples: samples(&change.content, &change.replaced),
+            note_id: change.note_id,
+            title: change.title,
+            matches: change.matches,
+        })
+        .collect();
+    Ok(ReplacePreview {
+        total_matches: notes.iter().map(|note| note.matches).sum(),
+        notes,
+        token,
+    })
+}
+
+// 必须先调用 preview_find_and_replace 并传回其 token；所有修改在同一事务中完成，
+// 开启了历史版本时先保存每条笔记修改前的版本
+#[tauri::command]
+pub async fn find_and_replace_all(
+    app: tauri::AppHandle,
+    query: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+    preview_token: String,
+) -> AppResult<ReplaceResult> {
+    let options = options.unwrap_or_default();
+    let replacer = Replacer::new(&query, &replacement, &options)?;
+    let keep_versions = versions::enabled(&app);
+
+    let result = db::with_write_conn(&app, |conn| {
+        let tx = conn.transaction()?;
+        let changes = changes(&tx, &replacer, &options)?;
+        if token(&query, &replacement, &options, &changes) != preview_token {
+            return Ok(None);
+        }
+
+        for change in &changes {
+            if keep_versions {
+                versions::snapshot(&tx, change.note_id)?;
+            }
+            tx.execute(
+                "UPDATE notes SET content = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![change.replaced, change.note_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(Some(ReplaceResult {
+            notes: changes.len(),
+            replacements: changes.iter().map(|change| change.matches).sum(),
+        }))
+    })?;
+
+    let result = result.ok_or_else(|| AppError::new("replace.preview_outdated"))?;
+    log::info!(
+        "已在 {} 条笔记中替换 {} 处",
+        result.notes,
+        result.replacements
+    );
+    Ok(result)
+}
@@ -0,0 +1,199 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::export::{ExportFormat, RenderOptions};
+use crate::periodic::{self, Schedule};
+use crate::{db, settings};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use tauri::Manager;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AutoExportStatus {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub format: String,
+    pub dir_path: String,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_file: Option<String>,
+    pub last_error: Option<AppError>,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+pub struct AutoExportState(pub Mutex<AutoExportStatus>);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AutoExportSchedule {
+    #[serde(flatten)]
+    schedule: Schedule,
+    format: String,
+    dir_path: String,
+    last_file: Option<String>,
+}
+
+impl AutoExportSchedule {
+    fn from_status(status: &AutoExportStatus) -> Self {
+        AutoExportSchedule {
+            schedule: Schedule {
+                enabled: status.enabled,
+                interval_hours: status.interval_hours,
+                last_run: status.last_run,
+            },
+            format: status.format.clone(),
+            dir_path: status.dir_path.clone(),
+            last_file: status.last_file.clone(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_auto_export(
+    app: tauri::AppHandle,
+    enabled: bool,
+    interval_hours: u32,
+    format: String,
+    dir_path: String,
+) -> AppResult<AutoExportStatus> {
+    if enabled {
+        if interval_hours == 0 {
+            return Err(AppError::new("auto_export.invalid_interval"));
+        }
+        format.parse::<ExportFormat>()?;
+        if !PathBuf::from(&dir_path).is_dir() {
+            return Err(AppError::new("auto_export.dir_not_found"));
+        }
+    }
+
+    let state = app.state::<AutoExportState>();
+    let mut status = state.0.lock().unwrap().clone();
+    status.enabled = enabled;
+    status.interval_hours = interval_hours;
+    status.format = format;
+    status.dir_path = dir_path;
+    let schedule = AutoExportSchedule::from_status(&status);
+    status.next_run = schedule.schedule.next_run();
+    settings::set(&app, settings::AUTO_EXPORT, &schedule)?;
+
+    *state.0.lock().unwrap() = status.clone();
+    Ok(status)
+}
+
+// 从设置备份恢复时经过 set_auto_export 校验格式和目录，上次导出记录保持本机的
+pub fn restore_schedule(app: &tauri::AppHandle, value: Value) -> AppResult<()> {
+    let schedule = serde_json::from_value::<Option<AutoExportSchedule>>(value)
+        .map_err(|_| AppError::new("settings.invalid_value").with("key", settings::AUTO_EXPORT))?
+        .unwrap_or_default();
+    set_auto_export(
+        app.clone(),
+        schedule.schedule.enabled,
+        schedule.schedule.interval_hours,
+        schedule.format,
+        schedule.dir_path,
+    )
+    .map(drop)
+}
+
+#[tauri::command]
+pub fn get_auto_export_status(state: tauri::State<'_, AutoExportState>) -> AutoExportStatus {
+    state.0.lock().unwrap().clone()
+}
+
+struct AutoExport;
+
+impl periodic::Task for AutoExport {
+    const NAME: &'static str = "auto_export";
+    const SETTING: &'static str = settings::AUTO_EXPORT;
+    type Output = AppResult<String>;
+    type Saved = AutoExportSchedule;
+
+    fn schedule(app: &tauri::AppHandle) -> Schedule {
+        AutoExportSchedule::from_status(&app.state::<AutoExportState>().0.lock().unwrap()).schedule
+    }
+
+    fn run(app: &tauri::AppHandle) -> Option<AppResult<String>> {
+        let status = app.state::<AutoExportState>().0.lock().unwrap().clone();
+        Some(run_auto_export(app, &status))
+    }
+
+    fn finish(
+        app: &tauri::AppHandle,
+        output: thread::Result<AppResult<String>>,
+    ) -> AutoExportSchedule {
+        let state = app.state::<AutoExportState>();
+        let mut current = state.0.lock().unwrap();
+        current.last_run = Some(Utc::now());
+        match output.unwrap_or_else(|_| Err(AppError::new("auto_export.panicked"))) {
+            Ok(file) => {
+                log::info!("自动导出完成");
+                current.last_file = Some(file);
+                current.last_error = None;
+            }
+            Err(e) => {
+                log::error!("自动导出失败: {}", e);
+                current.last_error = Some(e);
+            }
+        }
+        let schedule = AutoExportSchedule::from_status(&current);
+        current.next_run = schedule.schedule.next_run();
+        schedule
+    }
+}
+
+// 按保存的配置启动后台定时导出
+pub fn spawn(app: tauri::AppHandle) {
+    let schedule =
+        settings::get::<AutoExportSchedule>(&app, settings::AUTO_EXPORT).unwrap_or_default();
+    *app.state::<AutoExportState>().0.lock().unwrap() = AutoExportStatus {
+        enabled: schedule.schedule.enabled,
+        interval_hours: schedule.schedule.interval_hours,
+        format: schedule.format.clone(),
+        dir_path: schedule.dir_path.clone(),
+        last_run: schedule.schedule.last_run,
+        last_file: schedule.last_file.clone(),
+        last_error: None,
+        next_run: schedule.schedule.next_run(),
+    };
+    periodic::spawn::<AutoExport>(app);
+}
+
+fn run_auto_export(app: &tauri::AppHandle, status: &AutoExportStatus) -> AppResult<String> {
+    let format = status.format.parse::<ExportFormat>()?;
+    let notes = db::load_notes(app)?;
+    let content = format.render(&notes, &RenderOptions::default())?;
+
+    let file_path = PathBuf::from(&status.dir_path).join(format!(
+        "notes_export_{}.{}",
+        Utc::now().format("%Y%m%d_%H%M%S"),
+        format.extension()
+    ));
+    std::fs::write(&file_path, content).map_err(wrap("export.failed"))?;
+
+    Ok(file_path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_fields_are_saved_inline() {
+        let value = serde_json::json!({
+            "enabled": true,
+            "intervalHours": 6,
+            "format": "markdown",
+            "dirPath": "/tmp/notes",
+            "lastRun": "2024-01-01T00:00:00Z",
+            "lastFile": "/tmp/notes/notes_export.md",
+        });
+        let schedule = serde_json::from_value::<AutoExportSchedule>(value.clone()).unwrap();
+        assert!(schedule.schedule.enabled);
+        assert_eq!(schedule.schedule.interval_hours, 6);
+        assert!(schedule.schedule.last_run.is_some());
+        assert_eq!(schedule.dir_path, "/tmp/notes");
+        assert_eq!(serde_json::to_value(&schedule).unwrap(), value);
+    }
+}
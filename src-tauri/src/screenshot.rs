@@ -0,0 +1,96 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::read_only;
+use chrono::Local;
+use rusqlite::OptionalExtension;
+use xcap::image::ImageFormat;
+use xcap::Monitor;
+
+// 附件保存在应用数据目录下，按笔记 id 分目录
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+// macOS 10.15 起截屏需要“屏幕录制”权限，未授权时只能截到桌面背景而不会报错，需要事先检查
+#[cfg(target_os = "macos")]
+fn ensure_permission() -> AppResult<()> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    // 首次调用会弹出系统授权提示，授权后需重启应用才能生效
+    let granted = unsafe { CGPreflightScreenCaptureAccess() || CGRequestScreenCaptureAccess() };
+    if granted {
+        Ok(())
+    } else {
+        Err(AppError::new("screenshot.permission_denied"))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ensure_permission() -> AppResult<()> {
+    Ok(())
+}
+
+fn select_monitor(monitor_index: Option<usize>) -> AppResult<Monitor> {
+    let monitors = Monitor::all().map_err(wrap("screenshot.capture_failed"))?;
+    let count = monitors.len();
+    match monitor_index {
+        Some(index) => monitors.into_iter().nth(index).ok_or_else(|| {
+            AppError::new("screenshot.invalid_monitor")
+                .with("index", index)
+                .with("count", count)
+        }),
+        // 未指定时使用主显示器
+        None => {
+            let primary = monitors
+                .iter()
+                .position(|monitor| monitor.is_primary().unwrap_or(false))
+                .unwrap_or(0);
+            monitors
+                .into_iter()
+                .nth(primary)
+                .ok_or_else(|| AppError::new("screenshot.no_monitor"))
+        }
+    }
+}
+
+// 截取屏幕并保存为笔记附件，返回相对于应用数据目录的路径（使用 / 分隔）供前端嵌入
+#[tauri::command]
+pub async fn capture_screenshot(
+    app: tauri::AppHandle,
+    note_id: i64,
+    monitor_index: Option<usize>,
+) -> AppResult<String> {
+    let exists = db::with_read_conn(&app, |conn| {
+        conn.query_row("SELECT 1 FROM notes WHERE id = ?1", [note_id], |_| Ok(()))
+            .optional()
+    })?;
+    if exists.is_none() {
+        return Err(AppError::new("screenshot.note_not_found").with("id", note_id));
+    }
+
+    read_only::ensure_writable(&app)?;
+    ensure_permission()?;
+    let image = select_monitor(monitor_index)?
+        .capture_image()
+        .map_err(wrap("screenshot.capture_failed"))?;
+
+    let relative_dir = format!("{}/{}", ATTACHMENTS_DIR, note_id);
+    let dir = portable::resolve_data_dir(&app)?
+        .join(ATTACHMENTS_DIR)
+        .join(note_id.to_string());
+    std::fs::create_dir_all(&dir).map_err(wrap("screenshot.save_failed"))?;
+
+    let file_name = format!(
+        "screenshot-{}.png",
+        Local::now().format("%Y%m%d-%H%M%S-%3f")
+    );
+    image
+        .save_with_format(dir.join(&file_name), ImageFormat::Png)
+        .map_err(wrap("screenshot.save_failed"))?;
+
+    log::info!("已为笔记 {} 保存截图 {}", note_id, file_name);
+    Ok(format!("{}/{}", relative_dir, file_name))
+}
@@ -0,0 +1,241 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use crate::{db, settings};
+use chrono::Utc;
+use enigo::{Direction, Enigo, Key, Keyboard};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Y";
+
+// 等待用户松开快捷键后再模拟复制，之后最多等待 CLIPBOARD_TIMEOUT 让前台应用写入剪贴板
+const COPY_DELAY: Duration = Duration::from_millis(200);
+const CLIPBOARD_TIMEOUT: Duration = Duration::from_secs(1);
+const CLIPBOARD_POLL: Duration = Duration::from_millis(50);
+const TITLE_LENGTH: usize = 50;
+
+#[derive(Default)]
+pub struct SelectionState {
+    registered: Mutex<Option<Shortcut>>,
+    // Linux 上剪贴板内容由持有者提供，恢复原内容后需要保留该对象，否则内容随之丢失
+    clipboard: Mutex<Option<arboard::Clipboard>>,
+    capturing: Mutex<bool>,
+}
+
+// macOS 上模拟按键需要“辅助功能”权限；未授权时打开系统设置对应页面，授权后重新触发即可
+#[cfg(target_os = "macos")]
+fn ensure_permission(app: &tauri::AppHandle) -> AppResult<()> {
+    use tauri_plugin_opener::OpenerExt;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+
+    if unsafe { AXIsProcessTrusted() } {
+        return Ok(());
+    }
+    let _ = app.opener().open_url(
+        "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility",
+        None::<&str>,
+    );
+    Err(AppError::new("selection.permission_denied"))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ensure_permission(_app: &tauri::AppHandle) -> AppResult<()> {
+    Ok(())
+}
+
+// 当前拥有焦点的应用名称，无法获取时（如 Wayland）为 None
+fn active_app_name() -> Option<String> {
+    xcap::Window::all()
+        .ok()?
+        .into_iter()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .and_then(|window| window.app_name().ok())
+        .filter(|name| !name.is_empty())
+}
+
+fn simulate_copy() -> AppResult<()> {
+    let mut enigo =
+        Enigo::new(&enigo::Settings::default()).map_err(wrap("selection.input_failed"))?;
+    // 触发快捷键时用户可能还按着 Shift/Alt，先松开，避免组合成其他快捷键
+    for key in [Key::Shift, Key::Alt] {
+        let _ = enigo.key(key, Direction::Release);
+    }
+
+    #[cfg(target_os = "macos")]
+    let modifier = Key::Meta;
+    #[cfg(not(target_os = "macos"))]
+    let modifier = Key::Control;
+
+    enigo
+        .key(modifier, Direction::Press)
+        .and_then(|_| enigo.key(Key::Unicode('c'), Direction::Click))
+        .and_then(|_| enigo.key(modifier, Direction::Release))
+        .map_err(wrap("selection.input_failed"))
+}
+
+// 清空剪贴板后模拟复制，剪贴板仍为空说明没有选中文本；结束后恢复原来的文本内容
+fn copy_selection(app: &tauri::AppHandle) -> AppResult<Option<String>> {
+    let state = app.state::<SelectionState>();
+    let mut slot = state.clipboard.lock().unwrap();
+    if slot.is_none() {
+        *slot = Some(arboard::Clipboard::new().map_err(wrap("selection.clipboard_failed"))?);
+    }
+    let Some(clipboard) = slot.as_mut() else {
+        return Ok(None);
+    };
+
+    let previous = clipboard.get_text().ok();
+    clipboard
+        .clear()
+        .map_err(wrap("selection.clipboard_failed"))?;
+
+    thread::sleep(COPY_DELAY);
+    let copied = simulate_copy();
+    let mut text = None;
+    if copied.is_ok() {
+        let start = Instant::now();
+        while text.is_none() && start.elapsed() < CLIPBOARD_TIMEOUT {
+            thread::sleep(CLIPBOARD_POLL);
+            text = clipboard
+                .get_text()
+                .ok()
+                .filter(|text| !text.trim().is_empty());
+        }
+    }
+
+    let restored = match previous {
+        Some(previous) => clipboard.set_text(previous),
+        None => clipboard.clear(),
+    };
+    if let Err(e) = restored {
+        log::warn!("恢复剪贴板失败: {}", e);
+    }
+    copied.map(|_| text)
+}
+
+fn note_title(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty());
+    match line {
+        Some(line) => line.chars().take(TITLE_LENGTH).collect(),
+        None => t("export.untitled", &[]),
+    }
+}
+
+fn frontmatter(source: Option<&str>) -> String {
+    // JSON 字符串同时也是合法的 YAML 双引号字符串
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    let source = source
+        .map(|source| format!("source: {}\n", quote(source)))
+        .unwrap_or_default();
+    format!(
+        "---\n{}captured_at: {}\n---\n\n",
+        source,
+        quote(&Utc::now().to_rfc3339())
+    )
+}
+
+fn notify(app: &tauri::AppHandle, body: String) {
+    let _ = app
+        .notification()
+        .builder()
+        .title(t("notification.selection_title", &[]))
+        .body(body)
+        .show();
+}
+
+fn capture(app: &tauri::AppHandle) -> AppResult<()> {
+    ensure_permission(app)?;
+    let source = active_app_name();
+    let Some(text) = copy_selection(app)? else {
+        notify(app, t("notification.selection_empty", &[]));
+        return Ok(());
+    };
+
+    let title = note_title(&text);
+    let content = format!("{}{}", frontmatter(source.as_deref()), text.trim_end());
+    let note = db::with_write_conn(app, |conn| db::insert_note(conn, &title, &content))?;
+    log::info!(
+        "已从 {} 的选中文本创建笔记",
+        source.as_deref().unwrap_or("-")
+    );
+    let _ = app.emit("selection-note-created", note);
+    Ok(())
+}
+
+fn on_triggered(app: &tauri::AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        // 连续触发时忽略，避免两次模拟复制相互干扰
+        {
+            let state = app.state::<SelectionState>();
+            let mut capturing = state.capturing.lock().unwrap();
+            if *capturing {
+                return;
+            }
+            *capturing = true;
+        }
+        if let Err(e) = capture(&app) {
+            log::error!("从选中文本创建笔记失败: {}", e);
+            notify(&app, e.to_string());
+        }
+        *app.state::<SelectionState>().capturing.lock().unwrap() = false;
+    });
+}
+
+// 按设置注册或注销全局快捷键
+pub fn refresh(app: &tauri::AppHandle) -> AppResult<()> {
+    let state = app.state::<SelectionState>();
+    let mut registered = state.registered.lock().unwrap();
+    if let Some(shortcut) = registered.take() {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+    if !settings::get::<bool>(app, settings::SELECTION_TO_NOTE).unwrap_or(false) {
+        return Ok(());
+    }
+
+    let shortcut = settings::get::<String>(app, settings::SELECTION_SHORTCUT)
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string());
+    let shortcut = shortcut
+        .parse::<Shortcut>()
+        .map_err(|_| AppError::new("selection.invalid_shortcut").with("shortcut", &shortcut))?;
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                on_triggered(app);
+            }
+        })
+        .map_err(wrap("selection.register_failed"))?;
+    *registered = Some(shortcut);
+    Ok(())
+}
+
+pub fn init(app: &tauri::AppHandle) {
+    if let Err(e) = refresh(app) {
+        log::warn!("{}", e);
+    }
+}
+
+// 开启或关闭“选中文本创建笔记”；shortcut 为 None 时沿用当前快捷键
+#[tauri::command]
+pub fn set_selection_to_note(
+    app: tauri::AppHandle,
+    enabled: bool,
+    shortcut: Option<String>,
+) -> AppResult<()> {
+    if let Some(shortcut) = &shortcut {
+        shortcut
+            .parse::<Shortcut>()
+            .map_err(|_| AppError::new("selection.invalid_shortcut").with("shortcut", shortcut))?;
+        settings::set(&app, settings::SELECTION_SHORTCUT, shortcut)?;
+    }
+    settings::set(&app, settings::SELECTION_TO_NOTE, enabled)?;
+    refresh(&app)
+}
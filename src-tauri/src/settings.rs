@@ -0,0 +1,457 @@
+use crate::db;
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::{Emitter, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+const SETTINGS_VERSION: u32 = 1;
+
+// 由专用命令维护、带有副作用的设置项，不允许通过 set_setting 直接修改
+pub const LOG_LEVEL: &str = "logLevel";
+pub const LOCALE: &str = "locale";
+pub const ACTIVE_DATABASE: &str = "activeDatabase";
+pub const WINDOW_EFFECT: &str = "windowEffect";
+pub const SELECTION_TO_NOTE: &str = "selectionToNote";
+pub const SELECTION_SHORTCUT: &str = "selectionShortcut";
+pub const WINDOW_STATE: &str = "windowState";
+pub const ZOOM_LEVEL: &str = "zoomLevel";
+pub const INTEGRITY_CHECK: &str = "integrityCheck";
+pub const LAST_ACTIVE_NOTE: &str = "lastActiveNote";
+pub const LAST_BACKUP: &str = "lastBackup";
+pub const AUTO_UPDATE_CHECK: &str = "autoUpdateCheck";
+pub const AUTO_EXPORT: &str = "autoExport";
+const MANAGED_KEYS: &[&str] = &[
+    LOG_LEVEL,
+    LOCALE,
+    ACTIVE_DATABASE,
+    WINDOW_EFFECT,
+    SELECTION_TO_NOTE,
+    SELECTION_SHORTCUT,
+    WINDOW_STATE,
+    ZOOM_LEVEL,
+    INTEGRITY_CHECK,
+    LAST_ACTIVE_NOTE,
+    LAST_BACKUP,
+    AUTO_UPDATE_CHECK,
+    AUTO_EXPORT,
+];
+
+pub const THEME: &str = "theme";
+pub const NOTE_VERSIONS: &str = "noteVersions";
+pub const FEED_IMAGES: &str = "feedImages";
+const THEMES: &[&str] = &["system", "light", "dark"];
+
+// 早期版本的键名，加载时改为当前名称
+const RENAMED_KEYS: &[(&str, &str)] = &[
+    ("auto_save", "autoSave"),
+    ("auto_save_interval", "autoSaveInterval"),
+    ("sidebar_collapsed", "sidebarCollapsed"),
+    ("log_level", LOG_LEVEL),
+    ("active_db", ACTIVE_DATABASE),
+];
+
+// 可以从前端 localStorage 迁移过来的设置项
+const FRONTEND_KEYS: &[&str] = &[THEME, "autoSave", "autoSaveInterval", "sidebarCollapsed"];
+
+fn defaults() -> Map<String, Value> {
+    let defaults = json!({
+        THEME: "system",
+        "autoSave": true,
+        "autoSaveInterval": 3000,
+        "sidebarCollapsed": false,
+        "logLevel": "info",
+        "locale": Value::Null,
+        "activeDatabase": db::DEFAULT_DATABASE,
+        "windowEffect": Value::Null,
+        "selectionToNote": false,
+        "selectionShortcut": crate::selection::DEFAULT_SHORTCUT,
+        "windowState": Value::Null,
+        "zoomLevel": 1.0,
+        "integrityCheck": Value::Null,
+        "lastActiveNote": Value::Null,
+        "lastBackup": Value::Null,
+        "autoUpdateCheck": Value::Null,
+        "autoExport": Value::Null,
+        "noteVersions": true,
+        "feedImages": false,
+    });
+    match defaults {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsFile {
+    version: u32,
+    #[serde(default)]
+    local_storage_migrated: bool,
+    #[serde(default)]
+    values: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SettingChanged {
+    pub key: String,
+    pub value: Value,
+}
+
+// settings.json 的内存副本，所有读写都经过这里
+pub struct SettingsStore {
+    path: RwLock<PathBuf>,
+    file: Mutex<SettingsFile>,
+}
+
+// 旧版本的设置文件升级到当前结构
+fn migrate(raw: Value) -> SettingsFile {
+    let mut file = match raw {
+        // 0 版没有版本号，整个文件就是扁平的键值对
+        Value::Object(map) if !map.contains_key("version") => SettingsFile {
+            version: 0,
+            local_storage_migrated: false,
+            values: map,
+        },
+        raw => serde_json::from_value(raw).unwrap_or_default(),
+    };
+
+    if file.version < 1 {
+        for (old, new) in RENAMED_KEYS {
+            if let Some(value) = file.values.remove(*old) {
+                file.values.entry(new.to_string()).or_insert(value);
+            }
+        }
+    }
+    file.version = SETTINGS_VERSION;
+    file
+}
+
+// 引入设置存储之前，部分设置保存在应用数据目录下的单独文件中
+fn import_legacy_files(app_data_dir: &Path, values: &mut Map<String, Value>) -> bool {
+    let legacy = [
+        (ACTIVE_DATABASE, app_data_dir.join("active_db")),
+        (LOG_LEVEL, app_data_dir.join("logs").join("level")),
+        (LOCALE, app_data_dir.join("locale")),
+    ];
+
+    let mut changed = false;
+    for (key, path) in legacy {
+        let Ok(value) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let value = value.trim();
+        if !value.is_empty() && !values.contains_key(key) {
+            values.insert(key.to_string(), json!(value));
+            changed = true;
+        }
+        let _ = fs::remove_file(&path);
+    }
+    changed
+}
+
+impl SettingsStore {
+    pub fn load(app: &tauri::AppHandle) -> AppResult<Self> {
+        let dir = portable::resolve_data_dir(app)?;
+        let path = dir.join(SETTINGS_FILE);
+
+        let (mut file, mut needs_save) = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<Value>(&content) {
+                Ok(raw) => {
+                    let version = raw["version"].as_u64();
+                    (migrate(raw), version != Some(SETTINGS_VERSION as u64))
+                }
+                Err(e) => {
+                    // 文件损坏时保留一份副本，然后使用默认设置
+                    eprintln!("设置文件损坏，已使用默认设置: {}", e);
+                    let _ = fs::rename(&path, path.with_extension("json.bak"));
+                    (migrate(json!({})), true)
+                }
+            },
+            Err(_) => (migrate(json!({})), true),
+        };
+        needs_save |= import_legacy_files(&dir, &mut file.values);
+
+        let store = SettingsStore {
+            path: RwLock::new(path),
+            file: Mutex::new(file),
+        };
+        if needs_save {
+            store.save(&store.file.lock().unwrap())?;
+        }
+        Ok(store)
+    }
+
+    // 先写临时文件再重命名，避免写到一半时崩溃留下不完整的设置文件
+    fn save(&self, file: &SettingsFile) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(file).map_err(wrap("settings.save_failed"))?;
+        let path = self.path.read().unwrap();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(wrap("settings.save_failed"))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(wrap("settings.save_failed"))?;
+        fs::rename(&tmp, &*path).map_err(wrap("settings.save_failed"))
+    }
+
+    // 切换配置后从新的数据目录重新加载
+    pub fn reload(&self, app: &tauri::AppHandle) -> AppResult<()> {
+        let loaded = SettingsStore::load(app)?;
+        let mut file = self.file.lock().unwrap();
+        *self.path.write().unwrap() = loaded.path.into_inner().unwrap();
+        *file = loaded.file.into_inner().unwrap();
+        Ok(())
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<Value> {
+        self.file
+            .lock()
+            .unwrap()
+            .values
+            .get(key)
+            .cloned()
+            .or_else(|| defaults().remove(key))
+    }
+
+    pub fn all(&self) -> Map<String, Value> {
+        let mut all = defaults();
+        all.extend(self.file.lock().unwrap().values.clone());
+        all
+    }
+
+    fn set_value(&self, key: &str, value: Value) -> AppResult<bool> {
+        let mut file = self.file.lock().unwrap();
+        let changed = if value.is_null() {
+            file.values.remove(key).is_some()
+        } else {
+            file.values.insert(key.to_string(), value.clone()) != Some(value)
+        };
+        if !changed {
+            return Ok(false);
+        }
+        self.save(&file)?;
+        Ok(true)
+    }
+}
+
+// 默认值存在时新值必须是相同的 JSON 类型（null 表示恢复默认）
+fn validate(key: &str, value: &Value) -> AppResult<()> {
+    let Some(default) = defaults().remove(key) else {
+        return Ok(());
+    };
+    let same_type = matches!(
+        (&default, value),
+        (_, Value::Null)
+            | (Value::Null, _)
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::Number(_), Value::Number(_))
+            | (Value::String(_), Value::String(_))
+            | (Value::Array(_), Value::Array(_))
+            | (Value::Object(_), Value::Object(_))
+    );
+    let known_theme = key != THEME || value.as_str().is_none_or(|theme| THEMES.contains(&theme));
+    if same_type && known_theme {
+        Ok(())
+    } else {
+        Err(AppError::new("settings.invalid_value").with("key", key))
+    }
+}
+
+pub fn get<T: DeserializeOwned>(app: &tauri::AppHandle, key: &str) -> Option<T> {
+    let value = app.try_state::<SettingsStore>()?.get_value(key)?;
+    serde_json::from_value(value).ok()
+}
+
+// 写入设置并通知所有窗口；设置不属于笔记数据，只读模式下同样可以保存
+pub fn set<T: Serialize>(app: &tauri::AppHandle, key: &str, value: T) -> AppResult<()> {
+    let value = serde_json::to_value(value).map_err(wrap("settings.save_failed"))?;
+    validate(key, &value)?;
+
+    let store = app.state::<SettingsStore>();
+    if store.set_value(key, value)? {
+        let _ = app.emit(
+            "settings-changed",
+            SettingChanged {
+                key: key.to_string(),
+                value: store.get_value(key).unwrap_or(Value::Null),
+            },
+        );
+        if key == THEME {
+            crate::theme::refresh(app)?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_setting(app: tauri::AppHandle, key: String) -> Value {
+    app.state::<SettingsStore>()
+        .get_value(&key)
+        .unwrap_or(Value::Null)
+}
+
+#[tauri::command]
+pub fn get_all_settings(app: tauri::AppHandle) -> Map<String, Value> {
+    app.state::<SettingsStore>().all()
+}
+
+#[tauri::command]
+pub fn set_setting(app: tauri::AppHandle, key: String, value: Value) -> AppResult<()> {
+    if MANAGED_KEYS.contains(&key.as_str()) {
+        return Err(AppError::new("settings.managed_key").with("key", &key));
+    }
+    set(&app, &key, value)
+}
+
+// 把前端 localStorage 中的旧设置导入设置文件，只执行一次；已存在的值不会被覆盖，返回导入的项数
+#[tauri::command]
+pub fn migrate_local_settings(
+    app: tauri::AppHandle,
+    values: Map<String, Value>,
+) -> AppResult<usize> {
+    let store = app.state::<SettingsStore>();
+    let mut imported = Vec::new();
+    {
+        let mut file = store.file.lock().unwrap();
+        if file.local_storage_migrated {
+            return Ok(0);
+        }
+        for (key, value) in values {
+            if !FRONTEND_KEYS.contains(&key.as_str())
+                || file.values.contains_key(&key)
+                || validate(&key, &value).is_err()
+            {
+                continue;
+            }
+            file.values.insert(key.clone(), value.clone());
+            imported.push(SettingChanged { key, value });
+        }
+        file.local_storage_migrated = true;
+        store.save(&file)?;
+    }
+
+    log::info!("已从 localStorage 迁移 {} 项设置", imported.len());
+    let count = imported.len();
+    let theme_changed = imported.iter().any(|change| change.key == THEME);
+    for change in imported {
+        let _ = app.emit("settings-changed", change);
+    }
+    if theme_changed {
+        crate::theme::refresh(&app)?;
+    }
+    Ok(count)
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SettingsRestore {
+    // 值发生变化的设置项
+    pub changed: Vec<String>,
+    // 值无效或不随设置迁移的设置项
+    pub skipped: Vec<String>,
+}
+
+// 只导出设置值，便于在不同设备间迁移偏好设置
+#[tauri::command]
+pub fn backup_settings(app: tauri::AppHandle, file_path: String) -> AppResult<()> {
+    let store = app.state::<SettingsStore>();
+    let content = {
+        let file = store.file.lock().unwrap();
+        serde_json::to_string_pretty(&json!({
+            "version": SETTINGS_VERSION,
+            "values": file.values,
+        }))
+        .map_err(wrap("settings.backup_failed"))?
+    };
+    fs::write(&file_path, content).map_err(wrap("settings.backup_failed"))?;
+    log::info!("设置已备份");
+
+    Ok(())
+}
+
+// 带副作用的设置项通过各自的命令恢复，定时任务同时更新运行中的定时器；当前数据库、上次打开的笔记和备份记录
+// 属于笔记数据，窗口位置与显示器有关，都不随设置恢复；未知的设置项跳过
+fn restore_value(app: &tauri::AppHandle, key: &str, value: Value) -> AppResult<()> {
+    match key {
+        ACTIVE_DATABASE | WINDOW_STATE | LAST_ACTIVE_NOTE | LAST_BACKUP => {
+            Err(AppError::new("settings.managed_key").with("key", key))
+        }
+        INTEGRITY_CHECK => crate::integrity::restore_schedule(app, value),
+        AUTO_EXPORT => crate::scheduler::restore_schedule(app, value),
+        AUTO_UPDATE_CHECK => crate::updater::restore_schedule(app, value),
+        LOG_LEVEL | LOCALE | WINDOW_EFFECT => {
+            let value = value
+                .as_str()
+                .ok_or_else(|| AppError::new("settings.invalid_value").with("key", key))?
+                .to_string();
+            match key {
+                LOG_LEVEL => crate::logging::set_log_level(app.clone(), value),
+                LOCALE => crate::i18n::set_backend_locale(app.clone(), value),
+                _ => {
+                    let effect = serde_json::from_value(Value::String(value))
+                        .map_err(|_| AppError::new("settings.invalid_value").with("key", key))?;
+                    crate::window_effect::set_window_effect(app.clone(), effect).map(|_| ())
+                }
+            }
+        }
+        ZOOM_LEVEL => {
+            let factor = value
+                .as_f64()
+                .ok_or_else(|| AppError::new("settings.invalid_value").with("key", key))?;
+            crate::zoom::set_zoom_level(app.clone(), factor).map(|_| ())
+        }
+        SELECTION_TO_NOTE | SELECTION_SHORTCUT => {
+            set(app, key, value)?;
+            crate::selection::refresh(app)
+        }
+        _ if defaults().contains_key(key) => set(app, key, value),
+        _ => Err(AppError::new("settings.unknown_key").with("key", key)),
+    }
+}
+
+// 合并备份中的设置：备份中没有的设置项保持不变，无效的值跳过，返回变化和跳过的设置项
+#[tauri::command]
+pub fn restore_settings(app: tauri::AppHandle, file_path: String) -> AppResult<SettingsRestore> {
+    let content = fs::read_to_string(&file_path).map_err(wrap("settings.restore_failed"))?;
+    let raw = serde_json::from_str::<Value>(&content).map_err(wrap("settings.invalid_backup"))?;
+    // 当前结构为带版本号的 {version, values}，0 版为扁平的键值对
+    let valid = match &raw {
+        Value::Object(map) if map.contains_key("version") => {
+            raw["version"].is_u64() && raw["values"].is_object()
+        }
+        Value::Object(_) => true,
+        _ => false,
+    };
+    if !valid {
+        return Err(AppError::new("settings.invalid_backup").with("error", "structure"));
+    }
+    let backup = migrate(raw);
+
+    let store = app.state::<SettingsStore>();
+    let mut result = SettingsRestore::default();
+    for (key, value) in backup.values {
+        let before = store.get_value(&key);
+        if before.as_ref() == Some(&value) {
+            continue;
+        }
+        match restore_value(&app, &key, value) {
+            Ok(()) if store.get_value(&key) != before => result.changed.push(key),
+            Ok(()) => {}
+            Err(e) => {
+                log::warn!("跳过设置项 {}: {}", key, e);
+                result.skipped.push(key);
+            }
+        }
+    }
+    log::info!(
+        "已从备份恢复设置，变化 {} 项，跳过 {} 项",
+        result.changed.len(),
+        result.skipped.len()
+    );
+
+    Ok(result)
+}
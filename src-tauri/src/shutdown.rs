@@ -0,0 +1,171 @@
+use crate::error::AppResult;
+use crate::{backup, db, export, tts, window_state};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// 退出前等待后台任务的最长时间，系统注销时留给应用的时间通常只有几秒
+const DEADLINE: Duration = Duration::from_secs(4);
+const POLL: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+pub struct ShutdownState {
+    shutting_down: Mutex<bool>,
+    prepared: Mutex<bool>,
+    // 正在运行、退出前需要等待完成的后台任务
+    tasks: Mutex<HashMap<u64, &'static str>>,
+    next_task: Mutex<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ShutdownReport {
+    pub completed: Vec<String>,
+    // 超时或失败而跳过的步骤
+    pub skipped: Vec<String>,
+}
+
+impl ShutdownReport {
+    fn step(&mut self, name: &str, result: AppResult<()>) {
+        match result {
+            Ok(()) => self.completed.push(name.to_string()),
+            Err(e) => {
+                log::warn!("退出前的 {} 未完成: {}", name, e);
+                self.skipped.push(name.to_string());
+            }
+        }
+    }
+}
+
+// 后台任务运行期间持有，drop 时登记结束
+pub struct TaskGuard {
+    app: tauri::AppHandle,
+    id: u64,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let state = self.app.state::<ShutdownState>();
+        state.tasks.lock().unwrap().remove(&self.id);
+    }
+}
+
+// 登记一个后台任务；应用正在退出时返回 None，任务不应再开始
+pub fn begin_task(app: &tauri::AppHandle, name: &'static str) -> Option<TaskGuard> {
+    let state = app.state::<ShutdownState>();
+    if *state.shutting_down.lock().unwrap() {
+        return None;
+    }
+    let id = {
+        let mut next = state.next_task.lock().unwrap();
+        *next += 1;
+        *next
+    };
+    state.tasks.lock().unwrap().insert(id, name);
+    Some(TaskGuard {
+        app: app.clone(),
+        id,
+    })
+}
+
+pub fn is_prepared(app: &tauri::AppHandle) -> bool {
+    *app.state::<ShutdownState>().prepared.lock().unwrap()
+}
+
+// 在期限内等待后台任务结束，返回仍未结束的任务
+fn wait_for_tasks(app: &tauri::AppHandle, deadline: Instant) -> Vec<&'static str> {
+    let state = app.state::<ShutdownState>();
+    loop {
+        let running: Vec<&'static str> = state.tasks.lock().unwrap().values().copied().collect();
+        if running.is_empty() || Instant::now() >= deadline {
+            return running;
+        }
+        thread::sleep(POLL);
+    }
+}
+
+// 退出前的收尾：停止后台任务（最多等待 DEADLINE）、保存窗口状态、合并 WAL 并关闭连接池。
+// 可重复调用，只执行一次
+pub fn prepare(app: &tauri::AppHandle) -> ShutdownReport {
+    let state = app.state::<ShutdownState>();
+    *state.shutting_down.lock().unwrap() = true;
+    let mut report = ShutdownReport::default();
+    if is_prepared(app) {
+        return report;
+    }
+
+    let deadline = Instant::now() + DEADLINE;
+    export::cancel_all_jobs(app);
+    tts::stop(app);
+    let running = wait_for_tasks(app, deadline);
+    if running.is_empty() {
+        report.completed.push("background_tasks".to_string());
+    } else {
+        log::warn!("退出时以下后台任务仍未结束: {}", running.join(", "));
+        report
+            .skipped
+            .extend(running.iter().map(|name| name.to_string()));
+    }
+
+    report.step("window_state", window_state::save(app));
+    if let Some(pool) = app.try_state::<db::DbPool>() {
+        pool.close();
+        report.completed.push("checkpoint".to_string());
+    }
+
+    *state.prepared.lock().unwrap() = true;
+    log::info!("退出前收尾完成");
+    report
+}
+
+// Windows 注销或关机时不会经过正常的退出流程，在 WM_ENDSESSION 中同步完成收尾
+#[cfg(target_os = "windows")]
+pub fn init(app: &tauri::AppHandle) {
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::UI::Shell::{DefSubclassProc, SetWindowSubclass};
+    use windows_sys::Win32::UI::WindowsAndMessaging::WM_ENDSESSION;
+
+    unsafe extern "system" fn subclass_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+        _id: usize,
+        data: usize,
+    ) -> LRESULT {
+        if msg == WM_ENDSESSION && wparam != 0 {
+            let app = &*(data as *const tauri::AppHandle);
+            log::info!("系统会话结束");
+            prepare(app);
+        }
+        DefSubclassProc(hwnd, msg, wparam, lparam)
+    }
+
+    let Some(hwnd) = app
+        .get_webview_window("main")
+        .and_then(|window| window.hwnd().ok())
+    else {
+        return;
+    };
+    // AppHandle 在整个进程生命周期内有效，这里有意不释放
+    let data = Box::into_raw(Box::new(app.clone())) as usize;
+    unsafe {
+        SetWindowSubclass(hwnd.0 as _, Some(subclass_proc), 1, data);
+    }
+}
+
+// macOS 的退出（含注销）会触发 RunEvent::ExitRequested，不需要额外处理
+#[cfg(not(target_os = "windows"))]
+pub fn init(_app: &tauri::AppHandle) {}
+
+// 前端在 beforeunload 时调用：保存窗口状态并合并 WAL。页面刷新也会触发 beforeunload，
+// 因此这里不停止后台任务，也不关闭连接池
+#[tauri::command]
+pub fn prepare_for_shutdown(app: tauri::AppHandle) -> ShutdownReport {
+    let mut report = ShutdownReport::default();
+    report.step("window_state", window_state::save(&app));
+    report.step("checkpoint", backup::checkpoint(&app));
+    report
+}
@@ -0,0 +1,184 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::readability;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// 英文按单词、中日韩文字按单字切分
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[\p{Han}\p{Hiragana}\p{Katakana}]|[\w&&[^\p{Han}\p{Hiragana}\p{Katakana}]]+")
+        .unwrap()
+});
+
+// 连续 3 个词组成一个 shingle
+const SHINGLE_SIZE: usize = 3;
+// MinHash 签名长度，LSH 按 bands * rows = SIGNATURE_SIZE 分段
+const SIGNATURE_SIZE: usize = 128;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimilarNote {
+    pub id: i64,
+    pub title: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimilarPair {
+    pub a: SimilarNote,
+    pub b: SimilarNote,
+    // shingle 集合的 Jaccard 相似度
+    pub similarity: f64,
+}
+
+struct Document {
+    note: SimilarNote,
+    shingles: HashSet<u64>,
+    signature: Vec<u64>,
+}
+
+// FNV-1a，保证同一内容每次得到相同的哈希
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// splitmix64，与不同的种子组合后作为 MinHash 的一组哈希函数
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn shingles(content: &str) -> HashSet<u64> {
+    let text = readability::text_blocks(content).join("\n").to_lowercase();
+    let tokens: Vec<&str> = TOKEN.find_iter(&text).map(|token| token.as_str()).collect();
+    if tokens.len() < SHINGLE_SIZE {
+        return (!tokens.is_empty())
+            .then(|| fnv(&tokens.join(" ")))
+            .into_iter()
+            .collect();
+    }
+    tokens
+        .windows(SHINGLE_SIZE)
+        .map(|window| fnv(&window.join(" ")))
+        .collect()
+}
+
+fn signature(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..SIGNATURE_SIZE as u64)
+        .map(|seed| {
+            let seed = mix(seed);
+            shingles
+                .iter()
+                .map(|shingle| mix(shingle ^ seed))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    if union == 0 {
+        0.0
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+// 每段的行数：在阈值附近仍能以较高概率成为候选（S 曲线拐点约为 (1/bands)^(1/rows)），
+// 行数越大候选越少，阈值较低时退回到较小的行数
+fn rows_per_band(threshold: f64) -> usize {
+    [16, 8, 4, 2]
+        .into_iter()
+        .find(|rows| {
+            let bands = (SIGNATURE_SIZE / rows) as f64;
+            (1.0 / bands).powf(1.0 / *rows as f64) <= threshold - 0.1
+        })
+        .unwrap_or(1)
+}
+
+// 找出内容相似度不低于 threshold 的笔记对：先用 MinHash + LSH 筛出候选，再计算精确的 Jaccard 相似度，
+// 避免对所有笔记两两比较。相似度基于去掉标记后的正文，按相似度从高到低排序
+pub fn find_similar(notes: Vec<(i64, String, String)>, threshold: f64) -> Vec<SimilarPair> {
+    let documents: Vec<Document> = notes
+        .into_iter()
+        .filter_map(|(id, title, content)| {
+            let shingles = shingles(&content);
+            if shingles.is_empty() {
+                return None;
+            }
+            let signature = signature(&shingles);
+            Some(Document {
+                note: SimilarNote { id, title },
+                shingles,
+                signature,
+            })
+        })
+        .collect();
+
+    let rows = rows_per_band(threshold);
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for band in documents
+        .first()
+        .map(|doc| doc.signature.len() / rows)
+        .into_iter()
+        .flat_map(|bands| 0..bands)
+    {
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (i, doc) in documents.iter().enumerate() {
+            buckets
+                .entry(&doc.signature[band * rows..(band + 1) * rows])
+                .or_default()
+                .push(i);
+        }
+        for bucket in buckets.values().filter(|bucket| bucket.len() > 1) {
+            for (k, &i) in bucket.iter().enumerate() {
+                for &j in &bucket[k + 1..] {
+                    candidates.insert((i, j));
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<SimilarPair> = candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let similarity = jaccard(&documents[i].shingles, &documents[j].shingles);
+            (similarity >= threshold).then(|| SimilarPair {
+                a: documents[i].note.clone(),
+                b: documents[j].note.clone(),
+                similarity: (similarity * 1000.0).round() / 1000.0,
+            })
+        })
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.similarity
+            .total_cmp(&x.similarity)
+            .then_with(|| (x.a.id, x.b.id).cmp(&(y.a.id, y.b.id)))
+    });
+    pairs
+}
+
+// threshold 取值 (0, 1]，例如 0.8 表示八成以上的内容相同
+#[tauri::command]
+pub async fn find_similar_notes(
+    app: tauri::AppHandle,
+    threshold: f64,
+) -> AppResult<Vec<SimilarPair>> {
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(AppError::new("similarity.invalid_threshold").with("threshold", threshold));
+    }
+    let notes = db::with_read_conn(&app, |conn| {
+        let mut stmt = conn.prepare("SELECT id, title, content FROM notes ORDER BY id")?;
+        let notes = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>();
+        notes
+    })?;
+    Ok(find_similar(notes, threshold))
+}
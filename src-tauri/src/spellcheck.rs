@@ -0,0 +1,318 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::portable;
+use crate::read_only;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use spellbook::Dictionary;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::Manager;
+
+// 内置英文词典，其他语言从应用数据目录的 dictionaries 下读取 <语言>.aff 和 <语言>.dic
+const DEFAULT_LANGUAGE: &str = "en_US";
+const BUNDLED_AFF: &str = include_str!("../dictionaries/en_US/en_US.aff");
+const BUNDLED_DIC: &str = include_str!("../dictionaries/en_US/en_US.dic");
+const DICTIONARIES_DIR: &str = "dictionaries";
+
+// 用户词典，每行一个词
+const USER_DICTIONARY: &str = "user_dictionary.txt";
+const MAX_WORD_LENGTH: usize = 64;
+
+const MAX_SUGGESTIONS: usize = 5;
+// 段落检查结果的缓存条数上限，超出后整体清空
+const CACHE_CAPACITY: usize = 2000;
+
+// 不参与拼写检查的内容：代码块、行内代码、URL、邮箱、链接目标、HTML 标签和实体
+static EXCLUDED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?ms:^[ \t]*```.*?(?:^[ \t]*```|\z))",
+        r"|(?ms:^[ \t]*~~~.*?(?:^[ \t]*~~~|\z))",
+        r"|(?s:<(?:pre|code)\b[^>]*>.*?</(?:pre|code)>)",
+        r"|`[^`\n]+`",
+        r"|\b(?:https?|ftp|file)://\S+|\bwww\.\S+|\bmailto:\S+",
+        r"|[\w.+-]+@[\w-]+(?:\.[\w-]+)+",
+        r"|\]\([^)\n]*\)",
+        r"|(?m:^[ \t]*\[[^\]\n]+\]:[ \t]*\S+)",
+        r"|<[^<>\n]+>",
+        r"|&#?\w+;",
+    ))
+    .unwrap()
+});
+
+// 只检查拉丁字母组成的词，中日韩文字自然被排除；带数字或下划线的视为标识符
+static WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{Latin}\d_]+(?:['’][\p{Latin}\d_]+)*").unwrap());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Misspelling {
+    // UTF-16 偏移，与前端字符串下标一致
+    pub start: usize,
+    pub end: usize,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Paragraph {
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ParagraphResult {
+    pub id: String,
+    pub misspellings: Vec<Misspelling>,
+}
+
+#[derive(Default)]
+pub struct SpellState {
+    dictionaries: Mutex<HashMap<String, Arc<Dictionary>>>,
+    // 首次使用时从文件加载
+    user_words: Mutex<Option<BTreeSet<String>>>,
+    // (语言, 段落文本) -> 检查结果，编辑时只有改动的段落需要重新检查
+    cache: Mutex<HashMap<(String, String), Vec<Misspelling>>>,
+}
+
+impl SpellState {
+    pub fn dictionaries_loaded(&self) -> usize {
+        self.dictionaries.lock().unwrap().len()
+    }
+
+    // (段落条数, 段落文本和检查结果的近似字节数)
+    pub fn cache_usage(&self) -> (usize, usize) {
+        let cache = self.cache.lock().unwrap();
+        let bytes = cache
+            .iter()
+            .map(|((language, text), misspellings)| {
+                language.len()
+                    + text.len()
+                    + misspellings
+                        .iter()
+                        .map(|m| {
+                            m.word.len() + m.suggestions.iter().map(String::len).sum::<usize>()
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+        (cache.len(), bytes)
+    }
+}
+
+// en-US、en_us 等写法统一为 en_US；语言名会用于拼接文件路径，只允许字母、数字和下划线
+fn normalize_language(language: Option<String>) -> AppResult<String> {
+    let Some(language) = language.filter(|l| !l.trim().is_empty()) else {
+        return Ok(DEFAULT_LANGUAGE.to_string());
+    };
+    let language = language.trim().replace('-', "_");
+    if !language
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(AppError::new("spellcheck.dictionary_not_found").with("language", language));
+    }
+    Ok(match language.split_once('_') {
+        Some((lang, region)) => format!("{}_{}", lang.to_lowercase(), region.to_uppercase()),
+        None => language.to_lowercase(),
+    })
+}
+
+fn load_dictionary(app: &tauri::AppHandle, language: &str) -> AppResult<Arc<Dictionary>> {
+    let state = app.state::<SpellState>();
+    if let Some(dictionary) = state.dictionaries.lock().unwrap().get(language) {
+        return Ok(Arc::clone(dictionary));
+    }
+
+    // 应用数据目录中的同名词典优先，便于替换内置的英文词典
+    let dir = portable::root_data_dir(app)?.join(DICTIONARIES_DIR);
+    let aff_path = dir.join(format!("{}.aff", language));
+    let dic_path = dir.join(format!("{}.dic", language));
+    let dictionary = if aff_path.is_file() && dic_path.is_file() {
+        let aff =
+            std::fs::read_to_string(&aff_path).map_err(wrap("spellcheck.dictionary_invalid"))?;
+        let dic =
+            std::fs::read_to_string(&dic_path).map_err(wrap("spellcheck.dictionary_invalid"))?;
+        Dictionary::new(&aff, &dic).map_err(wrap("spellcheck.dictionary_invalid"))?
+    } else if language == DEFAULT_LANGUAGE {
+        Dictionary::new(BUNDLED_AFF, BUNDLED_DIC).map_err(wrap("spellcheck.dictionary_invalid"))?
+    } else {
+        return Err(AppError::new("spellcheck.dictionary_not_found").with("language", language));
+    };
+    log::info!("已加载拼写检查词典 {}", language);
+
+    let dictionary = Arc::new(dictionary);
+    state
+        .dictionaries
+        .lock()
+        .unwrap()
+        .insert(language.to_string(), Arc::clone(&dictionary));
+    Ok(dictionary)
+}
+
+// 切换配置后用户词典需要从新的数据目录重新加载
+pub fn reset(app: &tauri::AppHandle) {
+    let state = app.state::<SpellState>();
+    *state.user_words.lock().unwrap() = None;
+    state.cache.lock().unwrap().clear();
+}
+
+fn user_dictionary_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(portable::resolve_data_dir(app)?.join(USER_DICTIONARY))
+}
+
+fn user_words(app: &tauri::AppHandle) -> AppResult<BTreeSet<String>> {
+    let state = app.state::<SpellState>();
+    let mut words = state.user_words.lock().unwrap();
+    if words.is_none() {
+        let path = user_dictionary_path(app)?;
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        *words = Some(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_string)
+                .collect(),
+        );
+    }
+    Ok(words.clone().unwrap_or_default())
+}
+
+// 修改用户词典并写回文件；已缓存的检查结果随之失效
+fn update_user_words(
+    app: &tauri::AppHandle,
+    update: impl FnOnce(&mut BTreeSet<String>) -> bool,
+) -> AppResult<()> {
+    read_only::ensure_writable(app)?;
+    let mut words = user_words(app)?;
+    if !update(&mut words) {
+        return Ok(());
+    }
+
+    let mut content = words.iter().cloned().collect::<Vec<_>>().join("\n");
+    content.push('\n');
+    std::fs::write(user_dictionary_path(app)?, content).map_err(wrap("spellcheck.save_failed"))?;
+
+    let state = app.state::<SpellState>();
+    *state.user_words.lock().unwrap() = Some(words);
+    state.cache.lock().unwrap().clear();
+    Ok(())
+}
+
+fn validate_word(word: &str) -> AppResult<String> {
+    let word = word.trim();
+    if word.is_empty()
+        || word.chars().count() > MAX_WORD_LENGTH
+        || word.chars().any(char::is_whitespace)
+    {
+        return Err(AppError::new("spellcheck.invalid_word").with("word", word));
+    }
+    Ok(word.to_string())
+}
+
+fn is_known(word: &str, user_words: &BTreeSet<String>, dictionary: &Dictionary) -> bool {
+    // 用户词典优先；句首大写的词按小写形式再查一次
+    let normalized = word.replace('’', "'");
+    user_words.contains(&normalized)
+        || user_words.contains(&normalized.to_lowercase())
+        || dictionary.check(&normalized)
+}
+
+fn excluded_ranges(text: &str) -> Vec<Range<usize>> {
+    EXCLUDED.find_iter(text).map(|m| m.range()).collect()
+}
+
+fn check(text: &str, user_words: &BTreeSet<String>, dictionary: &Dictionary) -> Vec<Misspelling> {
+    let excluded = excluded_ranges(text);
+    let mut misspellings = Vec::new();
+    // 逐词累加 UTF-16 偏移，避免每个词都从头计算
+    let mut offset = 0;
+    let mut offset_at = 0;
+
+    for m in WORD.find_iter(text) {
+        let word = m.as_str();
+        if word.chars().count() < 2
+            || word.chars().any(|c| c.is_ascii_digit() || c == '_')
+            || excluded
+                .iter()
+                .any(|range| range.start < m.end() && m.start() < range.end)
+            || is_known(word, user_words, dictionary)
+        {
+            continue;
+        }
+
+        offset += text[offset_at..m.start()].encode_utf16().count();
+        offset_at = m.start();
+        let mut suggestions = Vec::new();
+        dictionary.suggest(word, &mut suggestions);
+        suggestions.truncate(MAX_SUGGESTIONS);
+        misspellings.push(Misspelling {
+            start: offset,
+            end: offset + word.encode_utf16().count(),
+            word: word.to_string(),
+            suggestions,
+        });
+    }
+    misspellings
+}
+
+#[tauri::command]
+pub async fn spellcheck_text(
+    app: tauri::AppHandle,
+    text: String,
+    language: Option<String>,
+) -> AppResult<Vec<Misspelling>> {
+    let language = normalize_language(language)?;
+    let dictionary = load_dictionary(&app, &language)?;
+    let user_words = user_words(&app)?;
+    Ok(check(&text, &user_words, &dictionary))
+}
+
+// 按段落检查，前端编辑时只需传入改动过的段落；偏移相对于各自段落
+#[tauri::command]
+pub async fn spellcheck_paragraphs(
+    app: tauri::AppHandle,
+    paragraphs: Vec<Paragraph>,
+    language: Option<String>,
+) -> AppResult<Vec<ParagraphResult>> {
+    let language = normalize_language(language)?;
+    let dictionary = load_dictionary(&app, &language)?;
+    let user_words = user_words(&app)?;
+    let state = app.state::<SpellState>();
+
+    let mut results = Vec::with_capacity(paragraphs.len());
+    for paragraph in paragraphs {
+        let key = (language.clone(), paragraph.text);
+        let cached = state.cache.lock().unwrap().get(&key).cloned();
+        let misspellings = match cached {
+            Some(misspellings) => misspellings,
+            None => {
+                let misspellings = check(&key.1, &user_words, &dictionary);
+                let mut cache = state.cache.lock().unwrap();
+                if cache.len() >= CACHE_CAPACITY {
+                    cache.clear();
+                }
+                cache.insert(key, misspellings.clone());
+                misspellings
+            }
+        };
+        results.push(ParagraphResult {
+            id: paragraph.id,
+            misspellings,
+        });
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn add_word_to_dictionary(app: tauri::AppHandle, word: String) -> AppResult<()> {
+    let word = validate_word(&word)?;
+    update_user_words(&app, |words| words.insert(word))
+}
+
+#[tauri::command]
+pub fn remove_word_from_dictionary(app: tauri::AppHandle, word: String) -> AppResult<()> {
+    let word = validate_word(&word)?;
+    update_user_words(&app, |words| words.remove(&word))
+}
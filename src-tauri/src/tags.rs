@@ -0,0 +1,442 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use tauri::Emitter;
+
+// 内容中的一个行内标签，range 覆盖包括 '#' 在内的字节范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineTag {
+    pub range: Range<usize>,
+    pub name: String,
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+// 扫描 #标签，跳过代码块、行内代码、HTML 标签属性以及 <code>/<pre> 内容；
+// 只有位于行首、空白或 HTML 标签之后的 '#' 才算标签，从而排除 URL 片段
+pub fn find_inline_tags(content: &str) -> Vec<InlineTag> {
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+    let mut code_depth = 0usize;
+    let mut line_start = 0;
+
+    for line in content.split_inclusive('\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_backtick = false;
+        let mut prev: Option<char> = None;
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '`' => in_backtick = !in_backtick,
+                '<' if !in_backtick => {
+                    let rest = &line[i + 1..];
+                    if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+                        let end = rest.find('>').map(|e| i + 1 + e).unwrap_or(line.len() - 1);
+                        let tag = rest[..end - i - 1].to_ascii_lowercase();
+                        let name = tag.split(|c: char| c.is_whitespace()).next().unwrap_or("");
+                        match name {
+                            "code" | "pre" => code_depth += 1,
+                            "/code" | "/pre" => code_depth = code_depth.saturating_sub(1),
+                            _ => {}
+                        }
+                        while chars.peek().is_some_and(|(j, _)| *j <= end) {
+                            chars.next();
+                        }
+                        prev = Some('>');
+                        continue;
+                    }
+                }
+                '#' if !in_backtick && code_depth == 0 => {
+                    let boundary = prev.is_none_or(|p| p.is_whitespace() || p == '>');
+                    let rest = &line[i + 1..];
+                    let len: usize = rest
+                        .chars()
+                        .take_while(|c| is_tag_char(*c))
+                        .map(char::len_utf8)
+                        .sum();
+                    let name = &rest[..len];
+                    if boundary && !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
+                        tags.push(InlineTag {
+                            range: offset + i..offset + i + 1 + len,
+                            name: name.to_string(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+            prev = Some(c);
+        }
+    }
+
+    tags
+}
+
+// 从内容中删除行内标签及其前面多余的一个空格
+pub fn strip_inline_tags(content: &str, tags: &[InlineTag]) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for tag in tags {
+        let mut start = tag.range.start;
+        let followed_by_space = content[tag.range.end..]
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace);
+        if followed_by_space && content[last..start].ends_with(' ') {
+            start -= 1;
+        }
+        result.push_str(&content[last..start]);
+        last = tag.range.end;
+    }
+    result.push_str(&content[last..]);
+
+    result
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteInlineTags {
+    pub note_id: i64,
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InlineTagReport {
+    pub dry_run: bool,
+    pub notes: Vec<NoteInlineTags>,
+    pub tags: Vec<String>,
+}
+
+// 将正文中的 #标签 迁移到标签表，dry_run 时只返回报告
+#[tauri::command]
+pub async fn extract_inline_tags(
+    app: tauri::AppHandle,
+    dry_run: bool,
+    strip: bool,
+) -> AppResult<InlineTagReport> {
+    let notes = db::with_read_conn(&app, |conn| {
+        let mut stmt = conn.prepare("SELECT id, title, content FROM notes ORDER BY id")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    })?;
+
+    let mut changes = Vec::new();
+    let mut report = InlineTagReport {
+        dry_run,
+        notes: Vec::new(),
+        tags: Vec::new(),
+    };
+    let mut all_tags = BTreeSet::new();
+
+    for (id, title, content) in notes {
+        let found = find_inline_tags(&content);
+        if found.is_empty() {
+            continue;
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        for tag in &found {
+            if !names.contains(&tag.name) {
+                names.push(tag.name.clone());
+            }
+        }
+        all_tags.extend(names.iter().cloned());
+
+        let stripped = strip.then(|| strip_inline_tags(&content, &found));
+        changes.push((id, names.clone(), stripped));
+        report.notes.push(NoteInlineTags {
+            note_id: id,
+            title,
+            tags: names,
+        });
+    }
+    report.tags = all_tags.into_iter().collect();
+
+    if dry_run || changes.is_empty() {
+        return Ok(report);
+    }
+
+    db::with_write_conn(&app, |conn| {
+        let tx = conn.transaction()?;
+        for (note_id, names, stripped) in &changes {
+            for name in names {
+                tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [name])?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+                     SELECT ?1, id FROM tags WHERE name = ?2",
+                    params![note_id, name],
+                )?;
+            }
+            if let Some(content) = stripped {
+                tx.execute(
+                    "UPDATE notes SET content = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                    params![content, note_id],
+                )?;
+            }
+        }
+        tx.commit()
+    })?;
+
+    Ok(report)
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagChange {
+    // 标签关系发生变化的笔记
+    pub affected_notes: usize,
+    // 重命名的目标名称已存在，两个标签被合并
+    pub merged: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagUsage {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub notes: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagReport {
+    pub tags: Vec<TagUsage>,
+    // 只有大小写或首尾空白不同的标签，每组按名称排序
+    pub near_duplicates: Vec<Vec<String>>,
+}
+
+enum TagError {
+    NotFound(String),
+    Sql(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for TagError {
+    fn from(e: rusqlite::Error) -> Self {
+        TagError::Sql(e)
+    }
+}
+
+fn validate_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::new("tag.empty_name"));
+    }
+    Ok(name.to_string())
+}
+
+fn tag_id(tx: &Transaction, name: &str) -> rusqlite::Result<Option<i64>> {
+    tx.query_row("SELECT id FROM tags WHERE name = ?1", [name], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+// 把来源标签的笔记关系移到目标标签（重复的关系自动去重）后删除来源标签，返回涉及的笔记
+fn merge_into(tx: &Transaction, sources: &[i64], target: i64) -> rusqlite::Result<BTreeSet<i64>> {
+    let mut notes = BTreeSet::new();
+    for source in sources.iter().filter(|id| **id != target) {
+        let mut stmt = tx.prepare("SELECT note_id FROM note_tags WHERE tag_id = ?1")?;
+        for note in stmt.query_map([source], |row| row.get::<_, i64>(0))? {
+            notes.insert(note?);
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+             SELECT note_id, ?2 FROM note_tags WHERE tag_id = ?1",
+            params![source, target],
+        )?;
+        tx.execute("DELETE FROM note_tags WHERE tag_id = ?1", [source])?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", [source])?;
+    }
+    Ok(notes)
+}
+
+fn touch_notes(tx: &Transaction, notes: &BTreeSet<i64>) -> rusqlite::Result<()> {
+    for note in notes {
+        tx.execute(
+            "UPDATE notes SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            [note],
+        )?;
+    }
+    Ok(())
+}
+
+// 在一个事务中修改标签表，成功后通知前端刷新标签列表和搜索结果
+fn mutate<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&Transaction) -> Result<T, TagError>,
+) -> AppResult<T> {
+    let mut not_found = None;
+    let result = db::with_write_conn(app, |conn| {
+        let tx = conn.transaction()?;
+        match f(&tx) {
+            Ok(value) => {
+                tx.commit()?;
+                Ok(Some(value))
+            }
+            Err(TagError::NotFound(name)) => {
+                not_found = Some(name);
+                Ok(None)
+            }
+            Err(TagError::Sql(e)) => Err(e),
+        }
+    })?;
+
+    match result {
+        Some(value) => {
+            let _ = app.emit("tags-changed", ());
+            Ok(value)
+        }
+        None => Err(AppError::new("tag.not_found").with("name", not_found.unwrap_or_default())),
+    }
+}
+
+// 新名称已存在时合并到该标签；touch_notes 为 true 时更新涉及笔记的 updated_at
+#[tauri::command]
+pub async fn rename_tag(
+    app: tauri::AppHandle,
+    old: String,
+    new: String,
+    touch_notes: Option<bool>,
+) -> AppResult<TagChange> {
+    let new = validate_name(&new)?;
+    let change = mutate(&app, |tx| {
+        let source = tag_id(tx, &old)?.ok_or_else(|| TagError::NotFound(old.clone()))?;
+        match tag_id(tx, &new)? {
+            Some(target) if target != source => {
+                let notes = merge_into(tx, &[source], target)?;
+                if touch_notes.unwrap_or(false) {
+                    self::touch_notes(tx, &notes)?;
+                }
+                Ok(TagChange {
+                    affected_notes: notes.len(),
+                    merged: true,
+                })
+            }
+            _ => {
+                tx.execute(
+                    "UPDATE tags SET name = ?1 WHERE id = ?2",
+                    params![new, source],
+                )?;
+                let notes: BTreeSet<i64> = tx
+                    .prepare("SELECT note_id FROM note_tags WHERE tag_id = ?1")?
+                    .query_map([source], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?;
+                if touch_notes.unwrap_or(false) {
+                    self::touch_notes(tx, &notes)?;
+                }
+                Ok(TagChange {
+                    affected_notes: notes.len(),
+                    merged: false,
+                })
+            }
+        }
+    })?;
+    log::info!("已将标签 {} 重命名为 {}", old, new);
+    Ok(change)
+}
+
+// 把多个标签合并到 target，target 不存在时新建
+#[tauri::command]
+pub async fn merge_tags(
+    app: tauri::AppHandle,
+    sources: Vec<String>,
+    target: String,
+    touch_notes: Option<bool>,
+) -> AppResult<TagChange> {
+    let target = validate_name(&target)?;
+    let change = mutate(&app, |tx| {
+        let mut ids = Vec::with_capacity(sources.len());
+        for name in &sources {
+            ids.push(tag_id(tx, name)?.ok_or_else(|| TagError::NotFound(name.clone()))?);
+        }
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [&target])?;
+        let target_id = tag_id(tx, &target)?.ok_or_else(|| TagError::NotFound(target.clone()))?;
+        let notes = merge_into(tx, &ids, target_id)?;
+        if touch_notes.unwrap_or(false) {
+            self::touch_notes(tx, &notes)?;
+        }
+        Ok(TagChange {
+            affected_notes: notes.len(),
+            merged: true,
+        })
+    })?;
+    log::info!("已将 {} 个标签合并到 {}", sources.len(), target);
+    Ok(change)
+}
+
+// 删除没有任何笔记使用的标签，返回删除的个数
+#[tauri::command]
+pub async fn delete_unused_tags(app: tauri::AppHandle) -> AppResult<usize> {
+    let removed = mutate(&app, |tx| {
+        Ok(tx.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM note_tags)",
+            [],
+        )?)
+    })?;
+    if removed > 0 {
+        log::info!("已删除 {} 个未使用的标签", removed);
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn get_tag_report(app: tauri::AppHandle) -> AppResult<TagReport> {
+    let tags = db::with_read_conn(&app, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, COUNT(nt.note_id) FROM tags t
+               LEFT JOIN note_tags nt ON nt.tag_id = t.id
+              GROUP BY t.id ORDER BY t.name",
+        )?;
+        let tags = stmt
+            .query_map([], |row| {
+                Ok(TagUsage {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    notes: row.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(tags)
+    })?;
+
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for tag in &tags {
+        groups
+            .entry(tag.name.trim().to_lowercase())
+            .or_default()
+            .push(tag.name.clone());
+    }
+    let near_duplicates = groups
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .collect();
+
+    Ok(TagReport {
+        tags,
+        near_duplicates,
+    })
+}
@@ -0,0 +1,308 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::read_only;
+use crate::tags::find_inline_tags;
+use crate::versions;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+// 列表项中的复选框：- [ ]、* [x]、1. [X] 等
+static TASK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*(?:[-*+]|\d+[.)])\s+\[)([ xX])\](?:\s+(.*))?$").unwrap());
+// 截止日期：📅 2024-03-01 或 @due(2024-03-01)
+static DUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\s*(?:📅\s*(\d{4}-\d{2}-\d{2})|@due\(\s*(\d{4}-\d{2}-\d{2})\s*\))").unwrap()
+});
+
+// 任务索引，与链接索引相同：前端直接写数据库，触发器让修改过的笔记失效，读取前重新解析
+const INDEX_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS note_tasks (
+        note_id INTEGER NOT NULL,
+        line INTEGER NOT NULL,
+        done INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        due TEXT,
+        tags TEXT NOT NULL,
+        PRIMARY KEY (note_id, line)
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS note_tasks_indexed (note_id INTEGER PRIMARY KEY);
+    CREATE TRIGGER IF NOT EXISTS note_tasks_on_update AFTER UPDATE OF content ON notes BEGIN
+        DELETE FROM note_tasks_indexed WHERE note_id = NEW.id;
+    END;
+    CREATE TRIGGER IF NOT EXISTS note_tasks_on_delete AFTER DELETE ON notes BEGIN
+        DELETE FROM note_tasks WHERE note_id = OLD.id;
+        DELETE FROM note_tasks_indexed WHERE note_id = OLD.id;
+    END;";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    All,
+    Open,
+    Completed,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+    pub status: TaskStatus,
+    pub note_id: Option<i64>,
+    pub tag: Option<String>,
+    // 只返回截止日期不晚于该日期（YYYY-MM-DD）的任务
+    pub due_before: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Task {
+    pub note_id: i64,
+    pub note_title: String,
+    // 从 1 开始的行号
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+    pub due: Option<String>,
+    pub tags: Vec<String>,
+}
+
+struct ParsedTask {
+    line: usize,
+    // 复选框中 ' ' / 'x' 的字节位置
+    mark: usize,
+    done: bool,
+    text: String,
+    due: Option<String>,
+    tags: Vec<String>,
+}
+
+// 逐行解析任务，跳过代码块
+fn parse_tasks(content: &str) -> Vec<ParsedTask> {
+    let mut tasks = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let Some(caps) = TASK.captures(line) else {
+            continue;
+        };
+
+        let text = caps.get(3).map_or("", |m| m.as_str());
+        let due = DUE
+            .captures(text)
+            .and_then(|due| due.get(1).or_else(|| due.get(2)))
+            .map(|m| m.as_str().to_string())
+            .filter(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+        let mut tags: Vec<String> = find_inline_tags(text)
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        tags.dedup();
+
+        tasks.push(ParsedTask {
+            line: i + 1,
+            mark: start + caps[1].len(),
+            done: &caps[2] != " ",
+            text: DUE.replace_all(text, "").trim().to_string(),
+            due,
+            tags,
+        });
+    }
+
+    tasks
+}
+
+fn index_note(conn: &Connection, note_id: i64, content: &str) -> rusqlite::Result<usize> {
+    let tasks = parse_tasks(content);
+    conn.execute("DELETE FROM note_tasks WHERE note_id = ?1", [note_id])?;
+    for task in &tasks {
+        conn.execute(
+            "INSERT INTO note_tasks (note_id, line, done, text, due, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                note_id,
+                task.line,
+                task.done,
+                task.text,
+                task.due,
+                serde_json::to_string(&task.tags).unwrap_or_default(),
+            ],
+        )?;
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO note_tasks_indexed (note_id) VALUES (?1)",
+        [note_id],
+    )?;
+    Ok(tasks.len())
+}
+
+// 重新解析索引失效的笔记，返回更新的笔记数
+fn refresh_tasks(conn: &mut Connection) -> rusqlite::Result<usize> {
+    conn.execute_batch(INDEX_SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let stale = {
+        let mut stmt = tx.prepare(
+            "SELECT id, content FROM notes
+              WHERE id NOT IN (SELECT note_id FROM note_tasks_indexed)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (id, content) in &stale {
+        index_note(&tx, *id, content)?;
+    }
+    tx.commit()?;
+
+    Ok(stale.len())
+}
+
+fn load_tasks(conn: &Connection) -> rusqlite::Result<Vec<Task>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.note_id, n.title, t.line, t.text, t.done, t.due, t.tags
+           FROM note_tasks t JOIN notes n ON n.id = t.note_id
+          ORDER BY t.note_id, t.line",
+    )?;
+    let tasks = stmt
+        .query_map([], |row| {
+            let tags: String = row.get(6)?;
+            Ok(Task {
+                note_id: row.get(0)?,
+                note_title: row.get(1)?,
+                line: row.get(2)?,
+                text: row.get(3)?,
+                done: row.get(4)?,
+                due: row.get(5)?,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(tasks)
+}
+
+impl TaskFilter {
+    fn matches(&self, task: &Task) -> bool {
+        let status = match self.status {
+            TaskStatus::All => true,
+            TaskStatus::Open => !task.done,
+            TaskStatus::Completed => task.done,
+        };
+        status
+            && self.note_id.is_none_or(|id| id == task.note_id)
+            && self.tag.as_ref().is_none_or(|tag| {
+                let tag = tag.trim_start_matches('#');
+                task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+            })
+            && self.due_before.as_ref().is_none_or(|date| {
+                task.due
+                    .as_ref()
+                    .is_some_and(|due| due.as_str() <= date.as_str())
+            })
+    }
+}
+
+// 汇总所有笔记中的任务
+#[tauri::command]
+pub async fn extract_tasks(
+    app: tauri::AppHandle,
+    filter: Option<TaskFilter>,
+) -> AppResult<Vec<Task>> {
+    // 只读模式下无法更新索引，使用已有的索引
+    if !read_only::is_enabled(&app) {
+        let refreshed = db::with_write_conn(&app, refresh_tasks)?;
+        if refreshed > 0 {
+            log::info!("已更新 {} 条笔记的任务索引", refreshed);
+        }
+    }
+
+    let filter = filter.unwrap_or_default();
+    let mut tasks = db::with_read_conn(&app, load_tasks)?;
+    tasks.retain(|task| filter.matches(task));
+    Ok(tasks)
+}
+
+// 前端保存笔记后调用，立即更新该笔记的任务索引，返回任务数
+#[tauri::command]
+pub async fn update_note_tasks(
+    app: tauri::AppHandle,
+    note_id: i64,
+    content: String,
+) -> AppResult<usize> {
+    db::with_write_conn(&app, |conn| {
+        conn.execute_batch(INDEX_SCHEMA)?;
+        let tx = conn.transaction()?;
+        let count = index_note(&tx, note_id, &content)?;
+        tx.commit()?;
+        Ok(count)
+    })
+}
+
+// 勾选或取消勾选第 line 行的任务，修改前保存一个历史版本，返回修改后的内容
+#[tauri::command]
+pub async fn toggle_task(
+    app: tauri::AppHandle,
+    note_id: i64,
+    line: usize,
+    done: bool,
+) -> AppResult<String> {
+    let keep_versions = versions::enabled(&app);
+    let content = db::with_write_conn(&app, |conn| {
+        conn.execute_batch(INDEX_SCHEMA)?;
+        let tx = conn.transaction()?;
+        let content: Option<String> = tx
+            .query_row(
+                "SELECT content FROM notes WHERE id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        let Some(mut content) = content else {
+            return Ok(None);
+        };
+        let Some(task) = parse_tasks(&content).into_iter().find(|t| t.line == line) else {
+            return Ok(None);
+        };
+        if task.done == done {
+            return Ok(Some(content));
+        }
+
+        if keep_versions {
+            versions::snapshot(&tx, note_id)?;
+        }
+        content.replace_range(task.mark..task.mark + 1, if done { "x" } else { " " });
+        tx.execute(
+            "UPDATE notes SET content = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![content, note_id],
+        )?;
+        index_note(&tx, note_id, &content)?;
+        tx.commit()?;
+        Ok(Some(content))
+    })?;
+
+    content.ok_or_else(|| {
+        AppError::new("task.not_found")
+            .with("id", note_id)
+            .with("line", line)
+    })
+}
@@ -0,0 +1,132 @@
+use crate::error::AppResult;
+use crate::{settings, tray};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl From<tauri::Theme> for Theme {
+    fn from(theme: tauri::Theme) -> Self {
+        match theme {
+            tauri::Theme::Dark => Theme::Dark,
+            _ => Theme::Light,
+        }
+    }
+}
+
+// 最近一次检测到的系统主题，用于判断是否真的发生了变化
+#[derive(Default)]
+pub struct ThemeState {
+    system: Mutex<Option<Theme>>,
+}
+
+// Linux 上窗口的主题事件依赖 GTK 主题名，GNOME 的深色模式开关不一定触发，改为定时读取 gsettings
+#[cfg(target_os = "linux")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[cfg(target_os = "linux")]
+fn gsettings(key: &str) -> Option<String> {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", key])
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .trim_matches('\'')
+            .to_lowercase()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn linux_theme() -> Option<Theme> {
+    match gsettings("color-scheme")?.as_str() {
+        "prefer-dark" => Some(Theme::Dark),
+        "prefer-light" => Some(Theme::Light),
+        _ => gsettings("gtk-theme").map(|name| {
+            if name.ends_with("-dark") {
+                Theme::Dark
+            } else {
+                Theme::Light
+            }
+        }),
+    }
+}
+
+pub fn detect_system_theme(app: &tauri::AppHandle) -> Theme {
+    #[cfg(target_os = "linux")]
+    if let Some(theme) = linux_theme() {
+        return theme;
+    }
+    app.get_webview_window("main")
+        .and_then(|window| window.theme().ok())
+        .map(Theme::from)
+        .unwrap_or(Theme::Light)
+}
+
+pub fn system_theme(app: &tauri::AppHandle) -> Theme {
+    let cached = *app.state::<ThemeState>().system.lock().unwrap();
+    cached.unwrap_or_else(|| detect_system_theme(app))
+}
+
+// 设置中的 theme 为 light/dark 时覆盖系统主题，前端、托盘和导出都以此为准
+pub fn effective_theme(app: &tauri::AppHandle) -> Theme {
+    match settings::get::<String>(app, settings::THEME).as_deref() {
+        Some("light") => Theme::Light,
+        Some("dark") => Theme::Dark,
+        _ => system_theme(app),
+    }
+}
+
+// 主题设置或系统主题变化后更新托盘图标
+pub fn refresh(app: &tauri::AppHandle) -> AppResult<()> {
+    tray::set_theme(app, effective_theme(app))
+}
+
+pub fn system_changed(app: &tauri::AppHandle, theme: Theme) {
+    let state = app.state::<ThemeState>();
+    {
+        let mut system = state.system.lock().unwrap();
+        if *system == Some(theme) {
+            return;
+        }
+        *system = Some(theme);
+    }
+
+    log::info!("系统主题已切换为 {:?}", theme);
+    let _ = app.emit("system-theme-changed", theme);
+    if let Err(e) = refresh(app) {
+        log::warn!("{}", e);
+    }
+}
+
+// 窗口创建后调用：记录当前系统主题，Linux 上启动轮询
+pub fn init(app: &tauri::AppHandle) {
+    let theme = detect_system_theme(app);
+    *app.state::<ThemeState>().system.lock().unwrap() = Some(theme);
+
+    #[cfg(target_os = "linux")]
+    {
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            system_changed(&app, detect_system_theme(&app));
+        });
+    }
+}
+
+#[tauri::command]
+pub fn get_system_theme(app: tauri::AppHandle) -> Theme {
+    system_theme(&app)
+}
+
+#[tauri::command]
+pub fn get_effective_theme(app: tauri::AppHandle) -> Theme {
+    effective_theme(&app)
+}
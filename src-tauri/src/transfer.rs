@@ -0,0 +1,219 @@
+use crate::error::{wrap, AppError, AppResult};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::Manager;
+
+// 超过该时间仍未读取或完成的临时文件会被清理
+const TEMP_TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_READ_LEN: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct TempResult {
+    pub path: String,
+    pub size: u64,
+    pub checksum: String,
+}
+
+struct Upload {
+    path: PathBuf,
+    finished: bool,
+}
+
+#[derive(Default)]
+pub struct UploadState(Mutex<HashMap<String, Upload>>);
+
+impl UploadState {
+    // 未完成或结果未被读取的上传数，数据本身在临时文件中
+    pub fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+fn transfer_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(wrap("app.cache_dir_unavailable"))?
+        .join("transfer");
+    fs::create_dir_all(&dir).map_err(wrap("transfer.create_dir_failed"))?;
+    Ok(dir)
+}
+
+// 清理过期的临时文件
+fn sweep(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_some_and(|age| age > TEMP_TTL);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// 将较大的命令结果写入临时文件，前端再通过 read_temp_result 分段读取
+pub fn write_temp_result(app: &tauri::AppHandle, bytes: &[u8]) -> AppResult<TempResult> {
+    let dir = transfer_dir(app)?;
+    sweep(&dir);
+
+    let path = dir.join(format!("{}.result", uuid::Uuid::new_v4()));
+    fs::write(&path, bytes).map_err(wrap("transfer.write_failed"))?;
+
+    Ok(TempResult {
+        path: path.to_string_lossy().into_owned(),
+        size: bytes.len() as u64,
+        checksum: sha256_hex(bytes),
+    })
+}
+
+#[tauri::command]
+pub fn read_temp_result(
+    app: tauri::AppHandle,
+    path: String,
+    offset: u64,
+    len: u64,
+) -> AppResult<tauri::ipc::Response> {
+    let dir = transfer_dir(&app)?
+        .canonicalize()
+        .map_err(wrap("transfer.dir_unavailable"))?;
+    let path = PathBuf::from(&path)
+        .canonicalize()
+        .map_err(|_| AppError::new("transfer.not_found"))?;
+    if !path.starts_with(&dir) {
+        return Err(AppError::new("transfer.invalid_path"));
+    }
+
+    let mut file = fs::File::open(&path).map_err(wrap("transfer.read_failed"))?;
+    let size = file.metadata().map_err(wrap("transfer.read_failed"))?.len();
+    let len = len.min(MAX_READ_LEN).min(size.saturating_sub(offset));
+
+    let mut buf = vec![0; len as usize];
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_exact(&mut buf))
+        .map_err(wrap("transfer.read_failed"))?;
+
+    // 读到末尾后自动删除
+    if offset + len >= size {
+        drop(file);
+        let _ = fs::remove_file(&path);
+    }
+
+    Ok(tauri::ipc::Response::new(buf))
+}
+
+#[tauri::command]
+pub fn begin_upload(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UploadState>,
+) -> AppResult<String> {
+    let dir = transfer_dir(&app)?;
+    sweep(&dir);
+
+    let handle = uuid::Uuid::new_v4().to_string();
+    let path = dir.join(format!("{}.upload", handle));
+    fs::write(&path, b"").map_err(wrap("transfer.create_failed"))?;
+
+    let mut uploads = state.0.lock().unwrap();
+    uploads.retain(|_, upload| upload.path.exists());
+    uploads.insert(
+        handle.clone(),
+        Upload {
+            path,
+            finished: false,
+        },
+    );
+
+    Ok(handle)
+}
+
+#[tauri::command]
+pub fn append_chunk(
+    state: tauri::State<'_, UploadState>,
+    handle: String,
+    chunk: String,
+) -> AppResult<()> {
+    let uploads = state.0.lock().unwrap();
+    let upload = uploads
+        .get(&handle)
+        .ok_or_else(|| AppError::new("upload.not_found"))?;
+    if upload.finished {
+        return Err(AppError::new("upload.already_finished"));
+    }
+
+    OpenOptions::new()
+        .append(true)
+        .open(&upload.path)
+        .and_then(|mut file| file.write_all(chunk.as_bytes()))
+        .map_err(wrap("transfer.write_failed"))
+}
+
+// 完成上传，可选地校验总大小和 SHA-256
+#[tauri::command]
+pub fn finish_upload(
+    state: tauri::State<'_, UploadState>,
+    handle: String,
+    size: Option<u64>,
+    checksum: Option<String>,
+) -> AppResult<()> {
+    let mut uploads = state.0.lock().unwrap();
+    let upload = uploads
+        .get_mut(&handle)
+        .ok_or_else(|| AppError::new("upload.not_found"))?;
+
+    let bytes = fs::read(&upload.path).map_err(wrap("transfer.read_failed"))?;
+    if size.is_some_and(|size| size != bytes.len() as u64) {
+        return Err(AppError::new("upload.size_mismatch"));
+    }
+    if checksum.is_some_and(|checksum| !checksum.eq_ignore_ascii_case(&sha256_hex(&bytes))) {
+        return Err(AppError::new("upload.checksum_mismatch"));
+    }
+
+    upload.finished = true;
+    Ok(())
+}
+
+// 取出已完成的上传内容，读取后删除临时文件
+pub fn take_upload(app: &tauri::AppHandle, handle: &str) -> AppResult<String> {
+    let state = app.state::<UploadState>();
+    let upload = {
+        let mut uploads = state.0.lock().unwrap();
+        match uploads.get(handle) {
+            Some(upload) if !upload.finished => return Err(AppError::new("upload.not_finished")),
+            Some(_) => uploads.remove(handle).unwrap(),
+            None => return Err(AppError::new("upload.not_found")),
+        }
+    };
+
+    let content = fs::read_to_string(&upload.path).map_err(wrap("transfer.read_failed"))?;
+    let _ = fs::remove_file(&upload.path);
+    Ok(content)
+}
+
+// 命令的大输入既可直接传入，也可通过上传句柄传入
+pub fn resolve_input(
+    app: &tauri::AppHandle,
+    inline: Option<String>,
+    upload_handle: Option<String>,
+) -> AppResult<String> {
+    match (inline, upload_handle) {
+        (_, Some(handle)) => take_upload(app, &handle),
+        (Some(inline), None) => Ok(inline),
+        (None, None) => Err(AppError::new("transfer.missing_input")),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::{wrap, AppResult};
+use crate::i18n::t;
+use crate::theme::Theme;
+use crate::{active_note, profiles, read_only};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{
+    CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder,
+};
+use tauri::Manager;
+
+pub const TRAY_ID: &str = "main";
+// 配置子菜单项的 id 为该前缀加配置名称
+pub const PROFILE_PREFIX: &str = "profile:";
+
+// 托盘菜单中随运行状态变化的部分
+#[derive(Default)]
+pub struct TrayState {
+    pub update_version: Mutex<Option<String>>,
+}
+
+// 显示并聚焦主窗口；从隐藏或最小化状态恢复时通知界面回到之前打开的笔记
+pub fn show_main_window(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let restoring = !window.is_visible().unwrap_or(true) || window.is_minimized().unwrap_or(false);
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.unminimize();
+    if restoring {
+        active_note::restore(app);
+    }
+}
+
+pub fn build_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let show_item = MenuItemBuilder::with_id("show", t("tray.show", &[])).build(app)?;
+    let hide_item = MenuItemBuilder::with_id("hide", t("tray.hide", &[])).build(app)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = MenuItemBuilder::with_id("quit", t("tray.quit", &[])).build(app)?;
+
+    let mut builder = MenuBuilder::new(app);
+
+    let update_version = app
+        .state::<TrayState>()
+        .update_version
+        .lock()
+        .unwrap()
+        .clone();
+    if let Some(version) = update_version {
+        let update_item = MenuItemBuilder::with_id(
+            "update",
+            t("tray.update_available", &[("version", version)]),
+        )
+        .build(app)?;
+        builder = builder
+            .item(&update_item)
+            .item(&PredefinedMenuItem::separator(app)?);
+    }
+
+    // 只有默认配置时不显示配置子菜单
+    let profiles = profiles::list_profiles(app.clone()).unwrap_or_default();
+    if profiles.len() > 1 {
+        let mut submenu = SubmenuBuilder::new(app, t("tray.profiles", &[]));
+        for profile in &profiles {
+            let item = CheckMenuItemBuilder::with_id(
+                format!("{}{}", PROFILE_PREFIX, profile.name),
+                &profile.name,
+            )
+            .checked(profile.active)
+            .build(app)?;
+            submenu = submenu.item(&item);
+        }
+        builder = builder
+            .item(&submenu.build()?)
+            .item(&PredefinedMenuItem::separator(app)?);
+    }
+
+    builder
+        .items(&[&show_item, &hide_item, &separator, &quit_item])
+        .build()
+}
+
+// 托盘提示中的应用名称，只读模式下附加提示
+fn title(app: &tauri::AppHandle) -> String {
+    if read_only::is_enabled(app) {
+        format!("{} {}", t("app.title", &[]), t("tray.read_only", &[]))
+    } else {
+        t("app.title", &[])
+    }
+}
+
+// 状态或界面语言变化后重新生成托盘菜单
+pub fn refresh(app: &tauri::AppHandle) -> AppResult<()> {
+    let menu = build_menu(app).map_err(wrap("tray.refresh_failed"))?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(menu))
+            .map_err(wrap("tray.refresh_failed"))?;
+        tray.set_tooltip(Some(title(app)))
+            .map_err(wrap("tray.refresh_failed"))?;
+    }
+    Ok(())
+}
+
+// 在托盘提示（及支持托盘文字的平台上的标题）中显示状态，None 时恢复默认
+pub fn set_status(app: &tauri::AppHandle, status: Option<&str>) -> AppResult<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = match status {
+            Some(status) => format!("{} - {}", title(app), status),
+            None => title(app),
+        };
+        tray.set_tooltip(Some(tooltip))
+            .map_err(wrap("tray.refresh_failed"))?;
+        tray.set_title(status)
+            .map_err(wrap("tray.refresh_failed"))?;
+    }
+    Ok(())
+}
+
+// 由应用图标生成单色托盘图标：按亮度取轮廓（浅色背景变透明），深色主题用白色，浅色主题用深灰色
+pub fn themed_icon(app: &tauri::AppHandle, theme: Theme) -> Option<Image<'static>> {
+    let icon = app.default_window_icon()?;
+    let shade = match theme {
+        Theme::Dark => 0xf0,
+        Theme::Light => 0x20,
+    };
+    let rgba = icon
+        .rgba()
+        .chunks_exact(4)
+        .flat_map(|p| {
+            let luma = (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000;
+            let alpha = (255 - luma) * p[3] as u32 / 255;
+            [shade, shade, shade, alpha as u8]
+        })
+        .collect();
+    Some(Image::new_owned(rgba, icon.width(), icon.height()))
+}
+
+pub fn set_theme(app: &tauri::AppHandle, theme: Theme) -> AppResult<()> {
+    if let (Some(tray), Some(icon)) = (app.tray_by_id(TRAY_ID), themed_icon(app, theme)) {
+        tray.set_icon(Some(icon))
+            .map_err(wrap("tray.refresh_failed"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,635 @@
+use crate::error::{AppError, AppResult};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::ops::Range;
+use std::process::{Child, Stdio};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+// 句末标点（含中文标点）后断句，允许标点后紧跟引号或括号
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[.!?。！？…]+["'”’)\]」』）]*\s*"#).unwrap());
+
+const MIN_RATE: f32 = 0.5;
+const MAX_RATE: f32 = 2.0;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SpeechOptions {
+    // list_tts_voices 返回的 id，为空时按内容语言选择
+    pub voice: Option<String>,
+    // 1.0 为正常语速
+    pub rate: Option<f32>,
+}
+
+// 朗读进度，start / end 为当前句子在原文中的 UTF-16 偏移，与编辑器的位置一致
+#[derive(Clone, Debug, Serialize)]
+struct SpeechProgress {
+    id: u64,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct SpeechFinished {
+    id: u64,
+    // 被停止或出错时为 false
+    completed: bool,
+}
+
+struct Sentence {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+struct Playback {
+    id: u64,
+    // 每次开始或继续朗读时递增，旧的朗读线程发现不一致后退出
+    run: u64,
+    sentences: Vec<Sentence>,
+    next: usize,
+    paused: bool,
+    voice: Option<String>,
+    rate: f32,
+    child: Option<Child>,
+}
+
+#[derive(Default)]
+struct Inner {
+    playback: Option<Playback>,
+    next_id: u64,
+    next_run: u64,
+    voices: Option<Vec<Voice>>,
+}
+
+#[derive(Default)]
+pub struct TtsState(Mutex<Inner>);
+
+fn not_supported() -> AppError {
+    AppError::new("tts.not_supported")
+}
+
+// 一段纯文本及其在原文中的字节范围；文本与原文逐字相同时可以按偏移精确映射
+struct Segment {
+    text: String,
+    source: Range<usize>,
+}
+
+// 去掉 Markdown 标记，按块返回纯文本片段；代码块不朗读
+fn text_blocks(content: &str) -> Vec<Vec<Segment>> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<Segment> = Vec::new();
+    let mut in_code = false;
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    for (event, range) in Parser::new_ext(content, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Text(text) | Event::Code(text) if !in_code => current.push(Segment {
+                text: text.to_string(),
+                source: range,
+            }),
+            Event::SoftBreak | Event::HardBreak => current.push(Segment {
+                text: " ".to_string(),
+                source: range,
+            }),
+            Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell,
+            ) if !current.is_empty() => blocks.push(std::mem::take(&mut current)),
+            _ => {}
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+// 纯文本中的字节位置换算回原文位置；转义、实体等与原文不一致的片段只能对齐到片段边界
+fn source_offset(segments: &[(usize, &Segment)], pos: usize, end: bool) -> usize {
+    for (start, segment) in segments {
+        let len = segment.text.len();
+        let inside = if end {
+            pos > *start && pos <= start + len
+        } else {
+            pos >= *start && pos < start + len
+        };
+        if !inside {
+            continue;
+        }
+        if len == segment.source.len() {
+            return segment.source.start + pos - start;
+        }
+        return if end {
+            segment.source.end
+        } else {
+            segment.source.start
+        };
+    }
+    segments
+        .last()
+        .map(|(_, segment)| segment.source.end)
+        .unwrap_or(0)
+}
+
+// 按句切分，句子范围换算为原文中的 UTF-16 偏移
+fn sentences(content: &str) -> Vec<Sentence> {
+    let mut ranges = Vec::new();
+    for block in text_blocks(content) {
+        let mut plain = String::new();
+        let mut segments = Vec::new();
+        for segment in &block {
+            segments.push((plain.len(), segment));
+            plain.push_str(&segment.text);
+        }
+
+        let mut last = 0;
+        let mut bounds: Vec<usize> = SENTENCE_END.find_iter(&plain).map(|m| m.end()).collect();
+        bounds.push(plain.len());
+        for bound in bounds {
+            if bound <= last {
+                continue;
+            }
+            let piece = &plain[last..bound];
+            let trimmed = piece.trim();
+            if !trimmed.is_empty() && trimmed.chars().any(char::is_alphanumeric) {
+                let start = last + (piece.len() - piece.trim_start().len());
+                let end = start + trimmed.len();
+                ranges.push((
+                    trimmed.to_string(),
+                    source_offset(&segments, start, false),
+                    source_offset(&segments, end, true),
+                ));
+            }
+            last = bound;
+        }
+    }
+
+    // 句子按原文顺序出现，逐段累计 UTF-16 长度
+    let mut result = Vec::with_capacity(ranges.len());
+    let mut byte = 0;
+    let mut utf16 = 0;
+    for (text, start, end) in ranges {
+        let start = start.max(byte);
+        let end = end.max(start);
+        let start_utf16 = utf16 + content[byte..start].encode_utf16().count();
+        let end_utf16 = start_utf16 + content[start..end].encode_utf16().count();
+        byte = start;
+        utf16 = start_utf16;
+        result.push(Sentence {
+            text,
+            start: start_utf16,
+            end: end_utf16,
+        });
+    }
+    result
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x2E80..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF)
+}
+
+fn mostly_cjk(sentences: &[Sentence]) -> bool {
+    let (mut cjk, mut total) = (0usize, 0usize);
+    for c in sentences
+        .iter()
+        .flat_map(|s| s.text.chars())
+        .filter(|c| c.is_alphabetic())
+    {
+        total += 1;
+        if is_cjk(c) {
+            cjk += 1;
+        }
+    }
+    total > 0 && cjk * 2 > total
+}
+
+fn is_chinese(language: &str) -> bool {
+    let language = language.to_ascii_lowercase();
+    ["zh", "cmn", "yue"]
+        .iter()
+        .any(|prefix| language.starts_with(prefix))
+}
+
+// macOS: say 命令（AVSpeechSynthesizer 同一套语音）
+#[cfg(target_os = "macos")]
+mod backend {
+    use super::Voice;
+    use std::process::{Command, Stdio};
+
+    // 输出形如 "Ting-Ting           zh_CN    # 你好，我叫婷婷。"，名称可能含空格
+    pub fn voices() -> std::io::Result<Vec<Voice>> {
+        let output = Command::new("say").args(["-v", "?"]).output()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .filter_map(|line| {
+                let head = line.split('#').next()?.trim_end();
+                let (name, language) = head.rsplit_once(char::is_whitespace)?;
+                let name = name.trim();
+                (!name.is_empty()).then(|| Voice {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    language: language.replace('_', "-"),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(voice: Option<&str>, rate: f32) -> (Command, bool) {
+        let mut command = Command::new("say");
+        if let Some(voice) = voice {
+            command.args(["-v", voice]);
+        }
+        // 默认语速约为每分钟 175 词
+        command.args([
+            "-r",
+            &((175.0 * rate).round() as u32).to_string(),
+            "-f",
+            "-",
+        ]);
+        command.stdin(Stdio::piped());
+        (command, true)
+    }
+
+    pub fn cancel() {}
+}
+
+// Windows: 通过 PowerShell 调用 System.Speech（SAPI），文本经标准输入以 UTF-8 传入
+#[cfg(target_os = "windows")]
+mod backend {
+    use super::Voice;
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const PRELUDE: &str = "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+        [Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+        Add-Type -AssemblyName System.Speech; \
+        $s = New-Object System.Speech.Synthesis.SpeechSynthesizer;";
+
+    fn powershell(script: &str) -> Command {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(format!("{} {}", PRELUDE, script))
+            .creation_flags(CREATE_NO_WINDOW);
+        command
+    }
+
+    pub fn voices() -> std::io::Result<Vec<Voice>> {
+        let output = powershell(
+            "$s.GetInstalledVoices() | Where-Object { $_.Enabled } | ForEach-Object { \
+             $_.VoiceInfo.Name + \"`t\" + $_.VoiceInfo.Culture.Name }",
+        )
+        .output()?;
+        if !output.status.success() {
+            return Err(std::io::ErrorKind::NotFound.into());
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .filter_map(|line| {
+                let (name, language) = line.trim().split_once('\t')?;
+                Some(Voice {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    language: language.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(voice: Option<&str>, rate: f32) -> (Command, bool) {
+        let mut command = powershell(
+            "if ($env:YUE_TTS_VOICE) { $s.SelectVoice($env:YUE_TTS_VOICE) }; \
+             $s.Rate = [int]$env:YUE_TTS_RATE; $s.Speak([Console]::In.ReadToEnd())",
+        );
+        // SAPI 语速范围 -10..10，0 为正常语速
+        let sapi_rate = ((rate - 1.0) * 10.0).round().clamp(-10.0, 10.0) as i32;
+        command
+            .env("YUE_TTS_VOICE", voice.unwrap_or_default())
+            .env("YUE_TTS_RATE", sapi_rate.to_string())
+            .stdin(Stdio::piped());
+        (command, true)
+    }
+
+    pub fn cancel() {}
+}
+
+// Linux: speech-dispatcher 的 spd-say，-w 等待朗读结束
+#[cfg(target_os = "linux")]
+mod backend {
+    use super::Voice;
+    use std::process::{Command, Stdio};
+
+    // 输出首行为表头 "NAME LANGUAGE VARIANT"
+    pub fn voices() -> std::io::Result<Vec<Voice>> {
+        let output = Command::new("spd-say").arg("-L").output()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let name = parts.next()?;
+                let language = parts.next().unwrap_or_default();
+                Some(Voice {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    language: language.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(voice: Option<&str>, rate: f32) -> (Command, bool) {
+        let mut command = Command::new("spd-say");
+        // spd-say 语速范围 -100..100，0 为正常语速
+        let rate = ((rate - 1.0) * 100.0).round().clamp(-100.0, 100.0) as i32;
+        command.args(["-w", "-r", &rate.to_string()]);
+        if let Some(voice) = voice {
+            command.args(["-y", voice]);
+        }
+        command.stdin(Stdio::null());
+        (command, false)
+    }
+
+    // 结束 spd-say 进程不会打断 speech-dispatcher 中正在朗读的内容
+    pub fn cancel() {
+        let _ = Command::new("spd-say").arg("-C").status();
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod backend {
+    use super::Voice;
+    use std::process::Command;
+
+    pub fn voices() -> std::io::Result<Vec<Voice>> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn command(_voice: Option<&str>, _rate: f32) -> (Command, bool) {
+        unreachable!()
+    }
+
+    pub fn cancel() {}
+}
+
+fn spawn(text: &str, voice: Option<&str>, rate: f32) -> std::io::Result<Child> {
+    let (mut command, stdin) = backend::command(voice, rate);
+    if !stdin {
+        command.arg("--").arg(text);
+    }
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if stdin {
+        if let Some(mut input) = child.stdin.take() {
+            input.write_all(text.as_bytes())?;
+        }
+    }
+    Ok(child)
+}
+
+fn kill(child: Option<Child>) {
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+        backend::cancel();
+    }
+}
+
+// 语音列表在首次使用时读取；命令不存在或读取失败视为不支持朗读
+fn cached_voices(app: &tauri::AppHandle) -> AppResult<Vec<Voice>> {
+    let state = app.state::<TtsState>();
+    if let Some(voices) = state.0.lock().unwrap().voices.clone() {
+        return Ok(voices);
+    }
+    let voices = backend::voices().map_err(|e| {
+        log::warn!("无法读取系统语音列表: {}", e);
+        not_supported()
+    })?;
+    state.0.lock().unwrap().voices = Some(voices.clone());
+    Ok(voices)
+}
+
+fn finish(app: &tauri::AppHandle, id: u64, completed: bool) {
+    let _ = app.emit("tts-finished", SpeechFinished { id, completed });
+}
+
+// 逐句启动朗读进程，run 不一致（已停止、暂停或重新开始）时退出
+fn run(app: tauri::AppHandle, run: u64) {
+    let state = app.state::<TtsState>();
+    loop {
+        let (id, text, voice, rate, start, end) = {
+            let mut inner = state.0.lock().unwrap();
+            let Some(playback) = inner
+                .playback
+                .as_mut()
+                .filter(|p| p.run == run && !p.paused)
+            else {
+                return;
+            };
+            let Some(sentence) = playback.sentences.get(playback.next) else {
+                let id = playback.id;
+                inner.playback = None;
+                drop(inner);
+                finish(&app, id, true);
+                return;
+            };
+            (
+                playback.id,
+                sentence.text.clone(),
+                playback.voice.clone(),
+                playback.rate,
+                sentence.start,
+                sentence.end,
+            )
+        };
+
+        let child = match spawn(&text, voice.as_deref(), rate) {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!("朗读失败: {}", e);
+                let mut inner = state.0.lock().unwrap();
+                if inner.playback.as_ref().is_some_and(|p| p.run == run) {
+                    inner.playback = None;
+                    drop(inner);
+                    finish(&app, id, false);
+                }
+                return;
+            }
+        };
+        {
+            let mut inner = state.0.lock().unwrap();
+            match inner
+                .playback
+                .as_mut()
+                .filter(|p| p.run == run && !p.paused)
+            {
+                Some(playback) => playback.child = Some(child),
+                None => {
+                    drop(inner);
+                    kill(Some(child));
+                    return;
+                }
+            }
+        }
+        let _ = app.emit("tts-progress", SpeechProgress { id, start, end });
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let mut inner = state.0.lock().unwrap();
+            let Some(playback) = inner
+                .playback
+                .as_mut()
+                .filter(|p| p.run == run && !p.paused)
+            else {
+                return;
+            };
+            // 暂停或停止时进程已被结束并取走
+            let Some(child) = playback.child.as_mut() else {
+                return;
+            };
+            if !matches!(child.try_wait(), Ok(None)) {
+                playback.child = None;
+                playback.next += 1;
+                break;
+            }
+        }
+    }
+}
+
+fn start_worker(app: &tauri::AppHandle, run: u64) {
+    let app = app.clone();
+    std::thread::spawn(move || self::run(app, run));
+}
+
+// 停止当前朗读，退出时也会调用
+pub fn stop(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<TtsState>() else {
+        return;
+    };
+    let playback = state.0.lock().unwrap().playback.take();
+    if let Some(mut playback) = playback {
+        kill(playback.child.take());
+        finish(app, playback.id, false);
+    }
+}
+
+#[tauri::command]
+pub fn list_tts_voices(app: tauri::AppHandle) -> AppResult<Vec<Voice>> {
+    cached_voices(&app)
+}
+
+// 开始朗读会先取消正在进行的朗读，返回本次朗读的 id，进度事件中携带该 id
+#[tauri::command]
+pub fn speak_note(
+    app: tauri::AppHandle,
+    content: String,
+    options: Option<SpeechOptions>,
+) -> AppResult<u64> {
+    let options = options.unwrap_or_default();
+    let rate = options.rate.unwrap_or(1.0);
+    if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+        return Err(AppError::new("tts.invalid_rate")
+            .with("min", MIN_RATE)
+            .with("max", MAX_RATE));
+    }
+    let voices = cached_voices(&app)?;
+    let sentences = sentences(&content);
+
+    let voice = match options.voice.filter(|voice| !voice.is_empty()) {
+        Some(voice) => {
+            if !voices.iter().any(|v| v.id == voice) {
+                return Err(AppError::new("tts.voice_not_found").with("voice", voice));
+            }
+            Some(voice)
+        }
+        // 中文内容优先使用中文语音，系统默认语音往往无法朗读中文
+        None if mostly_cjk(&sentences) => voices
+            .iter()
+            .find(|v| is_chinese(&v.language))
+            .map(|v| v.id.clone()),
+        None => None,
+    };
+
+    stop(&app);
+    let state = app.state::<TtsState>();
+    let mut inner = state.0.lock().unwrap();
+    inner.next_id += 1;
+    inner.next_run += 1;
+    let (id, run) = (inner.next_id, inner.next_run);
+    if sentences.is_empty() {
+        drop(inner);
+        finish(&app, id, true);
+        return Ok(id);
+    }
+    inner.playback = Some(Playback {
+        id,
+        run,
+        sentences,
+        next: 0,
+        paused: false,
+        voice,
+        rate,
+        child: None,
+    });
+    drop(inner);
+    start_worker(&app, run);
+    Ok(id)
+}
+
+// 暂停时结束当前句子的朗读，继续时从该句开头重新朗读
+#[tauri::command]
+pub fn pause_speech(app: tauri::AppHandle) -> AppResult<()> {
+    let state = app.state::<TtsState>();
+    let child = {
+        let mut inner = state.0.lock().unwrap();
+        let Some(playback) = inner.playback.as_mut() else {
+            return Ok(());
+        };
+        playback.paused = true;
+        playback.child.take()
+    };
+    kill(child);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_speech(app: tauri::AppHandle) -> AppResult<()> {
+    let state = app.state::<TtsState>();
+    let mut inner = state.0.lock().unwrap();
+    inner.next_run += 1;
+    let run = inner.next_run;
+    let Some(playback) = inner.playback.as_mut().filter(|p| p.paused) else {
+        return Ok(());
+    };
+    playback.paused = false;
+    playback.run = run;
+    drop(inner);
+    start_worker(&app, run);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_speech(app: tauri::AppHandle) -> AppResult<()> {
+    stop(&app);
+    Ok(())
+}
@@ -0,0 +1,349 @@
+use crate::error::{AppError, AppResult};
+use crate::i18n;
+use crate::periodic::{self, Schedule};
+use crate::{backup, db, delta, settings, tray};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DownloadProgress {
+    method: &'static str,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+// update-downloaded 事件：method 为 patch 时表示使用了增量补丁
+#[derive(Clone, Debug, Serialize)]
+struct UpdateDownloaded {
+    version: String,
+    method: &'static str,
+    patches: usize,
+    fallback_reason: Option<AppError>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AutoUpdateStatus {
+    // 未配置签名公钥时为 false，前端据此隐藏更新相关的界面
+    pub available: bool,
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub last_check: Option<DateTime<Utc>>,
+    pub next_check: Option<DateTime<Utc>>,
+}
+
+impl AutoUpdateStatus {
+    fn schedule(&self) -> Schedule {
+        Schedule {
+            enabled: self.enabled,
+            interval_hours: self.interval_hours,
+            last_run: self.last_check,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UpdaterState {
+    pending: Mutex<Option<Update>>,
+    downloaded: Mutex<Option<Vec<u8>>>,
+    auto_check: Mutex<AutoUpdateStatus>,
+}
+
+// 配置中的签名公钥；发布前还没有生成密钥时为空，此时无法校验更新包，更新功能整体关闭
+pub fn pubkey(app: &tauri::AppHandle) -> Option<String> {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|c| c["pubkey"].as_str())
+        .map(str::trim)
+        .filter(|pubkey| !pubkey.is_empty())
+        .map(str::to_string)
+}
+
+fn ensure_configured(app: &tauri::AppHandle) -> AppResult<()> {
+    match pubkey(app) {
+        Some(_) => Ok(()),
+        None => Err(AppError::new("update.not_configured")),
+    }
+}
+
+// 签名校验失败单独报告，其余错误统一附带上下文
+fn update_error(code: &'static str, e: tauri_plugin_updater::Error) -> AppError {
+    use tauri_plugin_updater::Error;
+
+    match e {
+        Error::Minisign(_) | Error::Base64(_) | Error::SignatureUtf8(_) => {
+            AppError::new("update.signature_invalid").with("error", e)
+        }
+        e => AppError::new(code).with("error", e),
+    }
+}
+
+async fn check(app: &tauri::AppHandle) -> AppResult<Option<UpdateInfo>> {
+    ensure_configured(app)?;
+    let update = app
+        .updater()
+        .map_err(|e| update_error("update.init_failed", e))?
+        .check()
+        .await
+        .map_err(|e| update_error("update.check_failed", e))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+    });
+
+    let state = app.state::<UpdaterState>();
+    *state.pending.lock().unwrap() = update;
+    *state.downloaded.lock().unwrap() = None;
+
+    let tray_state = app.state::<tray::TrayState>();
+    *tray_state.update_version.lock().unwrap() = info.as_ref().map(|info| info.version.clone());
+    tray::refresh(app)?;
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: tauri::AppHandle) -> AppResult<Option<UpdateInfo>> {
+    check(&app).await
+}
+
+#[tauri::command]
+pub async fn download_update(app: tauri::AppHandle) -> AppResult<()> {
+    let update = app
+        .state::<UpdaterState>()
+        .pending
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::new("update.none_available"))?;
+
+    // 优先使用增量补丁，补丁链不存在或校验失败时回退到完整下载
+    let mut fallback_reason = None;
+    let patched = delta::try_download(&app, &update, |applied, total| {
+        let _ = app.emit(
+            "update-download-progress",
+            DownloadProgress {
+                method: "patch",
+                downloaded: applied as u64,
+                total: Some(total as u64),
+            },
+        );
+    })
+    .await
+    .unwrap_or_else(|e| {
+        log::warn!("增量更新失败，回退到完整下载: {}", e);
+        fallback_reason = Some(e);
+        None
+    });
+
+    let (bytes, event) = match patched {
+        Some((bytes, patches)) => (
+            bytes,
+            UpdateDownloaded {
+                version: update.version.clone(),
+                method: "patch",
+                patches,
+                fallback_reason,
+            },
+        ),
+        None => {
+            let mut downloaded = 0u64;
+            let bytes = update
+                .download(
+                    |chunk_length, total| {
+                        downloaded += chunk_length as u64;
+                        let _ = app.emit(
+                            "update-download-progress",
+                            DownloadProgress {
+                                method: "full",
+                                downloaded,
+                                total,
+                            },
+                        );
+                    },
+                    || {},
+                )
+                .await
+                .map_err(|e| update_error("update.download_failed", e))?;
+            (
+                bytes,
+                UpdateDownloaded {
+                    version: update.version.clone(),
+                    method: "full",
+                    patches: 0,
+                    fallback_reason,
+                },
+            )
+        }
+    };
+
+    *app.state::<UpdaterState>().downloaded.lock().unwrap() = Some(bytes);
+    let _ = app.emit("update-downloaded", event);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn install_update_and_restart(app: tauri::AppHandle) -> AppResult<()> {
+    let state = app.state::<UpdaterState>();
+    let update = state
+        .pending
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::new("update.none_available"))?;
+    let bytes = state
+        .downloaded
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| AppError::new("update.not_downloaded"))?;
+
+    // 安装前合并 WAL 并备份数据库，防止安装中断损坏 notes.db
+    if db::db_path(&app)?.exists() {
+        backup::snapshot_current(&app)?;
+    }
+    app.state::<db::DbPool>().close();
+
+    // 缓存安装包，供下一次增量更新使用
+    if let Err(e) = delta::cache_artifact(&app, &update.version, &bytes) {
+        log::warn!("{}", e);
+    }
+
+    update
+        .install(bytes)
+        .map_err(|e| update_error("update.install_failed", e))?;
+
+    app.restart();
+}
+
+#[tauri::command]
+pub fn set_auto_update_check(
+    app: tauri::AppHandle,
+    enabled: bool,
+    interval_hours: u32,
+) -> AppResult<AutoUpdateStatus> {
+    if enabled {
+        ensure_configured(&app)?;
+        if interval_hours == 0 {
+            return Err(AppError::new("update.invalid_interval"));
+        }
+    }
+    let state = app.state::<UpdaterState>();
+    let last_check = state.auto_check.lock().unwrap().last_check;
+    let schedule = Schedule {
+        enabled,
+        interval_hours,
+        last_run: last_check,
+    };
+    settings::set(&app, settings::AUTO_UPDATE_CHECK, &schedule)?;
+
+    let mut status = state.auto_check.lock().unwrap();
+    status.enabled = enabled;
+    status.interval_hours = interval_hours;
+    status.next_check = schedule.next_run();
+
+    Ok(status.clone())
+}
+
+// 从设置备份恢复时经过 set_auto_update_check，上次检查时间保持本机的记录
+pub fn restore_schedule(app: &tauri::AppHandle, value: Value) -> AppResult<()> {
+    let schedule = serde_json::from_value::<Option<Schedule>>(value)
+        .map_err(|_| {
+            AppError::new("settings.invalid_value").with("key", settings::AUTO_UPDATE_CHECK)
+        })?
+        .unwrap_or_default();
+    set_auto_update_check(app.clone(), schedule.enabled, schedule.interval_hours).map(drop)
+}
+
+#[tauri::command]
+pub fn get_auto_update_status(state: tauri::State<'_, UpdaterState>) -> AutoUpdateStatus {
+    state.auto_check.lock().unwrap().clone()
+}
+
+struct AutoUpdateCheck;
+
+impl periodic::Task for AutoUpdateCheck {
+    const NAME: &'static str = "auto_update_check";
+    const SETTING: &'static str = settings::AUTO_UPDATE_CHECK;
+    type Output = AppResult<Option<UpdateInfo>>;
+    type Saved = Schedule;
+
+    fn schedule(app: &tauri::AppHandle) -> Schedule {
+        app.state::<UpdaterState>()
+            .auto_check
+            .lock()
+            .unwrap()
+            .schedule()
+    }
+
+    fn run(app: &tauri::AppHandle) -> Option<AppResult<Option<UpdateInfo>>> {
+        Some(tauri::async_runtime::block_on(check(app)))
+    }
+
+    fn finish(
+        app: &tauri::AppHandle,
+        output: thread::Result<AppResult<Option<UpdateInfo>>>,
+    ) -> Schedule {
+        match output {
+            Ok(Ok(Some(info))) => {
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title(i18n::t("notification.update_title", &[]))
+                    .body(i18n::t(
+                        "notification.update_body",
+                        &[("version", info.version.clone())],
+                    ))
+                    .show();
+            }
+            Ok(Ok(None)) | Err(_) => {}
+            Ok(Err(e)) => log::error!("自动检查更新失败: {}", e),
+        }
+
+        let state = app.state::<UpdaterState>();
+        let mut status = state.auto_check.lock().unwrap();
+        status.last_check = Some(Utc::now());
+        let schedule = status.schedule();
+        status.next_check = schedule.next_run();
+        schedule
+    }
+}
+
+// 按保存的配置启动后台定时检查更新，发现新版本时发送系统通知
+pub fn spawn(app: tauri::AppHandle) {
+    let schedule = settings::get::<Schedule>(&app, settings::AUTO_UPDATE_CHECK).unwrap_or_default();
+    let available = pubkey(&app).is_some();
+    {
+        let state = app.state::<UpdaterState>();
+        let mut status = state.auto_check.lock().unwrap();
+        status.available = available;
+        status.enabled = schedule.enabled && available;
+        status.interval_hours = schedule.interval_hours;
+        status.last_check = schedule.last_run;
+        status.next_check = schedule.next_run().filter(|_| available);
+    }
+    if !available {
+        log::info!("未配置更新签名公钥，不启动自动检查更新");
+        return;
+    }
+    periodic::spawn::<AutoUpdateCheck>(app);
+}
@@ -0,0 +1,178 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::i18n::t;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Integer,
+    String,
+    NullableString,
+    // SQLite 返回的 0/1 也视为布尔值
+    Bool,
+    NullableInteger,
+    Tags,
+}
+
+impl Kind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Kind::Integer => value.is_i64(),
+            Kind::String => value.is_string(),
+            Kind::NullableString => value.is_null() || value.is_string(),
+            Kind::Bool => value.is_boolean() || matches!(value.as_i64(), Some(0 | 1)),
+            Kind::NullableInteger => value.is_null() || value.is_i64(),
+            Kind::Tags => value.as_array().is_some_and(|tags| {
+                tags.iter()
+                    .all(|tag| tag.is_string() || tag["name"].is_string())
+            }),
+        }
+    }
+
+    fn expected(self) -> &'static str {
+        match self {
+            Kind::Integer => "integer",
+            Kind::String => "string",
+            Kind::NullableString => "string | null",
+            Kind::Bool => "boolean",
+            Kind::NullableInteger => "integer | null",
+            Kind::Tags => "string[]",
+        }
+    }
+}
+
+// (字段, 类型, 是否必需)，与前端 Note 类型一致
+const FIELDS: &[(&str, Kind, bool)] = &[
+    ("id", Kind::Integer, true),
+    ("title", Kind::String, true),
+    ("content", Kind::String, true),
+    ("editor_type", Kind::String, false),
+    ("created_at", Kind::NullableString, false),
+    ("updated_at", Kind::NullableString, false),
+    ("category_id", Kind::NullableInteger, false),
+    ("is_pinned", Kind::Bool, false),
+    ("is_favorited", Kind::Bool, false),
+    ("tags", Kind::Tags, false),
+];
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub index: usize,
+    // 为 None 时表示整条记录不是对象
+    pub field: Option<&'static str>,
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl ValidationIssue {
+    pub fn message(&self) -> String {
+        let mut params = vec![
+            ("index", self.index.to_string()),
+            ("expected", self.expected.to_string()),
+            ("found", self.found.to_string()),
+        ];
+        match self.field {
+            Some(field) => {
+                params.push(("field", field.to_string()));
+                t("validation.field_type", &params)
+            }
+            None => t("validation.not_object", &params),
+        }
+    }
+}
+
+impl Serialize for ValidationIssue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ValidationIssue", 5)?;
+        state.serialize_field("index", &self.index)?;
+        state.serialize_field("field", &self.field)?;
+        state.serialize_field("expected", self.expected)?;
+        state.serialize_field("found", self.found)?;
+        state.serialize_field("message", &self.message())?;
+        state.end()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub count: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+pub fn check_notes(notes: &[Value]) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    for (index, note) in notes.iter().enumerate() {
+        let Some(object) = note.as_object() else {
+            issues.push(ValidationIssue {
+                index,
+                field: None,
+                expected: "object",
+                found: type_name(note),
+            });
+            continue;
+        };
+
+        for &(field, kind, required) in FIELDS {
+            let found = match object.get(field) {
+                Some(value) if kind.matches(value) => continue,
+                Some(value) => type_name(value),
+                None if required => "missing",
+                None => continue,
+            };
+            issues.push(ValidationIssue {
+                index,
+                field: Some(field),
+                expected: kind.expected(),
+                found,
+            });
+        }
+    }
+
+    issues
+}
+
+fn parse_array(notes_json: &str) -> AppResult<Vec<Value>> {
+    match serde_json::from_str(notes_json).map_err(wrap("export.parse_failed"))? {
+        Value::Array(notes) => Ok(notes),
+        other => Err(AppError::new("validation.not_array").with("found", type_name(&other))),
+    }
+}
+
+// 批量操作共用的入口：解析并校验，有问题时返回第一处错误及总数
+pub fn parse_valid_notes(notes_json: &str) -> AppResult<Vec<Value>> {
+    let notes = parse_array(notes_json)?;
+    let issues = check_notes(&notes);
+    match issues.first() {
+        None => Ok(notes),
+        Some(first) => Err(AppError::new("validation.invalid_notes")
+            .with("issue", first.message())
+            .with("count", issues.len())),
+    }
+}
+
+#[tauri::command]
+pub fn validate_notes_json(notes_json: String) -> AppResult<ValidationReport> {
+    let notes = parse_array(&notes_json)?;
+    let issues = check_notes(&notes);
+
+    Ok(ValidationReport {
+        valid: issues.is_empty(),
+        count: notes.len(),
+        issues,
+    })
+}
@@ -0,0 +1,76 @@
+use crate::db;
+use crate::error::AppResult;
+use crate::settings;
+use rusqlite::Connection;
+use serde::Serialize;
+
+// 批量修改笔记内容前保存的历史版本
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS note_versions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        note_id INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS idx_note_versions_note ON note_versions (note_id, id);";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteVersion {
+    pub id: i64,
+    pub note_id: i64,
+    pub title: String,
+    pub content: String,
+    pub created_at: Option<String>,
+}
+
+// 关闭历史版本后批量修改不再保存旧版本
+pub fn enabled(app: &tauri::AppHandle) -> bool {
+    settings::get::<bool>(app, settings::NOTE_VERSIONS).unwrap_or(true)
+}
+
+// 保存笔记当前的标题和内容，应在修改前于同一事务中调用
+pub fn snapshot(conn: &Connection, note_id: i64) -> rusqlite::Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    conn.execute(
+        "INSERT INTO note_versions (note_id, title, content)
+         SELECT id, title, content FROM notes WHERE id = ?1",
+        [note_id],
+    )?;
+    Ok(())
+}
+
+// 按时间从新到旧列出笔记的历史版本
+#[tauri::command]
+pub async fn list_note_versions(
+    app: tauri::AppHandle,
+    note_id: i64,
+) -> AppResult<Vec<NoteVersion>> {
+    db::with_read_conn(&app, |conn| {
+        // 读连接上不建表（只读模式下会失败），还没有保存过版本时表不存在
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'note_versions')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(Vec::new());
+        }
+        let mut stmt = conn.prepare(
+            "SELECT id, note_id, title, content, created_at FROM note_versions
+              WHERE note_id = ?1 ORDER BY id DESC",
+        )?;
+        let versions = stmt
+            .query_map([note_id], |row| {
+                Ok(NoteVersion {
+                    id: row.get(0)?,
+                    note_id: row.get(1)?,
+                    title: row.get(2)?,
+                    content: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(versions)
+    })
+}
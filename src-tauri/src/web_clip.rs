@@ -0,0 +1,527 @@
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::link_preview::{self, Download};
+use crate::portable;
+use crate::read_only;
+use crate::screenshot::ATTACHMENTS_DIR;
+use chrono::Utc;
+use regex::Regex;
+use reqwest::Url;
+use rusqlite::params;
+use scraper::node::Node;
+use scraper::{ElementRef, Html, Selector};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+const PAGE_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_PAGE_SIZE: usize = 5 * 1024 * 1024;
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024;
+// 单个页面最多下载的图片数，其余保留远程链接
+const MAX_IMAGES: usize = 50;
+const CLIPPED_TAG: &str = "clipped";
+
+const HTML_ACCEPT: &str = "text/html,application/xhtml+xml";
+
+// 任何模式下都不输出的元素
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "iframe", "object", "embed", "form", "button", "input",
+    "select", "textarea", "template", "svg", "canvas", "dialog",
+];
+// 正文模式下额外跳过的页面框架元素
+const CHROME: &[&str] = &["nav", "header", "footer", "aside", "menu"];
+
+// class 或 id 命中时视为评论、侧栏、广告等非正文内容
+static UNLIKELY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)comment|sidebar|footer|masthead|\bnav|menu|breadcrumb|share|social|\bads?\b|advert|sponsor|promo|related|recommend|subscribe|newsletter|cookie|banner|popup|modal",
+    )
+    .unwrap()
+});
+static BLANK_LINES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n{3,}").unwrap());
+
+static ARTICLE: LazyLock<Selector> = LazyLock::new(|| Selector::parse("article").unwrap());
+static CANDIDATE: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("main, section, div, td").unwrap());
+static LINK: LazyLock<Selector> = LazyLock::new(|| Selector::parse("a").unwrap());
+static BODY: LazyLock<Selector> = LazyLock::new(|| Selector::parse("body").unwrap());
+static ROW: LazyLock<Selector> = LazyLock::new(|| Selector::parse("tr").unwrap());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipMode {
+    // 提取正文
+    Article,
+    // 清理后的整个 <body>
+    Full,
+    // 只保存标题、描述和链接
+    Bookmark,
+}
+
+impl FromStr for ClipMode {
+    type Err = AppError;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "article" => Ok(ClipMode::Article),
+            "full" => Ok(ClipMode::Full),
+            "bookmark" => Ok(ClipMode::Bookmark),
+            other => Err(AppError::new("clip.invalid_mode").with("mode", other)),
+        }
+    }
+}
+
+fn text_len(element: ElementRef) -> usize {
+    element.text().map(|text| text.trim().chars().count()).sum()
+}
+
+fn is_unlikely(element: ElementRef) -> bool {
+    let value = element.value();
+    [value.attr("class"), value.attr("id")]
+        .into_iter()
+        .flatten()
+        .any(|name| UNLIKELY.is_match(name))
+}
+
+// 简化的 Readability 打分：直接子段落的文字越多、逗号越多得分越高，链接文字占比高的（导航、列表页）降分
+fn score(element: ElementRef) -> f64 {
+    let mut score = 0.0;
+    for child in element.child_elements() {
+        if !matches!(child.value().name(), "p" | "pre" | "blockquote") {
+            continue;
+        }
+        let text: String = child.text().collect();
+        let len = text.trim().chars().count();
+        if len < 25 {
+            continue;
+        }
+        let commas = text
+            .chars()
+            .filter(|c| matches!(c, ',' | '，' | '、'))
+            .count();
+        score += 1.0 + commas as f64 + (len / 100).min(3) as f64;
+    }
+
+    let total = text_len(element).max(1);
+    let links: usize = element.select(&LINK).map(text_len).sum();
+    score *= 1.0 - links as f64 / total as f64;
+    if is_unlikely(element) {
+        score *= 0.2;
+    }
+    score
+}
+
+fn article_root(document: &Html) -> Option<ElementRef<'_>> {
+    // 页面自带 <article> 且有足够文字时直接使用
+    let article = document
+        .select(&ARTICLE)
+        .max_by_key(|article| text_len(*article))
+        .filter(|article| text_len(*article) >= 200);
+    article.or_else(|| {
+        document
+            .select(&CANDIDATE)
+            .map(|candidate| (score(candidate), candidate))
+            .filter(|(score, _)| *score > 0.0)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, candidate)| candidate)
+    })
+}
+
+// 括号会截断 Markdown 链接，需要转义
+fn markdown_url(url: &Url) -> String {
+    url.as_str().replace('(', "%28").replace(')', "%29")
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// 把列表项、引用等的内容缩进到前缀之后，续行对齐
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.trim()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.trim().is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Converter<'a> {
+    base: &'a Url,
+    article: bool,
+    out: String,
+    images: Vec<Url>,
+}
+
+impl Converter<'_> {
+    fn push_block(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push_str(if self.out.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        self.out.push_str(text);
+        self.out.push_str("\n\n");
+    }
+
+    // 单独渲染子节点，返回结果而不写入当前输出
+    fn children(&mut self, element: ElementRef) -> String {
+        let saved = std::mem::take(&mut self.out);
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => {
+                    let starts = text.starts_with(char::is_whitespace);
+                    let ends = text.ends_with(char::is_whitespace);
+                    let text = collapse(text);
+                    if (starts || text.is_empty())
+                        && !self.out.is_empty()
+                        && !self.out.ends_with(char::is_whitespace)
+                    {
+                        self.out.push(' ');
+                    }
+                    self.out.push_str(&text);
+                    if ends && !text.is_empty() {
+                        self.out.push(' ');
+                    }
+                }
+                Node::Element(_) => {
+                    if let Some(element) = ElementRef::wrap(child) {
+                        self.element(element);
+                    }
+                }
+                _ => {}
+            }
+        }
+        std::mem::replace(&mut self.out, saved)
+    }
+
+    fn resolve(&self, url: &str) -> Option<Url> {
+        self.base
+            .join(url.trim())
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https" | "mailto" | "data"))
+    }
+
+    fn wrap_inline(&mut self, element: ElementRef, marker: &str) {
+        let text = self.children(element);
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            self.out
+                .push_str(&format!("{}{}{}", marker, trimmed, marker));
+        }
+    }
+
+    fn list(&mut self, element: ElementRef, ordered: bool) {
+        let mut items = Vec::new();
+        for (i, item) in element
+            .child_elements()
+            .filter(|child| child.value().name() == "li")
+            .enumerate()
+        {
+            let marker = if ordered {
+                format!("{}. ", i + 1)
+            } else {
+                "- ".to_string()
+            };
+            let content = self.children(item);
+            let content = BLANK_LINES.replace_all(content.trim(), "\n\n");
+            items.push(prefix_lines(&content, &marker, &" ".repeat(marker.len())));
+        }
+        self.push_block(&items.join("\n"));
+    }
+
+    fn table(&mut self, element: ElementRef) {
+        let rows: Vec<Vec<String>> = element
+            .select(&ROW)
+            .map(|row| {
+                row.child_elements()
+                    .filter(|cell| matches!(cell.value().name(), "th" | "td"))
+                    .map(|cell| collapse(&self.children(cell)).replace('|', "\\|"))
+                    .collect()
+            })
+            .filter(|cells: &Vec<String>| !cells.is_empty())
+            .collect();
+        let Some(columns) = rows.iter().map(Vec::len).max() else {
+            return;
+        };
+
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if i == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        self.push_block(&lines.join("\n"));
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED.contains(&name)
+            || element.value().attr("hidden").is_some()
+            || (self.article && (CHROME.contains(&name) || is_unlikely(element)))
+        {
+            return;
+        }
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let text = collapse(&self.children(element));
+                if !text.is_empty() {
+                    self.push_block(&format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "pre" => {
+                let code: String = element.text().collect();
+                self.push_block(&format!("```\n{}\n```", code.trim_matches('\n')));
+            }
+            "blockquote" => {
+                let text = self.children(element);
+                self.push_block(&prefix_lines(&text, "> ", "> "));
+            }
+            "ul" | "menu" => self.list(element, false),
+            "ol" => self.list(element, true),
+            "table" => self.table(element),
+            "hr" => self.push_block("---"),
+            "br" => self.out.push_str("  \n"),
+            "strong" | "b" => self.wrap_inline(element, "**"),
+            "em" | "i" => self.wrap_inline(element, "*"),
+            "del" | "s" | "strike" => self.wrap_inline(element, "~~"),
+            "code" | "kbd" | "samp" => {
+                let code: String = element.text().collect();
+                if !code.trim().is_empty() {
+                    self.out.push_str(&format!("`{}`", code.trim()));
+                }
+            }
+            "a" => {
+                let text = collapse(&self.children(element));
+                let href = element
+                    .value()
+                    .attr("href")
+                    .filter(|href| !href.starts_with('#'))
+                    .and_then(|href| self.resolve(href));
+                match href {
+                    Some(href) if !text.is_empty() => {
+                        self.out
+                            .push_str(&format!("[{}]({})", text, markdown_url(&href)));
+                    }
+                    _ => self.out.push_str(&text),
+                }
+            }
+            "img" => {
+                // 懒加载的图片地址常放在 data-src 中
+                let src = ["data-src", "src"]
+                    .into_iter()
+                    .filter_map(|attr| element.value().attr(attr))
+                    .find_map(|src| self.resolve(src));
+                if let Some(src) = src {
+                    let alt = collapse(element.value().attr("alt").unwrap_or(""));
+                    self.out
+                        .push_str(&format!("![{}]({})", alt, markdown_url(&src)));
+                    if matches!(src.scheme(), "http" | "https") && !self.images.contains(&src) {
+                        self.images.push(src);
+                    }
+                }
+            }
+            "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "nav"
+            | "aside" | "figure" | "figcaption" | "dl" | "dt" | "dd" | "address" | "details"
+            | "summary" | "li" | "body" | "html" => {
+                let text = self.children(element);
+                self.push_block(&text);
+            }
+            _ => {
+                let text = self.children(element);
+                self.out.push_str(&text);
+            }
+        }
+    }
+}
+
+// 解析和转换必须在同步代码中完成：Html 不能跨 await 持有
+pub fn convert(html: &str, base: &Url, mode: ClipMode) -> (String, Vec<Url>) {
+    let document = Html::parse_document(html);
+    let root = match mode {
+        ClipMode::Article => article_root(&document),
+        _ => None,
+    }
+    .or_else(|| document.select(&BODY).next())
+    .unwrap_or_else(|| document.root_element());
+
+    let mut converter = Converter {
+        base,
+        article: mode == ClipMode::Article,
+        out: String::new(),
+        images: Vec::new(),
+    };
+    let markdown = converter.children(root);
+    let markdown = BLANK_LINES
+        .replace_all(markdown.trim(), "\n\n")
+        .into_owned();
+    converter.images.truncate(MAX_IMAGES);
+    (markdown, converter.images)
+}
+
+fn image_extension(image: &Download) -> String {
+    match image.mime() {
+        "image/png" => "png".to_string(),
+        "image/jpeg" => "jpg".to_string(),
+        "image/gif" => "gif".to_string(),
+        "image/webp" => "webp".to_string(),
+        "image/svg+xml" => "svg".to_string(),
+        "image/avif" => "avif".to_string(),
+        _ => image
+            .url
+            .path()
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or_else(|| "img".to_string()),
+    }
+}
+
+// 把图片下载到笔记的附件目录并改写链接；下载失败的图片保留远程地址
+pub async fn download_images(
+    app: &tauri::AppHandle,
+    note_id: i64,
+    markdown: &str,
+    images: &[Url],
+) -> AppResult<String> {
+    let dir = portable::resolve_data_dir(app)?
+        .join(ATTACHMENTS_DIR)
+        .join(note_id.to_string());
+    let mut markdown = markdown.to_string();
+
+    for (i, url) in images.iter().enumerate() {
+        let image =
+            match link_preview::download(url, "image/*", IMAGE_TIMEOUT, MAX_IMAGE_SIZE).await {
+                Ok(image) if image.mime().starts_with("image/") && !image.truncated => image,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::warn!("下载剪藏图片 {} 失败: {}", url, e);
+                    continue;
+                }
+            };
+
+        let name = format!("image-{}.{}", i + 1, image_extension(&image));
+        let saved = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(dir.join(&name), &image.body));
+        if let Err(e) = saved {
+            log::warn!("保存剪藏图片 {} 失败: {}", url, e);
+            continue;
+        }
+        markdown = markdown.replace(
+            &format!("]({})", markdown_url(url)),
+            &format!("]({}/{}/{})", ATTACHMENTS_DIR, note_id, name),
+        );
+    }
+    Ok(markdown)
+}
+
+fn frontmatter(source: &Url) -> String {
+    // JSON 字符串同时也是合法的 YAML 双引号字符串
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    format!(
+        "---\nsource: {}\nclipped_at: {}\n---\n\n",
+        quote(source.as_str()),
+        quote(&Utc::now().to_rfc3339())
+    )
+}
+
+fn bookmark(url: &Url, title: &str, description: Option<&str>) -> String {
+    let mut markdown = format!("[{}]({})", title, markdown_url(url));
+    if let Some(description) = description {
+        markdown.push_str(&format!("\n\n> {}", description));
+    }
+    markdown
+}
+
+// 剪藏网页为新笔记（Markdown，来源地址写入 frontmatter，并打上 clipped 标签），返回笔记 id
+#[tauri::command]
+pub async fn clip_url_to_note(app: tauri::AppHandle, url: String, mode: String) -> AppResult<i64> {
+    read_only::ensure_writable(&app)?;
+    let mode = mode.parse::<ClipMode>()?;
+    let url = link_preview::parse_url(&url)?;
+    let page = link_preview::download(&url, HTML_ACCEPT, PAGE_TIMEOUT, MAX_PAGE_SIZE).await?;
+
+    let (title, body, images) = if page.is_html() {
+        let html = link_preview::decode(&page.body, &page.content_type);
+        let metadata = link_preview::extract(&html, &page.url);
+        let title = metadata
+            .og_title
+            .or(metadata.title)
+            .unwrap_or_else(|| page.url.to_string());
+        match mode {
+            ClipMode::Bookmark => {
+                let body = bookmark(&page.url, &title, metadata.og_description.as_deref());
+                (title, body, Vec::new())
+            }
+            // 截断的页面结构不完整，正文提取结果不可靠
+            _ if page.truncated => {
+                return Err(
+                    AppError::new("clip.page_too_large").with("max", MAX_PAGE_SIZE / 1024 / 1024)
+                );
+            }
+            _ => {
+                let (body, images) = convert(&html, &page.url, mode);
+                (title, body, images)
+            }
+        }
+    } else if mode == ClipMode::Bookmark {
+        // 非网页（如 PDF）只能保存为书签
+        let title = page.url.to_string();
+        let body = bookmark(&page.url, &title, None);
+        (title, body, Vec::new())
+    } else {
+        return Err(AppError::new("link.not_html").with("type", page.mime()));
+    };
+
+    let content = format!("{}{}", frontmatter(&page.url), body);
+    let note_id = db::with_write_conn(&app, |conn| {
+        let tx = conn.transaction()?;
+        let note = db::insert_note(&tx, &title, &content)?;
+        let id = note["id"].as_i64().unwrap_or_default();
+        tx.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            [CLIPPED_TAG],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![id, CLIPPED_TAG],
+        )?;
+        tx.commit()?;
+        Ok(id)
+    })?;
+
+    // 笔记 id 确定后才能下载图片到对应的附件目录
+    if !images.is_empty() {
+        let updated = download_images(&app, note_id, &content, &images).await?;
+        if updated != content {
+            db::with_write_conn(&app, |conn| {
+                conn.execute(
+                    "UPDATE notes SET content = ?1 WHERE id = ?2",
+                    params![updated, note_id],
+                )
+            })?;
+        }
+    }
+
+    log::info!("已剪藏网页 {} 为笔记 {}", page.url, note_id);
+    Ok(note_id)
+}
@@ -0,0 +1,242 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::Manager;
+use window_vibrancy::{Error as VibrancyError, NSVisualEffectMaterial};
+
+// 窗口背景效果：前几项为 macOS 的 vibrancy 材质，后几项为 Windows 的效果
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowEffect {
+    Sidebar,
+    Titlebar,
+    Menu,
+    Popover,
+    HeaderView,
+    Sheet,
+    WindowBackground,
+    HudWindow,
+    ContentBackground,
+    UnderWindowBackground,
+    Acrylic,
+    Mica,
+    Tabbed,
+    Blur,
+}
+
+#[cfg(target_os = "macos")]
+const MACOS_EFFECTS: &[WindowEffect] = &[
+    WindowEffect::Sidebar,
+    WindowEffect::Titlebar,
+    WindowEffect::Menu,
+    WindowEffect::Popover,
+    WindowEffect::HeaderView,
+    WindowEffect::Sheet,
+    WindowEffect::WindowBackground,
+    WindowEffect::HudWindow,
+    WindowEffect::ContentBackground,
+    WindowEffect::UnderWindowBackground,
+];
+
+// 效果只在创建时启用了透明的窗口上可见，透明与否在启动时按是否保存了效果决定
+pub struct WindowEffectState {
+    transparent: bool,
+    applied: Mutex<Option<WindowEffect>>,
+}
+
+impl WindowEffectState {
+    pub fn new(transparent: bool) -> Self {
+        WindowEffectState {
+            transparent,
+            applied: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowEffectStatus {
+    // 实际生效的效果，可能因系统版本降级为其他效果
+    pub effect: Option<WindowEffect>,
+    // 当前窗口不透明，需重启应用后效果才会显示
+    pub restart_required: bool,
+}
+
+// Windows 10 build；无法识别时视为 0
+#[cfg(target_os = "windows")]
+fn windows_build() -> u64 {
+    tauri_plugin_os::version()
+        .to_string()
+        .rsplit('.')
+        .next()
+        .and_then(|build| build.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn supported() -> Vec<WindowEffect> {
+    #[cfg(target_os = "macos")]
+    return MACOS_EFFECTS.to_vec();
+
+    // Mica 需要 Windows 11（22000），标签页样式需要 22523，亚克力需要 Windows 10 1809（17763）
+    #[cfg(target_os = "windows")]
+    {
+        let build = windows_build();
+        let mut effects = vec![WindowEffect::Blur];
+        if build >= 17763 {
+            effects.insert(0, WindowEffect::Acrylic);
+        }
+        if build >= 22000 {
+            effects.insert(0, WindowEffect::Mica);
+        }
+        if build >= 22523 {
+            effects.insert(1, WindowEffect::Tabbed);
+        }
+        return effects;
+    }
+
+    // Linux 上的模糊效果由窗口合成器决定，应用无法控制
+    #[allow(unreachable_code)]
+    Vec::new()
+}
+
+fn material(effect: WindowEffect) -> Option<NSVisualEffectMaterial> {
+    Some(match effect {
+        WindowEffect::Sidebar => NSVisualEffectMaterial::Sidebar,
+        WindowEffect::Titlebar => NSVisualEffectMaterial::Titlebar,
+        WindowEffect::Menu => NSVisualEffectMaterial::Menu,
+        WindowEffect::Popover => NSVisualEffectMaterial::Popover,
+        WindowEffect::HeaderView => NSVisualEffectMaterial::HeaderView,
+        WindowEffect::Sheet => NSVisualEffectMaterial::Sheet,
+        WindowEffect::WindowBackground => NSVisualEffectMaterial::WindowBackground,
+        WindowEffect::HudWindow => NSVisualEffectMaterial::HudWindow,
+        WindowEffect::ContentBackground => NSVisualEffectMaterial::ContentBackground,
+        WindowEffect::UnderWindowBackground => NSVisualEffectMaterial::UnderWindowBackground,
+        _ => return None,
+    })
+}
+
+fn apply_to(window: &tauri::WebviewWindow, effect: WindowEffect) -> Result<(), VibrancyError> {
+    if let Some(material) = material(effect) {
+        return window_vibrancy::apply_vibrancy(window, material, None, None);
+    }
+    match effect {
+        WindowEffect::Acrylic => window_vibrancy::apply_acrylic(window, None),
+        WindowEffect::Mica => window_vibrancy::apply_mica(window, None),
+        WindowEffect::Tabbed => window_vibrancy::apply_tabbed(window, None),
+        _ => window_vibrancy::apply_blur(window, None),
+    }
+}
+
+fn clear_from(window: &tauri::WebviewWindow, effect: WindowEffect) -> Result<(), VibrancyError> {
+    if material(effect).is_some() {
+        return window_vibrancy::clear_vibrancy(window).map(|_| ());
+    }
+    match effect {
+        WindowEffect::Acrylic => window_vibrancy::clear_acrylic(window),
+        WindowEffect::Mica => window_vibrancy::clear_mica(window),
+        WindowEffect::Tabbed => window_vibrancy::clear_tabbed(window),
+        _ => window_vibrancy::clear_blur(window),
+    }
+}
+
+// 系统版本不支持时依次降级：Mica/标签页 -> 亚克力 -> 模糊，返回实际应用的效果
+fn apply_with_fallback(
+    window: &tauri::WebviewWindow,
+    effect: WindowEffect,
+) -> AppResult<WindowEffect> {
+    let fallbacks: &[WindowEffect] = match effect {
+        WindowEffect::Mica | WindowEffect::Tabbed => &[WindowEffect::Acrylic, WindowEffect::Blur],
+        WindowEffect::Acrylic => &[WindowEffect::Blur],
+        _ => &[],
+    };
+    let mut result = apply_to(window, effect).map(|_| effect);
+    for &fallback in fallbacks {
+        match result {
+            Err(VibrancyError::UnsupportedPlatformVersion(_)) => {
+                log::warn!("系统不支持窗口效果 {:?}，改用 {:?}", effect, fallback);
+                result = apply_to(window, fallback).map(|_| fallback);
+            }
+            _ => break,
+        }
+    }
+    result.map_err(wrap("window.effect_failed"))
+}
+
+fn main_window(app: &tauri::AppHandle) -> AppResult<tauri::WebviewWindow> {
+    app.get_webview_window("main")
+        .ok_or_else(|| AppError::new("window.effect_failed").with("error", "main window"))
+}
+
+fn clear_applied(app: &tauri::AppHandle, window: &tauri::WebviewWindow) -> AppResult<()> {
+    let state = app.state::<WindowEffectState>();
+    let mut applied = state.applied.lock().unwrap();
+    if let Some(effect) = *applied {
+        clear_from(window, effect).map_err(wrap("window.effect_failed"))?;
+        *applied = None;
+    }
+    Ok(())
+}
+
+// 启动时保存的效果，决定主窗口是否需要透明
+pub fn saved(app: &tauri::AppHandle) -> Option<WindowEffect> {
+    settings::get::<WindowEffect>(app, settings::WINDOW_EFFECT)
+        .filter(|effect| supported().contains(effect))
+}
+
+// 主窗口创建后重新应用保存的效果
+pub fn init(app: &tauri::AppHandle) {
+    let Some(effect) = saved(app) else {
+        return;
+    };
+    let result = main_window(app).and_then(|window| apply_with_fallback(&window, effect));
+    match result {
+        Ok(applied) => {
+            *app.state::<WindowEffectState>().applied.lock().unwrap() = Some(applied);
+        }
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+#[tauri::command]
+pub fn get_supported_window_effects() -> Vec<WindowEffect> {
+    supported()
+}
+
+// 保存并立即应用；窗口在启动时不透明的话需要重启后才能看到效果
+#[tauri::command]
+pub fn set_window_effect(
+    app: tauri::AppHandle,
+    effect: WindowEffect,
+) -> AppResult<WindowEffectStatus> {
+    if !supported().contains(&effect) {
+        return Err(
+            AppError::new("window.effect_unsupported").with("effect", format!("{:?}", effect))
+        );
+    }
+    settings::set(&app, settings::WINDOW_EFFECT, effect)?;
+
+    if !app.state::<WindowEffectState>().transparent {
+        return Ok(WindowEffectStatus {
+            effect: None,
+            restart_required: true,
+        });
+    }
+
+    let window = main_window(&app)?;
+    clear_applied(&app, &window)?;
+    let applied = apply_with_fallback(&window, effect)?;
+    *app.state::<WindowEffectState>().applied.lock().unwrap() = Some(applied);
+    log::info!("已应用窗口效果 {:?}", applied);
+
+    Ok(WindowEffectStatus {
+        effect: Some(applied),
+        restart_required: false,
+    })
+}
+
+// 恢复不透明背景；窗口本身保持透明直到下次启动，背景由前端绘制
+#[tauri::command]
+pub fn clear_window_effect(app: tauri::AppHandle) -> AppResult<()> {
+    settings::set(&app, settings::WINDOW_EFFECT, serde_json::Value::Null)?;
+    clear_applied(&app, &main_window(&app)?)
+}
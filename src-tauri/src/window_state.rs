@@ -0,0 +1,85 @@
+use crate::error::{wrap, AppResult};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use tauri::{LogicalPosition, LogicalSize, Manager, WebviewWindowBuilder};
+
+const DEFAULT_WIDTH: f64 = 800.0;
+const DEFAULT_HEIGHT: f64 = 600.0;
+
+// 主窗口的位置和大小，使用逻辑像素；最大化时保留最大化之前的大小
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub maximized: bool,
+}
+
+pub fn save(app: &tauri::AppHandle) -> AppResult<()> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    if crate::read_only::is_enabled(app) {
+        return Ok(());
+    }
+    if window.is_minimized().unwrap_or(false) {
+        return Ok(());
+    }
+
+    let maximized = window.is_maximized().map_err(wrap("window.state_failed"))?;
+    let saved = settings::get::<WindowState>(app, settings::WINDOW_STATE);
+    let state = match saved {
+        Some(saved) if maximized => WindowState { maximized, ..saved },
+        _ => {
+            let scale = window.scale_factor().map_err(wrap("window.state_failed"))?;
+            let position = window
+                .outer_position()
+                .map_err(wrap("window.state_failed"))?
+                .to_logical::<f64>(scale);
+            let size = window
+                .inner_size()
+                .map_err(wrap("window.state_failed"))?
+                .to_logical::<f64>(scale);
+            WindowState {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+            }
+        }
+    };
+    settings::set(app, settings::WINDOW_STATE, state)
+}
+
+// 按上次保存的状态设置窗口大小和位置，返回是否需要最大化；所在的显示器已不存在时不恢复位置
+pub fn restore<'a>(
+    app: &tauri::App,
+    builder: WebviewWindowBuilder<'a, tauri::Wry, tauri::App>,
+) -> (WebviewWindowBuilder<'a, tauri::Wry, tauri::App>, bool) {
+    let Some(state) = settings::get::<WindowState>(app.handle(), settings::WINDOW_STATE) else {
+        return (builder.inner_size(DEFAULT_WIDTH, DEFAULT_HEIGHT), false);
+    };
+
+    let builder = builder.inner_size(state.width, state.height);
+    let visible = app
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .any(|monitor| {
+            let scale = monitor.scale_factor();
+            let position: LogicalPosition<f64> = monitor.position().to_logical(scale);
+            let size: LogicalSize<f64> = monitor.size().to_logical(scale);
+            state.x >= position.x
+                && state.y >= position.y
+                && state.x < position.x + size.width
+                && state.y < position.y + size.height
+        });
+    let builder = if visible {
+        builder.position(state.x, state.y)
+    } else {
+        builder
+    };
+    (builder, state.maximized)
+}
@@ -0,0 +1,216 @@
+use crate::readability;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// 连续的字母或数字；汉字、假名单独成段，由 cjk_terms 再切分
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"[\p{Han}\p{Hiragana}\p{Katakana}]+|[\w&&[^\p{Han}\p{Hiragana}\p{Katakana}_]]+(?:['’][\w&&[^_]]+)*",
+    )
+    .unwrap()
+});
+static CJK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\p{Han}\p{Hiragana}\p{Katakana}]").unwrap());
+
+// 中日韩文字占比超过该值时视为以中文为主
+const CJK_THRESHOLD: f64 = 0.5;
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have",
+    "he", "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "more",
+    "my", "no", "not", "of", "on", "one", "only", "or", "our", "out", "she", "so", "some", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "up", "us",
+    "was", "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+// 常见的单字虚词和代词，中文文本在这些字处断开后再切分
+const CHINESE_STOPWORDS: &[&str] = &[
+    "的", "了", "是", "在", "和", "与", "及", "或", "也", "就", "都", "而", "又", "着", "把", "被",
+    "让", "给", "对", "从", "向", "这", "那", "我", "你", "他", "她", "它", "们", "个", "之", "其",
+    "有", "没", "不", "很", "还", "吗", "呢", "吧", "啊", "将", "为", "以", "于", "上", "中", "下",
+    "来", "去", "要", "会", "能", "可", "一", "些",
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WordCount {
+    pub word: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WordFrequency {
+    pub words: Vec<WordCount>,
+    // 去掉停用词后的总词数和不同词数
+    pub total_words: usize,
+    pub unique_words: usize,
+    // 内容以中日韩文字为主，词频基于双字切分，仅供参考
+    pub mostly_cjk: bool,
+}
+
+// 不依赖词典的中文切分：先在停用字处断开，剩下的片段取相邻两字组成的词，单字片段保留为一个词
+fn cjk_terms(run: &str, stopwords: &HashSet<String>) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut segment: Vec<char> = Vec::new();
+    let mut flush = |segment: &mut Vec<char>| {
+        match segment.len() {
+            0 => {}
+            1 => terms.push(segment[0].to_string()),
+            _ => terms.extend(
+                segment
+                    .windows(2)
+                    .map(|pair| pair.iter().collect::<String>()),
+            ),
+        }
+        segment.clear();
+    };
+    for c in run.chars() {
+        if stopwords.contains(c.encode_utf8(&mut [0; 4]) as &str) {
+            flush(&mut segment);
+        } else {
+            segment.push(c);
+        }
+    }
+    flush(&mut segment);
+    terms.retain(|term| !stopwords.contains(term));
+    terms
+}
+
+// 统计纯文本（去掉 Markdown、HTML 标签和代码块）中的词频，英文按单词小写后计数，纯数字不计入。
+// stopwords 为 None 时使用内置的中英文停用词表，传入列表（可为空）时替换内置列表；top 为 0 时返回全部
+pub fn analyze(content: &str, top: usize, stopwords: Option<&[String]>) -> WordFrequency {
+    let stopwords: HashSet<String> = match stopwords {
+        Some(list) => list.iter().map(|word| word.trim().to_lowercase()).collect(),
+        None => ENGLISH_STOPWORDS
+            .iter()
+            .chain(CHINESE_STOPWORDS)
+            .map(|word| word.to_string())
+            .collect(),
+    };
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut cjk_chars = 0usize;
+    let mut other_chars = 0usize;
+    for block in readability::text_blocks(content) {
+        for token in TOKEN.find_iter(&block) {
+            let token = token.as_str();
+            if CJK.is_match(token) {
+                cjk_chars += token.chars().count();
+                for term in cjk_terms(token, &stopwords) {
+                    *counts.entry(term).or_default() += 1;
+                }
+                continue;
+            }
+            other_chars += token.chars().count();
+            let word = token.to_lowercase().replace('’', "'");
+            if word.chars().all(|c| c.is_numeric()) || stopwords.contains(&word) {
+                continue;
+            }
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+
+    let total_words = counts.values().sum();
+    let unique_words = counts.len();
+    let mut words: Vec<WordCount> = counts
+        .into_iter()
+        .map(|(word, count)| WordCount { word, count })
+        .collect();
+    words.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    if top > 0 {
+        words.truncate(top);
+    }
+
+    WordFrequency {
+        words,
+        total_words,
+        unique_words,
+        mostly_cjk: cjk_chars + other_chars > 0
+            && cjk_chars as f64 / (cjk_chars + other_chars) as f64 > CJK_THRESHOLD,
+    }
+}
+
+#[tauri::command]
+pub fn analyze_word_frequency(
+    content: String,
+    top: u32,
+    stopwords: Option<Vec<String>>,
+) -> WordFrequency {
+    analyze(&content, top as usize, stopwords.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(frequency: &WordFrequency, word: &str) -> usize {
+        frequency
+            .words
+            .iter()
+            .find(|w| w.word == word)
+            .map_or(0, |w| w.count)
+    }
+
+    #[test]
+    fn mixed_chinese_and_english() {
+        let frequency = analyze("Rust 编程语言，rust's ownership. RUST 语言", 0, None);
+        assert_eq!(count(&frequency, "rust"), 2);
+        assert_eq!(count(&frequency, "rust's"), 1);
+        assert_eq!(count(&frequency, "ownership"), 1);
+        assert_eq!(count(&frequency, "编程"), 1);
+        assert_eq!(count(&frequency, "程语"), 1);
+        assert_eq!(count(&frequency, "语言"), 2);
+        assert!(!frequency.mostly_cjk);
+
+        let frequency = analyze("今天学习编程语言 Go", 0, None);
+        assert!(frequency.mostly_cjk);
+        assert_eq!(count(&frequency, "go"), 1);
+    }
+
+    #[test]
+    fn builtin_stopwords_are_removed() {
+        let frequency = analyze("The cat and the hat. 我的猫在家里 2024", 0, None);
+        assert_eq!(count(&frequency, "the"), 0);
+        assert_eq!(count(&frequency, "and"), 0);
+        assert_eq!(count(&frequency, "cat"), 1);
+        assert_eq!(count(&frequency, "hat"), 1);
+        // 在停用字“我”“的”“在”处断开，单字片段保留
+        assert_eq!(count(&frequency, "猫"), 1);
+        assert_eq!(count(&frequency, "家里"), 1);
+        assert_eq!(count(&frequency, "我"), 0);
+        assert_eq!(count(&frequency, "2024"), 0);
+        assert_eq!(frequency.total_words, 4);
+    }
+
+    #[test]
+    fn custom_stopwords_replace_builtin_list() {
+        let stopwords = vec![" Cat ".to_string()];
+        let frequency = analyze("the cat and the hat", 0, Some(&stopwords));
+        assert_eq!(count(&frequency, "cat"), 0);
+        assert_eq!(count(&frequency, "the"), 2);
+
+        let frequency = analyze("the cat", 0, Some(&[]));
+        assert_eq!(frequency.unique_words, 2);
+    }
+
+    #[test]
+    fn top_limits_and_orders_results() {
+        let frequency = analyze("beta alpha beta gamma alpha beta", 2, None);
+        let words: Vec<_> = frequency
+            .words
+            .iter()
+            .map(|w| (w.word.as_str(), w.count))
+            .collect();
+        assert_eq!(words, [("beta", 3), ("alpha", 2)]);
+        assert_eq!(frequency.unique_words, 3);
+    }
+
+    #[test]
+    fn code_blocks_and_tags_are_skipped() {
+        let frequency = analyze("<p>hello</p><pre><code>hidden</code></pre>", 0, None);
+        assert_eq!(count(&frequency, "hello"), 1);
+        assert_eq!(count(&frequency, "hidden"), 0);
+        assert_eq!(analyze("", 0, None).total_words, 0);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error::{wrap, AppError, AppResult};
+use crate::{read_only, settings};
+use tauri::Manager;
+
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const DEFAULT_ZOOM: f64 = 1.0;
+const ZOOM_STEP: f64 = 0.1;
+
+fn current(app: &tauri::AppHandle) -> f64 {
+    settings::get::<f64>(app, settings::ZOOM_LEVEL)
+        .filter(|factor| (MIN_ZOOM..=MAX_ZOOM).contains(factor))
+        .unwrap_or(DEFAULT_ZOOM)
+}
+
+fn apply(app: &tauri::AppHandle, factor: f64) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window("main") {
+        window
+            .set_zoom(factor)
+            .map_err(wrap("window.zoom_failed"))?;
+    }
+    Ok(())
+}
+
+// 启动或切换配置后应用保存的缩放比例
+pub fn init(app: &tauri::AppHandle) {
+    let factor = current(app);
+    if factor != DEFAULT_ZOOM {
+        if let Err(e) = apply(app, factor) {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+// 设置主窗口的缩放比例并保存，返回实际使用的比例；只读模式下只应用不保存
+#[tauri::command]
+pub fn set_zoom_level(app: tauri::AppHandle, factor: f64) -> AppResult<f64> {
+    if !(MIN_ZOOM..=MAX_ZOOM).contains(&factor) {
+        return Err(AppError::new("window.invalid_zoom")
+            .with("min", MIN_ZOOM)
+            .with("max", MAX_ZOOM));
+    }
+    // 按步长取整，避免多次放大缩小后累积浮点误差
+    let factor = (factor * 100.0).round() / 100.0;
+    apply(&app, factor)?;
+    if !read_only::is_enabled(&app) {
+        settings::set(&app, settings::ZOOM_LEVEL, factor)?;
+    }
+    log::info!("窗口缩放比例已设置为 {}", factor);
+    Ok(factor)
+}
+
+#[tauri::command]
+pub fn zoom_in(app: tauri::AppHandle) -> AppResult<f64> {
+    let factor = (current(&app) + ZOOM_STEP).min(MAX_ZOOM);
+    set_zoom_level(app, factor)
+}
+
+#[tauri::command]
+pub fn zoom_out(app: tauri::AppHandle) -> AppResult<f64> {
+    let factor = (current(&app) - ZOOM_STEP).max(MIN_ZOOM);
+    set_zoom_level(app, factor)
+}
+
+#[tauri::command]
+pub fn zoom_reset(app: tauri::AppHandle) -> AppResult<f64> {
+    set_zoom_level(app, DEFAULT_ZOOM)
+}
@@ -11,6 +11,7 @@ import { AlertDialogProvider } from './components/ui/alert-dialog';
 import { useAppStore } from './store/useAppStore';
 import { useNotesStore } from './store/useNotesStore';
 import { initDatabase } from './lib/database';
+import { migrateLocalSettings } from './lib/settings';
 import { ContextMenuProvider } from './components/ui/context-menu';
 import { EditorTestPage } from './pages/EditorTestPage';
 
@@ -33,13 +34,11 @@ function App() {
     // 初始化主题
     applyTheme();
 
-    // 监听系统主题变化
-    const mediaQuery = window.matchMedia('(prefers-color-scheme: dark)');
-    const handleThemeChange = () => applyTheme();
-    mediaQuery.addEventListener('change', handleThemeChange);
+    // 监听系统主题变化（由 Rust 端检测并广播）
+    const unlisten = listen('system-theme-changed', () => applyTheme());
 
     return () => {
-      mediaQuery.removeEventListener('change', handleThemeChange);
+      unlisten.then((fn) => fn());
     };
   }, [theme]);
 
@@ -47,6 +46,7 @@ function App() {
     // 初始化数据库和数据
     const initApp = async () => {
       try {
+        await migrateLocalSettings();
         await initDatabase();
         await loadCategories();
         await loadNotes();
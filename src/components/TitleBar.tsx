@@ -6,6 +6,7 @@ import { Minus, Square, X, Settings, Download, Keyboard, DatabaseZap } from 'luc
 import { Button } from './ui/button';
 import { useAppStore } from '../store/useAppStore';
 import { useAlertDialog } from './ui/alert-dialog';
+import { closeDatabase } from '../lib/database';
 
 interface TitleBarProps {
   onExportClick?: () => void;
@@ -115,12 +116,13 @@ export function TitleBar({ onExportClick, onShortcutsClick }: TitleBarProps) {
       destructive: true,
       onConfirm: async () => {
         try {
+          await closeDatabase();
           await invoke('delete_database');
         } catch (error) {
           console.error('Failed to delete database:', error);
           showAlert({
             title: '删除失败',
-            message: `无法删除数据库文件: ${error}`,
+            message: `无法删除数据库文件: ${(error as { message?: string })?.message ?? error}`,
             confirmText: '好的',
             onConfirm: () => {},
           });
@@ -1,5 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import { save, open } from '@tauri-apps/plugin-dialog';
+import { closeDatabase } from './database';
 
 // 备份数据库
 export async function backupDatabase(): Promise<void> {
@@ -39,6 +40,8 @@ export async function restoreDatabase(): Promise<void> {
       );
       
       if (confirmed) {
+        // 替换文件前关闭前端的数据库连接
+        await closeDatabase();
         await invoke('restore_database', { filePath: files });
         
         // 提示用户重启应用
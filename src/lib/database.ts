@@ -8,17 +8,22 @@ import {
   CreateCategoryData,
   CreateTagData,
 } from "../types";
-import { appDataDir } from "@tauri-apps/api/path";
-import { join } from "@tauri-apps/api/path";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 let db: Database | null = null;
 let dbPromise: Promise<Database> | null = null;
 
 async function _initDatabase(): Promise<Database> {
-  const appData = await appDataDir();
-  const dbPath = await join(appData, "notes.db");
-  const newDb = await Database.load(`sqlite:${dbPath}`);
-  await createTables(newDb);
+  const active = await invoke<{ path: string }>("get_active_database");
+  // 只读模式下以 mode=ro 打开，任何写入都会被 SQLite 拒绝；表结构由正常模式负责创建
+  const readOnly = await invoke<boolean>("get_read_only");
+  const newDb = await Database.load(
+    `sqlite:${active.path}${readOnly ? "?mode=ro" : ""}`
+  );
+  if (!readOnly) {
+    await createTables(newDb);
+  }
   db = newDb;
   return db;
 }
@@ -31,6 +36,20 @@ export function initDatabase(): Promise<Database> {
   return dbPromise;
 }
 
+// 关闭当前连接，下次访问时重新加载；恢复或删除数据库文件前须先调用
+export async function closeDatabase(): Promise<void> {
+  const oldDb = db ?? (dbPromise ? await dbPromise.catch(() => null) : null);
+  db = null;
+  dbPromise = null;
+  if (oldDb) {
+    await oldDb.close();
+  }
+}
+
+// 切换数据库或只读模式后关闭旧连接，下次访问时重新加载
+listen("database-switched", closeDatabase);
+listen("read-only-changed", closeDatabase);
+
 async function createTables(database: Database) {
   // 创建分类表
   await database.execute(`
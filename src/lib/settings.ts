@@ -0,0 +1,11 @@
+import { invoke } from '@tauri-apps/api/core';
+
+// zustand persist 在 localStorage 中使用的键名
+const LOCAL_SETTINGS_KEY = 'app-settings';
+
+// 将 localStorage 中的旧设置一次性导入 Rust 端的设置文件
+export async function migrateLocalSettings(): Promise<number> {
+  const raw = localStorage.getItem(LOCAL_SETTINGS_KEY);
+  const values = raw ? JSON.parse(raw).state ?? {} : {};
+  return invoke<number>('migrate_local_settings', { values });
+}
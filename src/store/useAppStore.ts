@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
+import { invoke } from '@tauri-apps/api/core';
 import { AppSettings } from '../types';
 
 interface AppState extends AppSettings {
@@ -29,7 +30,10 @@ export const useAppStore = create<AppState>()(
       // Actions
       setTheme: (theme) => {
         set({ theme });
-        get().applyTheme();
+        // 主题以 Rust 端设置为准，写入后再应用
+        invoke('set_setting', { key: 'theme', value: theme })
+          .catch(() => {})
+          .finally(() => get().applyTheme());
       },
 
       setAutoSave: (autoSave) => {
@@ -51,13 +55,14 @@ export const useAppStore = create<AppState>()(
       applyTheme: () => {
         const { theme } = get();
         const root = document.documentElement;
-        
-        if (theme === 'system') {
-          const systemTheme = window.matchMedia('(prefers-color-scheme: dark)').matches ? 'dark' : 'light';
-          root.classList.toggle('dark', systemTheme === 'dark');
-        } else {
-          root.classList.toggle('dark', theme === 'dark');
-        }
+
+        invoke<'light' | 'dark'>('get_effective_theme')
+          .then((effective) => root.classList.toggle('dark', effective === 'dark'))
+          .catch(() => {
+            // 后端不可用时退回浏览器的系统主题检测
+            const systemTheme = window.matchMedia('(prefers-color-scheme: dark)').matches ? 'dark' : 'light';
+            root.classList.toggle('dark', (theme === 'system' ? systemTheme : theme) === 'dark');
+          });
       },
     }),
     {